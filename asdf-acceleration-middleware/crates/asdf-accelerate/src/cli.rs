@@ -1,6 +1,8 @@
 //! CLI argument parsing
 
-use clap::{Parser, Subcommand};
+use crate::output::OutputMode;
+use asdf_metrics::TraceFormat;
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -20,6 +22,18 @@ pub struct Cli {
     #[arg(short, long, global = true)]
     pub jobs: Option<usize>,
 
+    /// Output mode (text, json, ndjson)
+    #[arg(short, long, global = true, value_enum, default_value = "text")]
+    pub output: OutputMode,
+
+    /// Record tracing spans and write them to this file on exit
+    #[arg(long, global = true, value_name = "PATH")]
+    pub trace_file: Option<PathBuf>,
+
+    /// Trace file format (chrome for Perfetto, otlp for Jaeger)
+    #[arg(long, global = true, default_value = "chrome")]
+    pub trace_format: TraceFormat,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -73,6 +87,27 @@ pub enum Commands {
         background: bool,
     },
 
+    /// Prepare a CI job: cache key, installs and environment exports
+    Ci {
+        /// Directory whose .tool-versions apply (default: current directory)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+
+        /// File to write PATH and *_HOME exports to (default: asdf.env, or
+        /// $GITHUB_ENV with --env-format github, which puts PATH entries in
+        /// $GITHUB_PATH instead)
+        #[arg(long)]
+        env_file: Option<PathBuf>,
+
+        /// Format of the exports file
+        #[arg(long, value_enum, default_value = "dotenv")]
+        env_format: EnvFormat,
+
+        /// Only compute the cache key; add, install and export nothing
+        #[arg(long)]
+        key_only: bool,
+    },
+
     /// List plugins
     List {
         /// Show URLs
@@ -84,6 +119,41 @@ pub enum Commands {
         format: String,
     },
 
+    /// Search the plugin index by short name
+    Search {
+        /// Name, part of a name or misspelling; lists every plugin if omitted
+        query: Option<String>,
+
+        /// Maximum number of results
+        #[arg(long, default_value = "20")]
+        limit: usize,
+
+        /// Refresh git indexes even if the cached copy is fresh
+        #[arg(long, conflicts_with = "offline")]
+        refresh: bool,
+
+        /// Only use cached and vendored indexes
+        #[arg(long)]
+        offline: bool,
+    },
+
+    /// Review and pin trusted plugin repositories
+    Trust {
+        #[command(subcommand)]
+        action: TrustAction,
+    },
+
+    /// Remove plugins, installs and downloads nothing uses
+    Prune {
+        /// Only report what would be removed
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Remove without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
     /// Clear cache
     Cache {
         /// Clear all cache
@@ -95,3 +165,73 @@ pub enum Commands {
         stats: bool,
     },
 }
+
+/// Format of the environment file written by `ci`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EnvFormat {
+    /// `KEY="value"` lines, replacing the file
+    Dotenv,
+
+    /// GitHub Actions `$GITHUB_ENV` lines, appended to the file, with PATH
+    /// entries appended to `$GITHUB_PATH`
+    Github,
+}
+
+#[derive(Subcommand)]
+pub enum TrustAction {
+    /// List trusted plugins
+    List,
+
+    /// Trust a plugin's current remote, pinned to its checkout
+    Add {
+        /// Plugin to trust
+        plugin: String,
+
+        /// Require incoming commits to be signed by a trusted key
+        #[arg(long)]
+        require_signature: bool,
+
+        /// Follow the remote instead of pinning the current commit
+        #[arg(long)]
+        no_pin: bool,
+    },
+
+    /// Show what an update would bring in, and optionally accept it
+    Review {
+        /// Plugin to review
+        plugin: String,
+
+        /// Move the pin to the incoming commit and check it out
+        #[arg(long)]
+        accept: bool,
+    },
+
+    /// Stop trusting a plugin
+    Remove {
+        /// Plugin to forget
+        plugin: String,
+    },
+
+    /// Set the allowed signers file used to verify SSH signatures
+    Signers {
+        /// Path to an allowed signers file
+        file: PathBuf,
+    },
+}
+
+impl Commands {
+    /// Subcommand name used in result documents
+    pub fn name(&self) -> &'static str {
+        match self {
+            Commands::Update { .. } => "update",
+            Commands::Install { .. } => "install",
+            Commands::Sync { .. } => "sync",
+            Commands::Ci { .. } => "ci",
+            Commands::List { .. } => "list",
+            Commands::Search { .. } => "search",
+            Commands::Trust { .. } => "trust",
+            Commands::Prune { .. } => "prune",
+            Commands::Cache { .. } => "cache",
+        }
+    }
+}
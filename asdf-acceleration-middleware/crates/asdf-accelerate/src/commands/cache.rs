@@ -1,31 +1,48 @@
 //! Cache management command
 
+use crate::output::{Reporter, Status};
 use anyhow::Result;
 use asdf_cache::DiskCache;
 use asdf_config::AcceleratorConfig;
 use colored::Colorize;
 
-pub fn execute(config: &AcceleratorConfig, clear: bool, stats: bool) -> Result<()> {
+pub fn execute(
+    config: &AcceleratorConfig,
+    reporter: &Reporter,
+    clear: bool,
+    stats: bool,
+) -> Result<i32> {
     let cache = DiskCache::open(&config.cache.directory)?;
 
     if clear {
-        println!("{} Clearing cache...", "→".cyan());
+        reporter.say(format!("{} Clearing cache...", "→".cyan()));
         cache.clear()?;
-        println!("{} Cache cleared", "✓".green());
+        reporter.say(format!("{} Cache cleared", "✓".green()));
     }
 
+    let mut expired_removed = None;
     if stats {
-        println!("\n{} Cache Statistics:", "→".cyan());
-        println!("  Location: {}", config.cache.directory.display());
-        println!("  Entries:  {}", cache.len());
-        println!("  TTL:      {}s", config.cache.ttl_secs);
+        reporter.say(format!("\n{} Cache Statistics:", "→".cyan()));
+        reporter.say(format!("  Location: {}", config.cache.directory.display()));
+        reporter.say(format!("  Entries:  {}", cache.len()));
+        reporter.say(format!("  TTL:      {}s", config.cache.ttl_secs));
 
         // Clean expired entries
         let removed = cache.clean_expired()?;
         if removed > 0 {
-            println!("  Cleaned:  {} expired entries", removed);
+            reporter.say(format!("  Cleaned:  {} expired entries", removed));
         }
+        expired_removed = Some(removed);
     }
 
-    Ok(())
+    reporter.finish(&serde_json::json!({
+        "status": Status::Ok,
+        "location": config.cache.directory,
+        "entries": cache.len(),
+        "ttl_secs": config.cache.ttl_secs,
+        "cleared": clear,
+        "expired_removed": expired_removed,
+    }))?;
+
+    Ok(Status::Ok.exit_code())
 }
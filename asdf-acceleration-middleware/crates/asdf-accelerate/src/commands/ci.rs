@@ -0,0 +1,544 @@
+//! CI integration command
+//!
+//! Prepares a CI job from the `.tool-versions` in effect: derives a cache
+//! key from the tool versions, plugin commits and platform, installs the
+//! runtimes that are missing, then writes their PATH and `*_HOME` variables
+//! to a file so later steps run the binaries directly instead of through
+//! shims.
+//!
+//! Plugins are keyed by the repository the local plugin index names and the
+//! commit checked out, or the commit the trust store pins while there is no
+//! checkout yet, so `--key-only` needs neither the network nor a clone. A
+//! pinned plugin keys the same before and after a cache restore; an
+//! unpinned one changes the key once its checkout is restored or moves.
+//!
+//! Versions are taken as written, the way asdf names installs, so values
+//! such as `temurin-21.0.1+12` or `stable` work as well as semantic
+//! versions. `ref:` versions are built by asdf from source; they are keyed
+//! by their ref but neither installed nor exported here, and resolve
+//! through the shims.
+
+use super::{install, print_failures, run_batch, Workload};
+use crate::cli::EnvFormat;
+use crate::output::{BatchReport, Reporter};
+use anyhow::{bail, Context, Result};
+use asdf_cache::{artifact, ArtifactCache};
+use asdf_config::AcceleratorConfig;
+use asdf_core::{tool_versions, Index, Plugin, Runtime, TrustStore, Version};
+use asdf_parallel::{ExecutorConfig, Strategy};
+use colored::Colorize;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Exports file used when neither `--env-file` nor `$GITHUB_ENV` is given
+const DEFAULT_ENV_FILE: &str = "asdf.env";
+
+/// Result document of `ci`
+#[derive(Serialize)]
+struct CiReport {
+    #[serde(flatten)]
+    batch: BatchReport,
+    cache_key: String,
+    restore_paths: Vec<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    env_file: Option<PathBuf>,
+    exports: BTreeMap<String, String>,
+    path: Vec<PathBuf>,
+}
+
+pub fn execute(
+    config: &AcceleratorConfig,
+    reporter: &Reporter,
+    dir: Option<PathBuf>,
+    env_file: Option<PathBuf>,
+    env_format: EnvFormat,
+    key_only: bool,
+    jobs: Option<usize>,
+) -> Result<i32> {
+    let dir = match dir {
+        Some(dir) => dir,
+        None => std::env::current_dir()?,
+    };
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let tools = tool_versions::effective(&dir, home.as_deref())?;
+    if tools.is_empty() {
+        bail!(
+            "No {} applies in {}",
+            tool_versions::FILE_NAME,
+            dir.display()
+        );
+    }
+
+    // Only local index copies, so a fresh runner and a restored one agree
+    let index = config
+        .registry
+        .registry()?
+        .index()
+        .unwrap_or_else(|e| {
+            tracing::debug!("Keying plugins without an index: {}", e);
+            Index::default()
+        });
+    let trust = TrustStore::load_default()?;
+    let sources: BTreeMap<String, String> = tools
+        .keys()
+        .map(|plugin| (plugin.clone(), plugin_source(plugin, &index, &trust)))
+        .collect();
+
+    let cache_key = cache_key(&tools, &sources);
+    let restore_paths = vec![asdf_core::installs_dir()?, asdf_core::plugins_dir()?];
+    reporter.say(format!("{} Cache key: {}", "→".cyan(), cache_key.bold()));
+    for path in &restore_paths {
+        reporter.say(format!("{} Restore path: {}", "→".cyan(), path.display()));
+    }
+    if env_format == EnvFormat::Github {
+        write_github_output(&cache_key, &restore_paths)?;
+    }
+
+    let mut report = CiReport {
+        batch: BatchReport::new(Vec::new(), Duration::ZERO),
+        cache_key,
+        restore_paths,
+        env_file: None,
+        exports: BTreeMap::new(),
+        path: Vec::new(),
+    };
+    if key_only {
+        reporter.finish(&report)?;
+        return Ok(report.batch.status.exit_code());
+    }
+
+    let installed: HashSet<String> = Plugin::list()?.into_iter().map(|p| p.name).collect();
+    for plugin in tools.keys().filter(|p| !installed.contains(*p)) {
+        reporter.say(format!("{} Adding plugin {}", "→".cyan(), plugin));
+        Plugin::new(plugin.as_str()).add_from(&index)?;
+    }
+    for (plugin, versions) in &tools {
+        for version in versions.iter().filter(|v| v.starts_with("ref:")) {
+            reporter.say(format!(
+                "{} Skipping {} {}; install it with asdf",
+                "!".yellow(),
+                plugin,
+                version
+            ));
+        }
+    }
+
+    let runtimes = runtimes(&tools);
+
+    let missing: Vec<Runtime> = runtimes
+        .into_iter()
+        .filter(|runtime| !runtime.install_dir().is_ok_and(|dir| dir.is_dir()))
+        .collect();
+    if missing.is_empty() {
+        reporter.say(format!("{} All runtimes are installed", "✓".green()));
+    } else {
+        reporter.say(format!(
+            "{} Installing {} runtimes",
+            "→".cyan(),
+            missing.len()
+        ));
+        report.batch = install_missing(config, reporter, missing, jobs)?;
+    }
+
+    if report.batch.failed > 0 {
+        reporter.say(format!(
+            "\n{} {} runtimes failed to install; no exports written",
+            "✗".red(),
+            report.batch.failed
+        ));
+        print_failures(reporter, &report.batch);
+        reporter.finish(&report)?;
+        return Ok(report.batch.status.exit_code());
+    }
+
+    let path = match env_file {
+        Some(path) => path,
+        None if env_format == EnvFormat::Github => std::env::var_os("GITHUB_ENV")
+            .map(PathBuf::from)
+            .context("GITHUB_ENV is not set; pass --env-file")?,
+        None => PathBuf::from(DEFAULT_ENV_FILE),
+    };
+    let Exports {
+        vars: mut exports,
+        path: path_dirs,
+    } = exports(&tools)?;
+    match env_format {
+        EnvFormat::Dotenv => {
+            let path = std::env::join_paths(path_dirs.iter().cloned().chain(
+                std::env::split_paths(&std::env::var_os("PATH").unwrap_or_default()),
+            ))?;
+            exports.push(("PATH".to_string(), path.to_string_lossy().into_owned()));
+        }
+        EnvFormat::Github => {
+            let github_path = std::env::var_os("GITHUB_PATH")
+                .map(PathBuf::from)
+                .context("GITHUB_PATH is not set")?;
+            write_github_path(&github_path, &path_dirs)?;
+            reporter.say(format!(
+                "{} Added {} directories to {}",
+                "✓".green(),
+                path_dirs.len(),
+                github_path.display()
+            ));
+        }
+    }
+    write_exports(&path, env_format, &exports)?;
+    reporter.say(format!(
+        "{} Wrote {} exports to {}",
+        "✓".green(),
+        exports.len(),
+        path.display()
+    ));
+
+    report.env_file = Some(path);
+    report.exports = exports.into_iter().collect();
+    report.path = path_dirs;
+    reporter.finish(&report)?;
+    Ok(report.batch.status.exit_code())
+}
+
+/// Install runtimes the way `install --parallel` does
+fn install_missing(
+    config: &AcceleratorConfig,
+    reporter: &Reporter,
+    runtimes: Vec<Runtime>,
+    jobs: Option<usize>,
+) -> Result<BatchReport> {
+    let executor_config = ExecutorConfig {
+        strategy: jobs
+            .map(Strategy::Fixed)
+            .unwrap_or(config.parallel.strategy),
+        fail_fast: config.parallel.fail_fast,
+        max_retries: config.parallel.max_retries,
+    };
+
+    let cache = config.artifacts.active_store().map(|store| {
+        Arc::new(
+            ArtifactCache::new(artifact::open_store(store)).with_upload(config.artifacts.upload),
+        )
+    });
+
+    run_batch(
+        reporter,
+        executor_config,
+        Workload::Cpu,
+        runtimes,
+        "Installing",
+        |runtime| format!("{}@{}", runtime.plugin, runtime.version),
+        move |runtime| install::install_runtime(runtime, cache.as_deref()),
+    )
+}
+
+/// Where a plugin comes from, as `<url>@<commit>`
+///
+/// The commit is the checkout's `HEAD`, else the trust store's pin. Either
+/// half is empty when the index does not name the plugin or neither commit
+/// is known.
+fn plugin_source(plugin: &str, index: &Index, trust: &TrustStore) -> String {
+    let url = index.get(plugin).map_or("", |entry| entry.repository.as_str());
+    let commit = Plugin::new(plugin)
+        .current_ref()
+        .ok()
+        .or_else(|| trust.get(plugin).and_then(|entry| entry.commit.clone()))
+        .unwrap_or_default();
+    format!("{}@{}", url, commit)
+}
+
+/// Stable key for the toolchain: platform, versions and plugin sources
+///
+/// Tools are hashed in name order, so the key does not depend on where
+/// entries sit in `.tool-versions`.
+fn cache_key(tools: &BTreeMap<String, Vec<String>>, sources: &BTreeMap<String, String>) -> String {
+    let platform = format!(
+        "{}-{}-{}",
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        artifact::host_libc()
+    );
+
+    let mut hasher = Sha256::new();
+    hasher.update(platform.as_bytes());
+    for (plugin, versions) in tools {
+        let source = sources.get(plugin).map_or("", String::as_str);
+        hasher.update(format!("\n{} {} {}", plugin, versions.join(" "), source).as_bytes());
+    }
+
+    format!(
+        "asdf-{}-{}",
+        platform,
+        &hex::encode(hasher.finalize())[..16]
+    )
+}
+
+/// Every runtime the tools name, except `system`, `path:` and `ref:` versions
+fn runtimes(tools: &BTreeMap<String, Vec<String>>) -> Vec<Runtime> {
+    let mut runtimes = Vec::new();
+
+    for (plugin, versions) in tools {
+        for version in versions {
+            if version == "system" || version.starts_with("path:") || version.starts_with("ref:") {
+                continue;
+            }
+            runtimes.push(Runtime::new(plugin.as_str(), Version::literal(version)));
+        }
+    }
+
+    runtimes
+}
+
+/// What later steps need to run the tools
+struct Exports {
+    /// `*_HOME` for each tool's active version
+    vars: Vec<(String, String)>,
+
+    /// Directories to put first on PATH: the binaries, then the shims
+    path: Vec<PathBuf>,
+}
+
+/// Exports for the tools' active versions
+fn exports(tools: &BTreeMap<String, Vec<String>>) -> Result<Exports> {
+    let mut exports = Vec::new();
+    let mut bin_dirs = Vec::new();
+
+    for (plugin, versions) in tools {
+        // The first version is the one asdf activates
+        let Some(version) = versions
+            .first()
+            .filter(|v| *v != "system" && !v.starts_with("ref:"))
+        else {
+            continue;
+        };
+
+        let home = match version.strip_prefix("path:") {
+            Some(path) => {
+                bin_dirs.push(Path::new(path).join("bin"));
+                PathBuf::from(path)
+            }
+            None => {
+                let runtime = Runtime::new(plugin.as_str(), Version::literal(version));
+                bin_dirs.extend(runtime.bin_dirs()?);
+                runtime.install_dir()?
+            }
+        };
+        exports.push((home_var(plugin), home.display().to_string()));
+    }
+    bin_dirs.push(asdf_core::shims_dir()?);
+
+    Ok(Exports {
+        vars: exports,
+        path: bin_dirs,
+    })
+}
+
+/// `nodejs` → `NODEJS_HOME`
+fn home_var(plugin: &str) -> String {
+    let name: String = plugin
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}_HOME", name)
+}
+
+/// Write exports; dotenv replaces the file, GitHub's env file is appended to
+fn write_exports(path: &Path, format: EnvFormat, exports: &[(String, String)]) -> Result<()> {
+    let mut content = String::new();
+    for (key, value) in exports {
+        match format {
+            EnvFormat::Dotenv => content.push_str(&format!("{}=\"{}\"\n", key, escape(value))),
+            EnvFormat::Github => content.push_str(&github_line(key, value)),
+        }
+    }
+
+    match format {
+        EnvFormat::Dotenv => std::fs::write(path, content)
+            .with_context(|| format!("Failed to write {}", path.display())),
+        EnvFormat::Github => append(path, &content),
+    }
+}
+
+/// Append directories to `$GITHUB_PATH`, so the first ends up first on PATH
+fn write_github_path(path: &Path, dirs: &[PathBuf]) -> Result<()> {
+    // The runner prepends each line in turn, so the last line written wins
+    let content: String = dirs
+        .iter()
+        .rev()
+        .map(|dir| format!("{}\n", dir.display()))
+        .collect();
+    append(path, &content)
+}
+
+/// Publish the key and paths as step outputs, for `actions/cache`
+fn write_github_output(cache_key: &str, restore_paths: &[PathBuf]) -> Result<()> {
+    let Some(path) = std::env::var_os("GITHUB_OUTPUT") else {
+        return Ok(());
+    };
+
+    let paths: Vec<String> = restore_paths
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect();
+    let content =
+        github_line("cache-key", cache_key) + &github_line("cache-paths", &paths.join("\n"));
+
+    append(Path::new(&path), &content)
+}
+
+/// Append to one of GitHub's command files
+fn append(path: &Path, content: &str) -> Result<()> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// One `$GITHUB_ENV`/`$GITHUB_OUTPUT` entry, as a heredoc if multi-line
+fn github_line(key: &str, value: &str) -> String {
+    if value.contains('\n') {
+        format!("{}<<ASDF_EOF\n{}\nASDF_EOF\n", key, value)
+    } else {
+        format!("{}={}\n", key, value)
+    }
+}
+
+/// Escape a value for a double-quoted dotenv string
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tools(entries: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        entries
+            .iter()
+            .map(|(plugin, versions)| {
+                (
+                    plugin.to_string(),
+                    versions.iter().map(|v| v.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cache_key_is_stable() {
+        let sources: BTreeMap<String, String> = [("nodejs", "https://a@abc123"), ("ruby", "https://b@")]
+            .iter()
+            .map(|(p, c)| (p.to_string(), c.to_string()))
+            .collect();
+        let key = cache_key(
+            &tools(&[("nodejs", &["20.11.0"]), ("ruby", &["3.2.2"])]),
+            &sources,
+        );
+
+        assert!(key.starts_with(&format!("asdf-{}-", std::env::consts::OS)));
+        assert_eq!(
+            key,
+            cache_key(
+                &tools(&[("ruby", &["3.2.2"]), ("nodejs", &["20.11.0"])]),
+                &sources
+            )
+        );
+        assert_ne!(
+            key,
+            cache_key(
+                &tools(&[("nodejs", &["20.12.0"]), ("ruby", &["3.2.2"])]),
+                &sources
+            )
+        );
+
+        let mut moved = sources.clone();
+        moved.insert("ruby".to_string(), "https://b@fff000".to_string());
+        assert_ne!(
+            key,
+            cache_key(
+                &tools(&[("nodejs", &["20.11.0"]), ("ruby", &["3.2.2"])]),
+                &moved
+            )
+        );
+    }
+
+    #[test]
+    fn test_plugin_source() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("plugins")).unwrap();
+        std::fs::write(
+            dir.path().join("plugins/nodejs"),
+            "repository = https://github.com/asdf-vm/asdf-nodejs.git\n",
+        )
+        .unwrap();
+        let index = Index::read_dir(dir.path(), "local").unwrap();
+
+        let mut trust = TrustStore::default();
+        trust.trust(
+            "nodejs",
+            asdf_core::TrustEntry {
+                url: "https://github.com/asdf-vm/asdf-nodejs.git".to_string(),
+                commit: Some("abc123".to_string()),
+                require_signature: false,
+            },
+        );
+
+        assert_eq!(
+            plugin_source("nodejs", &index, &trust),
+            "https://github.com/asdf-vm/asdf-nodejs.git@abc123"
+        );
+        assert_eq!(plugin_source("ruby", &index, &trust), "@");
+    }
+
+    #[test]
+    fn test_runtimes_skip_unmanaged_versions() {
+        let runtimes = runtimes(&tools(&[
+            ("nodejs", &["ref:v20.x", "20.11.0"]),
+            ("python", &["system"]),
+            ("ruby", &["path:/opt/ruby"]),
+        ]));
+
+        assert_eq!(runtimes.len(), 1);
+        assert_eq!(runtimes[0].plugin, "nodejs");
+        assert_eq!(runtimes[0].version.to_string(), "20.11.0");
+    }
+
+    #[test]
+    fn test_runtimes_keep_non_semver_versions() {
+        let runtimes = runtimes(&tools(&[
+            ("erlang", &["26.2.5.1"]),
+            ("java", &["temurin-21.0.1+12"]),
+            ("python", &["3.12"]),
+            ("rust", &["stable"]),
+        ]));
+
+        let versions: Vec<String> = runtimes.iter().map(|r| r.version.to_string()).collect();
+        assert_eq!(versions, vec!["26.2.5.1", "temurin-21.0.1+12", "3.12", "stable"]);
+    }
+
+    #[test]
+    fn test_home_var() {
+        assert_eq!(home_var("nodejs"), "NODEJS_HOME");
+        assert_eq!(home_var("github-cli"), "GITHUB_CLI_HOME");
+    }
+
+    #[test]
+    fn test_github_line() {
+        assert_eq!(github_line("A", "b"), "A=b\n");
+        assert_eq!(github_line("A", "b\nc"), "A<<ASDF_EOF\nb\nc\nASDF_EOF\n");
+    }
+}
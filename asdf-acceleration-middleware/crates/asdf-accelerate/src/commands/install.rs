@@ -1,19 +1,24 @@
 //! Runtime installation command
 
+use super::{print_failures, run_batch, Workload};
+use crate::output::Reporter;
 use anyhow::{Context, Result};
+use asdf_cache::{artifact, ArtifactCache, ArtifactKey};
 use asdf_config::AcceleratorConfig;
-use asdf_core::{Runtime, Version};
-use asdf_parallel::{Executor, ExecutorConfig, Strategy};
+use asdf_core::{Plugin, Runtime, Version};
+use asdf_parallel::{ExecutorConfig, Strategy};
 use colored::Colorize;
-use indicatif::{ProgressBar, ProgressStyle};
+use std::sync::Arc;
+use tracing::warn;
 
 pub fn execute(
     config: &AcceleratorConfig,
+    reporter: &Reporter,
     runtimes: Vec<String>,
     parallel: bool,
     jobs: Option<usize>,
-) -> Result<()> {
-    println!("{} Installing runtimes...", "→".cyan());
+) -> Result<i32> {
+    reporter.say(format!("{} Installing runtimes...", "→".cyan()));
 
     // Parse runtime specifications
     let runtime_list: Result<Vec<Runtime>> = runtimes
@@ -23,11 +28,11 @@ pub fn execute(
 
     let runtime_list = runtime_list?;
 
-    println!(
+    reporter.say(format!(
         "{} Installing {} runtimes",
         "✓".green(),
         runtime_list.len()
-    );
+    ));
 
     // Create executor
     let strategy = if parallel {
@@ -43,41 +48,83 @@ pub fn execute(
         max_retries: config.parallel.max_retries,
     };
 
-    let executor = Executor::new(executor_config);
-
-    // Create progress bar
-    let pb = ProgressBar::new(runtime_list.len() as u64);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
-            .unwrap()
-            .progress_chars("#>-"),
-    );
-
-    // Execute installations
-    let results = executor.execute(runtime_list, |runtime| {
-        pb.set_message(format!("Installing {}@{}", runtime.plugin, runtime.version));
-        let result = runtime.install().map_err(asdf_parallel::Error::from);
-        pb.inc(1);
-        result
+    let cache = config.artifacts.active_store().map(|store| {
+        Arc::new(
+            ArtifactCache::new(artifact::open_store(store)).with_upload(config.artifacts.upload),
+        )
     });
+    if let Some(cache) = &cache {
+        reporter.say(format!(
+            "{} Using artifact cache at {}",
+            "→".cyan(),
+            cache.location()
+        ));
+    }
 
-    pb.finish_with_message("Done");
+    // Execute installations
+    let report = run_batch(
+        reporter,
+        executor_config,
+        Workload::Cpu,
+        runtime_list,
+        "Installing",
+        |runtime| format!("{}@{}", runtime.plugin, runtime.version),
+        move |runtime| install_runtime(runtime, cache.as_deref()),
+    )?;
 
     // Report results
-    match results {
-        Ok(installed) => {
-            println!(
-                "\n{} Successfully installed {} runtimes",
-                "✓".green(),
-                installed.len()
-            );
-        }
+    if report.failed == 0 {
+        reporter.say(format!(
+            "\n{} Successfully installed {} runtimes",
+            "✓".green(),
+            report.succeeded
+        ));
+    } else {
+        reporter.say(format!(
+            "\n{} Installed {} runtimes, {} failed",
+            "✗".red(),
+            report.succeeded,
+            report.failed
+        ));
+        print_failures(reporter, &report);
+    }
+
+    reporter.finish(&report)?;
+    Ok(report.status.exit_code())
+}
+
+/// Install a runtime, restoring it from the artifact cache when possible
+///
+/// Cache problems never fail the install; they fall back to a build.
+pub(super) fn install_runtime(runtime: &Runtime, cache: Option<&ArtifactCache>) -> asdf_core::Result<()> {
+    let Some(cache) = cache else {
+        return runtime.install();
+    };
+
+    let key = match Plugin::new(runtime.plugin.as_str()).current_ref() {
+        Ok(commit) => ArtifactKey::for_host(&runtime.plugin, runtime.version.to_string(), commit),
         Err(e) => {
-            println!("\n{} Installation failed: {}", "✗".red(), e);
+            warn!("Not caching {}: unknown plugin commit: {}", runtime.plugin, e);
+            return runtime.install();
         }
+    };
+
+    let install_dir = runtime.install_dir()?;
+    if install_dir.exists() {
+        return runtime.install();
     }
 
+    match cache.restore(&key, &install_dir) {
+        Ok(true) => return runtime.reshim(),
+        Ok(false) => {}
+        Err(e) => warn!("Ignoring cached {}: {}", key.name(), e),
+    }
+
+    runtime.install()?;
+
+    if let Err(e) = cache.save(&key, &install_dir) {
+        warn!("Failed to save {} to the artifact cache: {}", key.name(), e);
+    }
     Ok(())
 }
 
@@ -1,13 +1,27 @@
 //! Plugin list command
 
+use crate::output::{Reporter, Status};
 use anyhow::Result;
 use asdf_config::AcceleratorConfig;
 use asdf_core::Plugin;
 use colored::Colorize;
 
-pub fn execute(_config: &AcceleratorConfig, urls: bool, format: &str) -> Result<()> {
+pub fn execute(
+    _config: &AcceleratorConfig,
+    reporter: &Reporter,
+    urls: bool,
+    format: &str,
+) -> Result<i32> {
     let plugins = Plugin::list()?;
 
+    if !reporter.is_text() {
+        reporter.finish(&serde_json::json!({
+            "status": Status::Ok,
+            "plugins": plugins,
+        }))?;
+        return Ok(Status::Ok.exit_code());
+    }
+
     match format {
         "json" => {
             let json = serde_json::to_string_pretty(&plugins)?;
@@ -29,5 +43,5 @@ pub fn execute(_config: &AcceleratorConfig, urls: bool, format: &str) -> Result<
         }
     }
 
-    Ok(())
+    Ok(Status::Ok.exit_code())
 }
@@ -1,20 +1,32 @@
 //! Command execution
 
 mod cache;
+mod ci;
 mod install;
 mod list;
+mod prune;
+mod search;
 mod sync;
+mod trust;
 mod update;
 
 use crate::cli::{Cli, Commands};
+use crate::output::{BatchReport, Reporter, TaskOutcome};
 use anyhow::Result;
 use asdf_config::{AcceleratorConfig, ConfigLoader};
+use asdf_metrics::jobs::{self, JobLog, JobRecord};
+use asdf_parallel::{AsyncExecutor, Executor, ExecutorConfig};
 use colored::Colorize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+
+/// Execute the CLI command, returning the process exit code
+pub fn execute(cli: Cli) -> Result<i32> {
+    let reporter = Reporter::new(cli.output, cli.command.name());
 
-/// Execute the CLI command
-pub fn execute(cli: Cli) -> Result<()> {
     // Load configuration
-    let config = load_config(cli.config.as_ref())?;
+    let config = load_config(&reporter, cli.config.as_ref())?;
 
     // Execute subcommand
     match cli.command {
@@ -24,32 +36,239 @@ pub fn execute(cli: Cli) -> Result<()> {
             exclude,
             cache_ttl,
             background,
-        } => update::execute(&config, all, plugins, exclude, cache_ttl, background, cli.jobs),
+        } => update::execute(
+            &config, &reporter, all, plugins, exclude, cache_ttl, background, cli.jobs,
+        ),
 
         Commands::Install { runtimes, parallel } => {
-            install::execute(&config, runtimes, parallel, cli.jobs)
+            install::execute(&config, &reporter, runtimes, parallel, cli.jobs)
         }
 
         Commands::Sync {
             exclude,
             only,
             background,
-        } => sync::execute(&config, exclude, only, background, cli.jobs),
+        } => sync::execute(&config, &reporter, exclude, only, background, cli.jobs),
+
+        Commands::Ci {
+            dir,
+            env_file,
+            env_format,
+            key_only,
+        } => ci::execute(&config, &reporter, dir, env_file, env_format, key_only, cli.jobs),
+
+        Commands::List { urls, format } => list::execute(&config, &reporter, urls, &format),
 
-        Commands::List { urls, format } => list::execute(&config, urls, &format),
+        Commands::Search {
+            query,
+            limit,
+            refresh,
+            offline,
+        } => search::execute(&config, &reporter, query, limit, refresh, offline),
+
+        Commands::Trust { action } => trust::execute(&reporter, action),
+
+        Commands::Prune { dry_run, yes } => {
+            prune::execute(&config, &reporter, dry_run, yes, cli.jobs)
+        }
 
-        Commands::Cache { clear, stats } => cache::execute(&config, clear, stats),
+        Commands::Cache { clear, stats } => cache::execute(&config, &reporter, clear, stats),
     }
 }
 
 /// Load configuration from file or defaults
-fn load_config(path: Option<&std::path::PathBuf>) -> Result<AcceleratorConfig> {
+fn load_config(
+    reporter: &Reporter,
+    path: Option<&std::path::PathBuf>,
+) -> Result<AcceleratorConfig> {
     let mut loader = ConfigLoader::new();
 
     if let Some(p) = path {
-        println!("{} Loading configuration from {}", "→".cyan(), p.display());
+        reporter.say(format!(
+            "{} Loading configuration from {}",
+            "→".cyan(),
+            p.display()
+        ));
         Ok(loader.load_file(p)?)
     } else {
         Ok(loader.load_with_defaults(None::<&std::path::Path>)?)
     }
 }
+
+/// Where the tasks of a batch run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Workload {
+    /// CPU-heavy work, such as compiling runtimes, on the rayon pool
+    Cpu,
+
+    /// Subprocess and network waits on the async executor
+    Io,
+}
+
+/// What a successful task has to say for itself
+trait TaskNote {
+    /// Note to attach to the task's outcome
+    fn note(&self) -> Option<String>;
+}
+
+impl TaskNote for () {
+    fn note(&self) -> Option<String> {
+        None
+    }
+}
+
+impl TaskNote for Option<String> {
+    fn note(&self) -> Option<String> {
+        self.clone()
+    }
+}
+
+/// Run `task` over every item, with progress and per-task events
+///
+/// Outcomes are returned in input order. Failed attempts that will be
+/// retried are streamed as `retry` events; final outcomes as `task` events.
+fn run_batch<T, R>(
+    reporter: &Reporter,
+    executor_config: ExecutorConfig,
+    workload: Workload,
+    items: Vec<T>,
+    verb: &str,
+    name: impl Fn(&T) -> String + Send + Sync + 'static,
+    task: impl Fn(&T) -> asdf_core::Result<R> + Send + Sync + 'static,
+) -> Result<BatchReport>
+where
+    T: Clone + Send + Sync + std::fmt::Debug + 'static,
+    R: TaskNote,
+{
+    let started = Instant::now();
+    let started_at = SystemTime::now();
+    let max_attempts = executor_config.max_retries + 1;
+
+    reporter.event("start", serde_json::json!({ "total": items.len() }));
+    let pb = reporter.progress(items.len());
+    let attempts: Arc<Mutex<HashMap<usize, usize>>> = Arc::default();
+    let name = Arc::new(name);
+
+    let run = {
+        let reporter = *reporter;
+        let pb = pb.clone();
+        let name = Arc::clone(&name);
+        let verb = verb.to_string();
+
+        move |(index, item): &(usize, T)| {
+            let label = name(item);
+            pb.set_message(format!("{} {}", verb, label));
+
+            let attempt_started = Instant::now();
+            let attempt = {
+                let mut attempts = attempts.lock().unwrap();
+                let count = attempts.entry(*index).or_insert(0);
+                *count += 1;
+                *count
+            };
+
+            match task(item) {
+                Ok(result) => {
+                    let outcome = TaskOutcome::succeeded(label, attempt_started.elapsed())
+                        .with_note(result.note());
+                    reporter.task(&outcome);
+                    pb.inc(1);
+                    Ok((*index, outcome))
+                }
+                Err(e) => {
+                    if attempt < max_attempts {
+                        reporter.event(
+                            "retry",
+                            serde_json::json!({
+                                "name": label,
+                                "attempt": attempt,
+                                "error": e.to_string(),
+                            }),
+                        );
+                    } else {
+                        reporter.task(&TaskOutcome::failed(label, &e));
+                        pb.inc(1);
+                    }
+                    Err(asdf_parallel::Error::Other(e.to_string()))
+                }
+            }
+        }
+    };
+
+    let indexed: Vec<(usize, T)> = items.into_iter().enumerate().collect();
+    let span = tracing::info_span!("batch", verb, total = indexed.len(), workload = ?workload);
+    let _span = span.enter();
+    let (successes, failures) = match workload {
+        Workload::Cpu => Executor::new(executor_config).execute_all(indexed, run),
+        Workload::Io => tokio::runtime::Runtime::new()?
+            .block_on(AsyncExecutor::new(executor_config).execute_blocking_all(indexed, run)),
+    };
+
+    pb.finish_with_message("Done");
+
+    let mut outcomes: Vec<(usize, TaskOutcome)> = successes;
+    outcomes.extend(failures.into_iter().map(|((index, item), e)| {
+        let error = match e {
+            asdf_parallel::Error::TaskFailed { error, .. } => error,
+            other => other.to_string(),
+        };
+        (index, TaskOutcome::failed(name(&item), error))
+    }));
+    outcomes.sort_by_key(|(index, _)| *index);
+
+    let report = BatchReport::new(
+        outcomes.into_iter().map(|(_, outcome)| outcome).collect(),
+        started.elapsed(),
+    );
+    record_job(reporter.command(), started_at, &report);
+
+    Ok(report)
+}
+
+/// Append a finished batch to the job log read by `asdf-monitor serve`
+///
+/// The log is informational, so failing to write it only logs a warning.
+fn record_job(command: &str, started_at: SystemTime, report: &BatchReport) {
+    let record = JobRecord {
+        command: command.to_string(),
+        started_at: JobRecord::timestamp(started_at),
+        duration_ms: report.duration_ms,
+        status: report.status.as_str().to_string(),
+        succeeded: report.succeeded,
+        failed: report.failed,
+        failed_tasks: report
+            .tasks
+            .iter()
+            .filter(|t| !t.ok)
+            .map(|t| t.name.clone())
+            .collect(),
+    };
+
+    let result = asdf_core::data_dir()
+        .map_err(anyhow::Error::from)
+        .and_then(|dir| Ok(JobLog::open(dir.join(jobs::FILE_NAME)).append(&record)?));
+    if let Err(e) = result {
+        tracing::warn!("Could not record job: {}", e);
+    }
+}
+
+/// Print notes on tasks that succeeded in text mode
+fn print_notes(reporter: &Reporter, report: &BatchReport) {
+    for task in report.tasks.iter().filter(|t| t.ok) {
+        if let Some(note) = &task.note {
+            reporter.say(format!("  {} {}: {}", "!".yellow(), task.name, note));
+        }
+    }
+}
+
+/// Print failed tasks in text mode
+fn print_failures(reporter: &Reporter, report: &BatchReport) {
+    for task in report.tasks.iter().filter(|t| !t.ok) {
+        reporter.say(format!(
+            "  {} {}: {}",
+            "✗".red(),
+            task.name,
+            task.error.as_deref().unwrap_or("unknown error")
+        ));
+    }
+}
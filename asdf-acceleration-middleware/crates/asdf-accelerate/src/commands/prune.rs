@@ -0,0 +1,218 @@
+//! Prune command
+//!
+//! Finds plugins without installed runtimes, installs whose plugin is gone
+//! and downloads of versions that are not installed, reports the space they
+//! take and removes them in parallel once confirmed.
+
+use super::{print_failures, run_batch, Workload};
+use crate::output::{BatchReport, Reporter, Status, TaskOutcome};
+use anyhow::{bail, Result};
+use asdf_config::AcceleratorConfig;
+use asdf_core::prune::{self, Orphan};
+use asdf_core::tool_versions;
+use asdf_parallel::{ExecutorConfig, Strategy};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Result document of `prune`
+#[derive(Serialize)]
+struct PruneReport {
+    #[serde(flatten)]
+    batch: BatchReport,
+    dry_run: bool,
+    orphans: Vec<Orphan>,
+    reclaimable_bytes: u64,
+    reclaimed_bytes: u64,
+}
+
+pub fn execute(
+    config: &AcceleratorConfig,
+    reporter: &Reporter,
+    dry_run: bool,
+    yes: bool,
+    jobs: Option<usize>,
+) -> Result<i32> {
+    reporter.say(format!("{} Scanning for orphans...", "→".cyan()));
+
+    // Tools the current project asks for stay, even before they're installed
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let keep: HashSet<String> =
+        tool_versions::effective(&std::env::current_dir()?, home.as_deref())?
+            .into_keys()
+            .collect();
+    let orphans = prune::scan(&asdf_core::data_dir()?, &keep)?;
+    let reclaimable_bytes = orphans.iter().map(|o| o.bytes).sum();
+
+    let mut report = PruneReport {
+        batch: BatchReport::new(Vec::new(), Duration::ZERO),
+        dry_run,
+        orphans,
+        reclaimable_bytes,
+        reclaimed_bytes: 0,
+    };
+
+    if report.orphans.is_empty() {
+        reporter.say(format!("{} Nothing to prune", "✓".green()));
+        reporter.finish(&report)?;
+        return Ok(Status::Ok.exit_code());
+    }
+
+    for orphan in &report.orphans {
+        reporter.say(format!(
+            "  {:<8} {:<24} {:>10}  {}",
+            orphan.kind.to_string().yellow(),
+            orphan.name,
+            format_bytes(orphan.bytes),
+            orphan.path.display()
+        ));
+    }
+    reporter.say(format!(
+        "{} {} orphans, {} reclaimable",
+        "!".yellow(),
+        report.orphans.len(),
+        format_bytes(reclaimable_bytes)
+    ));
+
+    if dry_run {
+        reporter.say(format!("{} Dry run; nothing removed", "→".cyan()));
+        reporter.finish(&report)?;
+        return Ok(Status::Ok.exit_code());
+    }
+
+    if !yes && !confirm(report.orphans.len())? {
+        reporter.say(format!("{} Nothing removed", "→".cyan()));
+        reporter.finish(&report)?;
+        return Ok(Status::Ok.exit_code());
+    }
+
+    let executor_config = ExecutorConfig {
+        strategy: jobs
+            .map(Strategy::Fixed)
+            .unwrap_or(config.parallel.strategy),
+        fail_fast: false,
+        max_retries: 0,
+    };
+
+    report.batch = run_batch(
+        reporter,
+        executor_config,
+        Workload::Io,
+        report.orphans.clone(),
+        "Removing",
+        task_name,
+        |orphan| orphan.remove(),
+    )?;
+    report.reclaimed_bytes = reclaimed_bytes(&report.orphans, &report.batch.tasks);
+
+    if report.batch.failed == 0 {
+        reporter.say(format!(
+            "\n{} Removed {} orphans, reclaimed {}",
+            "✓".green(),
+            report.batch.succeeded,
+            format_bytes(report.reclaimed_bytes)
+        ));
+    } else {
+        reporter.say(format!(
+            "\n{} Removed {} orphans, {} failed",
+            "✗".red(),
+            report.batch.succeeded,
+            report.batch.failed
+        ));
+        print_failures(reporter, &report.batch);
+    }
+
+    reporter.finish(&report)?;
+    Ok(report.batch.status.exit_code())
+}
+
+/// Task name of an orphan's removal
+fn task_name(orphan: &Orphan) -> String {
+    format!("{} {}", orphan.kind, orphan.name)
+}
+
+/// Space freed by the removals that succeeded, matched up by task name
+fn reclaimed_bytes(orphans: &[Orphan], tasks: &[TaskOutcome]) -> u64 {
+    let removed: HashSet<&str> = tasks
+        .iter()
+        .filter(|task| task.ok)
+        .map(|task| task.name.as_str())
+        .collect();
+    orphans
+        .iter()
+        .filter(|orphan| removed.contains(task_name(orphan).as_str()))
+        .map(|orphan| orphan.bytes)
+        .sum()
+}
+
+/// Ask on the terminal; without one, removal needs `--yes`
+fn confirm(count: usize) -> Result<bool> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        bail!(
+            "Refusing to remove {} orphans without confirmation; pass --yes",
+            count
+        );
+    }
+
+    // On stderr, so stdout stays parseable
+    eprint!("Remove {} orphans? [y/N] ", count);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    stdin.lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Human-readable size, in binary units
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asdf_core::prune::OrphanKind;
+
+    #[test]
+    fn test_reclaimed_bytes_matches_tasks_by_name() {
+        let orphan = |kind, name: &str, bytes| Orphan {
+            kind,
+            name: name.to_string(),
+            path: PathBuf::from(name),
+            bytes,
+        };
+        let orphans = [
+            orphan(OrphanKind::Plugin, "ruby", 1),
+            orphan(OrphanKind::Install, "golang", 10),
+            orphan(OrphanKind::Download, "nodejs/18.0.0", 100),
+        ];
+
+        // Out of order, with one outcome missing
+        let tasks = [
+            TaskOutcome::succeeded("download nodejs/18.0.0", Duration::ZERO),
+            TaskOutcome::failed("plugin ruby", "permission denied"),
+        ];
+        assert_eq!(reclaimed_bytes(&orphans, &tasks), 100);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+}
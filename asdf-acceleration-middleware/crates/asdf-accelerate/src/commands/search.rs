@@ -0,0 +1,97 @@
+//! Plugin index search command
+
+use crate::output::{Reporter, Status};
+use anyhow::Result;
+use asdf_config::AcceleratorConfig;
+use asdf_core::Plugin;
+use colored::Colorize;
+use std::collections::HashSet;
+
+pub fn execute(
+    config: &AcceleratorConfig,
+    reporter: &Reporter,
+    query: Option<String>,
+    limit: usize,
+    refresh: bool,
+    offline: bool,
+) -> Result<i32> {
+    let registry = config.registry.registry()?;
+
+    let failures = if offline {
+        Vec::new()
+    } else {
+        registry.refresh(refresh)
+    };
+    for (source, error) in &failures {
+        reporter.say(format!(
+            "{} Using cached index for {}: {}",
+            "!".yellow(),
+            source,
+            error
+        ));
+    }
+
+    let index = registry.index()?;
+    let query = query.unwrap_or_default();
+    let hits = index.search(&query);
+
+    // Installed plugins are marked, but a broken asdf should not stop a search
+    let installed: HashSet<String> = Plugin::list()
+        .map(|plugins| plugins.into_iter().map(|p| p.name).collect())
+        .unwrap_or_default();
+
+    if hits.is_empty() {
+        reporter.say(format!("{} No plugins match '{}'", "!".yellow(), query));
+    }
+    for hit in hits.iter().take(limit) {
+        let marker = if installed.contains(&hit.entry.name) {
+            " (installed)".green().to_string()
+        } else {
+            String::new()
+        };
+        reporter.say(format!(
+            "  {}{} {}",
+            hit.entry.name.bright_white(),
+            marker,
+            hit.entry.repository.bright_black()
+        ));
+    }
+    if hits.len() > limit {
+        reporter.say(format!(
+            "{} {} more; narrow the query or raise --limit",
+            "→".cyan(),
+            hits.len() - limit
+        ));
+    }
+
+    let results: Vec<_> = hits
+        .iter()
+        .take(limit)
+        .map(|hit| {
+            serde_json::json!({
+                "name": hit.entry.name,
+                "repository": hit.entry.repository,
+                "source": hit.entry.source,
+                "score": hit.score,
+                "installed": installed.contains(&hit.entry.name),
+            })
+        })
+        .collect();
+
+    reporter.finish(&serde_json::json!({
+        "status": Status::Ok,
+        "query": query,
+        "indexed": index.len(),
+        "matches": hits.len(),
+        "results": results,
+        "stale_sources": failures
+            .iter()
+            .map(|(source, error)| serde_json::json!({
+                "source": source.to_string(),
+                "error": error.to_string(),
+            }))
+            .collect::<Vec<_>>(),
+    }))?;
+
+    Ok(Status::Ok.exit_code())
+}
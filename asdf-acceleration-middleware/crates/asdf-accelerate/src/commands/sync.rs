@@ -2,24 +2,25 @@
 //!
 //! Synchronizes all plugins by fetching latest updates from their repositories.
 
+use super::{print_failures, print_notes, run_batch, trust, Workload};
+use crate::output::{BatchReport, Reporter, Status};
 use anyhow::Result;
 use asdf_config::AcceleratorConfig;
-use asdf_core::Plugin;
+use asdf_core::{Plugin, TrustStore};
 use asdf_metrics::MetricsCollector;
-use asdf_parallel::{Executor, ExecutorConfig, Strategy};
+use asdf_parallel::{ExecutorConfig, Strategy};
 use colored::Colorize;
-use indicatif::{ProgressBar, ProgressStyle};
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::time::Duration;
 
 pub fn execute(
     config: &AcceleratorConfig,
+    reporter: &Reporter,
     exclude: Vec<String>,
     only: Vec<String>,
     background: bool,
     jobs: Option<usize>,
-) -> Result<()> {
-    println!("{} Syncing plugins...", "→".cyan());
+) -> Result<i32> {
+    reporter.say(format!("{} Syncing plugins...", "→".cyan()));
 
     // Get list of installed plugins
     let mut plugins = Plugin::list()?;
@@ -33,19 +34,20 @@ pub fn execute(
     plugins.retain(|p| !exclude.contains(&p.name));
 
     if plugins.is_empty() {
-        println!("{} No plugins to sync", "!".yellow());
-        return Ok(());
+        reporter.say(format!("{} No plugins to sync", "!".yellow()));
+        reporter.finish(&BatchReport::new(Vec::new(), Duration::ZERO))?;
+        return Ok(Status::Ok.exit_code());
     }
 
-    println!(
+    reporter.say(format!(
         "{} Found {} plugins to sync",
         "✓".green(),
         plugins.len()
-    );
+    ));
 
     if background {
-        println!("{} Running in background mode", "→".cyan());
-        return execute_background(config, plugins, jobs);
+        reporter.say(format!("{} Running in background mode", "→".cyan()));
+        return execute_background(config, reporter, plugins, jobs);
     }
 
     // Create executor with configured strategy
@@ -59,105 +61,75 @@ pub fn execute(
         max_retries: config.parallel.max_retries,
     };
 
-    let executor = Executor::new(executor_config);
-
-    // Create progress bar
-    let pb = ProgressBar::new(plugins.len() as u64);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
-            .unwrap()
-            .progress_chars("#>-"),
-    );
+    // Trusted plugins only move within the trust store's rules
+    let store = TrustStore::load_default()?;
+    let require_trust = config.plugins.require_trust;
 
     // Initialize metrics
     let mut metrics = MetricsCollector::new();
     metrics.start();
 
-    // Track sync results
-    let success_count = Arc::new(AtomicUsize::new(0));
-    let failure_count = Arc::new(AtomicUsize::new(0));
-    let success_counter = success_count.clone();
-    let failure_counter = failure_count.clone();
-
-    // Execute sync operations in parallel
-    let results = executor.execute(plugins, |plugin| {
-        pb.set_message(format!("Syncing {}", plugin.name));
-
-        // Sync operation = update plugin to latest
-        let result = plugin.update().map_err(asdf_parallel::Error::from);
-
-        match &result {
-            Ok(_) => {
-                success_counter.fetch_add(1, Ordering::SeqCst);
-            }
-            Err(_) => {
-                failure_counter.fetch_add(1, Ordering::SeqCst);
-            }
-        }
-
-        pb.inc(1);
-        result
-    });
-
-    pb.finish_with_message("Done");
+    // Sync operation = update plugin to latest
+    let report = run_batch(
+        reporter,
+        executor_config,
+        Workload::Io,
+        plugins,
+        "Syncing",
+        |plugin| plugin.name.clone(),
+        move |plugin| trust::update(plugin, &store, require_trust),
+    )?;
 
     // Record metrics
-    let successes = success_count.load(Ordering::SeqCst);
-    let failures = failure_count.load(Ordering::SeqCst);
-
-    for _ in 0..successes {
+    for _ in 0..report.succeeded {
         metrics.record_success();
     }
-    for _ in 0..failures {
+    for _ in 0..report.failed {
         metrics.record_failure();
     }
 
     // Report results
-    println!();
-    match results {
-        Ok(_) => {
-            println!(
-                "{} Sync complete: {} succeeded, {} failed",
-                "✓".green(),
-                successes.to_string().green(),
-                failures.to_string().red()
-            );
-        }
-        Err(e) => {
-            println!(
-                "{} Sync completed with errors: {}",
-                "!".yellow(),
-                e
-            );
-            println!(
-                "  {} succeeded, {} failed",
-                successes.to_string().green(),
-                failures.to_string().red()
-            );
-        }
+    reporter.say("");
+    if report.failed == 0 {
+        reporter.say(format!(
+            "{} Sync complete: {} succeeded, {} failed",
+            "✓".green(),
+            report.succeeded.to_string().green(),
+            report.failed.to_string().red()
+        ));
+    } else {
+        reporter.say(format!(
+            "{} Sync completed with errors: {} succeeded, {} failed",
+            "!".yellow(),
+            report.succeeded.to_string().green(),
+            report.failed.to_string().red()
+        ));
+        print_failures(reporter, &report);
     }
+    print_notes(reporter, &report);
 
     // Show timing info
     let timing = metrics.metrics();
     if timing.operations_total > 0 {
-        println!(
+        reporter.say(format!(
             "{} Total time: {:.2}s (avg: {:.2}s per plugin)",
             "→".cyan(),
             timing.total_duration.as_secs_f64(),
             timing.average_duration.as_secs_f64()
-        );
+        ));
     }
 
-    Ok(())
+    reporter.finish(&report)?;
+    Ok(report.status.exit_code())
 }
 
 /// Execute sync in background mode (daemonized)
 fn execute_background(
     config: &AcceleratorConfig,
+    reporter: &Reporter,
     plugins: Vec<Plugin>,
     jobs: Option<usize>,
-) -> Result<()> {
+) -> Result<i32> {
     use std::process::Command;
     use std::env;
 
@@ -188,34 +160,83 @@ fn execute_background(
 
     match child {
         Ok(c) => {
-            println!(
+            reporter.say(format!(
                 "{} Background sync started (PID: {})",
                 "✓".green(),
                 c.id()
-            );
-            println!("{} Plugins will be synced in the background", "→".cyan());
+            ));
+            reporter.say(format!("{} Plugins will be synced in the background", "→".cyan()));
+            reporter.finish(&serde_json::json!({
+                "status": Status::Ok,
+                "background": true,
+                "pid": c.id(),
+                "plugins": plugins.iter().map(|p| &p.name).collect::<Vec<_>>(),
+            }))?;
         }
         Err(e) => {
-            println!(
+            reporter.say(format!(
                 "{} Failed to start background sync: {}",
                 "✗".red(),
                 e
-            );
+            ));
             // Fall back to foreground execution
-            println!("{} Falling back to foreground sync...", "→".yellow());
-            return execute(config, vec![], plugins.iter().map(|p| p.name.clone()).collect(), false, jobs);
+            reporter.say(format!("{} Falling back to foreground sync...", "→".yellow()));
+            return execute(
+                config,
+                reporter,
+                vec![],
+                plugins.iter().map(|p| p.name.clone()).collect(),
+                false,
+                jobs,
+            );
         }
     }
 
-    Ok(())
+    Ok(Status::Ok.exit_code())
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::output::{OutputMode, EXIT_PARTIAL};
+    use asdf_core::testing::{FakeAsdf, Fault};
+
     #[test]
     fn test_execute_empty_plugins() {
-        // Test would require mocking Plugin::list()
-        // This is a placeholder for integration tests
-        assert!(true);
+        let fake = FakeAsdf::new();
+        let _guard = fake.activate();
+
+        let reporter = Reporter::new(OutputMode::Json, "sync");
+        let code = execute(&AcceleratorConfig::default(), &reporter, vec![], vec![], false, None)
+            .unwrap();
+
+        assert_eq!(code, Status::Ok.exit_code());
+        assert!(fake.calls().iter().all(|c| !c.starts_with("plugin update")));
+    }
+
+    #[test]
+    fn test_execute_partial_failure() {
+        let fake = FakeAsdf::new()
+            .plugin("nodejs", &[])
+            .plugin("python", &[])
+            .plugin("ruby", &[])
+            .fault("plugin-update-ruby", Fault::Fail("remote hung up".into()));
+        let _guard = fake.activate();
+
+        let reporter = Reporter::new(OutputMode::Json, "sync");
+        let code = execute(
+            &AcceleratorConfig::default(),
+            &reporter,
+            vec!["python".to_string()],
+            vec![],
+            false,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(code, EXIT_PARTIAL);
+        assert_eq!(fake.call_count("plugin update nodejs"), 1);
+        assert_eq!(fake.call_count("plugin update ruby"), 1);
+        assert_eq!(fake.call_count("plugin update python"), 0);
     }
 }
@@ -0,0 +1,228 @@
+//! Plugin trust command
+//!
+//! Records which plugin repositories are trusted and at which commit, and
+//! shows the commits and diff an update would bring in before accepting it.
+
+use crate::cli::TrustAction;
+use crate::output::{Reporter, Status};
+use anyhow::{Context, Result};
+use asdf_core::trust::{self, Review, Signature, TrustedUpdate};
+use asdf_core::{Plugin, TrustEntry, TrustStore};
+use colored::Colorize;
+
+pub fn execute(reporter: &Reporter, action: TrustAction) -> Result<i32> {
+    let path = TrustStore::default_path()?;
+    let mut store = TrustStore::load(&path)?;
+
+    match action {
+        TrustAction::List => {
+            if store.plugins.is_empty() {
+                reporter.say(format!("{} No trusted plugins", "!".yellow()));
+            }
+            for (name, entry) in &store.plugins {
+                let pin = match &entry.commit {
+                    Some(commit) => trust::short(commit).to_string(),
+                    None => "unpinned".to_string(),
+                };
+                let signed = if entry.require_signature {
+                    " (signed)"
+                } else {
+                    ""
+                };
+                reporter.say(format!(
+                    "  {} {} {}{}",
+                    name.green(),
+                    pin.bright_black(),
+                    entry.url,
+                    signed
+                ));
+            }
+
+            reporter.finish(&serde_json::json!({
+                "status": Status::Ok,
+                "allowed_signers": store.allowed_signers,
+                "plugins": store.plugins,
+            }))?;
+        }
+
+        TrustAction::Add {
+            plugin,
+            require_signature,
+            no_pin,
+        } => {
+            let mut entry = TrustEntry::for_plugin(&Plugin::new(&plugin), !no_pin)?;
+            entry.require_signature = require_signature;
+
+            reporter.say(format!(
+                "{} Trusting {} from {}",
+                "✓".green(),
+                plugin,
+                entry.url
+            ));
+            if let Some(commit) = &entry.commit {
+                reporter.say(format!("  Pinned to {}", trust::short(commit)));
+            }
+
+            store.trust(plugin.as_str(), entry.clone());
+            store.save(&path)?;
+            reporter.finish(&serde_json::json!({
+                "status": Status::Ok,
+                "plugin": plugin,
+                "entry": entry,
+            }))?;
+        }
+
+        TrustAction::Review { plugin, accept } => {
+            return review(reporter, &path, store, &plugin, accept);
+        }
+
+        TrustAction::Remove { plugin } => {
+            let removed = store.remove(&plugin).is_some();
+            if removed {
+                store.save(&path)?;
+                reporter.say(format!("{} No longer trusting {}", "✓".green(), plugin));
+            } else {
+                reporter.say(format!("{} {} was not trusted", "!".yellow(), plugin));
+            }
+
+            reporter.finish(&serde_json::json!({
+                "status": Status::Ok,
+                "plugin": plugin,
+                "removed": removed,
+            }))?;
+        }
+
+        TrustAction::Signers { file } => {
+            let file = file
+                .canonicalize()
+                .with_context(|| format!("cannot read {}", file.display()))?;
+            reporter.say(format!(
+                "{} Verifying SSH signatures with {}",
+                "✓".green(),
+                file.display()
+            ));
+
+            store.allowed_signers = Some(file.clone());
+            store.save(&path)?;
+            reporter.finish(&serde_json::json!({
+                "status": Status::Ok,
+                "allowed_signers": file,
+            }))?;
+        }
+    }
+
+    Ok(Status::Ok.exit_code())
+}
+
+/// Show a plugin's incoming changes, and move its pin if accepted
+fn review(
+    reporter: &Reporter,
+    path: &std::path::Path,
+    mut store: TrustStore,
+    name: &str,
+    accept: bool,
+) -> Result<i32> {
+    let plugin = Plugin::new(name);
+    let review = Review::fetch(&plugin, &store)?;
+    let diff = review.diff()?;
+
+    reporter.say(format!("{} Reviewing {}", "→".cyan(), name));
+    match &review.trusted_url {
+        Some(_) => reporter.say(format!("  Remote:    {}", review.remote_url)),
+        None => reporter.say(format!(
+            "  {} Remote:    {} (not trusted)",
+            "!".yellow(),
+            review.remote_url
+        )),
+    }
+    reporter.say(format!("  Reviewed:  {}", trust::short(review.base())));
+    reporter.say(format!("  Incoming:  {}", trust::short(&review.incoming)));
+    reporter.say(match &review.signature {
+        Signature::Verified { signer, via } => {
+            format!("  {} Signed ({}): {}", "✓".green(), via, signer)
+        }
+        Signature::Unsigned => format!("  {} Unsigned", "!".yellow()),
+        Signature::Invalid { reason } => format!("  {} Bad signature: {}", "✗".red(), reason),
+    });
+
+    if review.has_changes() {
+        reporter.say(format!(
+            "\n{} {} new commits:",
+            "→".cyan(),
+            review.commits.len()
+        ));
+        for commit in &review.commits {
+            reporter.say(format!("  {}", commit));
+        }
+        reporter.say("");
+        reporter.say(&diff);
+    } else {
+        reporter.say(format!("\n{} No new commits", "✓".green()));
+    }
+
+    let mut accepted = false;
+    if accept {
+        let Some(entry) = store.get(name).cloned() else {
+            anyhow::bail!(
+                "{} is not trusted; run `asdf-accelerate trust add {}` first",
+                name,
+                name
+            );
+        };
+        if let Some(reason) = review.rejection(&entry) {
+            anyhow::bail!("Refusing to accept {}: {}", name, reason);
+        }
+
+        // Move the checkout first, so the pin never names a commit it is not on
+        review.accept(&plugin)?;
+        if entry.commit.is_some() {
+            store.trust(
+                name,
+                TrustEntry {
+                    commit: Some(review.incoming.clone()),
+                    ..entry
+                },
+            );
+            store.save(path)?;
+        }
+        accepted = true;
+
+        reporter.say(format!(
+            "{} Accepted {} at {}",
+            "✓".green(),
+            name,
+            trust::short(&review.incoming)
+        ));
+    }
+
+    reporter.finish(&serde_json::json!({
+        "status": Status::Ok,
+        "review": review,
+        "diff": diff,
+        "accepted": accepted,
+    }))?;
+
+    Ok(Status::Ok.exit_code())
+}
+
+/// Update a plugin, within the trust store's rules when it is trusted
+///
+/// A pinned plugin with unreviewed upstream commits is not a failure; it
+/// comes back as a note pointing at `trust review`.
+pub(super) fn update(
+    plugin: &Plugin,
+    store: &TrustStore,
+    require_trust: bool,
+) -> asdf_core::Result<Option<String>> {
+    if store.get(&plugin.name).is_none() && !require_trust {
+        return plugin.update().map(|()| None);
+    }
+
+    Ok(match plugin.update_trusted(store)? {
+        TrustedUpdate::Current => None,
+        TrustedUpdate::AwaitingReview(commits) => Some(format!(
+            "held at its pin, {} unreviewed commits; run `asdf-accelerate trust review {}`",
+            commits, plugin.name
+        )),
+    })
+}
@@ -1,23 +1,25 @@
 //! Plugin update command
 
+use super::{print_failures, print_notes, run_batch, trust, Workload};
+use crate::output::Reporter;
 use anyhow::Result;
 use asdf_config::AcceleratorConfig;
-use asdf_core::Plugin;
-use asdf_metrics::MetricsCollector;
-use asdf_parallel::{Executor, ExecutorConfig, Strategy};
+use asdf_core::{Plugin, TrustStore};
+use asdf_parallel::{ExecutorConfig, Strategy};
 use colored::Colorize;
-use indicatif::{ProgressBar, ProgressStyle};
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute(
     config: &AcceleratorConfig,
+    reporter: &Reporter,
     all: bool,
     plugins: Vec<String>,
     exclude: Vec<String>,
-    cache_ttl: u64,
-    background: bool,
+    _cache_ttl: u64,
+    _background: bool,
     jobs: Option<usize>,
-) -> Result<()> {
-    println!("{} Updating asdf plugins...", "→".cyan());
+) -> Result<i32> {
+    reporter.say(format!("{} Updating asdf plugins...", "→".cyan()));
 
     // Get list of plugins
     let mut plugin_list = if all {
@@ -25,21 +27,20 @@ pub fn execute(
     } else if !plugins.is_empty() {
         plugins
             .into_iter()
-            .map(|name| Plugin::new(name))
+            .map(Plugin::new)
             .collect()
     } else {
-        println!("{} Please specify --all or plugin names", "✗".red());
-        return Ok(());
+        anyhow::bail!("Please specify --all or plugin names");
     };
 
     // Filter excluded plugins
     plugin_list.retain(|p| !exclude.contains(&p.name));
 
-    println!(
+    reporter.say(format!(
         "{} Found {} plugins to update",
         "✓".green(),
         plugin_list.len()
-    );
+    ));
 
     // Create executor
     let strategy = jobs
@@ -52,48 +53,39 @@ pub fn execute(
         max_retries: config.parallel.max_retries,
     };
 
-    let executor = Executor::new(executor_config);
-
-    // Create progress bar
-    let pb = ProgressBar::new(plugin_list.len() as u64);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
-            .unwrap()
-            .progress_chars("#>-"),
-    );
-
-    // Initialize metrics
-    let mut metrics = MetricsCollector::new();
-    metrics.start();
+    // Trusted plugins only move within the trust store's rules
+    let store = TrustStore::load_default()?;
+    let require_trust = config.plugins.require_trust;
 
     // Execute updates
-    let results = executor.execute(plugin_list, |plugin| {
-        pb.set_message(format!("Updating {}", plugin.name));
-        let result = plugin.update().map_err(asdf_parallel::Error::from);
-        pb.inc(1);
-        result
-    });
-
-    pb.finish_with_message("Done");
+    let report = run_batch(
+        reporter,
+        executor_config,
+        Workload::Io,
+        plugin_list,
+        "Updating",
+        |plugin| plugin.name.clone(),
+        move |plugin| trust::update(plugin, &store, require_trust),
+    )?;
 
     // Report results
-    match results {
-        Ok(updated) => {
-            for _ in updated {
-                metrics.record_success();
-            }
-            println!(
-                "\n{} Successfully updated {} plugins",
-                "✓".green(),
-                metrics.metrics().operations_succeeded
-            );
-        }
-        Err(e) => {
-            metrics.record_failure();
-            println!("\n{} Update failed: {}", "✗".red(), e);
-        }
+    if report.failed == 0 {
+        reporter.say(format!(
+            "\n{} Successfully updated {} plugins",
+            "✓".green(),
+            report.succeeded
+        ));
+    } else {
+        reporter.say(format!(
+            "\n{} Updated {} plugins, {} failed",
+            "!".yellow(),
+            report.succeeded,
+            report.failed
+        ));
+        print_failures(reporter, &report);
     }
+    print_notes(reporter, &report);
 
-    Ok(())
+    reporter.finish(&report)?;
+    Ok(report.status.exit_code())
 }
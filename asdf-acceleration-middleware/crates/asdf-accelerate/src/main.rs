@@ -2,26 +2,54 @@
 
 mod cli;
 mod commands;
+mod output;
 
 use anyhow::Result;
+use asdf_metrics::TraceRecorder;
 use clap::Parser;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use output::{OutputMode, Reporter, EXIT_FAILURE};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
 fn main() -> Result<()> {
-    // Initialize tracing
+    // Parse command-line arguments
+    let cli = cli::Cli::parse();
+    let mode = cli.output;
+    let command = cli.command.name();
+
+    // Initialize tracing (on stderr, so stdout stays parseable); with
+    // --trace-file, spans are also recorded for export
+    let trace = cli
+        .trace_file
+        .clone()
+        .map(|path| (path, cli.trace_format, TraceRecorder::new("asdf-accelerate")));
     tracing_subscriber::registry()
         .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "asdf_accelerate=info".into()),
+            tracing_subscriber::fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_filter(
+                    tracing_subscriber::EnvFilter::try_from_default_env()
+                        .unwrap_or_else(|_| "asdf_accelerate=info".into()),
+                ),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(trace.as_ref().map(|(_, _, recorder)| recorder.layer()))
         .init();
 
-    // Parse command-line arguments
-    let cli = cli::Cli::parse();
-
     // Execute command
-    commands::execute(cli)?;
+    let result = tracing::info_span!("asdf-accelerate", command)
+        .in_scope(|| commands::execute(cli));
+
+    if let Some((path, format, recorder)) = trace {
+        if let Err(e) = recorder.write(&path, format) {
+            tracing::warn!("Could not write trace file: {}", e);
+        }
+    }
 
-    Ok(())
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(e) if mode != OutputMode::Text => {
+            Reporter::new(mode, command).fail(&e);
+            std::process::exit(EXIT_FAILURE);
+        }
+        Err(e) => Err(e),
+    }
 }
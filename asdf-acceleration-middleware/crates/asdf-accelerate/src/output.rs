@@ -0,0 +1,333 @@
+//! Machine-readable output
+//!
+//! With `--output json` every command prints exactly one result document on
+//! stdout when it finishes. With `--output ndjson` it streams one event per
+//! line while running and ends with a `result` event whose `result` field
+//! carries the same document. Progress bars and coloured text are disabled
+//! in both modes.
+//!
+//! See `docs/OUTPUT.md` for the schemas.
+
+use clap::ValueEnum;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Version of the result and event schemas
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Exit code when everything succeeded
+pub const EXIT_OK: i32 = 0;
+
+/// Exit code when the command failed outright
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Exit code when some, but not all, tasks failed
+pub const EXIT_PARTIAL: i32 = 3;
+
+/// Output mode selected with `--output`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputMode {
+    /// Human-readable text with progress bars
+    #[default]
+    Text,
+
+    /// A single JSON result document
+    Json,
+
+    /// Newline-delimited JSON events
+    Ndjson,
+}
+
+/// Overall outcome of a command
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// Every task succeeded
+    Ok,
+
+    /// Some tasks failed
+    Partial,
+
+    /// Nothing succeeded
+    Failed,
+}
+
+impl Status {
+    /// Derive the status from success and failure counts
+    pub fn from_counts(succeeded: usize, failed: usize) -> Self {
+        match (succeeded, failed) {
+            (_, 0) => Status::Ok,
+            (0, _) => Status::Failed,
+            _ => Status::Partial,
+        }
+    }
+
+    /// Name of the status, as serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Partial => "partial",
+            Status::Failed => "failed",
+        }
+    }
+
+    /// Process exit code for this status
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Status::Ok => EXIT_OK,
+            Status::Partial => EXIT_PARTIAL,
+            Status::Failed => EXIT_FAILURE,
+        }
+    }
+}
+
+/// Result of a single task (plugin update, runtime install, ...)
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct TaskOutcome {
+    /// Plugin name or `plugin@version`
+    pub name: String,
+
+    /// Whether the task succeeded
+    pub ok: bool,
+
+    /// Error message for failed tasks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// Something to follow up on for a task that succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+
+    /// Wall time of the successful attempt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+impl TaskOutcome {
+    /// A successful task
+    pub fn succeeded(name: impl Into<String>, duration: Duration) -> Self {
+        Self {
+            name: name.into(),
+            ok: true,
+            error: None,
+            note: None,
+            duration_ms: Some(duration.as_millis() as u64),
+        }
+    }
+
+    /// Attach a note to this outcome
+    pub fn with_note(mut self, note: Option<String>) -> Self {
+        self.note = note;
+        self
+    }
+
+    /// A failed task
+    pub fn failed(name: impl Into<String>, error: impl ToString) -> Self {
+        Self {
+            name: name.into(),
+            ok: false,
+            error: Some(error.to_string()),
+            note: None,
+            duration_ms: None,
+        }
+    }
+}
+
+/// Result document for commands that run a batch of tasks
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchReport {
+    /// Overall status
+    pub status: Status,
+
+    /// Number of tasks that succeeded
+    pub succeeded: usize,
+
+    /// Number of tasks that failed
+    pub failed: usize,
+
+    /// Total wall time
+    pub duration_ms: u64,
+
+    /// Per-task results, in input order
+    pub tasks: Vec<TaskOutcome>,
+}
+
+impl BatchReport {
+    /// Build a report from task outcomes
+    pub fn new(tasks: Vec<TaskOutcome>, duration: Duration) -> Self {
+        let succeeded = tasks.iter().filter(|t| t.ok).count();
+        let failed = tasks.len() - succeeded;
+        Self {
+            status: Status::from_counts(succeeded, failed),
+            succeeded,
+            failed,
+            duration_ms: duration.as_millis() as u64,
+            tasks,
+        }
+    }
+}
+
+/// Envelope shared by every result document
+#[derive(Serialize)]
+struct Document<'a, T: Serialize> {
+    schema: String,
+    schema_version: u32,
+    command: &'a str,
+    #[serde(flatten)]
+    body: &'a T,
+}
+
+/// Envelope shared by every ndjson event
+#[derive(Serialize)]
+struct EventLine<'a, T: Serialize> {
+    schema: &'static str,
+    schema_version: u32,
+    command: &'a str,
+    event: &'a str,
+    #[serde(flatten)]
+    body: T,
+}
+
+/// Routes command output according to the selected mode
+#[derive(Debug, Clone, Copy)]
+pub struct Reporter {
+    mode: OutputMode,
+    command: &'static str,
+}
+
+impl Reporter {
+    /// Create a reporter for a command
+    pub fn new(mode: OutputMode, command: &'static str) -> Self {
+        Self { mode, command }
+    }
+
+    /// Subcommand being reported on
+    pub fn command(&self) -> &'static str {
+        self.command
+    }
+
+    /// Whether human-readable text should be printed
+    pub fn is_text(&self) -> bool {
+        self.mode == OutputMode::Text
+    }
+
+    /// Print a line of human-readable output (text mode only)
+    pub fn say(&self, line: impl AsRef<str>) {
+        if self.is_text() {
+            println!("{}", line.as_ref());
+        }
+    }
+
+    /// Progress bar for `len` tasks, hidden in machine modes
+    pub fn progress(&self, len: usize) -> ProgressBar {
+        if !self.is_text() {
+            return ProgressBar::hidden();
+        }
+
+        let pb = ProgressBar::new(len as u64);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+                .unwrap()
+                .progress_chars("#>-"),
+        );
+        pb
+    }
+
+    /// Emit a streaming event (ndjson mode only)
+    pub fn event<T: Serialize>(&self, event: &str, body: T) {
+        if self.mode != OutputMode::Ndjson {
+            return;
+        }
+
+        let line = EventLine {
+            schema: "asdf-accelerate/event",
+            schema_version: SCHEMA_VERSION,
+            command: self.command,
+            event,
+            body,
+        };
+        if let Ok(json) = serde_json::to_string(&line) {
+            println!("{}", json);
+        }
+    }
+
+    /// Emit a task outcome as it happens
+    pub fn task(&self, outcome: &TaskOutcome) {
+        self.event("task", outcome);
+    }
+
+    /// Emit the final result document
+    pub fn finish<T: Serialize>(&self, body: &T) -> anyhow::Result<()> {
+        let doc = Document {
+            schema: format!("asdf-accelerate/{}", self.command),
+            schema_version: SCHEMA_VERSION,
+            command: self.command,
+            body,
+        };
+
+        match self.mode {
+            OutputMode::Text => {}
+            OutputMode::Json => println!("{}", serde_json::to_string_pretty(&doc)?),
+            OutputMode::Ndjson => self.event("result", serde_json::json!({ "result": doc })),
+        }
+        Ok(())
+    }
+
+    /// Report a fatal error as a result document
+    pub fn fail(&self, error: &anyhow::Error) {
+        let body = serde_json::json!({
+            "status": Status::Failed,
+            "error": format!("{:#}", error),
+        });
+        let _ = self.finish(&body);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_from_counts() {
+        assert_eq!(Status::from_counts(3, 0), Status::Ok);
+        assert_eq!(Status::from_counts(0, 0), Status::Ok);
+        assert_eq!(Status::from_counts(2, 1), Status::Partial);
+        assert_eq!(Status::from_counts(0, 2), Status::Failed);
+        assert_eq!(Status::Partial.exit_code(), EXIT_PARTIAL);
+    }
+
+    #[test]
+    fn test_batch_report_serialization() {
+        let report = BatchReport::new(
+            vec![
+                TaskOutcome::succeeded("nodejs", Duration::from_millis(1500)),
+                TaskOutcome::failed("ruby", "network unreachable"),
+            ],
+            Duration::from_secs(2),
+        );
+
+        let doc = Document {
+            schema: "asdf-accelerate/sync".to_string(),
+            schema_version: SCHEMA_VERSION,
+            command: "sync",
+            body: &report,
+        };
+        let json = serde_json::to_value(&doc).unwrap();
+
+        assert_eq!(json["status"], "partial");
+        assert_eq!(json["schema_version"], 1);
+        assert_eq!(json["succeeded"], 1);
+        assert_eq!(json["tasks"][0]["duration_ms"], 1500);
+        assert_eq!(json["tasks"][1]["error"], "network unreachable");
+        assert!(json["tasks"][1].get("duration_ms").is_none());
+    }
+
+    #[test]
+    fn test_machine_modes_hide_progress() {
+        let reporter = Reporter::new(OutputMode::Json, "sync");
+        assert!(reporter.progress(10).is_hidden());
+        assert!(!reporter.is_text());
+    }
+}
@@ -0,0 +1,698 @@
+//! End-to-end tests against a fake asdf backend
+
+use asdf_core::testing::{FakeAsdf, Fault};
+use assert_cmd::Command;
+use std::time::Duration;
+
+fn accelerate(fake: &FakeAsdf) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_asdf-accelerate"));
+    cmd.envs(fake.env()).current_dir(fake.home());
+    cmd
+}
+
+fn json_output(cmd: &mut Command) -> (i32, serde_json::Value) {
+    let output = cmd.output().unwrap();
+    let doc = serde_json::from_slice(&output.stdout).unwrap_or_else(|e| {
+        panic!(
+            "invalid JSON ({}): {}",
+            e,
+            String::from_utf8_lossy(&output.stdout)
+        )
+    });
+    (output.status.code().unwrap(), doc)
+}
+
+fn ndjson_events(cmd: &mut Command) -> (i32, Vec<serde_json::Value>) {
+    let output = cmd.output().unwrap();
+    let events = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    (output.status.code().unwrap(), events)
+}
+
+#[test]
+fn test_list_plugins() {
+    let fake = FakeAsdf::new().plugin("golang", &[]).plugin("nodejs", &[]);
+
+    let (code, doc) = json_output(accelerate(&fake).args(["--output", "json", "list"]));
+
+    assert_eq!(code, 0);
+    assert_eq!(doc["schema"], "asdf-accelerate/list");
+    assert_eq!(doc["plugins"][0]["name"], "golang");
+    assert_eq!(
+        doc["plugins"][1]["url"],
+        "https://github.com/asdf-vm/asdf-nodejs.git"
+    );
+}
+
+#[test]
+fn test_update_partial_failure() {
+    let fake = FakeAsdf::new()
+        .plugin("nodejs", &[])
+        .plugin("ruby", &[])
+        .fault("plugin-update-ruby", Fault::Fail("fatal: remote hung up".into()));
+
+    let (code, doc) =
+        json_output(accelerate(&fake).args(["--output", "json", "update", "--all"]));
+
+    assert_eq!(code, 3);
+    assert_eq!(doc["status"], "partial");
+    assert_eq!(doc["tasks"][0]["name"], "nodejs");
+    assert_eq!(doc["tasks"][0]["ok"], true);
+    assert_eq!(doc["tasks"][1]["ok"], false);
+}
+
+#[test]
+fn test_update_total_failure() {
+    let fake = FakeAsdf::new()
+        .plugin("nodejs", &[])
+        .fault("plugin-update-nodejs", Fault::Fail("offline".into()));
+
+    accelerate(&fake)
+        .args(["update", "nodejs"])
+        .assert()
+        .code(1)
+        .stdout(predicates::str::contains("nodejs"));
+}
+
+#[test]
+fn test_trace_file_records_spans() {
+    let fake = FakeAsdf::new().plugin("nodejs", &[]).plugin("ruby", &[]);
+    let chrome = fake.root().join("trace.json");
+    let otlp = fake.root().join("trace.otlp.json");
+
+    accelerate(&fake)
+        .args(["update", "--all", "--trace-file"])
+        .arg(&chrome)
+        .assert()
+        .code(0);
+
+    let trace: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&chrome).unwrap()).unwrap();
+    let names: Vec<&str> = trace["traceEvents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|e| e["ph"] == "X")
+        .map(|e| e["name"].as_str().unwrap())
+        .collect();
+    for name in ["asdf-accelerate", "batch", "task", "update"] {
+        assert!(names.contains(&name), "no {} span in {:?}", name, names);
+    }
+    assert_eq!(names.iter().filter(|n| **n == "task").count(), 2);
+
+    accelerate(&fake)
+        .args(["update", "nodejs", "--trace-format", "otlp", "--trace-file"])
+        .arg(&otlp)
+        .assert()
+        .code(0);
+
+    let trace: serde_json::Value = serde_json::from_slice(&std::fs::read(&otlp).unwrap()).unwrap();
+    let spans = trace["resourceSpans"][0]["scopeSpans"][0]["spans"]
+        .as_array()
+        .unwrap();
+    let span = |name: &str| spans.iter().find(|s| s["name"] == name).unwrap();
+    // The plugin update runs on a worker thread, but stays under its task
+    assert_eq!(span("update")["parentSpanId"], span("task")["spanId"]);
+    assert_eq!(span("task")["parentSpanId"], span("batch")["spanId"]);
+}
+
+#[test]
+fn test_install_retries_flaky_network() {
+    let fake = FakeAsdf::new()
+        .plugin("nodejs", &["20.11.0"])
+        .fault("install-nodejs", Fault::Flaky(2));
+
+    let config = fake.root().join("accelerate.toml");
+    std::fs::write(&config, "[parallel]\nmax_retries = 2\n").unwrap();
+
+    let (code, events) = ndjson_events(accelerate(&fake).args([
+        "--config",
+        config.to_str().unwrap(),
+        "--output",
+        "ndjson",
+        "install",
+        "nodejs@20.11.0",
+    ]));
+
+    assert_eq!(code, 0);
+    let kinds: Vec<&str> = events.iter().map(|e| e["event"].as_str().unwrap()).collect();
+    assert_eq!(kinds, ["start", "retry", "retry", "task", "result"]);
+    assert_eq!(events[4]["result"]["status"], "ok");
+    assert_eq!(fake.call_count("install nodejs 20.11.0"), 3);
+    assert!(fake.is_installed("nodejs", "20.11.0"));
+}
+
+#[test]
+fn test_install_slow_runtimes_in_parallel() {
+    let fake = FakeAsdf::new()
+        .plugin("nodejs", &["20.11.0"])
+        .plugin("python", &["3.12.1"])
+        .fault("install-nodejs", Fault::Slow(Duration::from_millis(300)))
+        .fault("install-python", Fault::Slow(Duration::from_millis(300)));
+
+    let (code, doc) = json_output(accelerate(&fake).args([
+        "--output",
+        "json",
+        "--jobs",
+        "2",
+        "install",
+        "--parallel",
+        "nodejs@20.11.0",
+        "python@3.12.1",
+    ]));
+
+    assert_eq!(code, 0);
+    assert_eq!(doc["succeeded"], 2);
+    for task in doc["tasks"].as_array().unwrap() {
+        assert!(task["duration_ms"].as_u64().unwrap() >= 300);
+    }
+    assert!(fake.is_installed("python", "3.12.1"));
+}
+
+#[test]
+fn test_install_unknown_version_fails() {
+    let fake = FakeAsdf::new().plugin("nodejs", &["20.11.0"]);
+
+    let (code, doc) = json_output(accelerate(&fake).args([
+        "--output",
+        "json",
+        "install",
+        "nodejs@99.0.0",
+    ]));
+
+    assert_eq!(code, 1);
+    assert_eq!(doc["status"], "failed");
+    assert!(!fake.is_installed("nodejs", "99.0.0"));
+}
+
+fn artifact_config(fake: &FakeAsdf, store: &std::path::Path) -> std::path::PathBuf {
+    let config = fake.root().join("accelerate.toml");
+    std::fs::write(
+        &config,
+        format!("[artifacts]\nenabled = true\nstore = \"{}\"\n", store.display()),
+    )
+    .unwrap();
+    config
+}
+
+#[test]
+fn test_install_restores_from_artifact_cache() {
+    let store = tempfile::TempDir::new().unwrap();
+
+    let builder = FakeAsdf::new().plugin("ruby", &["3.3.0"]);
+    accelerate(&builder)
+        .arg("--config")
+        .arg(artifact_config(&builder, store.path()))
+        .args(["install", "ruby@3.3.0"])
+        .assert()
+        .success();
+    assert_eq!(builder.call_count("install ruby 3.3.0"), 1);
+
+    let runner = FakeAsdf::new().plugin("ruby", &["3.3.0"]);
+    accelerate(&runner)
+        .arg("--config")
+        .arg(artifact_config(&runner, store.path()))
+        .args(["install", "ruby@3.3.0"])
+        .assert()
+        .success();
+
+    assert!(!runner.asdf_dir().join("installs").exists());
+    assert_eq!(runner.call_count("install ruby 3.3.0"), 0);
+    assert_eq!(runner.call_count("reshim ruby 3.3.0"), 1);
+    assert!(runner.is_installed("ruby", "3.3.0"));
+    assert!(runner.data_dir().join("shims/ruby").is_file());
+}
+
+#[test]
+fn test_install_rebuilds_corrupt_artifact() {
+    let store = tempfile::TempDir::new().unwrap();
+
+    let builder = FakeAsdf::new().plugin("ruby", &["3.3.0"]);
+    accelerate(&builder)
+        .arg("--config")
+        .arg(artifact_config(&builder, store.path()))
+        .args(["install", "ruby@3.3.0"])
+        .assert()
+        .success();
+
+    // Truncate every archive in the store
+    let ruby = store.path().join("ruby/3.3.0");
+    for entry in std::fs::read_dir(&ruby).unwrap().flatten() {
+        if entry.path().extension().is_some_and(|e| e == "zst") {
+            std::fs::write(entry.path(), b"garbage").unwrap();
+        }
+    }
+
+    let runner = FakeAsdf::new().plugin("ruby", &["3.3.0"]);
+    accelerate(&runner)
+        .arg("--config")
+        .arg(artifact_config(&runner, store.path()))
+        .args(["install", "ruby@3.3.0"])
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("Checksum mismatch"));
+
+    assert_eq!(runner.call_count("install ruby 3.3.0"), 1);
+    assert!(runner.is_installed("ruby", "3.3.0"));
+}
+
+#[test]
+fn test_trust_review_and_accept() {
+    let fake = FakeAsdf::new();
+    let upstream = fake.upstream("nodejs");
+    let fake = fake.plugin_with_url("nodejs", upstream.to_str().unwrap(), &["20.11.0"]);
+
+    let (code, doc) =
+        json_output(accelerate(&fake).args(["--output", "json", "trust", "add", "nodejs"]));
+    assert_eq!(code, 0);
+    let pinned = doc["entry"]["commit"].as_str().unwrap().to_string();
+
+    let incoming = fake.upstream_commit("nodejs", "Download from a new mirror", None);
+
+    // Pinned plugins hold back unreviewed commits, which is not a failure
+    let (code, doc) =
+        json_output(accelerate(&fake).args(["--output", "json", "update", "nodejs"]));
+    assert_eq!(code, 0);
+    assert_eq!(doc["tasks"][0]["ok"], true);
+    assert!(doc["tasks"][0]["note"]
+        .as_str()
+        .unwrap()
+        .contains("1 unreviewed commits"));
+
+    let (code, doc) =
+        json_output(accelerate(&fake).args(["--output", "json", "trust", "review", "nodejs"]));
+    assert_eq!(code, 0);
+    assert_eq!(doc["review"]["pinned"], pinned.as_str());
+    assert_eq!(doc["review"]["incoming"], incoming.as_str());
+    assert_eq!(doc["review"]["signature"]["status"], "unsigned");
+    assert!(doc["diff"].as_str().unwrap().contains("+Download from a new mirror"));
+
+    let (code, doc) = json_output(accelerate(&fake).args([
+        "--output", "json", "trust", "review", "nodejs", "--accept",
+    ]));
+    assert_eq!(code, 0);
+    assert_eq!(doc["accepted"], true);
+
+    let (code, _) = json_output(accelerate(&fake).args(["--output", "json", "update", "nodejs"]));
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn test_trust_accept_keeps_pin_when_checkout_fails() {
+    let fake = FakeAsdf::new();
+    let upstream = fake.upstream("nodejs");
+    let fake = fake
+        .plugin_with_url("nodejs", upstream.to_str().unwrap(), &[])
+        .fault("plugin-update-nodejs", Fault::Fail("fatal: remote hung up".into()));
+    let (_, doc) = json_output(accelerate(&fake).args(["--output", "json", "trust", "add", "nodejs"]));
+    let pinned = doc["entry"]["commit"].as_str().unwrap().to_string();
+    fake.upstream_commit("nodejs", "Download from a new mirror", None);
+
+    let (code, _) = json_output(accelerate(&fake).args([
+        "--output", "json", "trust", "review", "nodejs", "--accept",
+    ]));
+    assert_eq!(code, 1);
+
+    let store: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(fake.data_dir().join("trust.json")).unwrap()).unwrap();
+    assert_eq!(store["plugins"]["nodejs"]["commit"], pinned.as_str());
+}
+
+#[test]
+fn test_trust_refuses_changed_url() {
+    let fake = FakeAsdf::new();
+    let upstream = fake.upstream("nodejs");
+    let fake = fake.plugin_with_url("nodejs", upstream.to_str().unwrap(), &[]);
+    accelerate(&fake).args(["trust", "add", "nodejs", "--no-pin"]).assert().success();
+
+    let moved = fake.root().join("upstream").join("hijacked");
+    std::fs::rename(&upstream, &moved).unwrap();
+    std::process::Command::new("git")
+        .arg("-C")
+        .arg(fake.data_dir().join("plugins").join("nodejs"))
+        .args(["remote", "set-url", "origin"])
+        .arg(&moved)
+        .status()
+        .unwrap();
+
+    let (code, doc) = json_output(accelerate(&fake).args([
+        "--output", "json", "trust", "review", "nodejs", "--accept",
+    ]));
+    assert_eq!(code, 1);
+    assert!(doc["error"]
+        .as_str()
+        .unwrap()
+        .contains("remote URL changed"));
+
+    let (code, _) = json_output(accelerate(&fake).args(["--output", "json", "sync"]));
+    assert_eq!(code, 1);
+}
+
+#[test]
+fn test_trust_requires_signed_commits() {
+    let fake = FakeAsdf::new();
+    let upstream = fake.upstream("nodejs");
+    let fake = fake.plugin_with_url("nodejs", upstream.to_str().unwrap(), &[]);
+    let (key, signers) = fake.signing_key("maintainer");
+
+    accelerate(&fake)
+        .args(["trust", "add", "nodejs", "--no-pin", "--require-signature"])
+        .assert()
+        .success();
+    accelerate(&fake)
+        .args(["trust", "signers"])
+        .arg(&signers)
+        .assert()
+        .success();
+
+    fake.upstream_commit("nodejs", "Unsigned change", None);
+    let (code, _) = json_output(accelerate(&fake).args(["--output", "json", "sync"]));
+    assert_eq!(code, 1);
+
+    fake.upstream_commit("nodejs", "Signed change", Some(&key));
+    let (code, doc) = json_output(accelerate(&fake).args(["--output", "json", "sync"]));
+    assert_eq!(code, 0, "{}", doc);
+}
+
+fn vendored_index(fake: &FakeAsdf, plugins: &[(&str, &str)]) -> std::path::PathBuf {
+    let dir = fake.root().join("asdf-plugins");
+    std::fs::create_dir_all(dir.join("plugins")).unwrap();
+    for (name, url) in plugins {
+        std::fs::write(dir.join("plugins").join(name), format!("repository = {}\n", url)).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn test_search_vendored_index_offline() {
+    let fake = FakeAsdf::new().plugin("nodejs", &[]);
+    let index = vendored_index(
+        &fake,
+        &[
+            ("nodejs", "https://github.com/asdf-vm/asdf-nodejs.git"),
+            ("deno", "https://github.com/asdf-community/asdf-deno.git"),
+            ("python", "https://github.com/danhper/asdf-python.git"),
+        ],
+    );
+    let config = fake.root().join("accelerate.toml");
+    std::fs::write(
+        &config,
+        format!(
+            "[registry]\nsources = [\"https://example.invalid/asdf-plugins.git\", \"{}\"]\n",
+            index.display()
+        ),
+    )
+    .unwrap();
+
+    let (code, doc) = json_output(accelerate(&fake).arg("--config").arg(&config).args([
+        "--output", "json", "search", "nodjs", "--offline",
+    ]));
+
+    assert_eq!(code, 0, "{}", doc);
+    assert_eq!(doc["indexed"], 3);
+    assert_eq!(doc["matches"], 1);
+    assert_eq!(doc["results"][0]["name"], "nodejs");
+    assert_eq!(doc["results"][0]["installed"], true);
+    assert_eq!(doc["stale_sources"].as_array().unwrap().len(), 0);
+}
+
+#[test]
+fn test_ci_installs_and_exports() {
+    let fake = FakeAsdf::new().plugin("nodejs", &["20.11.0", "21.6.0"]);
+    std::fs::write(fake.home().join(".tool-versions"), "nodejs 20.11.0\n").unwrap();
+    let env_file = fake.root().join("ci.env");
+
+    let (code, doc) = json_output(
+        accelerate(&fake)
+            .args(["--output", "json", "ci", "--env-file"])
+            .arg(&env_file),
+    );
+
+    assert_eq!(code, 0);
+    assert_eq!(doc["tasks"][0]["name"], "nodejs@20.11.0");
+    assert!(fake.is_installed("nodejs", "20.11.0"));
+    let install_dir = fake.data_dir().join("installs/nodejs/20.11.0");
+    assert_eq!(
+        doc["exports"]["NODEJS_HOME"],
+        install_dir.display().to_string()
+    );
+
+    let env = std::fs::read_to_string(&env_file).unwrap();
+    assert!(env.contains(&format!("NODEJS_HOME=\"{}\"\n", install_dir.display())));
+    assert!(env.contains(&format!("PATH=\"{}:", install_dir.join("bin").display())));
+
+    // Nothing left to install, and the key only depends on the toolchain
+    let key = doc["cache_key"].clone();
+    let (_, again) = json_output(
+        accelerate(&fake)
+            .args(["--output", "json", "ci", "--env-file"])
+            .arg(&env_file),
+    );
+    assert_eq!(again["cache_key"], key);
+    assert_eq!(again["tasks"].as_array().unwrap().len(), 0);
+
+    std::fs::write(fake.home().join(".tool-versions"), "nodejs 21.6.0\n").unwrap();
+    let (_, changed) = json_output(accelerate(&fake).args(["--output", "json", "ci", "--key-only"]));
+    assert_ne!(changed["cache_key"], key);
+    assert!(!fake.is_installed("nodejs", "21.6.0"));
+}
+
+#[test]
+fn test_ci_github_env() {
+    let fake = FakeAsdf::new().plugin("nodejs", &["20.11.0"]);
+    std::fs::write(fake.home().join(".tool-versions"), "nodejs 20.11.0\n").unwrap();
+    let github_env = fake.root().join("github_env");
+    let github_path = fake.root().join("github_path");
+    let github_output = fake.root().join("github_output");
+    std::fs::write(&github_env, "EXISTING=1\n").unwrap();
+
+    accelerate(&fake)
+        .args(["ci", "--env-format", "github"])
+        .env("GITHUB_ENV", &github_env)
+        .env("GITHUB_PATH", &github_path)
+        .env("GITHUB_OUTPUT", &github_output)
+        .assert()
+        .code(0)
+        .stdout(predicates::str::contains("Cache key: asdf-"));
+
+    let env = std::fs::read_to_string(&github_env).unwrap();
+    assert!(env.starts_with("EXISTING=1\n"));
+    assert!(env.contains("NODEJS_HOME="));
+    assert!(!env.contains("PATH="));
+
+    // Prepended line by line, so the runtime ends up ahead of the shims
+    let path = std::fs::read_to_string(&github_path).unwrap();
+    assert_eq!(
+        path,
+        format!(
+            "{}\n{}\n",
+            fake.data_dir().join("shims").display(),
+            fake.data_dir().join("installs/nodejs/20.11.0/bin").display()
+        )
+    );
+
+    let output = std::fs::read_to_string(&github_output).unwrap();
+    assert!(output.contains("cache-key=asdf-"));
+    assert!(output.contains(&format!(
+        "cache-paths<<ASDF_EOF\n{}\n",
+        fake.data_dir().join("installs").display()
+    )));
+}
+
+#[test]
+fn test_ci_key_only_adds_no_plugins() {
+    let fake = FakeAsdf::new().plugin("nodejs", &["20.11.0"]);
+    std::fs::write(
+        fake.home().join(".tool-versions"),
+        "nodejs 20.11.0\nruby 3.3.0\n",
+    )
+    .unwrap();
+
+    let (code, doc) = json_output(accelerate(&fake).args(["--output", "json", "ci", "--key-only"]));
+
+    assert_eq!(code, 0, "{}", doc);
+    assert!(doc["cache_key"].as_str().unwrap().starts_with("asdf-"));
+    assert!(!fake.calls().iter().any(|c| c.starts_with("plugin add")));
+}
+
+/// `ci --key-only` cache key for `nodejs 20.11.0`
+fn ci_key(fake: &FakeAsdf) -> serde_json::Value {
+    std::fs::write(fake.home().join(".tool-versions"), "nodejs 20.11.0\n").unwrap();
+    let index = vendored_index(fake, &[("nodejs", "https://github.com/asdf-vm/asdf-nodejs.git")]);
+    let config = fake.root().join("accelerate.toml");
+    std::fs::write(&config, format!("[registry]\nsources = [\"{}\"]\n", index.display())).unwrap();
+    let (code, doc) = json_output(
+        accelerate(fake)
+            .arg("--config")
+            .arg(&config)
+            .args(["--output", "json", "ci", "--key-only"]),
+    );
+    assert_eq!(code, 0, "{}", doc);
+    doc["cache_key"].clone()
+}
+
+fn plugin_head(fake: &FakeAsdf, plugin: &str) -> String {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(fake.data_dir().join("plugins").join(plugin))
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+#[test]
+fn test_ci_key_same_before_and_after_restore() {
+    // A fresh runner has no plugins; a restored one has them checked out
+    let fresh = FakeAsdf::new();
+    let restored = FakeAsdf::new()
+        .plugin("nodejs", &["20.11.0"])
+        .installed("nodejs", "20.11.0");
+
+    // Both share the trust store, which pins the restored checkout
+    let store = serde_json::json!({"plugins": {"nodejs": {
+        "url": "https://github.com/asdf-vm/asdf-nodejs.git",
+        "commit": plugin_head(&restored, "nodejs"),
+    }}});
+    for fake in [&fresh, &restored] {
+        std::fs::write(fake.data_dir().join("trust.json"), store.to_string()).unwrap();
+    }
+
+    assert_eq!(ci_key(&fresh), ci_key(&restored));
+}
+
+#[test]
+fn test_ci_key_follows_plugin_checkout() {
+    let fake = FakeAsdf::new().plugin("nodejs", &["20.11.0"]);
+    let before = ci_key(&fake);
+
+    std::process::Command::new("git")
+        .arg("-C")
+        .arg(fake.data_dir().join("plugins").join("nodejs"))
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.invalid"])
+        .args(["commit", "--quiet", "--allow-empty", "-m", "Move the plugin"])
+        .status()
+        .unwrap();
+
+    assert_ne!(before, ci_key(&fake));
+}
+
+#[test]
+fn test_ci_installs_non_semver_versions() {
+    let fake = FakeAsdf::new()
+        .plugin("java", &["temurin-21.0.1+12"])
+        .plugin("erlang", &["26.2.5.1"]);
+    std::fs::write(
+        fake.home().join(".tool-versions"),
+        "java temurin-21.0.1+12\nerlang 26.2.5.1\n",
+    )
+    .unwrap();
+    let env_file = fake.root().join("ci.env");
+
+    let (code, doc) = json_output(
+        accelerate(&fake)
+            .args(["--output", "json", "ci", "--env-file"])
+            .arg(&env_file),
+    );
+
+    assert_eq!(code, 0, "{}", doc);
+    assert!(fake.is_installed("java", "temurin-21.0.1+12"));
+    assert!(fake.is_installed("erlang", "26.2.5.1"));
+    assert_eq!(
+        doc["exports"]["JAVA_HOME"],
+        fake.data_dir().join("installs/java/temurin-21.0.1+12").display().to_string()
+    );
+}
+
+#[test]
+fn test_ci_skips_ref_versions() {
+    let fake = FakeAsdf::new().plugin("nodejs", &["20.11.0"]);
+    std::fs::write(
+        fake.home().join(".tool-versions"),
+        "nodejs ref:v20.x 20.11.0\n",
+    )
+    .unwrap();
+    let env_file = fake.root().join("ci.env");
+
+    let (code, doc) = json_output(
+        accelerate(&fake)
+            .args(["--output", "json", "ci", "--env-file"])
+            .arg(&env_file),
+    );
+
+    assert_eq!(code, 0, "{}", doc);
+    assert!(fake.is_installed("nodejs", "20.11.0"));
+    assert_eq!(doc["tasks"].as_array().unwrap().len(), 1);
+    assert!(doc["exports"].get("NODEJS_HOME").is_none());
+    assert_eq!(
+        doc["path"][0],
+        fake.data_dir().join("shims").display().to_string()
+    );
+}
+
+#[test]
+fn test_prune_orphans() {
+    let fake = FakeAsdf::new()
+        .plugin("nodejs", &["20.11.0"])
+        .installed("nodejs", "20.11.0")
+        .plugin("ruby", &[]);
+    let data = fake.data_dir();
+    std::fs::create_dir_all(data.join("installs/golang/1.22.0/bin")).unwrap();
+    std::fs::write(data.join("installs/golang/1.22.0/bin/go"), "go").unwrap();
+    std::fs::create_dir_all(data.join("downloads/nodejs/18.0.0")).unwrap();
+
+    let (code, doc) =
+        json_output(accelerate(&fake).args(["--output", "json", "prune", "--dry-run"]));
+    assert_eq!(code, 0);
+    assert_eq!(doc["dry_run"], true);
+    let orphans: Vec<(&str, &str)> = doc["orphans"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|o| (o["kind"].as_str().unwrap(), o["name"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        orphans,
+        [
+            ("plugin", "ruby"),
+            ("install", "golang"),
+            ("download", "nodejs/18.0.0")
+        ]
+    );
+    assert!(data.join("installs/golang").exists());
+
+    // Without a terminal to confirm on, removal needs --yes
+    accelerate(&fake)
+        .args(["prune"])
+        .assert()
+        .code(1)
+        .stderr(predicates::str::contains("--yes"));
+
+    let (code, doc) = json_output(accelerate(&fake).args(["--output", "json", "prune", "--yes"]));
+    assert_eq!(code, 0);
+    assert_eq!(doc["succeeded"], 3);
+    assert!(doc["reclaimed_bytes"].as_u64().unwrap() >= 2);
+    assert!(!data.join("plugins/ruby").exists());
+    assert!(!data.join("installs/golang").exists());
+    assert!(!data.join("downloads/nodejs/18.0.0").exists());
+    assert!(fake.is_installed("nodejs", "20.11.0"));
+}
+
+#[test]
+fn test_prune_reads_data_dir_not_asdf_dir() {
+    let fake = FakeAsdf::new()
+        .plugin("nodejs", &["20.11.0"])
+        .installed("nodejs", "20.11.0");
+    // A plugin tree in the asdf checkout, which prune must not look at
+    let asdf_dir = fake.asdf_dir();
+    std::fs::create_dir_all(asdf_dir.join("plugins/decoy")).unwrap();
+
+    let (code, doc) = json_output(accelerate(&fake).args(["--output", "json", "prune", "--yes"]));
+    assert_eq!(code, 0);
+    assert_eq!(doc["orphans"], serde_json::json!([]));
+    assert!(asdf_dir.join("plugins/decoy").exists());
+    assert!(fake.is_installed("nodejs", "20.11.0"));
+}
@@ -0,0 +1,478 @@
+//! Prebuilt runtime artifacts
+//!
+//! An install tree is packed into a zstd-compressed tarball and stored under
+//! a key made of plugin, version, OS/arch, libc and plugin commit, next to a
+//! `.sha256` file holding its checksum. Restoring verifies the checksum before
+//! anything is unpacked.
+//!
+//! Install trees are restored as-is, not relocated. Runtimes that bake their
+//! prefix into binaries need the same `ASDF_DATA_DIR` on every machine that
+//! shares a store.
+
+use crate::{Error, Result};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, instrument};
+
+/// Identifies one prebuilt runtime
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArtifactKey {
+    /// asdf plugin name
+    pub plugin: String,
+
+    /// Runtime version
+    pub version: String,
+
+    /// Operating system, as in `std::env::consts::OS`
+    pub os: String,
+
+    /// CPU architecture, as in `std::env::consts::ARCH`
+    pub arch: String,
+
+    /// C library flavour (`gnu`, `musl`) or `none`
+    pub libc: String,
+
+    /// Commit of the plugin that built the runtime
+    pub plugin_commit: String,
+}
+
+impl ArtifactKey {
+    /// Key for a runtime built on this machine
+    pub fn for_host(
+        plugin: impl Into<String>,
+        version: impl Into<String>,
+        plugin_commit: impl Into<String>,
+    ) -> Self {
+        Self {
+            plugin: plugin.into(),
+            version: version.into(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            libc: host_libc().to_string(),
+            plugin_commit: plugin_commit.into(),
+        }
+    }
+
+    /// Object name of the archive in a store
+    pub fn name(&self) -> String {
+        format!(
+            "{}/{}/{}-{}-{}-{}.tar.zst",
+            self.plugin, self.version, self.os, self.arch, self.libc, self.plugin_commit
+        )
+    }
+
+    /// Object name of the archive checksum
+    pub fn checksum_name(&self) -> String {
+        format!("{}.sha256", self.name())
+    }
+}
+
+/// C library of this machine
+///
+/// Detected at runtime rather than from the build target, since a static
+/// musl binary can still install glibc runtimes.
+pub fn host_libc() -> &'static str {
+    if std::env::consts::OS != "linux" {
+        return "none";
+    }
+
+    let musl = ["/lib", "/usr/lib"].iter().any(|dir| {
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .any(|e| e.file_name().to_string_lossy().starts_with("ld-musl-"))
+    });
+
+    if musl {
+        "musl"
+    } else {
+        "gnu"
+    }
+}
+
+/// Where artifacts are kept
+pub trait ArtifactStore: Send + Sync {
+    /// Human-readable location, for logs
+    fn location(&self) -> String;
+
+    /// Download `name` to `dest`; `false` if the store doesn't have it
+    fn fetch(&self, name: &str, dest: &Path) -> Result<bool>;
+
+    /// Upload `src` as `name`
+    fn put(&self, name: &str, src: &Path) -> Result<()>;
+}
+
+/// Open the store at `location`
+///
+/// `http://` and `https://` URLs use [`HttpStore`]; anything else is a
+/// directory, local or network-mounted, handled by [`DirStore`].
+pub fn open_store(location: &str) -> Box<dyn ArtifactStore> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        Box::new(HttpStore::new(location))
+    } else {
+        let path = location.strip_prefix("file://").unwrap_or(location);
+        Box::new(DirStore::new(path))
+    }
+}
+
+/// Store in a local directory or NFS mount
+///
+/// Writes go to a temporary file in the destination directory and are
+/// renamed into place, so concurrent readers never see partial archives.
+#[derive(Debug, Clone)]
+pub struct DirStore {
+    root: PathBuf,
+}
+
+impl DirStore {
+    /// Store rooted at `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl ArtifactStore for DirStore {
+    fn location(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    fn fetch(&self, name: &str, dest: &Path) -> Result<bool> {
+        match fs::copy(self.root.join(name), dest) {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn put(&self, name: &str, src: &Path) -> Result<()> {
+        let target = self.root.join(name);
+        let dir = target
+            .parent()
+            .ok_or_else(|| Error::Other(format!("Invalid artifact name: {}", name)))?;
+        fs::create_dir_all(dir)?;
+
+        let staged = tempfile::NamedTempFile::new_in(dir)?;
+        fs::copy(src, staged.path())?;
+        staged
+            .persist(&target)
+            .map_err(|e| Error::Io(e.error))?;
+        Ok(())
+    }
+}
+
+/// Store behind an HTTP server that supports `GET` and `PUT`
+#[derive(Debug, Clone)]
+pub struct HttpStore {
+    base_url: String,
+    agent: ureq::Agent,
+}
+
+impl HttpStore {
+    /// Store at `base_url`; object names are appended to it
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new().build(),
+        }
+    }
+
+    fn url(&self, name: &str) -> String {
+        format!("{}/{}", self.base_url, name)
+    }
+}
+
+impl ArtifactStore for HttpStore {
+    fn location(&self) -> String {
+        self.base_url.clone()
+    }
+
+    fn fetch(&self, name: &str, dest: &Path) -> Result<bool> {
+        let response = match self.agent.get(&self.url(name)).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return Ok(false),
+            Err(e) => return Err(Error::Other(format!("GET {}: {}", self.url(name), e))),
+        };
+
+        let mut file = File::create(dest)?;
+        io::copy(&mut response.into_reader(), &mut file)?;
+        Ok(true)
+    }
+
+    fn put(&self, name: &str, src: &Path) -> Result<()> {
+        let file = File::open(src)?;
+        let len = file.metadata()?.len();
+
+        self.agent
+            .put(&self.url(name))
+            .set("Content-Length", &len.to_string())
+            .send(file)
+            .map_err(|e| Error::Other(format!("PUT {}: {}", self.url(name), e)))?;
+        Ok(())
+    }
+}
+
+/// Pack `dir` into a zstd tarball at `archive`, returning its SHA-256
+pub fn pack(dir: &Path, archive: &Path) -> Result<String> {
+    let file = File::create(archive)?;
+    let encoder = zstd::Encoder::new(file, 0)?;
+
+    let mut builder = tar::Builder::new(encoder);
+    builder.follow_symlinks(false);
+    builder.append_dir_all(".", dir)?;
+    builder.into_inner()?.finish()?;
+
+    sha256_file(archive)
+}
+
+/// Unpack a tarball made by [`pack`] into `dest`
+pub fn unpack(archive: &Path, dest: &Path) -> Result<()> {
+    let decoder = zstd::Decoder::new(File::open(archive)?)?;
+    let mut archive = tar::Archive::new(decoder);
+    archive.set_preserve_permissions(true);
+    archive.set_preserve_mtime(true);
+    archive.unpack(dest)?;
+    Ok(())
+}
+
+/// Hex-encoded SHA-256 of a file
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Saves and restores install trees through an [`ArtifactStore`]
+pub struct ArtifactCache {
+    store: Box<dyn ArtifactStore>,
+    upload: bool,
+}
+
+impl ArtifactCache {
+    /// Cache backed by `store`
+    pub fn new(store: Box<dyn ArtifactStore>) -> Self {
+        Self {
+            store,
+            upload: true,
+        }
+    }
+
+    /// Whether [`save`](Self::save) uploads; read-only runners turn it off
+    pub fn with_upload(mut self, upload: bool) -> Self {
+        self.upload = upload;
+        self
+    }
+
+    /// Location of the underlying store
+    pub fn location(&self) -> String {
+        self.store.location()
+    }
+
+    /// Pack `install_dir` and upload it under `key`
+    ///
+    /// The checksum is uploaded last, so a restore never sees a checksum
+    /// without its archive.
+    #[instrument(name = "artifact_save", level = "debug", skip_all, fields(artifact = %key.name()))]
+    pub fn save(&self, key: &ArtifactKey, install_dir: &Path) -> Result<()> {
+        if !self.upload {
+            return Ok(());
+        }
+
+        let scratch = tempfile::tempdir()?;
+        let archive = scratch.path().join("artifact.tar.zst");
+        let checksum = pack(install_dir, &archive)?;
+
+        let checksum_file = scratch.path().join("artifact.sha256");
+        fs::write(&checksum_file, format!("{}\n", checksum))?;
+
+        self.store.put(&key.name(), &archive)?;
+        self.store.put(&key.checksum_name(), &checksum_file)?;
+
+        info!("Saved {} to {}", key.name(), self.store.location());
+        Ok(())
+    }
+
+    /// Restore `key` into `install_dir`, which must not exist yet
+    ///
+    /// Returns `false` on a cache miss. A checksum mismatch is an error and
+    /// leaves `install_dir` untouched.
+    #[instrument(name = "artifact_restore", level = "debug", skip_all, fields(artifact = %key.name()))]
+    pub fn restore(&self, key: &ArtifactKey, install_dir: &Path) -> Result<bool> {
+        let scratch = tempfile::tempdir()?;
+
+        let checksum_file = scratch.path().join("artifact.sha256");
+        if !self.store.fetch(&key.checksum_name(), &checksum_file)? {
+            debug!("No artifact for {}", key.name());
+            return Ok(false);
+        }
+        let expected = fs::read_to_string(&checksum_file)?.trim().to_string();
+
+        let archive = scratch.path().join("artifact.tar.zst");
+        if !self.store.fetch(&key.name(), &archive)? {
+            return Ok(false);
+        }
+
+        let actual = sha256_file(&archive)?;
+        if actual != expected {
+            return Err(Error::ChecksumMismatch {
+                name: key.name(),
+                expected,
+                actual,
+            });
+        }
+
+        // Unpack next to the destination and rename, so a failed restore
+        // never leaves a half-populated install behind
+        let parent = install_dir
+            .parent()
+            .ok_or_else(|| Error::Other(format!("Invalid install dir: {}", install_dir.display())))?;
+        fs::create_dir_all(parent)?;
+        let staging = tempfile::Builder::new()
+            .prefix(".restore-")
+            .tempdir_in(parent)?;
+        unpack(&archive, staging.path())?;
+        fs::rename(staging.keep(), install_dir)?;
+
+        info!("Restored {} from {}", key.name(), self.store.location());
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn install_tree(root: &Path) -> PathBuf {
+        let dir = root.join("installs/ruby/3.3.0");
+        fs::create_dir_all(dir.join("bin")).unwrap();
+        fs::create_dir_all(dir.join("lib/ruby")).unwrap();
+        fs::write(dir.join("bin/ruby"), "#!/bin/sh\necho ruby 3.3.0\n").unwrap();
+        fs::set_permissions(dir.join("bin/ruby"), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(dir.join("lib/ruby/core.rb"), "puts 1\n".repeat(100)).unwrap();
+        std::os::unix::fs::symlink("ruby", dir.join("bin/ruby3")).unwrap();
+        dir
+    }
+
+    fn key() -> ArtifactKey {
+        ArtifactKey::for_host("ruby", "3.3.0", "0123456789abcdef")
+    }
+
+    #[test]
+    fn test_key_name() {
+        let key = ArtifactKey {
+            plugin: "ruby".into(),
+            version: "3.3.0".into(),
+            os: "linux".into(),
+            arch: "x86_64".into(),
+            libc: "gnu".into(),
+            plugin_commit: "abc123".into(),
+        };
+        assert_eq!(key.name(), "ruby/3.3.0/linux-x86_64-gnu-abc123.tar.zst");
+        assert_eq!(key.checksum_name(), "ruby/3.3.0/linux-x86_64-gnu-abc123.tar.zst.sha256");
+    }
+
+    #[test]
+    fn test_save_and_restore_dir_store() {
+        let tmp = TempDir::new().unwrap();
+        let source = install_tree(tmp.path());
+        let cache = ArtifactCache::new(open_store(&tmp.path().join("store").display().to_string()));
+
+        let target = tmp.path().join("other/installs/ruby/3.3.0");
+        assert!(!cache.restore(&key(), &target).unwrap());
+
+        cache.save(&key(), &source).unwrap();
+        assert!(cache.restore(&key(), &target).unwrap());
+
+        let ruby = target.join("bin/ruby");
+        assert_eq!(fs::read_to_string(&ruby).unwrap(), "#!/bin/sh\necho ruby 3.3.0\n");
+        assert_eq!(fs::metadata(&ruby).unwrap().permissions().mode() & 0o777, 0o755);
+        assert_eq!(fs::read_link(target.join("bin/ruby3")).unwrap(), Path::new("ruby"));
+        assert!(target.join("lib/ruby/core.rb").is_file());
+    }
+
+    #[test]
+    fn test_corrupt_artifact_is_rejected() {
+        let tmp = TempDir::new().unwrap();
+        let source = install_tree(tmp.path());
+        let store_dir = tmp.path().join("store");
+        let cache = ArtifactCache::new(Box::new(DirStore::new(&store_dir)));
+        cache.save(&key(), &source).unwrap();
+
+        fs::write(store_dir.join(key().name()), b"not a tarball").unwrap();
+
+        let target = tmp.path().join("restored");
+        let err = cache.restore(&key(), &target).unwrap_err();
+        assert!(matches!(err, Error::ChecksumMismatch { .. }));
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn test_read_only_cache_does_not_upload() {
+        let tmp = TempDir::new().unwrap();
+        let source = install_tree(tmp.path());
+        let store_dir = tmp.path().join("store");
+        let cache = ArtifactCache::new(Box::new(DirStore::new(&store_dir))).with_upload(false);
+
+        cache.save(&key(), &source).unwrap();
+        assert!(!store_dir.exists());
+    }
+
+    #[test]
+    fn test_http_store() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}/artifacts", server.server_addr());
+
+        // Minimal stand-in for an artifact server: PUT stores, GET serves
+        let handle = std::thread::spawn(move || {
+            let mut objects = std::collections::HashMap::new();
+            for _ in 0..6 {
+                let mut request = server.recv().unwrap();
+                let url = request.url().to_string();
+                match request.method() {
+                    tiny_http::Method::Put => {
+                        let mut body = Vec::new();
+                        request.as_reader().read_to_end(&mut body).unwrap();
+                        objects.insert(url, body);
+                        request.respond(tiny_http::Response::empty(201)).unwrap();
+                    }
+                    _ => match objects.get(&url) {
+                        Some(body) => request
+                            .respond(tiny_http::Response::from_data(body.clone()))
+                            .unwrap(),
+                        None => request.respond(tiny_http::Response::empty(404)).unwrap(),
+                    },
+                }
+            }
+        });
+
+        let tmp = TempDir::new().unwrap();
+        let source = install_tree(tmp.path());
+        let cache = ArtifactCache::new(open_store(&base));
+        let target = tmp.path().join("restored");
+
+        // 1 GET (miss), 2 PUTs, 2 GETs, then 1 GET for the second miss
+        assert!(!cache.restore(&key(), &target).unwrap());
+        cache.save(&key(), &source).unwrap();
+        assert!(cache.restore(&key(), &target).unwrap());
+        assert!(target.join("bin/ruby").is_file());
+
+        let other = ArtifactKey::for_host("ruby", "3.2.0", "0123456789abcdef");
+        assert!(!cache.restore(&other, &tmp.path().join("other")).unwrap());
+
+        handle.join().unwrap();
+    }
+}
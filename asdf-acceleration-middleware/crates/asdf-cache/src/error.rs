@@ -32,6 +32,14 @@ pub enum Error {
     #[error("Cache entry expired")]
     Expired,
 
+    /// Artifact does not match its recorded checksum
+    #[error("Checksum mismatch for {name}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+
     /// Generic error
     #[error("{0}")]
     Other(String),
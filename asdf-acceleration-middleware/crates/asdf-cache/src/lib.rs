@@ -4,12 +4,15 @@
 //! - L1: In-memory LRU cache
 //! - L2: Sled embedded database
 //! - L3: Filesystem cache
+//! - Prebuilt runtime artifacts, shared between machines
 
+pub mod artifact;
 pub mod error;
 pub mod l1;
 pub mod l2;
 pub mod manager;
 
+pub use artifact::{ArtifactCache, ArtifactKey, ArtifactStore};
 pub use error::{Error, Result};
 pub use l1::MemoryCache;
 pub use l2::DiskCache;
@@ -44,7 +47,7 @@ pub fn new(value: T, ttl: Duration) -> Self {
     /// Check if this entry is expired
     pub fn is_expired(&self) -> bool {
         if let Ok(elapsed) = self.created_at.elapsed() {
-            elapsed.as_secs() > self.ttl_secs
+            elapsed > Duration::from_secs(self.ttl_secs)
         } else {
             true
         }
@@ -1,6 +1,6 @@
 //! Cache manager coordinating L1 and L2 caches
 
-use crate::{DiskCache, Error, MemoryCache, Result};
+use crate::{DiskCache, MemoryCache, Result};
 use serde::{de::DeserializeOwned, Serialize};
 use std::hash::Hash;
 use std::path::Path;
@@ -6,7 +6,10 @@
 
 pub use error::{Error, Result};
 pub use loader::ConfigLoader;
-pub use schema::{AcceleratorConfig, CacheConfig, ParallelConfig, NotificationConfig};
+pub use schema::{
+    AcceleratorConfig, ArtifactConfig, CacheConfig, NotificationConfig, ParallelConfig,
+    RegistryConfig,
+};
 
 use std::path::Path;
 
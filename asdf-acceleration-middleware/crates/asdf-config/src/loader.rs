@@ -98,7 +98,7 @@ fn default() -> Self {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tempfile::NamedTempFile;
+    use tempfile::Builder;
     use std::io::Write;
 
     #[test]
@@ -110,7 +110,7 @@ fn test_load_nonexistent_file() {
 
     #[test]
     fn test_load_from_file() {
-        let mut file = NamedTempFile::new().unwrap();
+        let mut file = Builder::new().suffix(".toml").tempfile().unwrap();
         writeln!(
             file,
             r#"
@@ -135,4 +135,25 @@ fn test_load_from_file() {
         assert!(config.parallel.fail_fast);
         assert!(!config.notifications.enabled);
     }
+
+    #[test]
+    fn test_load_adaptive_strategy() {
+        let mut file = Builder::new().suffix(".toml").tempfile().unwrap();
+        writeln!(
+            file,
+            r#"
+[parallel]
+strategy = {{ adaptive = {{ min = 1, max = 8 }} }}
+            "#
+        )
+        .unwrap();
+
+        let mut loader = ConfigLoader::new();
+        let config = loader.load_file(file.path()).unwrap();
+
+        assert_eq!(
+            config.parallel.strategy,
+            asdf_parallel::Strategy::Adaptive { min: 1, max: 8 }
+        );
+    }
 }
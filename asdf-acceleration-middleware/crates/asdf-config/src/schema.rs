@@ -1,12 +1,14 @@
 //! Configuration schema
 
+use asdf_core::registry::{self, IndexSource, Registry};
 use asdf_parallel::Strategy;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::time::Duration;
 
 /// Main configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AcceleratorConfig {
     /// Cache configuration
     pub cache: CacheConfig,
@@ -19,21 +21,17 @@ pub struct AcceleratorConfig {
 
     /// Plugin configuration
     pub plugins: PluginConfig,
-}
 
-impl Default for AcceleratorConfig {
-    fn default() -> Self {
-        Self {
-            cache: CacheConfig::default(),
-            parallel: ParallelConfig::default(),
-            notifications: NotificationConfig::default(),
-            plugins: PluginConfig::default(),
-        }
-    }
+    /// Prebuilt runtime artifact configuration
+    pub artifacts: ArtifactConfig,
+
+    /// Plugin short-name index configuration
+    pub registry: RegistryConfig,
 }
 
 /// Cache configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CacheConfig {
     /// Whether caching is enabled
     pub enabled: bool,
@@ -74,8 +72,78 @@ pub fn ttl(&self) -> Duration {
     }
 }
 
+/// Prebuilt runtime artifact configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArtifactConfig {
+    /// Whether installs are restored from and saved to the store
+    pub enabled: bool,
+
+    /// Directory, NFS mount or `http(s)://` URL of the store
+    pub store: Option<String>,
+
+    /// Whether new builds are uploaded (disable on read-only runners)
+    pub upload: bool,
+}
+
+impl Default for ArtifactConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            store: None,
+            upload: true,
+        }
+    }
+}
+
+impl ArtifactConfig {
+    /// Store location, if artifacts are enabled and configured
+    pub fn active_store(&self) -> Option<&str> {
+        self.store.as_deref().filter(|_| self.enabled)
+    }
+}
+
+/// Plugin short-name index configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RegistryConfig {
+    /// Index git URLs or vendored directories, highest precedence first
+    pub sources: Vec<String>,
+
+    /// Where git sources are cached (defaults to the asdf data directory)
+    pub directory: Option<PathBuf>,
+
+    /// Seconds before a cached git source is refreshed
+    pub max_age_secs: u64,
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        Self {
+            sources: vec![registry::DEFAULT_INDEX_URL.to_string()],
+            directory: None,
+            max_age_secs: registry::DEFAULT_MAX_AGE.as_secs(),
+        }
+    }
+}
+
+impl RegistryConfig {
+    /// Build the registry these settings describe
+    pub fn registry(&self) -> asdf_core::Result<Registry> {
+        let cache_dir = match &self.directory {
+            Some(dir) => dir.clone(),
+            None => Registry::default_cache_dir()?,
+        };
+
+        Ok(Registry::new(cache_dir)
+            .with_sources(self.sources.iter().map(|s| IndexSource::parse(s)).collect())
+            .with_max_age(Duration::from_secs(self.max_age_secs)))
+    }
+}
+
 /// Parallel execution configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ParallelConfig {
     /// Execution strategy
     #[serde(default)]
@@ -104,6 +172,7 @@ fn default() -> Self {
 
 /// Notification configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct NotificationConfig {
     /// Whether notifications are enabled
     pub enabled: bool,
@@ -144,6 +213,7 @@ pub enum NotificationLevel {
 
 /// Plugin configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PluginConfig {
     /// Plugins to exclude from operations
     pub exclude: Vec<String>,
@@ -153,6 +223,9 @@ pub struct PluginConfig {
 
     /// Whether to auto-update plugins
     pub auto_update: bool,
+
+    /// Refuse to update plugins missing from the trust store
+    pub require_trust: bool,
 }
 
 impl Default for PluginConfig {
@@ -161,6 +234,7 @@ fn default() -> Self {
             exclude: Vec::new(),
             only: Vec::new(),
             auto_update: true,
+            require_trust: false,
         }
     }
 }
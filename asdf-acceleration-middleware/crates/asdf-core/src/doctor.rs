@@ -0,0 +1,854 @@
+//! Rule-based health checks for an asdf installation
+//!
+//! Each [`Rule`] inspects a [`DoctorContext`] and reports [`Finding`]s with a
+//! severity and a suggested fix. A [`Report`] can be rendered as JSON or SARIF
+//! and maps its worst finding to a process exit code.
+
+use crate::git;
+use crate::tool_versions::{self, ToolVersions};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How serious a finding is
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Informational, nothing to fix
+    Info,
+
+    /// Something works but is likely to cause trouble
+    Warning,
+
+    /// Something is broken
+    Error,
+}
+
+impl Severity {
+    /// SARIF `level` for this severity
+    pub fn sarif_level(&self) -> &'static str {
+        match self {
+            Severity::Info => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A single problem found by a rule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Finding {
+    /// Identifier of the rule that produced this finding
+    pub rule: String,
+
+    /// Severity
+    pub severity: Severity,
+
+    /// What is wrong
+    pub message: String,
+
+    /// How to fix it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+
+    /// File or directory the finding is about
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+impl Finding {
+    /// Create a new finding
+    pub fn new(rule: &str, severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            rule: rule.to_string(),
+            severity,
+            message: message.into(),
+            fix: None,
+            path: None,
+        }
+    }
+
+    /// Attach a fix suggestion
+    pub fn with_fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+
+    /// Attach the affected path
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.rule, self.message)
+    }
+}
+
+/// Everything the rules look at
+///
+/// Built from the environment by [`DoctorContext::from_env`], or by hand in tests.
+#[derive(Debug, Clone)]
+pub struct DoctorContext {
+    /// asdf data directory (`$ASDF_DATA_DIR` or `~/.asdf`)
+    pub data_dir: PathBuf,
+
+    /// Whether `data_dir` was set explicitly through `$ASDF_DATA_DIR`
+    pub data_dir_from_env: bool,
+
+    /// Value of `$PATH`
+    pub path_var: Option<OsString>,
+
+    /// Directory whose `.tool-versions` chain is checked
+    pub working_dir: PathBuf,
+
+    /// Home directory, for the global `.tool-versions`
+    pub home: Option<PathBuf>,
+}
+
+impl DoctorContext {
+    /// Build a context for the current process
+    pub fn from_env() -> crate::Result<Self> {
+        let env_data_dir = env::var_os("ASDF_DATA_DIR").map(PathBuf::from);
+        let data_dir_from_env = env_data_dir.is_some();
+        let data_dir = match env_data_dir {
+            Some(dir) => dir,
+            None => crate::data_dir()?,
+        };
+
+        Ok(Self {
+            data_dir,
+            data_dir_from_env,
+            path_var: env::var_os("PATH"),
+            working_dir: env::current_dir()?,
+            home: env::var_os("HOME").map(PathBuf::from),
+        })
+    }
+
+    fn plugins_dir(&self) -> PathBuf {
+        self.data_dir.join("plugins")
+    }
+
+    fn installs_dir(&self) -> PathBuf {
+        self.data_dir.join("installs")
+    }
+
+    fn shims_dir(&self) -> PathBuf {
+        self.data_dir.join("shims")
+    }
+
+    fn path_entries(&self) -> Vec<PathBuf> {
+        self.path_var
+            .as_ref()
+            .map(|p| env::split_paths(p).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// A named health check
+#[derive(Clone, Copy)]
+pub struct Rule {
+    /// Stable identifier, e.g. `path-order`
+    pub id: &'static str,
+
+    /// One-line description
+    pub description: &'static str,
+
+    /// The check itself
+    pub check: fn(&DoctorContext) -> Vec<Finding>,
+}
+
+impl fmt::Debug for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rule").field("id", &self.id).finish()
+    }
+}
+
+/// All built-in rules, in the order they run
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "asdf-installed",
+        description: "asdf is on PATH",
+        check: check_asdf_installed,
+    },
+    Rule {
+        id: "data-dir",
+        description: "ASDF_DATA_DIR points at a usable directory",
+        check: check_data_dir,
+    },
+    Rule {
+        id: "path-order",
+        description: "asdf shims come before system binaries on PATH",
+        check: check_path_order,
+    },
+    Rule {
+        id: "plugin-remote",
+        description: "Every plugin is a git checkout with a remote",
+        check: check_plugin_remotes,
+    },
+    Rule {
+        id: "plugin-dirty",
+        description: "Plugin checkouts have no local modifications",
+        check: check_plugin_dirty,
+    },
+    Rule {
+        id: "missing-runtime",
+        description: "Versions referenced by .tool-versions are installed",
+        check: check_missing_runtimes,
+    },
+    Rule {
+        id: "corrupt-install",
+        description: "Install directories are complete",
+        check: check_corrupt_installs,
+    },
+    Rule {
+        id: "stale-shim",
+        description: "Shims point at installed runtimes",
+        check: check_stale_shims,
+    },
+];
+
+/// Look up a rule by id
+pub fn rule(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|r| r.id == id)
+}
+
+/// Result of a doctor run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Report {
+    /// All findings, in rule order
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    /// Run all built-in rules
+    pub fn run(ctx: &DoctorContext) -> Self {
+        Self::run_rules(ctx, RULES)
+    }
+
+    /// Run a specific set of rules
+    pub fn run_rules(ctx: &DoctorContext, rules: &[Rule]) -> Self {
+        let findings = rules.iter().flat_map(|rule| (rule.check)(ctx)).collect();
+        Self { findings }
+    }
+
+    /// Most severe finding, if any
+    pub fn worst(&self) -> Option<Severity> {
+        self.findings.iter().map(|f| f.severity).max()
+    }
+
+    /// Whether nothing above informational was found
+    pub fn is_healthy(&self) -> bool {
+        self.worst().map_or(true, |s| s == Severity::Info)
+    }
+
+    /// Process exit code: 0 healthy, 1 warnings, 2 errors
+    pub fn exit_code(&self) -> i32 {
+        match self.worst() {
+            None | Some(Severity::Info) => 0,
+            Some(Severity::Warning) => 1,
+            Some(Severity::Error) => 2,
+        }
+    }
+
+    /// Number of findings per severity
+    pub fn counts(&self) -> BTreeMap<Severity, usize> {
+        let mut counts = BTreeMap::new();
+        for finding in &self.findings {
+            *counts.entry(finding.severity).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Number of findings at a severity
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings.iter().filter(|f| f.severity == severity).count()
+    }
+
+    /// Render findings for a terminal, one per line with their fixes
+    pub fn render_findings(&self) -> String {
+        let mut out = String::new();
+        for finding in &self.findings {
+            let marker = match finding.severity {
+                Severity::Info => "i".cyan(),
+                Severity::Warning => "!".yellow(),
+                Severity::Error => "✗".red(),
+            };
+            out.push_str(&format!("{} {}\n", marker, finding));
+            if let Some(fix) = &finding.fix {
+                out.push_str(&format!("    {} {}\n", "fix:".bright_black(), fix));
+            }
+        }
+        out
+    }
+
+    /// Render as a SARIF 2.1.0 log
+    pub fn to_sarif(&self, tool_name: &str, tool_version: &str) -> serde_json::Value {
+        let rules: Vec<_> = RULES
+            .iter()
+            .map(|rule| {
+                serde_json::json!({
+                    "id": rule.id,
+                    "shortDescription": { "text": rule.description },
+                })
+            })
+            .collect();
+
+        let results: Vec<_> = self
+            .findings
+            .iter()
+            .map(|finding| {
+                let mut result = serde_json::json!({
+                    "ruleId": finding.rule,
+                    "level": finding.severity.sarif_level(),
+                    "message": { "text": finding.message },
+                });
+
+                if let Some(path) = &finding.path {
+                    result["locations"] = serde_json::json!([{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": format!("file://{}", path.display()) }
+                        }
+                    }]);
+                }
+                if let Some(fix) = &finding.fix {
+                    result["properties"] = serde_json::json!({ "fix": fix });
+                }
+
+                result
+            })
+            .collect();
+
+        serde_json::json!({
+            "version": "2.1.0",
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": tool_name,
+                        "version": tool_version,
+                        "rules": rules,
+                    }
+                },
+                "results": results,
+            }],
+        })
+    }
+}
+
+fn check_asdf_installed(ctx: &DoctorContext) -> Vec<Finding> {
+    if which::which_in("asdf", ctx.path_var.as_ref(), &ctx.working_dir).is_ok() {
+        Vec::new()
+    } else {
+        vec![Finding::new("asdf-installed", Severity::Error, "asdf is not on PATH")
+            .with_fix("Install asdf and add it to PATH: https://asdf-vm.com/guide/getting-started.html")]
+    }
+}
+
+fn check_data_dir(ctx: &DoctorContext) -> Vec<Finding> {
+    let dir = &ctx.data_dir;
+    let origin = if ctx.data_dir_from_env {
+        "ASDF_DATA_DIR"
+    } else {
+        "asdf data directory"
+    };
+
+    let finding = if !dir.exists() {
+        Finding::new(
+            "data-dir",
+            Severity::Error,
+            format!("{} {} does not exist", origin, dir.display()),
+        )
+        .with_fix("Create the directory or point ASDF_DATA_DIR at your asdf data directory")
+    } else if !dir.is_dir() {
+        Finding::new(
+            "data-dir",
+            Severity::Error,
+            format!("{} {} is not a directory", origin, dir.display()),
+        )
+        .with_fix("Point ASDF_DATA_DIR at a directory")
+    } else if fs::metadata(dir).map(|m| m.permissions().readonly()).unwrap_or(false) {
+        Finding::new(
+            "data-dir",
+            Severity::Error,
+            format!("{} {} is read-only", origin, dir.display()),
+        )
+        .with_fix("Make the directory writable by the current user")
+    } else {
+        return Vec::new();
+    };
+
+    vec![finding.with_path(dir)]
+}
+
+/// Directories that commonly hold distribution-provided runtimes
+const SYSTEM_BIN_DIRS: &[&str] = &["/usr/local/bin", "/usr/bin", "/bin"];
+
+fn check_path_order(ctx: &DoctorContext) -> Vec<Finding> {
+    let shims = ctx.shims_dir();
+    let entries = ctx.path_entries();
+
+    let Some(shims_pos) = entries.iter().position(|p| *p == shims) else {
+        return vec![Finding::new(
+            "path-order",
+            Severity::Error,
+            format!("{} is not on PATH", shims.display()),
+        )
+        .with_fix("Source asdf.sh from your shell profile, or prepend the shims directory to PATH")
+        .with_path(shims)];
+    };
+
+    let shadowing: Vec<String> = entries[..shims_pos]
+        .iter()
+        .filter(|p| SYSTEM_BIN_DIRS.iter().any(|s| Path::new(s) == p.as_path()))
+        .map(|p| p.display().to_string())
+        .collect();
+
+    if shadowing.is_empty() {
+        Vec::new()
+    } else {
+        vec![Finding::new(
+            "path-order",
+            Severity::Warning,
+            format!(
+                "{} comes after {} on PATH, so system binaries shadow asdf",
+                shims.display(),
+                shadowing.join(", ")
+            ),
+        )
+        .with_fix("Load asdf at the end of your shell profile so its shims are prepended to PATH")]
+    }
+}
+
+/// Plugin checkouts below the plugins directory
+fn plugin_dirs(ctx: &DoctorContext) -> Vec<(String, PathBuf)> {
+    let mut dirs: Vec<(String, PathBuf)> = fs::read_dir(ctx.plugins_dir())
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| Some((e.file_name().into_string().ok()?, e.path())))
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+fn check_plugin_remotes(ctx: &DoctorContext) -> Vec<Finding> {
+    plugin_dirs(ctx)
+        .into_iter()
+        .filter_map(|(name, dir)| {
+            if !dir.join(".git").exists() {
+                return Some(
+                    Finding::new(
+                        "plugin-remote",
+                        Severity::Warning,
+                        format!("Plugin {} is not a git checkout and cannot be updated", name),
+                    )
+                    .with_fix(format!(
+                        "Re-add it: asdf plugin remove {0} && asdf plugin add {0} <url>",
+                        name
+                    ))
+                    .with_path(dir),
+                );
+            }
+
+            let remotes = git::run(&dir, &["remote"]).ok()?;
+            if remotes.trim().is_empty() {
+                Some(
+                    Finding::new(
+                        "plugin-remote",
+                        Severity::Warning,
+                        format!("Plugin {} has no git remote", name),
+                    )
+                    .with_fix(format!("git -C {} remote add origin <url>", dir.display()))
+                    .with_path(dir),
+                )
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn check_plugin_dirty(ctx: &DoctorContext) -> Vec<Finding> {
+    plugin_dirs(ctx)
+        .into_iter()
+        .filter(|(_, dir)| dir.join(".git").exists())
+        .filter_map(|(name, dir)| {
+            let status = git::run(&dir, &["status", "--porcelain"]).ok()?;
+            let changed = status.lines().filter(|l| !l.trim().is_empty()).count();
+            if changed == 0 {
+                return None;
+            }
+            Some(
+                Finding::new(
+                    "plugin-dirty",
+                    Severity::Warning,
+                    format!("Plugin {} has {} locally modified files", name, changed),
+                )
+                .with_fix(format!(
+                    "Review with `git -C {0} status`, then commit or `git -C {0} checkout .`",
+                    dir.display()
+                ))
+                .with_path(dir),
+            )
+        })
+        .collect()
+}
+
+/// `.tool-versions` files that apply to the working directory, nearest first
+fn tool_versions_chain(ctx: &DoctorContext) -> Vec<PathBuf> {
+    tool_versions::chain(&ctx.working_dir, ctx.home.as_deref())
+}
+
+fn check_missing_runtimes(ctx: &DoctorContext) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for file in tool_versions_chain(ctx) {
+        let Ok(tv) = ToolVersions::load(&file) else {
+            continue;
+        };
+
+        for (plugin, versions) in tv.entries() {
+            for version in versions {
+                if version == "system" {
+                    continue;
+                }
+
+                if let Some(path) = version.strip_prefix("path:") {
+                    if !Path::new(path).is_dir() {
+                        findings.push(
+                            Finding::new(
+                                "missing-runtime",
+                                Severity::Error,
+                                format!("{} {} points at a missing directory", plugin, version),
+                            )
+                            .with_fix(format!("Fix or remove the {} entry", plugin))
+                            .with_path(&file),
+                        );
+                    }
+                    continue;
+                }
+
+                let install_name = match version.strip_prefix("ref:") {
+                    Some(r) => format!("ref-{}", r),
+                    None => version.clone(),
+                };
+
+                if !ctx.installs_dir().join(plugin).join(install_name).is_dir() {
+                    findings.push(
+                        Finding::new(
+                            "missing-runtime",
+                            Severity::Error,
+                            format!(
+                                "{} {} is referenced but not installed",
+                                plugin, version
+                            ),
+                        )
+                        .with_fix(format!("asdf install {} {}", plugin, version))
+                        .with_path(&file),
+                    );
+                }
+            }
+        }
+    }
+
+    findings
+}
+
+fn check_corrupt_installs(ctx: &DoctorContext) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for plugin in fs::read_dir(ctx.installs_dir()).into_iter().flatten().flatten() {
+        let plugin_name = plugin.file_name().to_string_lossy().to_string();
+
+        for install in fs::read_dir(plugin.path()).into_iter().flatten().flatten() {
+            let path = install.path();
+            let version = install.file_name().to_string_lossy().to_string();
+
+            let problem = if !path.is_dir() {
+                Some("is not a directory")
+            } else if fs::read_dir(&path).map(|mut d| d.next().is_none()).unwrap_or(true) {
+                Some("is empty")
+            } else if !path.join("bin").is_dir() {
+                Some("has no bin directory")
+            } else {
+                None
+            };
+
+            if let Some(problem) = problem {
+                findings.push(
+                    Finding::new(
+                        "corrupt-install",
+                        Severity::Error,
+                        format!("Install of {} {} {}", plugin_name, version, problem),
+                    )
+                    .with_fix(format!(
+                        "asdf uninstall {0} {1} && asdf install {0} {1}",
+                        plugin_name, version
+                    ))
+                    .with_path(path),
+                );
+            }
+        }
+    }
+
+    findings
+}
+
+/// `(plugin, version)` pairs a shim was generated for
+///
+/// asdf writes one `# asdf-plugin: <plugin> <version>` line per install.
+fn shim_targets(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .filter_map(|line| line.strip_prefix("# asdf-plugin:"))
+        .filter_map(|rest| {
+            let mut parts = rest.split_whitespace();
+            Some((parts.next()?.to_string(), parts.next()?.to_string()))
+        })
+        .collect()
+}
+
+fn check_stale_shims(ctx: &DoctorContext) -> Vec<Finding> {
+    let mut stale = Vec::new();
+
+    for shim in fs::read_dir(ctx.shims_dir()).into_iter().flatten().flatten() {
+        let Ok(content) = fs::read_to_string(shim.path()) else {
+            continue;
+        };
+
+        let targets = shim_targets(&content);
+        if targets.is_empty() {
+            continue;
+        }
+
+        let alive = targets.iter().any(|(plugin, version)| {
+            ctx.installs_dir().join(plugin).join(version).is_dir()
+        });
+
+        if !alive {
+            stale.push(shim.file_name().to_string_lossy().to_string());
+        }
+    }
+
+    if stale.is_empty() {
+        return Vec::new();
+    }
+
+    stale.sort();
+    vec![Finding::new(
+        "stale-shim",
+        Severity::Warning,
+        format!(
+            "{} shims point at runtimes that are no longer installed: {}",
+            stale.len(),
+            stale.join(", ")
+        ),
+    )
+    .with_fix("asdf reshim")
+    .with_path(ctx.shims_dir())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn context(dir: &Path) -> DoctorContext {
+        let data_dir = dir.join("asdf");
+        fs::create_dir_all(data_dir.join("plugins")).unwrap();
+        fs::create_dir_all(data_dir.join("installs")).unwrap();
+        fs::create_dir_all(data_dir.join("shims")).unwrap();
+        let work = dir.join("project");
+        fs::create_dir_all(&work).unwrap();
+
+        DoctorContext {
+            path_var: Some(env::join_paths([data_dir.join("shims"), "/usr/bin".into()]).unwrap()),
+            data_dir,
+            data_dir_from_env: true,
+            working_dir: work,
+            home: None,
+        }
+    }
+
+    fn run(ctx: &DoctorContext, id: &str) -> Vec<Finding> {
+        (rule(id).unwrap().check)(ctx)
+    }
+
+    #[test]
+    fn test_clean_setup_has_no_findings() {
+        let dir = TempDir::new().unwrap();
+        let ctx = context(dir.path());
+        for id in ["data-dir", "path-order", "missing-runtime", "corrupt-install", "stale-shim"] {
+            assert!(run(&ctx, id).is_empty(), "rule {} fired", id);
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_asdf_installed_from_context_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let mut ctx = context(dir.path());
+        ctx.path_var = Some(dir.path().join("bin").into());
+        assert_eq!(run(&ctx, "asdf-installed").len(), 1);
+
+        let asdf = dir.path().join("bin").join("asdf");
+        fs::create_dir_all(asdf.parent().unwrap()).unwrap();
+        fs::write(&asdf, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&asdf, fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(run(&ctx, "asdf-installed").is_empty());
+    }
+
+    #[test]
+    fn test_render_findings() {
+        colored::control::set_override(false);
+        let report = Report {
+            findings: vec![
+                Finding::new("path-order", Severity::Warning, "shims after /usr/bin").with_fix("Move shims first"),
+                Finding::new("stale-shim", Severity::Info, "old shim"),
+            ],
+        };
+        assert_eq!(
+            report.render_findings(),
+            "! [path-order] shims after /usr/bin\n    fix: Move shims first\ni [stale-shim] old shim\n"
+        );
+        assert_eq!(report.count(Severity::Warning), 1);
+    }
+
+    #[test]
+    fn test_data_dir_missing() {
+        let dir = TempDir::new().unwrap();
+        let mut ctx = context(dir.path());
+        ctx.data_dir = dir.path().join("nope");
+        let findings = run(&ctx, "data-dir");
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert!(findings[0].message.contains("ASDF_DATA_DIR"));
+    }
+
+    #[test]
+    fn test_path_order() {
+        let dir = TempDir::new().unwrap();
+        let mut ctx = context(dir.path());
+        ctx.path_var = Some(env::join_paths(["/usr/bin".into(), ctx.shims_dir()]).unwrap());
+
+        let findings = run(&ctx, "path-order");
+        assert_eq!(findings[0].severity, Severity::Warning);
+
+        ctx.path_var = Some("/usr/bin".into());
+        assert_eq!(run(&ctx, "path-order")[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_plugin_without_git() {
+        let dir = TempDir::new().unwrap();
+        let ctx = context(dir.path());
+        fs::create_dir_all(ctx.plugins_dir().join("nodejs")).unwrap();
+
+        let findings = run(&ctx, "plugin-remote");
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].message.contains("nodejs"));
+    }
+
+    #[test]
+    fn test_missing_runtime() {
+        let dir = TempDir::new().unwrap();
+        let ctx = context(dir.path());
+        fs::create_dir_all(ctx.installs_dir().join("nodejs/20.1.0/bin")).unwrap();
+        fs::write(
+            ctx.working_dir.join(tool_versions::FILE_NAME),
+            "nodejs 20.1.0\nruby 3.2.2\npython system\ngolang path:/does/not/exist\n",
+        )
+        .unwrap();
+
+        let findings = run(&ctx, "missing-runtime");
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].fix.as_deref(), Some("asdf install ruby 3.2.2"));
+        assert!(findings[1].message.contains("golang"));
+    }
+
+    #[test]
+    fn test_corrupt_installs() {
+        let dir = TempDir::new().unwrap();
+        let ctx = context(dir.path());
+        fs::create_dir_all(ctx.installs_dir().join("nodejs/20.1.0/bin")).unwrap();
+        fs::create_dir_all(ctx.installs_dir().join("nodejs/18.0.0")).unwrap();
+        fs::create_dir_all(ctx.installs_dir().join("ruby/3.2.2/lib")).unwrap();
+
+        let findings = run(&ctx, "corrupt-install");
+        assert_eq!(findings.len(), 2);
+        assert!(findings.iter().any(|f| f.message.contains("is empty")));
+        assert!(findings.iter().any(|f| f.message.contains("no bin")));
+    }
+
+    #[test]
+    fn test_stale_shims() {
+        let dir = TempDir::new().unwrap();
+        let ctx = context(dir.path());
+        fs::create_dir_all(ctx.installs_dir().join("nodejs/20.1.0/bin")).unwrap();
+        fs::write(
+            ctx.shims_dir().join("node"),
+            "#!/usr/bin/env bash\n# asdf-plugin: nodejs 20.1.0\nexec asdf exec node\n",
+        )
+        .unwrap();
+        fs::write(
+            ctx.shims_dir().join("ruby"),
+            "#!/usr/bin/env bash\n# asdf-plugin: ruby 3.1.0\nexec asdf exec ruby\n",
+        )
+        .unwrap();
+
+        let findings = run(&ctx, "stale-shim");
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].message.ends_with(": ruby"));
+    }
+
+    #[test]
+    fn test_report_exit_code_and_sarif() {
+        let report = Report {
+            findings: vec![
+                Finding::new("path-order", Severity::Warning, "w"),
+                Finding::new("data-dir", Severity::Error, "e")
+                    .with_fix("fix it")
+                    .with_path("/tmp/x"),
+            ],
+        };
+
+        assert_eq!(report.worst(), Some(Severity::Error));
+        assert_eq!(report.exit_code(), 2);
+        assert!(!report.is_healthy());
+        assert_eq!(report.counts()[&Severity::Warning], 1);
+
+        let sarif = report.to_sarif("asdf-discover", "1.0.0");
+        let results = &sarif["runs"][0]["results"];
+        assert_eq!(results[1]["level"], "error");
+        assert_eq!(results[1]["properties"]["fix"], "fix it");
+        assert_eq!(
+            sarif["runs"][0]["tool"]["driver"]["rules"].as_array().unwrap().len(),
+            RULES.len()
+        );
+
+        assert_eq!(Report::default().exit_code(), 0);
+        assert!(Report::default().is_healthy());
+    }
+}
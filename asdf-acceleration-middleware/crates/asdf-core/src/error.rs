@@ -24,6 +24,22 @@ pub enum Error {
     #[error("Invalid version string: {0}")]
     InvalidVersion(String),
 
+    /// Invalid version requirement
+    #[error("Invalid version requirement: {0}")]
+    InvalidVersionReq(String),
+
+    /// Version alias not provided by the plugin
+    #[error("Unknown version alias: {0}")]
+    UnknownVersionAlias(String),
+
+    /// Plugin update refused by the trust store
+    #[error("Refusing to update plugin '{plugin}': {reason}")]
+    TrustViolation { plugin: String, reason: String },
+
+    /// No plugin index source has a local copy
+    #[error("No plugin index available; tried {0}")]
+    RegistryUnavailable(String),
+
     /// Command execution failed
     #[error("Command '{command}' failed: {error}")]
     CommandFailed { command: String, error: String },
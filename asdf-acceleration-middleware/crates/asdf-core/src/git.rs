@@ -0,0 +1,47 @@
+//! Running git in plugin checkouts and index clones
+//!
+//! Doctor rules, trust checks and registry syncs all go through [`run`], so
+//! a failing git command is reported the same way everywhere.
+
+use crate::{Error, Result};
+use std::path::Path;
+
+/// Run git in `dir`, returning its trimmed standard output
+///
+/// A non-zero exit is an [`Error::CommandFailed`] carrying git's stderr.
+pub(crate) fn run(dir: &Path, args: &[&str]) -> Result<String> {
+    let command = || format!("git {}", args.join(" "));
+    let output = duct::cmd("git", args)
+        .dir(dir)
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()
+        .map_err(|e| Error::CommandFailed {
+            command: command(),
+            error: e.to_string(),
+        })?;
+
+    if !output.status.success() {
+        return Err(Error::CommandFailed {
+            command: command(),
+            error: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_reports_stderr() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let err = run(dir.path(), &["rev-parse", "HEAD"]).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("git rev-parse HEAD"), "{}", message);
+        assert!(message.to_lowercase().contains("not a git repository"), "{}", message);
+    }
+}
@@ -3,17 +3,30 @@
 //! This crate provides the fundamental types and operations for interacting
 //! with asdf, the extendable version manager.
 
+pub mod doctor;
 pub mod error;
+mod git;
 pub mod plugin;
+pub mod prune;
+pub mod registry;
 pub mod runtime;
+#[cfg(all(unix, any(test, feature = "testing")))]
+pub mod testing;
+pub mod tool_versions;
+pub mod trust;
 pub mod version;
+pub mod version_req;
 
 pub use error::{Error, Result};
 pub use plugin::Plugin;
+pub use registry::{Index, IndexSource, Registry};
 pub use runtime::Runtime;
+pub use tool_versions::ToolVersions;
+pub use trust::{TrustEntry, TrustStore};
 pub use version::Version;
+pub use version_req::{VersionHints, VersionReq};
 
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::env;
 
 /// Get the asdf installation directory
@@ -28,19 +41,33 @@ pub fn asdf_dir() -> Result<PathBuf> {
         .map_err(|_| Error::AsdfNotFound)
 }
 
+/// Get the asdf data directory
+///
+/// Plugins, installs and shims live here rather than in [`asdf_dir`]; the
+/// two only coincide when `ASDF_DATA_DIR` is unset and asdf sits in `~/.asdf`.
+pub fn data_dir() -> Result<PathBuf> {
+    env::var("ASDF_DATA_DIR")
+        .map(PathBuf::from)
+        .or_else(|_| {
+            env::var("HOME")
+                .map(|home| PathBuf::from(home).join(".asdf"))
+        })
+        .map_err(|_| Error::AsdfNotFound)
+}
+
 /// Get the asdf plugins directory
 pub fn plugins_dir() -> Result<PathBuf> {
-    Ok(asdf_dir()?.join("plugins"))
+    Ok(data_dir()?.join("plugins"))
 }
 
 /// Get the asdf installs directory
 pub fn installs_dir() -> Result<PathBuf> {
-    Ok(asdf_dir()?.join("installs"))
+    Ok(data_dir()?.join("installs"))
 }
 
 /// Get the asdf shims directory
 pub fn shims_dir() -> Result<PathBuf> {
-    Ok(asdf_dir()?.join("shims"))
+    Ok(data_dir()?.join("shims"))
 }
 
 /// Check if asdf is installed
@@ -1,8 +1,9 @@
 //! Plugin management
 
-use crate::{Error, Result};
+use crate::{Error, Result, VersionHints, VersionReq};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use tracing::instrument;
 
 /// Represents an asdf plugin
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -50,6 +51,7 @@ pub fn with_ref(mut self, ref_: impl Into<String>) -> Self {
     }
 
     /// List all installed plugins
+    #[instrument(level = "debug")]
     pub fn list() -> Result<Vec<Plugin>> {
         let output = duct::cmd!("asdf", "plugin", "list", "--urls")
             .read()
@@ -75,9 +77,11 @@ pub fn list() -> Result<Vec<Plugin>> {
     }
 
     /// Add this plugin
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.name))]
     pub fn add(&self) -> Result<()> {
         if let Some(url) = &self.url {
             duct::cmd!("asdf", "plugin", "add", &self.name, url)
+                .stdout_to_stderr()
                 .run()
                 .map_err(|e| Error::CommandFailed {
                     command: format!("asdf plugin add {} {}", self.name, url),
@@ -85,6 +89,7 @@ pub fn add(&self) -> Result<()> {
                 })?;
         } else {
             duct::cmd!("asdf", "plugin", "add", &self.name)
+                .stdout_to_stderr()
                 .run()
                 .map_err(|e| Error::CommandFailed {
                     command: format!("asdf plugin add {}", self.name),
@@ -96,8 +101,10 @@ pub fn add(&self) -> Result<()> {
     }
 
     /// Remove this plugin
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.name))]
     pub fn remove(&self) -> Result<()> {
         duct::cmd!("asdf", "plugin", "remove", &self.name)
+            .stdout_to_stderr()
             .run()
             .map_err(|e| Error::CommandFailed {
                 command: format!("asdf plugin remove {}", self.name),
@@ -108,9 +115,11 @@ pub fn remove(&self) -> Result<()> {
     }
 
     /// Update this plugin
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.name))]
     pub fn update(&self) -> Result<()> {
         if let Some(ref_) = &self.ref_ {
             duct::cmd!("asdf", "plugin", "update", &self.name, ref_)
+                .stdout_to_stderr()
                 .run()
                 .map_err(|e| Error::CommandFailed {
                     command: format!("asdf plugin update {} {}", self.name, ref_),
@@ -118,6 +127,7 @@ pub fn update(&self) -> Result<()> {
                 })?;
         } else {
             duct::cmd!("asdf", "plugin", "update", &self.name)
+                .stdout_to_stderr()
                 .run()
                 .map_err(|e| Error::CommandFailed {
                     command: format!("asdf plugin update {}", self.name),
@@ -128,7 +138,32 @@ pub fn update(&self) -> Result<()> {
         Ok(())
     }
 
+    /// Local checkout directory of this plugin
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.name))]
+    pub fn dir(&self) -> Result<PathBuf> {
+        match &self.path {
+            Some(path) => Ok(path.clone()),
+            None => Ok(crate::plugins_dir()?.join(&self.name)),
+        }
+    }
+
+    /// Commit currently checked out in the plugin repository
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.name))]
+    pub fn current_ref(&self) -> Result<String> {
+        let dir = self.dir()?;
+        let output = duct::cmd!("git", "-C", &dir, "rev-parse", "HEAD")
+            .stderr_null()
+            .read()
+            .map_err(|e| Error::CommandFailed {
+                command: format!("git -C {} rev-parse HEAD", dir.display()),
+                error: e.to_string(),
+            })?;
+
+        Ok(output.trim().to_string())
+    }
+
     /// List available versions for this plugin
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.name))]
     pub fn list_all_versions(&self) -> Result<Vec<String>> {
         let output = duct::cmd!("asdf", "list", "all", &self.name)
             .read()
@@ -145,6 +180,41 @@ pub fn list_all_versions(&self) -> Result<Vec<String>> {
 
         Ok(versions)
     }
+
+    /// Version aliases such as `lts` published by the plugin
+    ///
+    /// Plugins opt in with an executable `bin/version-aliases` that prints
+    /// `<alias> <requirement>` lines, e.g. `lts ^20`.
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.name))]
+    pub fn version_hints(&self) -> Result<VersionHints> {
+        let script = self.dir()?.join("bin").join("version-aliases");
+        if !script.is_file() {
+            return Ok(VersionHints::new());
+        }
+
+        let output = duct::cmd!(&script)
+            .stderr_null()
+            .read()
+            .map_err(|e| Error::CommandFailed {
+                command: script.display().to_string(),
+                error: e.to_string(),
+            })?;
+
+        Ok(VersionHints::parse(&output))
+    }
+
+    /// Highest available version satisfying `req`
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.name, req = %req))]
+    pub fn resolve_version(&self, req: &VersionReq) -> Result<Option<String>> {
+        let available = self.list_all_versions()?;
+        // Only aliases need the plugin's hook run
+        let hints = match req.alias() {
+            Some(_) => self.version_hints()?,
+            None => VersionHints::new(),
+        };
+
+        req.resolve_with(&available, &hints)
+    }
 }
 
 #[cfg(test)]
@@ -167,6 +237,12 @@ fn test_plugin_with_url() {
         assert!(plugin.url.is_some());
     }
 
+    #[test]
+    fn test_plugin_dir() {
+        let plugin = Plugin::new("nodejs").with_path("/opt/asdf/plugins/nodejs");
+        assert_eq!(plugin.dir().unwrap(), PathBuf::from("/opt/asdf/plugins/nodejs"));
+    }
+
     #[test]
     fn test_plugin_serialization() {
         let plugin = Plugin::new("nodejs")
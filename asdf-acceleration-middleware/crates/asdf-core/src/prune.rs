@@ -0,0 +1,215 @@
+//! Orphan detection
+//!
+//! The asdf data directory collects things nothing uses any more: plugins
+//! without a single installed runtime, install trees left behind when their
+//! plugin was removed, and downloads of versions that are no longer
+//! installed. [`scan`] finds them and [`Orphan::remove`] reclaims one.
+
+use crate::{Plugin, Result};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What kind of leftover an [`Orphan`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanKind {
+    /// A plugin with no installed runtime
+    Plugin,
+
+    /// `installs/<plugin>` whose plugin is gone
+    Install,
+
+    /// `downloads/<plugin>[/<version>]` with no matching install
+    Download,
+}
+
+impl fmt::Display for OrphanKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OrphanKind::Plugin => "plugin",
+            OrphanKind::Install => "install",
+            OrphanKind::Download => "download",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Something in the data directory that can be removed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Orphan {
+    /// Kind of leftover
+    pub kind: OrphanKind,
+
+    /// Plugin name, or `plugin/version` for a single download
+    pub name: String,
+
+    /// Directory that would be removed
+    pub path: PathBuf,
+
+    /// Disk space it takes up
+    pub bytes: u64,
+}
+
+impl Orphan {
+    fn new(kind: OrphanKind, name: impl Into<String>, path: PathBuf) -> Self {
+        let bytes = dir_size(&path);
+        Self {
+            kind,
+            name: name.into(),
+            path,
+            bytes,
+        }
+    }
+
+    /// Remove it: plugins through asdf, everything else from disk
+    ///
+    /// A directory that is already gone counts as removed, since removing a
+    /// plugin also takes its downloads with it.
+    pub fn remove(&self) -> Result<()> {
+        match self.kind {
+            OrphanKind::Plugin => Plugin::new(self.name.as_str()).remove(),
+            OrphanKind::Install | OrphanKind::Download => match fs::remove_dir_all(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+/// Find orphans in the asdf data directory `data_dir`
+///
+/// Plugins named in `keep`, such as the tools of the `.tool-versions` in
+/// effect, are kept even without installs. Results are ordered by kind,
+/// then name.
+pub fn scan(data_dir: &Path, keep: &HashSet<String>) -> Result<Vec<Orphan>> {
+    let plugins: HashSet<String> = subdirs(&data_dir.join("plugins"))?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    let installs_dir = data_dir.join("installs");
+    let mut orphans = Vec::new();
+
+    for plugin in &plugins {
+        let installed = subdirs(&installs_dir.join(plugin))?;
+        if installed.is_empty() && !keep.contains(plugin) {
+            orphans.push(Orphan::new(
+                OrphanKind::Plugin,
+                plugin.as_str(),
+                data_dir.join("plugins").join(plugin),
+            ));
+        }
+    }
+
+    for (plugin, path) in subdirs(&installs_dir)? {
+        if !plugins.contains(&plugin) {
+            orphans.push(Orphan::new(OrphanKind::Install, plugin, path));
+        }
+    }
+
+    for (plugin, path) in subdirs(&data_dir.join("downloads"))? {
+        if !plugins.contains(&plugin) {
+            orphans.push(Orphan::new(OrphanKind::Download, plugin, path));
+            continue;
+        }
+        for (version, download) in subdirs(&path)? {
+            if !installs_dir.join(&plugin).join(&version).is_dir() {
+                let name = format!("{}/{}", plugin, version);
+                orphans.push(Orphan::new(OrphanKind::Download, name, download));
+            }
+        }
+    }
+
+    orphans.sort_by(|a, b| (a.kind, &a.name).cmp(&(b.kind, &b.name)));
+    Ok(orphans)
+}
+
+/// Subdirectories of `dir` by name; none if it does not exist
+fn subdirs(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut dirs = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            if let Ok(name) = entry.file_name().into_string() {
+                dirs.push((name, entry.path()));
+            }
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// Total size of the files under `path`, without following symlinks
+fn dir_size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+
+    fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| dir_size(&e.path()))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn mkdir(root: &Path, path: &str) {
+        fs::create_dir_all(root.join(path)).unwrap();
+    }
+
+    #[test]
+    fn test_scan() {
+        let data = TempDir::new().unwrap();
+        let root = data.path();
+
+        // In use
+        mkdir(root, "plugins/nodejs");
+        mkdir(root, "installs/nodejs/20.11.0");
+        mkdir(root, "downloads/nodejs/20.11.0");
+        // No installs, but named in .tool-versions
+        mkdir(root, "plugins/python");
+        // Orphans
+        mkdir(root, "plugins/ruby");
+        mkdir(root, "installs/golang/1.22.0/bin");
+        fs::write(root.join("installs/golang/1.22.0/bin/go"), "0123456789").unwrap();
+        mkdir(root, "downloads/nodejs/18.0.0");
+        mkdir(root, "downloads/golang/1.22.0");
+
+        let keep = HashSet::from(["python".to_string()]);
+        let orphans = scan(root, &keep).unwrap();
+        let found: Vec<(OrphanKind, &str)> =
+            orphans.iter().map(|o| (o.kind, o.name.as_str())).collect();
+
+        assert_eq!(
+            found,
+            [
+                (OrphanKind::Plugin, "ruby"),
+                (OrphanKind::Install, "golang"),
+                (OrphanKind::Download, "golang"),
+                (OrphanKind::Download, "nodejs/18.0.0"),
+            ]
+        );
+        assert_eq!(orphans[1].bytes, 10);
+
+        orphans[3].remove().unwrap();
+        assert!(!root.join("downloads/nodejs/18.0.0").exists());
+        // Already gone is fine
+        orphans[3].remove().unwrap();
+    }
+}
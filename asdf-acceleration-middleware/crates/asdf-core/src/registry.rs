@@ -0,0 +1,626 @@
+//! Plugin short-name registry
+//!
+//! asdf resolves `asdf plugin add <name>` through the asdf-plugins
+//! repository: one file per plugin under `plugins/`, holding
+//! `repository = <url>`. [`Registry`] keeps local copies of one or more such
+//! indexes, refreshing git sources when they go stale and reading vendored
+//! directories as they are, so lookups and [`Index::search`] work offline.
+//!
+//! Sources are listed in order of precedence: when two sources name the same
+//! plugin, the first one wins.
+
+use crate::{git, Error, Plugin, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, instrument, warn};
+
+/// The index asdf itself uses
+pub const DEFAULT_INDEX_URL: &str = "https://github.com/asdf-vm/asdf-plugins.git";
+
+/// How long a fetched index is used before it is refreshed
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(3600);
+
+/// Where an index comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexSource {
+    /// Git repository, cloned into the registry cache
+    Git(String),
+
+    /// Vendored index directory, used as is
+    Dir(PathBuf),
+}
+
+impl IndexSource {
+    /// Parse a source: URLs are git repositories, anything else a directory
+    pub fn parse(s: &str) -> Self {
+        if s.contains("://") || s.starts_with("git@") || s.ends_with(".git") {
+            IndexSource::Git(s.to_string())
+        } else {
+            IndexSource::Dir(PathBuf::from(s))
+        }
+    }
+
+    /// Directory name of a git source's checkout in the cache
+    fn slug(url: &str) -> String {
+        let trimmed = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let trimmed = trimmed.trim_end_matches('/').trim_end_matches(".git");
+
+        trimmed
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' {
+                    c
+                } else {
+                    '-'
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for IndexSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexSource::Git(url) => write!(f, "{}", url),
+            IndexSource::Dir(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// A plugin named by an index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IndexEntry {
+    /// Short name
+    pub name: String,
+
+    /// Repository URL
+    pub repository: String,
+
+    /// Source the entry was read from
+    pub source: String,
+}
+
+/// A search result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchHit {
+    /// Matching entry
+    #[serde(flatten)]
+    pub entry: IndexEntry,
+
+    /// How well the name matched; higher is better
+    pub score: u32,
+}
+
+/// Merged plugin index
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Index {
+    entries: BTreeMap<String, IndexEntry>,
+}
+
+impl Index {
+    /// Read an index directory in the asdf-plugins layout
+    pub fn read_dir(dir: impl AsRef<Path>, source: &str) -> Result<Self> {
+        let plugins = dir.as_ref().join("plugins");
+        let mut entries = BTreeMap::new();
+
+        for file in fs::read_dir(&plugins)? {
+            let path = file?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if name.starts_with('.') || !path.is_file() {
+                continue;
+            }
+
+            match parse_entry(&fs::read_to_string(&path)?) {
+                Some(repository) => {
+                    entries.insert(
+                        name.to_string(),
+                        IndexEntry {
+                            name: name.to_string(),
+                            repository,
+                            source: source.to_string(),
+                        },
+                    );
+                }
+                None => debug!("No repository in {}", path.display()),
+            }
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Add the entries of a lower-precedence index
+    pub fn merge(&mut self, other: Index) {
+        for (name, entry) in other.entries {
+            self.entries.entry(name).or_insert(entry);
+        }
+    }
+
+    /// Entry for a short name
+    pub fn get(&self, name: &str) -> Option<&IndexEntry> {
+        self.entries.get(name)
+    }
+
+    /// Number of plugins in the index
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the index names no plugins
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries in name order
+    pub fn iter(&self) -> impl Iterator<Item = &IndexEntry> {
+        self.entries.values()
+    }
+
+    /// Plugins whose name matches `query`, best matches first
+    ///
+    /// Matching is case-insensitive and tolerates missing letters
+    /// (`nodjs`) and small typos (`pyhton`).
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let query = query.to_lowercase();
+        let mut hits: Vec<SearchHit> = self
+            .entries
+            .values()
+            .filter_map(|entry| {
+                fuzzy_score(&query, &entry.name.to_lowercase()).map(|score| SearchHit {
+                    entry: entry.clone(),
+                    score,
+                })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| b.score.cmp(&a.score).then(a.entry.name.cmp(&b.entry.name)));
+        hits
+    }
+}
+
+/// Local copies of plugin indexes
+#[derive(Debug, Clone)]
+pub struct Registry {
+    sources: Vec<IndexSource>,
+    cache_dir: PathBuf,
+    max_age: Duration,
+}
+
+impl Registry {
+    /// Create a registry caching git sources under `cache_dir`
+    pub fn new(cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            sources: vec![IndexSource::Git(DEFAULT_INDEX_URL.to_string())],
+            cache_dir: cache_dir.into(),
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+
+    /// Default cache location
+    pub fn default_cache_dir() -> Result<PathBuf> {
+        Ok(crate::data_dir()?.join("registry"))
+    }
+
+    /// Replace the sources, highest precedence first
+    pub fn with_sources(mut self, sources: Vec<IndexSource>) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    /// Set how long a fetched index is used before it is refreshed
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Sources, highest precedence first
+    pub fn sources(&self) -> &[IndexSource] {
+        &self.sources
+    }
+
+    /// Local directory holding a source's index
+    pub fn checkout(&self, source: &IndexSource) -> PathBuf {
+        match source {
+            IndexSource::Git(url) => self.cache_dir.join(IndexSource::slug(url)),
+            IndexSource::Dir(path) => path.clone(),
+        }
+    }
+
+    /// Fetch git sources that are missing or stale, or all of them if `force`
+    ///
+    /// Failures are returned rather than raised, so a registry with a cached
+    /// copy keeps working offline.
+    #[instrument(level = "debug", skip(self))]
+    pub fn refresh(&self, force: bool) -> Vec<(IndexSource, Error)> {
+        let mut failures = Vec::new();
+
+        for source in &self.sources {
+            let IndexSource::Git(url) = source else {
+                continue;
+            };
+            let dir = self.checkout(source);
+            if !force && self.is_fresh(&dir) {
+                continue;
+            }
+
+            debug!("Refreshing plugin index {}", url);
+            if let Err(e) = fetch(url, &dir) {
+                warn!("Could not refresh plugin index {}: {}", url, e);
+                failures.push((source.clone(), e));
+            }
+        }
+
+        failures
+    }
+
+    /// Merge the local copies of every source, without touching the network
+    ///
+    /// Sources without a local copy are skipped; it is an error only if
+    /// none has one.
+    pub fn index(&self) -> Result<Index> {
+        let mut index = Index::default();
+        let mut found = false;
+
+        for source in &self.sources {
+            let dir = self.checkout(source);
+            if !dir.join("plugins").is_dir() {
+                debug!("No local copy of plugin index {}", source);
+                continue;
+            }
+            index.merge(Index::read_dir(&dir, &source.to_string())?);
+            found = true;
+        }
+
+        if found {
+            Ok(index)
+        } else {
+            Err(Error::RegistryUnavailable(
+                self.sources
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ))
+        }
+    }
+
+    /// Refresh stale sources where possible, then merge them
+    pub fn load(&self) -> Result<Index> {
+        self.refresh(false);
+        self.index()
+    }
+
+    fn is_fresh(&self, dir: &Path) -> bool {
+        let git = dir.join(".git");
+        let stamp = fs::metadata(git.join("FETCH_HEAD"))
+            .or_else(|_| fs::metadata(git.join("HEAD")))
+            .and_then(|m| m.modified());
+
+        match stamp.map(|modified| SystemTime::now().duration_since(modified)) {
+            Ok(Ok(age)) => age < self.max_age,
+            // Modified in the future: treat as just fetched
+            Ok(Err(_)) => true,
+            Err(_) => false,
+        }
+    }
+}
+
+impl Plugin {
+    /// Add this plugin, taking its URL from `index` when none is set
+    ///
+    /// Plugins the index does not name fall back to asdf's own lookup.
+    pub fn add_from(&self, index: &Index) -> Result<()> {
+        match (&self.url, index.get(&self.name)) {
+            (None, Some(entry)) => self.clone().with_url(entry.repository.as_str()).add(),
+            _ => self.add(),
+        }
+    }
+}
+
+/// Repository URL of a `plugins/<name>` file
+fn parse_entry(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == "repository")
+            .then(|| value.trim().to_string())
+            .filter(|url| !url.is_empty())
+    })
+}
+
+/// Clone `url` into `dir`, or fast-forward an existing clone
+#[instrument(level = "debug", skip(dir))]
+fn fetch(url: &str, dir: &Path) -> Result<()> {
+    if dir.join(".git").is_dir() {
+        git::run(dir, &["fetch", "--quiet", "--depth", "1", "origin", "HEAD"])?;
+        git::run(dir, &["reset", "--quiet", "--hard", "FETCH_HEAD"])?;
+        return Ok(());
+    }
+
+    if let Some(parent) = dir.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Clone beside the target, so an interrupted clone never looks cached
+    let partial = partial_dir(dir);
+    if partial.exists() {
+        fs::remove_dir_all(&partial)?;
+    }
+    let parent = dir.parent().unwrap_or(Path::new("."));
+    let partial_str = partial.to_string_lossy();
+    git::run(
+        parent,
+        &["clone", "--quiet", "--depth", "1", url, &partial_str],
+    )?;
+    fs::rename(&partial, dir)?;
+
+    Ok(())
+}
+
+/// Staging directory for a clone of `dir`, beside it
+///
+/// Cache directories are named after hosts such as `github.com-...`, so
+/// the suffix is appended rather than replacing an "extension".
+fn partial_dir(dir: &Path) -> PathBuf {
+    let name = dir.file_name().unwrap_or_default().to_string_lossy();
+    dir.with_file_name(format!("{}.partial", name))
+}
+
+/// Score `name` against `query`, both lowercase; `None` if it does not match
+///
+/// Exact names beat prefixes, prefixes beat substrings, substrings beat
+/// scattered letters, and those beat near-misses by edit distance.
+fn fuzzy_score(query: &str, name: &str) -> Option<u32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    if name == query {
+        return Some(1000);
+    }
+    if name.starts_with(query) {
+        return Some(900u32.saturating_sub(extra(name, query)));
+    }
+    if let Some(position) = name.find(query) {
+        return Some(700u32.saturating_sub(position as u32 + extra(name, query)));
+    }
+    if let Some(gaps) = subsequence_gaps(query, name) {
+        return Some(
+            500u32
+                .saturating_sub(gaps * 10 + extra(name, query))
+                .max(300),
+        );
+    }
+
+    let distance = edit_distance(query, name);
+    let allowed = (query.chars().count() / 4).max(1);
+    (distance <= allowed).then(|| 200u32.saturating_sub(distance as u32 * 50))
+}
+
+/// Characters of `name` beyond the query's length
+fn extra(name: &str, query: &str) -> u32 {
+    (name.chars().count().saturating_sub(query.chars().count())) as u32
+}
+
+/// Letters skipped while finding `query` in order within `name`
+fn subsequence_gaps(query: &str, name: &str) -> Option<u32> {
+    let mut gaps = 0;
+    let mut chars = name.chars();
+
+    for wanted in query.chars() {
+        loop {
+            match chars.next() {
+                Some(c) if c == wanted => break,
+                Some(_) => gaps += 1,
+                None => return None,
+            }
+        }
+    }
+
+    Some(gaps)
+}
+
+/// Optimal string alignment distance: edits, counting swapped neighbours as one
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn index_dir(entries: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let plugins = dir.path().join("plugins");
+        fs::create_dir_all(&plugins).unwrap();
+        for (name, url) in entries {
+            fs::write(plugins.join(name), format!("repository = {}\n", url)).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_source_parse() {
+        assert_eq!(
+            IndexSource::parse(DEFAULT_INDEX_URL),
+            IndexSource::Git(DEFAULT_INDEX_URL.to_string())
+        );
+        assert_eq!(
+            IndexSource::parse("/opt/asdf-plugins"),
+            IndexSource::Dir(PathBuf::from("/opt/asdf-plugins"))
+        );
+        assert_eq!(
+            IndexSource::slug(DEFAULT_INDEX_URL),
+            "github.com-asdf-vm-asdf-plugins"
+        );
+    }
+
+    #[test]
+    fn test_read_dir() {
+        let dir = index_dir(&[
+            ("nodejs", "https://github.com/asdf-vm/asdf-nodejs.git"),
+            ("python", "https://github.com/danhper/asdf-python.git"),
+        ]);
+        fs::write(dir.path().join("plugins").join("broken"), "ref = main\n").unwrap();
+
+        let index = Index::read_dir(dir.path(), "vendored").unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(
+            index.get("python").unwrap().repository,
+            "https://github.com/danhper/asdf-python.git"
+        );
+        assert!(index.get("broken").is_none());
+    }
+
+    #[test]
+    fn test_sources_in_order_of_precedence() {
+        let internal = index_dir(&[("nodejs", "https://git.example.com/asdf-nodejs.git")]);
+        let upstream = index_dir(&[
+            ("nodejs", "https://github.com/asdf-vm/asdf-nodejs.git"),
+            ("ruby", "https://github.com/asdf-vm/asdf-ruby.git"),
+        ]);
+
+        let index = Registry::new("/nonexistent")
+            .with_sources(vec![
+                IndexSource::Dir(internal.path().to_path_buf()),
+                IndexSource::Git("https://example.com/never-fetched.git".to_string()),
+                IndexSource::Dir(upstream.path().to_path_buf()),
+            ])
+            .index()
+            .unwrap();
+
+        assert_eq!(
+            index.get("nodejs").unwrap().repository,
+            "https://git.example.com/asdf-nodejs.git"
+        );
+        assert!(index.get("ruby").is_some());
+    }
+
+    #[test]
+    fn test_no_local_copy() {
+        let registry = Registry::new("/nonexistent")
+            .with_sources(vec![IndexSource::Git(DEFAULT_INDEX_URL.to_string())]);
+
+        assert!(matches!(
+            registry.index(),
+            Err(Error::RegistryUnavailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_partial_dir_per_source() {
+        let registry = Registry::new("/cache");
+        let checkout = |url: &str| registry.checkout(&IndexSource::Git(url.to_string()));
+        let ours = partial_dir(&checkout("https://github.com/asdf-vm/asdf-plugins.git"));
+        let theirs = partial_dir(&checkout("https://github.com/acme/asdf-plugins.git"));
+
+        assert_ne!(ours, theirs);
+        assert_eq!(ours.parent(), Some(Path::new("/cache")));
+        assert!(ours.to_string_lossy().ends_with("asdf-plugins.partial"), "{}", ours.display());
+    }
+
+    #[test]
+    fn test_git_source_is_cached() {
+        let upstream = index_dir(&[(
+            "golang",
+            "https://github.com/asdf-community/asdf-golang.git",
+        )]);
+        let run = |args: &[&str]| {
+            duct::cmd("git", args)
+                .dir(upstream.path())
+                .env("GIT_AUTHOR_NAME", "test")
+                .env("GIT_AUTHOR_EMAIL", "test@example.invalid")
+                .env("GIT_COMMITTER_NAME", "test")
+                .env("GIT_COMMITTER_EMAIL", "test@example.invalid")
+                .stdout_null()
+                .run()
+                .unwrap();
+        };
+        run(&["init", "--quiet"]);
+        run(&["add", "-A"]);
+        run(&["commit", "--quiet", "-m", "Index"]);
+
+        let cache = TempDir::new().unwrap();
+        let url = format!("file://{}", upstream.path().display());
+        let registry = Registry::new(cache.path()).with_sources(vec![IndexSource::Git(url)]);
+
+        assert!(registry.refresh(false).is_empty());
+        assert!(registry.index().unwrap().get("golang").is_some());
+
+        // A later source change only shows after a refresh
+        fs::write(
+            upstream.path().join("plugins").join("rust"),
+            "repository = https://github.com/code-lever/asdf-rust.git\n",
+        )
+        .unwrap();
+        run(&["add", "-A"]);
+        run(&["commit", "--quiet", "-m", "Add rust"]);
+
+        assert!(registry.refresh(false).is_empty());
+        assert!(registry.index().unwrap().get("rust").is_none());
+        assert!(registry.refresh(true).is_empty());
+        assert!(registry.index().unwrap().get("rust").is_some());
+    }
+
+    #[test]
+    fn test_search_ranking() {
+        let dir = index_dir(&[
+            ("nodejs", "a"),
+            ("node", "b"),
+            ("deno", "c"),
+            ("python", "d"),
+            ("pdm", "e"),
+            ("ruby", "f"),
+        ]);
+        let index = Index::read_dir(dir.path(), "test").unwrap();
+        let names = |query: &str| -> Vec<String> {
+            index
+                .search(query)
+                .into_iter()
+                .map(|hit| hit.entry.name)
+                .collect()
+        };
+
+        assert_eq!(names("node"), vec!["node", "nodejs"]);
+        assert_eq!(names("NodJS"), vec!["nodejs"]);
+        assert_eq!(names("pyhton"), vec!["python"]);
+        assert_eq!(names("eno"), vec!["deno"]);
+        assert!(names("haskell").is_empty());
+        assert_eq!(names("").len(), 6);
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("ruby", "ruby"), 0);
+        assert_eq!(edit_distance("rbuy", "ruby"), 1);
+        assert_eq!(edit_distance("rub", "ruby"), 1);
+        assert_eq!(edit_distance("java", "ruby"), 4);
+    }
+}
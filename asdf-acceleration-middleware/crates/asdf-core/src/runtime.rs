@@ -2,6 +2,8 @@
 
 use crate::{Error, Result, Version};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::instrument;
 
 /// Represents an installed runtime version
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -27,6 +29,7 @@ pub fn new(plugin: impl Into<String>, version: Version) -> Self {
     }
 
     /// List installed runtimes for a plugin
+    #[instrument(level = "debug")]
     pub fn list_for_plugin(plugin: &str) -> Result<Vec<Runtime>> {
         let output = duct::cmd!("asdf", "list", plugin)
             .read()
@@ -58,6 +61,7 @@ pub fn list_for_plugin(plugin: &str) -> Result<Vec<Runtime>> {
     }
 
     /// Install this runtime
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.plugin, version = %self.version))]
     pub fn install(&self) -> Result<()> {
         duct::cmd!(
             "asdf",
@@ -65,6 +69,7 @@ pub fn install(&self) -> Result<()> {
             &self.plugin,
             self.version.to_string()
         )
+        .stdout_to_stderr()
         .run()
         .map_err(|e| Error::CommandFailed {
             command: format!("asdf install {} {}", self.plugin, self.version),
@@ -75,6 +80,7 @@ pub fn install(&self) -> Result<()> {
     }
 
     /// Uninstall this runtime
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.plugin, version = %self.version))]
     pub fn uninstall(&self) -> Result<()> {
         duct::cmd!(
             "asdf",
@@ -82,6 +88,7 @@ pub fn uninstall(&self) -> Result<()> {
             &self.plugin,
             self.version.to_string()
         )
+        .stdout_to_stderr()
         .run()
         .map_err(|e| Error::CommandFailed {
             command: format!("asdf uninstall {} {}", self.plugin, self.version),
@@ -92,6 +99,7 @@ pub fn uninstall(&self) -> Result<()> {
     }
 
     /// Set this runtime as global default
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.plugin, version = %self.version))]
     pub fn set_global(&self) -> Result<()> {
         duct::cmd!(
             "asdf",
@@ -99,6 +107,7 @@ pub fn set_global(&self) -> Result<()> {
             &self.plugin,
             self.version.to_string()
         )
+        .stdout_to_stderr()
         .run()
         .map_err(|e| Error::CommandFailed {
             command: format!("asdf global {} {}", self.plugin, self.version),
@@ -109,6 +118,7 @@ pub fn set_global(&self) -> Result<()> {
     }
 
     /// Set this runtime as local default
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.plugin, version = %self.version))]
     pub fn set_local(&self) -> Result<()> {
         duct::cmd!(
             "asdf",
@@ -116,6 +126,7 @@ pub fn set_local(&self) -> Result<()> {
             &self.plugin,
             self.version.to_string()
         )
+        .stdout_to_stderr()
         .run()
         .map_err(|e| Error::CommandFailed {
             command: format!("asdf local {} {}", self.plugin, self.version),
@@ -124,6 +135,65 @@ pub fn set_local(&self) -> Result<()> {
 
         Ok(())
     }
+
+    /// Directory this runtime is installed into
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.plugin, version = %self.version))]
+    pub fn install_dir(&self) -> Result<PathBuf> {
+        Ok(crate::installs_dir()?
+            .join(&self.plugin)
+            .join(self.version.to_string()))
+    }
+
+    /// Directories of this runtime's executables, as they would go on PATH
+    ///
+    /// Plugins with a `bin/list-bin-paths` hook name them relative to the
+    /// install directory; the rest use `bin`.
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.plugin, version = %self.version))]
+    pub fn bin_dirs(&self) -> Result<Vec<PathBuf>> {
+        let install_dir = self.install_dir()?;
+        let hook = crate::plugins_dir()?
+            .join(&self.plugin)
+            .join("bin")
+            .join("list-bin-paths");
+        if !hook.is_file() {
+            return Ok(vec![install_dir.join("bin")]);
+        }
+
+        let output = duct::cmd!(&hook)
+            .env("ASDF_INSTALL_TYPE", "version")
+            .env("ASDF_INSTALL_VERSION", self.version.to_string())
+            .env("ASDF_INSTALL_PATH", &install_dir)
+            .stderr_null()
+            .read()
+            .map_err(|e| Error::CommandFailed {
+                command: hook.display().to_string(),
+                error: e.to_string(),
+            })?;
+
+        Ok(output
+            .split_whitespace()
+            .map(|dir| install_dir.join(dir))
+            .collect())
+    }
+
+    /// Regenerate shims for this runtime
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.plugin, version = %self.version))]
+    pub fn reshim(&self) -> Result<()> {
+        duct::cmd!(
+            "asdf",
+            "reshim",
+            &self.plugin,
+            self.version.to_string()
+        )
+        .stdout_to_stderr()
+        .run()
+        .map_err(|e| Error::CommandFailed {
+            command: format!("asdf reshim {} {}", self.plugin, self.version),
+            error: e.to_string(),
+        })?;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
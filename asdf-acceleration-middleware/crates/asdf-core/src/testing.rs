@@ -0,0 +1,777 @@
+//! Fake asdf backend for tests
+//!
+//! [`FakeAsdf`] builds a throwaway asdf installation in a temporary directory:
+//! an asdf directory, a separate data directory with git-backed plugins and
+//! installs, and a scriptable `asdf` shell script that implements the subcommands this workspace uses.
+//! Failures, slow operations and flaky networks are injected per operation.
+//!
+//! Binaries are driven end-to-end by passing [`FakeAsdf::env`] to the child
+//! process. In-process code (anything calling [`Plugin::list`] and friends)
+//! uses [`FakeAsdf::activate`], which points this process at the fake until
+//! the guard is dropped.
+//!
+//! The harness is a test fixture: setup errors panic rather than return.
+//!
+//! ```no_run
+//! use asdf_core::testing::{FakeAsdf, Fault};
+//! use asdf_core::Plugin;
+//!
+//! let fake = FakeAsdf::new()
+//!     .plugin("nodejs", &["18.19.0", "20.11.0"])
+//!     .installed("nodejs", "20.11.0")
+//!     .fault("plugin-update-nodejs", Fault::Flaky(2));
+//!
+//! let _guard = fake.activate();
+//! assert_eq!(Plugin::list().unwrap().len(), 1);
+//! ```
+//!
+//! [`Plugin::list`]: crate::Plugin::list
+
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Version reported by `asdf --version`
+pub const FAKE_VERSION: &str = "v0.14.0-fake";
+
+/// A failure mode injected into one fake operation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fault {
+    /// Always fail with this message
+    Fail(String),
+
+    /// Sleep before doing the work
+    Slow(Duration),
+
+    /// Fail the first `n` calls with a network error, then succeed
+    Flaky(usize),
+}
+
+impl Fault {
+    fn script_line(&self) -> String {
+        match self {
+            Fault::Fail(message) => format!("fail {}", message),
+            Fault::Slow(duration) => format!("sleep {:.3}", duration.as_secs_f64()),
+            Fault::Flaky(n) => format!("flaky {}", n),
+        }
+    }
+}
+
+/// A temporary asdf installation backed by a fake `asdf` binary
+pub struct FakeAsdf {
+    root: TempDir,
+}
+
+impl FakeAsdf {
+    /// Create an empty installation with no plugins
+    pub fn new() -> Self {
+        let root = tempfile::Builder::new()
+            .prefix("fake-asdf-")
+            .tempdir()
+            .expect("failed to create fake asdf root");
+
+        for dir in ["asdf", "bin", "data/plugins", "data/installs", "data/shims", "home", "faults"] {
+            fs::create_dir_all(root.path().join(dir)).expect("failed to create fake asdf layout");
+        }
+
+        let fake = Self { root };
+        let script = SCRIPT
+            .replace("@ROOT@", &fake.root().display().to_string())
+            .replace("@FAKE_VERSION@", FAKE_VERSION);
+        write_executable(&fake.bin_dir().join("asdf"), &script);
+        fs::write(fake.root().join("calls.log"), "").expect("failed to create call log");
+        fake
+    }
+
+    /// Add a plugin offering `versions`, with the default GitHub URL
+    pub fn plugin(self, name: &str, versions: &[&str]) -> Self {
+        let url = format!("https://github.com/asdf-vm/asdf-{}.git", name);
+        self.plugin_with_url(name, &url, versions)
+    }
+
+    /// Add a plugin offering `versions`, cloned from `url`
+    pub fn plugin_with_url(self, name: &str, url: &str, versions: &[&str]) -> Self {
+        let status = Command::new(self.bin_dir().join("asdf"))
+            .args(["plugin", "add", name, url])
+            .envs(self.env())
+            .env("FAKE_ASDF_VERSIONS", versions.join(" "))
+            .env("FAKE_ASDF_QUIET", "1")
+            .status()
+            .expect("failed to run fake asdf");
+        assert!(status.success(), "fake asdf could not add plugin {}", name);
+        self
+    }
+
+    /// Mark `plugin` `version` as installed, with a binary and a shim
+    pub fn installed(self, plugin: &str, version: &str) -> Self {
+        let status = Command::new(self.bin_dir().join("asdf"))
+            .args(["install", plugin, version])
+            .envs(self.env())
+            .env("FAKE_ASDF_QUIET", "1")
+            .status()
+            .expect("failed to run fake asdf");
+        assert!(status.success(), "fake asdf could not install {} {}", plugin, version);
+        self
+    }
+
+    /// Pin `plugin` to `version` in the global `.tool-versions`
+    pub fn global(self, plugin: &str, version: &str) -> Self {
+        let path = self.home().join(crate::tool_versions::FILE_NAME);
+        let mut tool_versions = crate::ToolVersions::load(&path).expect("invalid .tool-versions");
+        tool_versions.set(plugin, vec![version.to_string()]);
+        tool_versions.save(&path).expect("failed to write .tool-versions");
+        self
+    }
+
+    /// Inject a fault into an operation
+    ///
+    /// Operations are keyed by subcommand and target, e.g. `install-nodejs`,
+    /// `install-nodejs-20.11.0`, `plugin-update-ruby`, `plugin-add-golang`,
+    /// `plugin-list` or `list-all-nodejs`. Several faults on one key apply in
+    /// the order they were added.
+    pub fn fault(self, operation: &str, fault: Fault) -> Self {
+        use std::io::Write;
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.root().join("faults").join(operation))
+            .expect("failed to write fault");
+        writeln!(file, "{}", fault.script_line()).expect("failed to write fault");
+        self
+    }
+
+    /// Create a local upstream repository for plugin `name`
+    ///
+    /// Pass the returned path to [`FakeAsdf::plugin_with_url`] to clone it;
+    /// commits added with [`FakeAsdf::upstream_commit`] are then fetchable.
+    pub fn upstream(&self, name: &str) -> PathBuf {
+        let dir = self.root().join("upstream").join(name);
+        fs::create_dir_all(dir.join("bin")).expect("failed to create upstream");
+        write_executable(
+            &dir.join("bin").join("list-all"),
+            "#!/bin/sh\ncat \"$(dirname \"$0\")/../.git/fake-versions\" 2>/dev/null\n",
+        );
+        git(&dir, &["init", "--quiet"]);
+        git(&dir, &["add", "-A"]);
+        git(&dir, &["commit", "--quiet", "-m", "Initial commit"]);
+        dir
+    }
+
+    /// Commit a change to the upstream of `name`, returning the commit
+    ///
+    /// With a `signing_key`, the commit is SSH-signed with that private key.
+    pub fn upstream_commit(&self, name: &str, message: &str, signing_key: Option<&Path>) -> String {
+        use std::io::Write;
+
+        let dir = self.root().join("upstream").join(name);
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join("CHANGELOG"))
+            .expect("failed to write upstream change");
+        writeln!(file, "{}", message).expect("failed to write upstream change");
+
+        git(&dir, &["add", "-A"]);
+        match signing_key {
+            Some(key) => {
+                let key = format!("user.signingkey={}", key.display());
+                git(
+                    &dir,
+                    &["-c", "gpg.format=ssh", "-c", &key, "commit", "--quiet", "-S", "-m", message],
+                )
+            }
+            None => git(&dir, &["commit", "--quiet", "-m", message]),
+        };
+        git(&dir, &["rev-parse", "HEAD"])
+    }
+
+    /// Create an SSH signing key, returning it and an allowed signers file
+    pub fn signing_key(&self, name: &str) -> (PathBuf, PathBuf) {
+        let dir = self.root().join("keys");
+        fs::create_dir_all(&dir).expect("failed to create key directory");
+        let key = dir.join(name);
+
+        let status = Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-C", name, "-f"])
+            .arg(&key)
+            .status()
+            .expect("failed to run ssh-keygen");
+        assert!(status.success(), "ssh-keygen failed");
+
+        let public = fs::read_to_string(key.with_extension("pub")).expect("missing public key");
+        let signers = dir.join(format!("{}.allowed_signers", name));
+        fs::write(&signers, format!("fake-asdf@example.invalid {}", public))
+            .expect("failed to write allowed signers");
+        (key, signers)
+    }
+
+    /// Root of the temporary installation
+    pub fn root(&self) -> &Path {
+        self.root.path()
+    }
+
+    /// Directory holding the fake `asdf` binary
+    pub fn bin_dir(&self) -> PathBuf {
+        self.root().join("bin")
+    }
+
+    /// asdf installation directory (`ASDF_DIR`)
+    ///
+    /// Kept apart from [`FakeAsdf::data_dir`], so anything that reads or
+    /// writes data here instead is caught.
+    pub fn asdf_dir(&self) -> PathBuf {
+        self.root().join("asdf")
+    }
+
+    /// asdf data directory (`ASDF_DATA_DIR`)
+    pub fn data_dir(&self) -> PathBuf {
+        self.root().join("data")
+    }
+
+    /// Home directory, holding the global `.tool-versions`
+    pub fn home(&self) -> PathBuf {
+        self.root().join("home")
+    }
+
+    /// Environment that points a child process at this installation
+    pub fn env(&self) -> Vec<(OsString, OsString)> {
+        let mut path = vec![self.data_dir().join("shims"), self.bin_dir()];
+        if let Some(existing) = std::env::var_os("PATH") {
+            path.extend(std::env::split_paths(&existing));
+        }
+
+        vec![
+            ("PATH".into(), std::env::join_paths(path).expect("invalid PATH")),
+            ("ASDF_DIR".into(), self.asdf_dir().into()),
+            ("ASDF_DATA_DIR".into(), self.data_dir().into()),
+            ("HOME".into(), self.home().into()),
+        ]
+    }
+
+    /// Point this process at the installation until the guard is dropped
+    ///
+    /// Activations are serialized process-wide, since the environment is
+    /// shared by every test thread.
+    pub fn activate(&self) -> ActiveGuard<'_> {
+        let lock = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+
+        let saved = self
+            .env()
+            .into_iter()
+            .map(|(key, value)| {
+                let previous = std::env::var_os(&key);
+                std::env::set_var(&key, value);
+                (key, previous)
+            })
+            .collect();
+
+        ActiveGuard {
+            _lock: lock,
+            saved,
+            _fake: self,
+        }
+    }
+
+    /// Every invocation of the fake binary, as its argument string
+    pub fn calls(&self) -> Vec<String> {
+        fs::read_to_string(self.root().join("calls.log"))
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Number of invocations whose arguments equal `args`
+    pub fn call_count(&self, args: &str) -> usize {
+        self.calls().iter().filter(|c| *c == args).count()
+    }
+
+    /// Whether an install directory exists for `plugin` `version`
+    pub fn is_installed(&self, plugin: &str, version: &str) -> bool {
+        self.data_dir()
+            .join("installs")
+            .join(plugin)
+            .join(version)
+            .is_dir()
+    }
+}
+
+impl Default for FakeAsdf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static ACTIVE: Mutex<()> = Mutex::new(());
+
+/// Keeps a [`FakeAsdf`] active; restores the environment on drop
+pub struct ActiveGuard<'a> {
+    _lock: MutexGuard<'static, ()>,
+    saved: Vec<(OsString, Option<OsString>)>,
+    _fake: &'a FakeAsdf,
+}
+
+impl Drop for ActiveGuard<'_> {
+    fn drop(&mut self) {
+        for (key, previous) in self.saved.drain(..) {
+            match previous {
+                Some(value) => std::env::set_var(&key, value),
+                None => std::env::remove_var(OsStr::new(&key)),
+            }
+        }
+    }
+}
+
+/// Run git with a fixed identity and default branch, returning its output
+///
+/// Panics on failure.
+fn git(dir: &Path, args: &[&str]) -> String {
+    let mut full = vec!["-c", "user.name=fake-asdf", "-c", "user.email=fake-asdf@example.invalid"];
+    full.extend(["-c", "init.defaultBranch=master"]);
+    full.extend(args);
+    crate::git::run(dir, &full).unwrap_or_else(|e| panic!("{}", e))
+}
+
+fn write_executable(path: &Path, content: &str) {
+    fs::write(path, content).expect("failed to write fake asdf script");
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        .expect("failed to make fake asdf executable");
+}
+
+/// The fake `asdf` binary; `@ROOT@` is replaced with the installation root
+const SCRIPT: &str = r##"#!/bin/sh
+# Fake asdf generated by asdf_core::testing
+ROOT='@ROOT@'
+DATA="$ROOT/data"
+FAULTS="$ROOT/faults"
+
+[ -n "$FAKE_ASDF_QUIET" ] || printf '%s\n' "$*" >> "$ROOT/calls.log"
+
+die() {
+    echo "$*" >&2
+    exit 1
+}
+
+# Apply the faults registered for an operation key
+inject() {
+    [ -n "$FAKE_ASDF_QUIET" ] && return 0
+    for key in "$@"; do
+        [ -f "$FAULTS/$key" ] || continue
+        while read -r kind arg; do
+            case "$kind" in
+                sleep) sleep "$arg" ;;
+                fail) die "${arg:-injected failure}" ;;
+                flaky)
+                    count=$(cat "$FAULTS/$key.count" 2>/dev/null || echo 0)
+                    count=$((count + 1))
+                    echo "$count" > "$FAULTS/$key.count"
+                    [ "$count" -gt "$arg" ] || die "Failed to connect: network unreachable (attempt $count)"
+                    ;;
+            esac
+        done < "$FAULTS/$key"
+    done
+}
+
+# Fixed identity and dates, so equal plugins get equal commits everywhere
+git_quiet() {
+    GIT_AUTHOR_DATE='2024-01-01T00:00:00Z' GIT_COMMITTER_DATE='2024-01-01T00:00:00Z' \
+        git -c user.name=fake-asdf -c user.email=fake-asdf@example.invalid \
+        -c init.defaultBranch=master "$@" >/dev/null 2>&1
+}
+
+require_plugin() {
+    [ -d "$DATA/plugins/$1" ] || die "No such plugin: $1"
+}
+
+global_version() {
+    [ -f "$HOME/.tool-versions" ] || return 0
+    awk -v p="$1" '$1 == p { print $2; exit }' "$HOME/.tool-versions"
+}
+
+plugin_cmd() {
+    sub="$1"
+    [ $# -gt 0 ] && shift
+    case "$sub" in
+        list)
+            inject plugin-list
+            for dir in "$DATA"/plugins/*/; do
+                [ -d "$dir" ] || continue
+                name=$(basename "$dir")
+                if [ "$1" = "--urls" ]; then
+                    url=$(git -C "$dir" remote get-url origin 2>/dev/null)
+                    printf '%-30s %s\n' "$name" "$url"
+                else
+                    echo "$name"
+                fi
+            done
+            ;;
+        add)
+            [ -n "$1" ] || die "Usage: asdf plugin add <name> [<git-url>]"
+            inject "plugin-add-$1"
+            dir="$DATA/plugins/$1"
+            [ -d "$dir" ] && die "Plugin named $1 already added"
+            if [ -d "$2" ]; then
+                # Local upstreams are cloned, so their new commits can be fetched
+                git_quiet clone "$2" "$dir" || die "fatal: could not clone $2"
+                echo "$FAKE_ASDF_VERSIONS" > "$dir/.git/fake-versions"
+                exit 0
+            fi
+            mkdir -p "$dir/bin"
+            # Versions live outside the tree, so every clone has the same commit
+            printf '#!/bin/sh\ncat "$(dirname "$0")/../.git/fake-versions" 2>/dev/null\n' \
+                > "$dir/bin/list-all"
+            chmod +x "$dir/bin/list-all"
+            git_quiet init "$dir"
+            echo "$FAKE_ASDF_VERSIONS" > "$dir/.git/fake-versions"
+            git_quiet -C "$dir" remote add origin "${2:-https://github.com/asdf-vm/asdf-$1.git}"
+            git_quiet -C "$dir" add -A
+            git_quiet -C "$dir" commit -m "Initial commit"
+            ;;
+        remove)
+            require_plugin "$1"
+            inject "plugin-remove-$1"
+            rm -rf "$DATA/plugins/$1" "$DATA/installs/$1"
+            ;;
+        update)
+            if [ "$1" = "--all" ]; then
+                for dir in "$DATA"/plugins/*/; do
+                    [ -d "$dir" ] && inject "plugin-update-$(basename "$dir")"
+                done
+                exit 0
+            fi
+            require_plugin "$1"
+            inject "plugin-update-$1"
+            if [ -n "$2" ]; then
+                git_quiet -C "$DATA/plugins/$1" checkout "$2" ||
+                    die "fatal: couldn't find remote ref $2"
+            fi
+            echo "Updating $1 to ${2:-master}"
+            ;;
+        *)
+            die "Unknown plugin command: $sub"
+            ;;
+    esac
+}
+
+reshim() {
+    plugin="$1"
+    version="$2"
+    for bin in "$DATA/installs/$plugin/$version"/bin/*; do
+        [ -f "$bin" ] || continue
+        shim="$DATA/shims/$(basename "$bin")"
+        if [ ! -f "$shim" ]; then
+            printf '#!/usr/bin/env bash\n' > "$shim"
+            chmod +x "$shim"
+        fi
+        grep -q "^# asdf-plugin: $plugin $version\$" "$shim" ||
+            echo "# asdf-plugin: $plugin $version" >> "$shim"
+    done
+}
+
+cmd="$1"
+[ $# -gt 0 ] && shift
+
+case "$cmd" in
+    --version|version)
+        echo "@FAKE_VERSION@"
+        ;;
+    plugin)
+        plugin_cmd "$@"
+        ;;
+    list)
+        if [ "$1" = "all" ]; then
+            require_plugin "$2"
+            inject "list-all-$2"
+            for version in $("$DATA/plugins/$2/bin/list-all"); do
+                echo "$version"
+            done
+            exit 0
+        fi
+        require_plugin "$1"
+        inject "list-$1"
+        current=$(global_version "$1")
+        for dir in "$DATA/installs/$1"/*/; do
+            [ -d "$dir" ] || continue
+            version=$(basename "$dir")
+            if [ "$version" = "$current" ]; then
+                echo " *$version"
+            else
+                echo "  $version"
+            fi
+        done
+        ;;
+    install)
+        [ -n "$2" ] || die "Usage: asdf install <name> <version>"
+        require_plugin "$1"
+        inject "install-$1" "install-$1-$2"
+        available=$("$DATA/plugins/$1/bin/list-all")
+        if [ -n "$available" ]; then
+            case " $available " in
+                *" $2 "*) ;;
+                *) die "Version $2 of $1 is not available" ;;
+            esac
+        fi
+        dir="$DATA/installs/$1/$2"
+        mkdir -p "$dir/bin"
+        printf '#!/bin/sh\necho "%s %s"\n' "$1" "$2" > "$dir/bin/$1"
+        chmod +x "$dir/bin/$1"
+        reshim "$1" "$2"
+        ;;
+    uninstall)
+        inject "uninstall-$1" "uninstall-$1-$2"
+        [ -d "$DATA/installs/$1/$2" ] || die "Version $2 of $1 is not installed"
+        rm -rf "$DATA/installs/$1/$2"
+        ;;
+    global|local)
+        [ -n "$2" ] || die "Usage: asdf $cmd <name> <version>"
+        if [ "$cmd" = global ]; then file="$HOME/.tool-versions"; else file=".tool-versions"; fi
+        touch "$file"
+        awk -v p="$1" '$1 != p' "$file" > "$file.tmp"
+        echo "$1 $2" >> "$file.tmp"
+        mv "$file.tmp" "$file"
+        ;;
+    current)
+        version=$(global_version "$1")
+        [ -n "$version" ] || die "No version is set for $1"
+        echo "$1 $version $HOME/.tool-versions"
+        ;;
+    where)
+        [ -d "$DATA/installs/$1/$2" ] || die "Version $2 of $1 is not installed"
+        echo "$DATA/installs/$1/$2"
+        ;;
+    reshim)
+        inject "reshim-$1"
+        if [ -n "$2" ]; then
+            reshim "$1" "$2"
+            exit 0
+        fi
+        for dir in "$DATA/installs/$1"/*/; do
+            [ -d "$dir" ] && reshim "$1" "$(basename "$dir")"
+        done
+        ;;
+    *)
+        die "Unknown command: asdf $cmd $*"
+        ;;
+esac
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Plugin, Runtime, Version};
+
+    #[test]
+    fn test_plugins_and_installs() {
+        let fake = FakeAsdf::new()
+            .plugin("nodejs", &["18.19.0", "20.11.0"])
+            .plugin_with_url("ruby", "https://example.com/asdf-ruby.git", &["3.3.0"])
+            .installed("nodejs", "20.11.0")
+            .global("nodejs", "20.11.0");
+
+        let _guard = fake.activate();
+
+        let plugins = Plugin::list().unwrap();
+        assert_eq!(plugins.len(), 2);
+        assert_eq!(plugins[1].url.as_deref(), Some("https://example.com/asdf-ruby.git"));
+        assert_eq!(plugins[0].current_ref().unwrap().len(), 40);
+        assert_eq!(
+            Plugin::new("nodejs").list_all_versions().unwrap(),
+            vec!["18.19.0", "20.11.0"]
+        );
+
+        let runtimes = Runtime::list_for_plugin("nodejs").unwrap();
+        assert_eq!(runtimes.len(), 1);
+        assert!(runtimes[0].active);
+
+        Runtime::new("ruby", Version::parse("3.3.0").unwrap())
+            .install()
+            .unwrap();
+        assert!(fake.is_installed("ruby", "3.3.0"));
+        assert!(Runtime::new("ruby", Version::parse("9.9.9").unwrap())
+            .install()
+            .is_err());
+        assert_eq!(fake.call_count("install ruby 3.3.0"), 1);
+    }
+
+    #[test]
+    fn test_injected_faults() {
+        let fake = FakeAsdf::new()
+            .plugin("nodejs", &[])
+            .plugin("ruby", &[])
+            .fault("plugin-update-ruby", Fault::Fail("remote hung up".into()))
+            .fault("plugin-update-nodejs", Fault::Flaky(2))
+            .fault("install-nodejs", Fault::Slow(Duration::from_millis(200)));
+
+        let _guard = fake.activate();
+
+        assert!(Plugin::new("ruby").update().is_err());
+
+        let nodejs = Plugin::new("nodejs");
+        assert!(nodejs.update().is_err());
+        assert!(nodejs.update().is_err());
+        assert!(nodejs.update().is_ok());
+
+        let started = std::time::Instant::now();
+        Runtime::new("nodejs", Version::parse("20.0.0").unwrap())
+            .install()
+            .unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_healthy_installation_passes_doctor() {
+        let fake = FakeAsdf::new()
+            .plugin("nodejs", &["20.11.0"])
+            .installed("nodejs", "20.11.0")
+            .global("nodejs", "20.11.0");
+
+        let _guard = fake.activate();
+        let ctx = crate::doctor::DoctorContext::from_env().unwrap();
+        let report = crate::doctor::Report::run(&ctx);
+
+        assert!(report.is_healthy(), "{:?}", report.findings);
+    }
+
+    #[test]
+    fn test_resolve_version_with_plugin_aliases() {
+        let fake = FakeAsdf::new().plugin("nodejs", &["18.19.0", "20.11.0", "20.11.1", "21.6.1"]);
+        let script = fake.data_dir().join("plugins/nodejs/bin/version-aliases");
+        fs::write(&script, "#!/bin/sh\necho 'lts ^20'\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let _guard = fake.activate();
+        let nodejs = Plugin::new("nodejs");
+        let resolve = |req: &str| {
+            nodejs
+                .resolve_version(&crate::VersionReq::parse(req).unwrap())
+                .unwrap()
+        };
+
+        assert_eq!(resolve("latest").as_deref(), Some("21.6.1"));
+        assert_eq!(resolve("lts").as_deref(), Some("20.11.1"));
+        assert_eq!(resolve("~18").as_deref(), Some("18.19.0"));
+        assert_eq!(resolve(">=22"), None);
+    }
+
+    fn trusted(fake: &FakeAsdf, plugin: &str, pinned: bool) -> crate::TrustStore {
+        let _guard = fake.activate();
+        let entry = crate::TrustEntry::for_plugin(&Plugin::new(plugin), pinned).unwrap();
+        let mut store = crate::TrustStore::default();
+        store.trust(plugin, entry);
+        store
+    }
+
+    #[test]
+    fn test_pinned_plugin_holds_unreviewed_commits() {
+        let fake = FakeAsdf::new();
+        let upstream = fake.upstream("nodejs");
+        let fake = fake.plugin_with_url("nodejs", upstream.to_str().unwrap(), &["20.11.0"]);
+        let mut store = trusted(&fake, "nodejs", true);
+        let pinned = store.get("nodejs").unwrap().commit.clone().unwrap();
+        let incoming = fake.upstream_commit("nodejs", "Run a new script", None);
+
+        let _guard = fake.activate();
+        let nodejs = Plugin::new("nodejs");
+
+        assert_eq!(
+            nodejs.update_trusted(&store).unwrap(),
+            crate::trust::TrustedUpdate::AwaitingReview(1)
+        );
+        assert_eq!(nodejs.current_ref().unwrap(), pinned);
+
+        let review = crate::trust::Review::fetch(&nodejs, &store).unwrap();
+        assert_eq!(review.incoming, incoming);
+        assert_eq!(review.commits.len(), 1);
+        assert!(review.diff().unwrap().contains("+Run a new script"));
+
+        store.plugins.get_mut("nodejs").unwrap().commit = Some(incoming.clone());
+        assert_eq!(
+            nodejs.update_trusted(&store).unwrap(),
+            crate::trust::TrustedUpdate::Current
+        );
+        assert_eq!(nodejs.current_ref().unwrap(), incoming);
+    }
+
+    #[test]
+    fn test_changed_url_is_refused() {
+        let fake = FakeAsdf::new();
+        let upstream = fake.upstream("nodejs");
+        let fake = fake.plugin_with_url("nodejs", upstream.to_str().unwrap(), &[]);
+        let store = trusted(&fake, "nodejs", false);
+        let unseen = fake.upstream_commit("nodejs", "Hijacked", None);
+
+        let moved = fake.root().join("upstream").join("elsewhere");
+        fs::rename(&upstream, &moved).unwrap();
+        let checkout = fake.data_dir().join("plugins").join("nodejs");
+        git(&checkout, &["remote", "set-url", "origin", moved.to_str().unwrap()]);
+
+        let _guard = fake.activate();
+        let nodejs = Plugin::new("nodejs");
+        let err = nodejs.update_trusted(&store).unwrap_err();
+        assert!(matches!(err, crate::Error::TrustViolation { .. }));
+        assert!(err.to_string().contains("remote URL changed"), "{}", err);
+
+        let err = crate::trust::Review::fetch(&nodejs, &store).unwrap_err();
+        assert!(matches!(err, crate::Error::TrustViolation { .. }));
+
+        // Nothing was fetched from the new remote
+        let fetched = Command::new("git")
+            .current_dir(&checkout)
+            .args(["cat-file", "-e", &format!("{}^{{commit}}", unseen)])
+            .stderr(std::process::Stdio::null())
+            .status()
+            .unwrap();
+        assert!(!fetched.success());
+    }
+
+    #[test]
+    fn test_accept_checks_out_the_reviewed_commit() {
+        let fake = FakeAsdf::new();
+        let upstream = fake.upstream("nodejs");
+        let fake = fake.plugin_with_url("nodejs", upstream.to_str().unwrap(), &[]);
+        let store = trusted(&fake, "nodejs", false);
+        let reviewed = fake.upstream_commit("nodejs", "Reviewed change", None);
+
+        let _guard = fake.activate();
+        let nodejs = Plugin::new("nodejs");
+        let review = crate::trust::Review::fetch(&nodejs, &store).unwrap();
+        assert_eq!(review.incoming, reviewed);
+
+        // The remote moves on between reading the review and accepting it
+        fake.upstream_commit("nodejs", "Unreviewed change", None);
+        review.accept(&nodejs).unwrap();
+        assert_eq!(nodejs.current_ref().unwrap(), reviewed);
+    }
+
+    #[test]
+    fn test_required_signature() {
+        let fake = FakeAsdf::new();
+        let upstream = fake.upstream("nodejs");
+        let fake = fake.plugin_with_url("nodejs", upstream.to_str().unwrap(), &[]);
+        let (key, signers) = fake.signing_key("maintainer");
+        let mut store = trusted(&fake, "nodejs", false);
+        store.allowed_signers = Some(signers);
+        store.plugins.get_mut("nodejs").unwrap().require_signature = true;
+
+        let _guard = fake.activate();
+        let nodejs = Plugin::new("nodejs");
+
+        let unsigned = fake.upstream_commit("nodejs", "Unsigned change", None);
+        let err = nodejs.update_trusted(&store).unwrap_err().to_string();
+        assert!(err.contains("not signed by a trusted key"), "{}", err);
+        assert_ne!(nodejs.current_ref().unwrap(), unsigned);
+
+        let signed = fake.upstream_commit("nodejs", "Signed change", Some(&key));
+        let review = crate::trust::Review::fetch(&nodejs, &store).unwrap();
+        assert!(review.signature.is_verified(), "{:?}", review.signature);
+
+        nodejs.update_trusted(&store).unwrap();
+        assert_eq!(nodejs.current_ref().unwrap(), signed);
+    }
+}
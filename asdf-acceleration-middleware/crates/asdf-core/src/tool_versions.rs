@@ -0,0 +1,299 @@
+//! `.tool-versions` file handling
+
+use crate::Result;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Name of the per-directory version file used by asdf
+pub const FILE_NAME: &str = ".tool-versions";
+
+/// A single line of a `.tool-versions` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+    /// `plugin version [version...] [# comment]`
+    Entry {
+        plugin: String,
+        versions: Vec<String>,
+        comment: Option<String>,
+    },
+
+    /// Comment, blank line or anything we don't understand, kept verbatim
+    Other(String),
+}
+
+/// Parsed `.tool-versions` file
+///
+/// Comments and unrecognised lines are preserved so that rewriting a file
+/// only touches the entries that were changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolVersions {
+    lines: Vec<Line>,
+}
+
+impl ToolVersions {
+    /// Create an empty file
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse the contents of a `.tool-versions` file
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asdf_core::ToolVersions;
+    ///
+    /// let tv = ToolVersions::parse("nodejs 20.1.0\npython 3.12.1 3.11.7\n");
+    /// assert_eq!(tv.get("nodejs"), Some(&["20.1.0".to_string()][..]));
+    /// assert_eq!(tv.get("python").map(|v| v.len()), Some(2));
+    /// ```
+    pub fn parse(content: &str) -> Self {
+        let lines = content
+            .lines()
+            .map(|line| {
+                // Inline comments are allowed after the versions
+                let (data, comment) = match line.find('#') {
+                    Some(at) => (&line[..at], Some(line[at..].to_string())),
+                    None => (line, None),
+                };
+                let mut parts = data.split_whitespace();
+
+                match parts.next() {
+                    Some(plugin) if !line.trim_start().starts_with('#') => {
+                        let versions: Vec<String> = parts.map(str::to_string).collect();
+                        if versions.is_empty() {
+                            Line::Other(line.to_string())
+                        } else {
+                            Line::Entry {
+                                plugin: plugin.to_string(),
+                                versions,
+                                comment,
+                            }
+                        }
+                    }
+                    _ => Line::Other(line.to_string()),
+                }
+            })
+            .collect();
+
+        Self { lines }
+    }
+
+    /// Load a `.tool-versions` file, returning an empty one if it does not exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Ok(Self::parse(&content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write this file to disk
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// Get the versions pinned for a plugin
+    pub fn get(&self, plugin: &str) -> Option<&[String]> {
+        self.lines.iter().find_map(|line| match line {
+            Line::Entry { plugin: p, versions, .. } if p == plugin => Some(versions.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// Check whether a plugin has an entry
+    pub fn contains(&self, plugin: &str) -> bool {
+        self.get(plugin).is_some()
+    }
+
+    /// Set the versions for a plugin, replacing an existing entry in place
+    ///
+    /// An inline comment on the existing entry is kept.
+    pub fn set(&mut self, plugin: impl Into<String>, versions: Vec<String>) {
+        let plugin = plugin.into();
+
+        for line in &mut self.lines {
+            if let Line::Entry { plugin: p, versions: v, .. } = line {
+                if *p == plugin {
+                    *v = versions;
+                    return;
+                }
+            }
+        }
+
+        self.lines.push(Line::Entry {
+            plugin,
+            versions,
+            comment: None,
+        });
+    }
+
+    /// Remove the entry for a plugin
+    pub fn remove(&mut self, plugin: &str) -> bool {
+        let before = self.lines.len();
+        self.lines
+            .retain(|line| !matches!(line, Line::Entry { plugin: p, .. } if p == plugin));
+        self.lines.len() != before
+    }
+
+    /// Iterate over `(plugin, versions)` entries in file order
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &[String])> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Entry { plugin, versions, .. } => Some((plugin.as_str(), versions.as_slice())),
+            Line::Other(_) => None,
+        })
+    }
+
+    /// Number of plugin entries
+    pub fn len(&self) -> usize {
+        self.entries().count()
+    }
+
+    /// Check if there are no plugin entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Display for ToolVersions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Entry {
+                    plugin,
+                    versions,
+                    comment,
+                } => {
+                    write!(f, "{} {}", plugin, versions.join(" "))?;
+                    match comment {
+                        Some(comment) => writeln!(f, " {}", comment)?,
+                        None => writeln!(f)?,
+                    }
+                }
+                Line::Other(raw) => writeln!(f, "{}", raw)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `.tool-versions` files that apply in `dir`, nearest first
+///
+/// Every ancestor of `dir` is searched, then `home` for the global file.
+pub fn chain(dir: &Path, home: Option<&Path>) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = dir
+        .ancestors()
+        .map(|dir| dir.join(FILE_NAME))
+        .filter(|p| p.is_file())
+        .collect();
+
+    if let Some(home) = home {
+        let global = home.join(FILE_NAME);
+        if global.is_file() && !files.contains(&global) {
+            files.push(global);
+        }
+    }
+
+    files
+}
+
+/// Versions in effect in `dir`, by plugin
+///
+/// Each plugin takes its entry from the nearest file that names it, as
+/// asdf itself resolves them.
+pub fn effective(dir: &Path, home: Option<&Path>) -> Result<BTreeMap<String, Vec<String>>> {
+    let mut tools = BTreeMap::new();
+
+    for file in chain(dir, home) {
+        for (plugin, versions) in ToolVersions::load(&file)?.entries() {
+            tools
+                .entry(plugin.to_string())
+                .or_insert_with(|| versions.to_vec());
+        }
+    }
+
+    Ok(tools)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_entries() {
+        let tv = ToolVersions::parse("nodejs 20.1.0\n# comment\n\npython 3.12.1 system\n");
+        assert_eq!(tv.len(), 2);
+        assert_eq!(tv.get("python").unwrap(), ["3.12.1", "system"]);
+        assert!(tv.get("ruby").is_none());
+    }
+
+    #[test]
+    fn test_inline_comment() {
+        let tv = ToolVersions::parse("ruby 3.2.2 # pinned for CI\n");
+        assert_eq!(tv.get("ruby").unwrap(), ["3.2.2"]);
+    }
+
+    #[test]
+    fn test_inline_comment_round_trip() {
+        let content = "nodejs 20.1.0 # LTS
+python 3.12.1 system #keep system
+";
+        let mut tv = ToolVersions::parse(content);
+        assert_eq!(tv.to_string(), content);
+
+        tv.set("nodejs", vec!["20.11.0".to_string()]);
+        assert_eq!(
+            tv.to_string(),
+            "nodejs 20.11.0 # LTS\npython 3.12.1 system #keep system\n"
+        );
+    }
+
+    #[test]
+    fn test_set_preserves_layout() {
+        let mut tv = ToolVersions::parse("# tools\nnodejs 18.0.0\n");
+        tv.set("nodejs", vec!["20.1.0".to_string()]);
+        tv.set("golang", vec!["1.21.5".to_string()]);
+        assert_eq!(tv.to_string(), "# tools\nnodejs 20.1.0\ngolang 1.21.5\n");
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut tv = ToolVersions::parse("nodejs 20.1.0\nruby 3.2.2\n");
+        assert!(tv.remove("nodejs"));
+        assert!(!tv.remove("nodejs"));
+        assert_eq!(tv.to_string(), "ruby 3.2.2\n");
+    }
+
+    #[test]
+    fn test_load_missing_and_save() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(FILE_NAME);
+
+        let mut tv = ToolVersions::load(&path).unwrap();
+        assert!(tv.is_empty());
+
+        tv.set("nodejs", vec!["path:/opt/node".to_string()]);
+        tv.save(&path).unwrap();
+
+        let loaded = ToolVersions::load(&path).unwrap();
+        assert_eq!(loaded.get("nodejs").unwrap(), ["path:/opt/node"]);
+    }
+
+    #[test]
+    fn test_effective_nearest_wins() {
+        let home = TempDir::new().unwrap();
+        let project = home.path().join("src/app");
+        std::fs::create_dir_all(&project).unwrap();
+
+        std::fs::write(home.path().join(FILE_NAME), "nodejs 18.0.0\nruby 3.2.2\n").unwrap();
+        std::fs::write(project.join(FILE_NAME), "nodejs 20.1.0\n").unwrap();
+
+        let tools = effective(&project, Some(home.path())).unwrap();
+        assert_eq!(tools["nodejs"], ["20.1.0"]);
+        assert_eq!(tools["ruby"], ["3.2.2"]);
+        assert_eq!(chain(&project, Some(home.path())).len(), 2);
+    }
+}
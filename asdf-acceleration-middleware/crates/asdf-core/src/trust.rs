@@ -0,0 +1,402 @@
+//! Plugin repository trust
+//!
+//! Plugins are shell scripts fetched from git and run with the user's
+//! privileges. The trust store records, per plugin, the repository URL that
+//! was reviewed, optionally a pinned commit, and whether incoming commits
+//! must carry a valid signature. [`Plugin::update_trusted`] refuses updates
+//! that break those rules; [`Review`] shows what an update would bring in.
+//!
+//! Signatures are checked locally with `git verify-commit` and
+//! `git verify-tag`, so GPG keys come from the user's keyring and SSH keys
+//! from the store's allowed signers file.
+
+use crate::{git, Error, Plugin, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::instrument;
+
+/// File name of the trust store in the asdf data directory
+pub const FILE_NAME: &str = "trust.json";
+
+/// Trusted plugin repositories
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustStore {
+    /// `gpg.ssh.allowedSignersFile` used to verify SSH signatures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_signers: Option<PathBuf>,
+
+    /// Trust entries by plugin name
+    #[serde(default)]
+    pub plugins: BTreeMap<String, TrustEntry>,
+}
+
+/// What is trusted about one plugin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustEntry {
+    /// Reviewed repository URL
+    pub url: String,
+
+    /// Reviewed commit; updates stay here until a new commit is accepted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+
+    /// Whether incoming commits must be signed by a trusted key
+    #[serde(default)]
+    pub require_signature: bool,
+}
+
+impl TrustEntry {
+    /// Trust a plugin's current remote, pinned to its checkout if `pin`
+    pub fn for_plugin(plugin: &Plugin, pin: bool) -> Result<Self> {
+        let dir = plugin.dir()?;
+        if !dir.is_dir() {
+            return Err(Error::PluginNotFound(plugin.name.clone()));
+        }
+
+        Ok(Self {
+            url: git::run(&dir, &["remote", "get-url", "origin"])?,
+            commit: if pin {
+                Some(git::run(&dir, &["rev-parse", "HEAD"])?)
+            } else {
+                None
+            },
+            require_signature: false,
+        })
+    }
+}
+
+impl TrustStore {
+    /// Default trust store location
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(crate::data_dir()?.join(FILE_NAME))
+    }
+
+    /// Load a trust store; a missing file is an empty store
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        match fs::read_to_string(path.as_ref()) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Load the trust store from its default location
+    pub fn load_default() -> Result<Self> {
+        Self::load(Self::default_path()?)
+    }
+
+    /// Write the trust store
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    /// Trust entry for a plugin
+    pub fn get(&self, plugin: &str) -> Option<&TrustEntry> {
+        self.plugins.get(plugin)
+    }
+
+    /// Add or replace the trust entry for a plugin
+    pub fn trust(&mut self, plugin: impl Into<String>, entry: TrustEntry) {
+        self.plugins.insert(plugin.into(), entry);
+    }
+
+    /// Forget a plugin, returning its entry
+    pub fn remove(&mut self, plugin: &str) -> Option<TrustEntry> {
+        self.plugins.remove(plugin)
+    }
+}
+
+/// Outcome of checking a commit's signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Signature {
+    /// Signed by a trusted key, on the commit or on a tag pointing at it
+    Verified { signer: String, via: String },
+
+    /// Neither the commit nor any tag on it is signed
+    Unsigned,
+
+    /// Signed, but the signature could not be verified
+    Invalid { reason: String },
+}
+
+impl Signature {
+    /// Whether the signature checked out
+    pub fn is_verified(&self) -> bool {
+        matches!(self, Signature::Verified { .. })
+    }
+}
+
+/// What updating a plugin would bring in, compared with what was reviewed
+#[derive(Debug, Clone, Serialize)]
+pub struct Review {
+    /// Plugin name
+    pub plugin: String,
+
+    /// URL recorded in the trust store, if the plugin is trusted
+    pub trusted_url: Option<String>,
+
+    /// URL the plugin currently fetches from
+    pub remote_url: String,
+
+    /// Commit the plugin is pinned to, if any
+    pub pinned: Option<String>,
+
+    /// Commit currently checked out
+    pub current: String,
+
+    /// Commit at the tip of the remote's default branch
+    pub incoming: String,
+
+    /// Signature of the incoming commit
+    pub signature: Signature,
+
+    /// Commits between the reviewed and incoming commits, newest first
+    pub commits: Vec<String>,
+
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+impl Review {
+    /// Fetch the plugin's remote and compare it with the trust store
+    ///
+    /// A trusted plugin whose remote URL no longer matches the store is a
+    /// [`Error::TrustViolation`], and the new remote is not contacted.
+    #[instrument(name = "review", level = "debug", skip_all, fields(plugin = %plugin.name))]
+    pub fn fetch(plugin: &Plugin, store: &TrustStore) -> Result<Self> {
+        let dir = plugin.dir()?;
+        let entry = store.get(&plugin.name);
+
+        let remote_url = git::run(&dir, &["remote", "get-url", "origin"])?;
+        if let Some(entry) = entry.filter(|e| e.url != remote_url) {
+            return Err(Error::TrustViolation {
+                plugin: plugin.name.clone(),
+                reason: format!("remote URL changed from {} to {}", entry.url, remote_url),
+            });
+        }
+        git::run(&dir, &["fetch", "--quiet", "--tags", "origin", "HEAD"])?;
+        let incoming = git::run(&dir, &["rev-parse", "FETCH_HEAD"])?;
+        let current = git::run(&dir, &["rev-parse", "HEAD"])?;
+        let pinned = entry.and_then(|e| e.commit.clone());
+
+        let base = pinned.as_deref().unwrap_or(&current);
+        let commits = git::run(
+            &dir,
+            &["log", "--format=%h %s", &format!("{}..{}", base, incoming)],
+        )?
+        .lines()
+        .map(String::from)
+        .collect();
+        let signature = verify_signature(&dir, &incoming, store.allowed_signers.as_deref())?;
+
+        Ok(Self {
+            plugin: plugin.name.clone(),
+            trusted_url: entry.map(|e| e.url.clone()),
+            remote_url,
+            pinned,
+            current,
+            incoming,
+            signature,
+            commits,
+            dir,
+        })
+    }
+
+    /// Commit the review is relative to: the pin, else the checkout
+    pub fn base(&self) -> &str {
+        self.pinned.as_deref().unwrap_or(&self.current)
+    }
+
+    /// Whether the remote has commits that were not reviewed
+    pub fn has_changes(&self) -> bool {
+        self.base() != self.incoming
+    }
+
+    /// Full diff from the reviewed commit to the incoming one
+    pub fn diff(&self) -> Result<String> {
+        git::run(
+            &self.dir,
+            &["diff", "--stat", "--patch", self.base(), &self.incoming],
+        )
+    }
+
+    /// Why accepting the incoming commit would break trust, if it would
+    pub fn rejection(&self, entry: &TrustEntry) -> Option<String> {
+        if entry.require_signature && !self.signature.is_verified() {
+            return Some(format!(
+                "commit {} is not signed by a trusted key",
+                short(&self.incoming)
+            ));
+        }
+        None
+    }
+
+    /// Check out the incoming commit this review showed
+    ///
+    /// Nothing is fetched, so the checkout ends up on exactly the reviewed
+    /// commit even if the remote has moved on since.
+    pub fn accept(&self, plugin: &Plugin) -> Result<()> {
+        if self.current != self.incoming {
+            plugin.clone().with_ref(self.incoming.as_str()).update()?;
+        }
+        Ok(())
+    }
+}
+
+/// Where [`Plugin::update_trusted`] left a plugin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrustedUpdate {
+    /// At its pin, or at the latest trusted commit
+    Current,
+
+    /// Held at its pin while upstream has this many unreviewed commits
+    AwaitingReview(usize),
+}
+
+impl Plugin {
+    /// Update this plugin within the rules of the trust store
+    ///
+    /// Pinned plugins stay at their pin; new upstream commits are reported
+    /// as awaiting review rather than as an error. Unpinned plugins follow
+    /// the remote once its URL and, if required, signature check out.
+    #[instrument(level = "debug", skip_all, fields(plugin = %self.name))]
+    pub fn update_trusted(&self, store: &TrustStore) -> Result<TrustedUpdate> {
+        let violation = |reason: String| Error::TrustViolation {
+            plugin: self.name.clone(),
+            reason,
+        };
+
+        let entry = store.get(&self.name).ok_or_else(|| {
+            violation(format!(
+                "not in the trust store; run `asdf-accelerate trust add {}`",
+                self.name
+            ))
+        })?;
+        let review = Review::fetch(self, store)?;
+
+        let target = match &entry.commit {
+            Some(pinned) => pinned,
+            None => {
+                if let Some(reason) = review.rejection(entry) {
+                    return Err(violation(reason));
+                }
+                &review.incoming
+            }
+        };
+
+        if review.current != *target {
+            self.clone().with_ref(target.as_str()).update()?;
+        }
+
+        if entry.commit.is_some() && review.has_changes() {
+            return Ok(TrustedUpdate::AwaitingReview(review.commits.len()));
+        }
+
+        Ok(TrustedUpdate::Current)
+    }
+}
+
+/// Check the signature of a commit, or of a tag pointing at it
+#[instrument(level = "debug", skip(dir, allowed_signers))]
+pub fn verify_signature(
+    dir: &Path,
+    commit: &str,
+    allowed_signers: Option<&Path>,
+) -> Result<Signature> {
+    let mut candidates = vec![("commit".to_string(), commit.to_string())];
+    for tag in git::run(dir, &["tag", "--points-at", commit])?.lines() {
+        candidates.push((format!("tag {}", tag), tag.to_string()));
+    }
+
+    let mut invalid = None;
+    for (via, object) in candidates {
+        let mut args = Vec::new();
+        if let Some(signers) = allowed_signers {
+            args.push("-c".to_string());
+            args.push(format!("gpg.ssh.allowedSignersFile={}", signers.display()));
+        }
+        let verify = if via == "commit" {
+            "verify-commit"
+        } else {
+            "verify-tag"
+        };
+        args.extend([verify.to_string(), object]);
+
+        let output = duct::cmd("git", &args)
+            .dir(dir)
+            .stderr_to_stdout()
+            .stdout_capture()
+            .unchecked()
+            .run()?;
+        let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        if output.status.success() {
+            let signer = text
+                .lines()
+                .find(|line| line.starts_with("Good"))
+                .unwrap_or(&text)
+                .to_string();
+            return Ok(Signature::Verified { signer, via });
+        }
+        // git prints nothing when there is no signature at all
+        if !text.is_empty() && invalid.is_none() {
+            invalid = Some(text);
+        }
+    }
+
+    Ok(match invalid {
+        Some(reason) => Signature::Invalid { reason },
+        None => Signature::Unsigned,
+    })
+}
+
+/// Abbreviated commit hash for display
+pub fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(12)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("nested").join(FILE_NAME);
+
+        assert_eq!(TrustStore::load(&path).unwrap(), TrustStore::default());
+
+        let mut store = TrustStore {
+            allowed_signers: Some(PathBuf::from("/etc/asdf/allowed_signers")),
+            ..Default::default()
+        };
+        store.trust(
+            "nodejs",
+            TrustEntry {
+                url: "https://github.com/asdf-vm/asdf-nodejs.git".to_string(),
+                commit: Some("0123456789abcdef".to_string()),
+                require_signature: true,
+            },
+        );
+        store.save(&path).unwrap();
+
+        let loaded = TrustStore::load(&path).unwrap();
+        assert_eq!(loaded, store);
+        assert!(loaded.get("nodejs").unwrap().require_signature);
+        assert!(loaded.get("ruby").is_none());
+    }
+
+    #[test]
+    fn test_short() {
+        assert_eq!(short("0123456789abcdef0123"), "0123456789ab");
+        assert_eq!(short("abc"), "abc");
+    }
+}
@@ -89,6 +89,22 @@ pub fn parse(s: &str) -> Result<Self> {
         })
     }
 
+    /// Keep an asdf version string as written, whether or not it parses
+    ///
+    /// asdf names installs by any string a plugin lists, such as
+    /// `temurin-21.0.1+12`, `26.2.5.1` or `stable`. Those that are not
+    /// versions here sort as `0.0.0` but still display as written.
+    pub fn literal(s: &str) -> Self {
+        Self::parse(s).unwrap_or_else(|_| Version {
+            major: 0,
+            minor: 0,
+            patch: 0,
+            pre: None,
+            build: None,
+            original: s.to_string(),
+        })
+    }
+
     /// Check if this is a pre-release version
     pub fn is_prerelease(&self) -> bool {
         self.pre.is_some()
@@ -190,6 +206,14 @@ fn test_version_display() {
         assert_eq!(v.to_string(), "1.2.3-beta.1+build.123");
     }
 
+    #[test]
+    fn test_version_literal() {
+        for s in ["temurin-21.0.1+12", "26.2.5.1", "stable", "3.12"] {
+            assert_eq!(Version::literal(s).to_string(), s);
+        }
+        assert_eq!(Version::literal("3.12").minor, 12);
+    }
+
     #[test]
     fn test_base_version() {
         let v = Version::parse("1.2.3-beta.1+build.123").unwrap();
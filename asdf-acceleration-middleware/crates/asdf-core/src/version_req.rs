@@ -0,0 +1,767 @@
+//! Version constraints and "latest satisfying" resolution
+//!
+//! Supported forms:
+//!
+//! - exact versions: `3.12.1` (matched against the version string)
+//! - comparators: `=1.2.3`, `>1.2`, `>=3.10`, `<3.13`, `<=2`, combined with
+//!   commas or spaces: `>=3.10, <3.13`
+//!
+//!   A bare version names one asdf version, as in `.tool-versions`, so `3.12`
+//!   only matches a version called `3.12`. With an operator it is a bound
+//!   filled in with wildcards: `=3.12` matches any `3.12.x`, like `3.12.*`.
+//! - hyphen ranges: `3.10 - 3.12` (inclusive)
+//! - caret and tilde: `^1.2.3`, `~1.2`
+//! - wildcards: `3.12.*`, `3.x`, `*`
+//! - `latest` and `latest:<prefix>`, as in `asdf latest`
+//! - asdf's non-version values `system`, `ref:<ref>` and `path:<path>`,
+//!   matched as exact strings
+//! - plugin aliases such as `lts` or `lts-hydrogen` (bare words without
+//!   digits), expanded through [`VersionHints`]
+//!
+//! Every comparator is reduced to lower and upper bounds checked with
+//! [`Version`]'s `Ord`. Pre-releases only match when a comparator names a
+//! pre-release of the same `major.minor.patch`, and never match `latest`.
+
+use crate::{Error, Result, Version};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A version constraint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionReq {
+    source: String,
+    kind: ReqKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ReqKind {
+    /// Exact version string
+    Exact(String),
+
+    /// Highest stable version, optionally with a string prefix
+    Latest(Option<String>),
+
+    /// Plugin-defined alias
+    Alias(String),
+
+    /// Intersection of bounds
+    Range(Range),
+}
+
+/// Bounds that a version must fall within
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Range {
+    lower: Vec<Bound>,
+    upper: Vec<Bound>,
+
+    /// `major.minor.patch` of pre-releases named by the constraint
+    pre_bases: Vec<(u32, u32, u32)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bound {
+    version: Version,
+    inclusive: bool,
+}
+
+/// Operator of a single comparator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    Tilde,
+    Caret,
+}
+
+/// A possibly partial version such as `3`, `3.12` or `3.12.*`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Partial {
+    major: Option<u32>,
+    minor: Option<u32>,
+    patch: Option<u32>,
+    pre: Option<String>,
+}
+
+impl VersionReq {
+    /// Parse a version constraint
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asdf_core::{Version, VersionReq};
+    ///
+    /// let req = VersionReq::parse(">=3.10, <3.13").unwrap();
+    /// assert!(req.matches(&Version::parse("3.12.1").unwrap()));
+    /// assert!(!req.matches(&Version::parse("3.13.0").unwrap()));
+    /// ```
+    pub fn parse(s: &str) -> Result<Self> {
+        let source = s.trim();
+        let invalid = || Error::InvalidVersionReq(s.to_string());
+
+        let kind = if source.is_empty() {
+            return Err(invalid());
+        } else if is_literal(source) {
+            ReqKind::Exact(source.to_string())
+        } else if source == "latest" {
+            ReqKind::Latest(None)
+        } else if let Some(prefix) = source.strip_prefix("latest:") {
+            if prefix.is_empty() {
+                return Err(invalid());
+            }
+            ReqKind::Latest(Some(prefix.to_string()))
+        } else if let Some((low, high)) = source.split_once(" - ") {
+            ReqKind::Range(Range::hyphen(
+                Partial::parse(low.trim()).ok_or_else(invalid)?,
+                Partial::parse(high.trim()).ok_or_else(invalid)?,
+            ))
+        } else {
+            let tokens = tokenize(source);
+            match tokens.as_slice() {
+                [single] if !single.starts_with(is_op_char) => {
+                    if !single.contains(|c: char| c.is_ascii_digit()) && single != "*" {
+                        ReqKind::Alias(single.clone())
+                    } else if is_wildcard(single) {
+                        let partial = Partial::parse(single).ok_or_else(invalid)?;
+                        let mut range = Range::default();
+                        range.add(Op::Exact, &partial);
+                        ReqKind::Range(range)
+                    } else {
+                        ReqKind::Exact(single.clone())
+                    }
+                }
+                _ => {
+                    let mut range = Range::default();
+                    for token in &tokens {
+                        let (op, rest) = split_op(token).ok_or_else(invalid)?;
+                        range.add(op, &Partial::parse(rest).ok_or_else(invalid)?);
+                    }
+                    ReqKind::Range(range)
+                }
+            }
+        };
+
+        Ok(Self {
+            source: source.to_string(),
+            kind,
+        })
+    }
+
+    /// Name of the alias this requirement refers to, if it is one
+    pub fn alias(&self) -> Option<&str> {
+        match &self.kind {
+            ReqKind::Alias(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Check whether a version satisfies this requirement
+    ///
+    /// Aliases match nothing until expanded with [`VersionReq::resolve_with`].
+    pub fn matches(&self, version: &Version) -> bool {
+        match &self.kind {
+            ReqKind::Exact(exact) => version.original == *exact,
+            ReqKind::Latest(prefix) => {
+                let prefixed = match prefix {
+                    Some(prefix) => version.original.starts_with(prefix.as_str()),
+                    None => true,
+                };
+                prefixed && is_stable(version)
+            }
+            ReqKind::Alias(_) => false,
+            ReqKind::Range(range) => range.matches(version),
+        }
+    }
+
+    /// Pick the highest of `available` that satisfies this requirement
+    ///
+    /// Strings that are not versions, such as `system` or `ref:main`, are
+    /// only considered by exact requirements, which is how those strings
+    /// parse themselves.
+    pub fn resolve(&self, available: &[String]) -> Option<String> {
+        if let ReqKind::Exact(exact) = &self.kind {
+            return available.iter().find(|v| *v == exact).cloned();
+        }
+
+        available
+            .iter()
+            .filter_map(|s| Version::parse(s).ok())
+            .filter(|v| self.matches(v))
+            .max()
+            .map(|v| v.original)
+    }
+
+    /// Like [`VersionReq::resolve`], expanding aliases through `hints`
+    pub fn resolve_with(&self, available: &[String], hints: &VersionHints) -> Result<Option<String>> {
+        let Some(alias) = self.alias() else {
+            return Ok(self.resolve(available));
+        };
+
+        let target = hints
+            .get(alias)
+            .ok_or_else(|| Error::UnknownVersionAlias(alias.to_string()))?;
+        let expanded = VersionReq::parse(target)?;
+        if expanded.alias().is_some() {
+            return Err(Error::InvalidVersionReq(format!(
+                "alias '{}' refers to another alias '{}'",
+                alias, target
+            )));
+        }
+
+        Ok(expanded.resolve(available))
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl std::str::FromStr for VersionReq {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+/// Plugin-provided aliases such as `lts`, each naming a requirement
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionHints {
+    aliases: BTreeMap<String, String>,
+}
+
+impl VersionHints {
+    /// Create an empty set of hints
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse `<alias> <requirement>` lines; `#` starts a comment
+    pub fn parse(text: &str) -> Self {
+        let aliases = text
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter_map(|line| line.split_once(char::is_whitespace))
+            .map(|(alias, req)| (alias.to_string(), req.trim().to_string()))
+            .collect();
+
+        Self { aliases }
+    }
+
+    /// Add an alias
+    pub fn with_alias(mut self, alias: impl Into<String>, req: impl Into<String>) -> Self {
+        self.aliases.insert(alias.into(), req.into());
+        self
+    }
+
+    /// Requirement an alias stands for
+    pub fn get(&self, alias: &str) -> Option<&str> {
+        self.aliases.get(alias).map(String::as_str)
+    }
+}
+
+impl Range {
+    fn hyphen(low: Partial, high: Partial) -> Self {
+        let mut range = Range::default();
+        range.add(Op::GreaterEq, &low);
+        range.add(Op::LessEq, &high);
+        range
+    }
+
+    fn add(&mut self, op: Op, partial: &Partial) {
+        let Some(major) = partial.major else {
+            // `*` places no bounds
+            return;
+        };
+        let minor = partial.minor.unwrap_or(0);
+        let patch = partial.patch.unwrap_or(0);
+        let floor = version(major, minor, patch, partial.pre.clone());
+
+        // The first version past the specified components, e.g. 1.3.0 for 1.2
+        let next = successor(major, partial.minor, partial.patch);
+        let complete = partial.patch.is_some();
+
+        if partial.pre.is_some() {
+            self.pre_bases.push((major, minor, patch));
+        }
+
+        match op {
+            Op::Exact if complete => {
+                self.lower(floor.clone(), true);
+                self.upper(floor, true);
+            }
+            Op::Exact => {
+                self.lower(floor, true);
+                self.upper_before(next);
+            }
+            Op::Greater if complete => self.lower(floor, false),
+            Op::Greater => match next {
+                Some(next) => self.lower(next, true),
+                // Nothing lies past the highest representable version
+                None => self.lower(version(u32::MAX, u32::MAX, u32::MAX, None), false),
+            },
+            Op::GreaterEq => self.lower(floor, true),
+            Op::Less => self.upper(floor, false),
+            Op::LessEq if complete => self.upper(floor, true),
+            Op::LessEq => self.upper_before(next),
+            Op::Tilde => {
+                self.lower(floor, true);
+                self.upper_before(successor(major, partial.minor, None));
+            }
+            Op::Caret => {
+                let ceiling = match (major, partial.minor, partial.patch) {
+                    (0, Some(0), Some(patch)) => successor(0, Some(0), Some(patch)),
+                    (0, Some(minor), _) => successor(0, Some(minor), None),
+                    _ => successor(major, None, None),
+                };
+                self.lower(floor, true);
+                self.upper_before(ceiling);
+            }
+        }
+    }
+
+    /// Exclusive upper bound; `None` past the highest version leaves it open
+    fn upper_before(&mut self, ceiling: Option<Version>) {
+        if let Some(ceiling) = ceiling {
+            self.upper(ceiling, false);
+        }
+    }
+
+    fn lower(&mut self, version: Version, inclusive: bool) {
+        self.lower.push(Bound { version, inclusive });
+    }
+
+    fn upper(&mut self, version: Version, inclusive: bool) {
+        self.upper.push(Bound { version, inclusive });
+    }
+
+    fn matches(&self, version: &Version) -> bool {
+        let above = self.lower.iter().all(|b| {
+            if b.inclusive {
+                *version >= b.version
+            } else {
+                *version > b.version
+            }
+        });
+        let below = self.upper.iter().all(|b| {
+            if b.inclusive {
+                *version <= b.version
+            } else {
+                *version < b.version
+            }
+        });
+
+        above
+            && below
+            && (is_stable(version)
+                || self
+                    .pre_bases
+                    .contains(&(version.major, version.minor, version.patch)))
+    }
+}
+
+impl Partial {
+    fn parse(s: &str) -> Option<Self> {
+        let (numbers, pre) = match s.split_once('-') {
+            Some((numbers, pre)) if !pre.is_empty() => (numbers, Some(pre.to_string())),
+            Some(_) => return None,
+            None => (s, None),
+        };
+
+        let mut parts = numbers.split('.');
+        let mut component = |seen_wildcard: &mut bool| -> Option<Option<u32>> {
+            match parts.next() {
+                None => Some(None),
+                Some("*" | "x" | "X") => {
+                    *seen_wildcard = true;
+                    Some(None)
+                }
+                Some(_) if *seen_wildcard => None,
+                Some(n) => n.parse().ok().map(Some),
+            }
+        };
+
+        let mut wildcard = false;
+        let major = component(&mut wildcard)?;
+        let minor = component(&mut wildcard)?;
+        let patch = component(&mut wildcard)?;
+
+        // Missing leading components cannot be followed by present ones
+        let valid = parts.next().is_none()
+            && !(major.is_none() && minor.is_some())
+            && !(minor.is_none() && patch.is_some())
+            && (pre.is_none() || patch.is_some());
+
+        valid.then_some(Partial {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+}
+
+/// The first version past the given components, carrying into the next
+/// component on overflow; `None` when no higher version can be represented
+fn successor(major: u32, minor: Option<u32>, patch: Option<u32>) -> Option<Version> {
+    let next = match (minor, patch) {
+        (Some(minor), Some(patch)) => patch
+            .checked_add(1)
+            .map(|patch| (major, minor, patch))
+            .or_else(|| minor.checked_add(1).map(|minor| (major, minor, 0))),
+        (Some(minor), None) => minor.checked_add(1).map(|minor| (major, minor, 0)),
+        (None, _) => None,
+    };
+    next.or_else(|| major.checked_add(1).map(|major| (major, 0, 0)))
+        .map(|(major, minor, patch)| version(major, minor, patch, None))
+}
+
+fn version(major: u32, minor: u32, patch: u32, pre: Option<String>) -> Version {
+    let original = match &pre {
+        Some(pre) => format!("{}.{}.{}-{}", major, minor, patch, pre),
+        None => format!("{}.{}.{}", major, minor, patch),
+    };
+
+    Version {
+        major,
+        minor,
+        patch,
+        pre,
+        build: None,
+        original,
+    }
+}
+
+fn is_op_char(c: char) -> bool {
+    matches!(c, '<' | '>' | '=' | '^' | '~')
+}
+
+/// `system`, `ref:<ref>` and `path:<path>`, which asdf accepts in place of a version
+fn is_literal(s: &str) -> bool {
+    s == "system" || s.starts_with("ref:") || s.starts_with("path:")
+}
+
+fn is_wildcard(s: &str) -> bool {
+    s.split('.').any(|part| matches!(part, "*" | "x" | "X"))
+}
+
+/// Split on commas and whitespace, keeping operators with their versions
+fn tokenize(s: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut pending_op = String::new();
+
+    for word in s.split(|c: char| c == ',' || c.is_whitespace()) {
+        if word.is_empty() {
+            continue;
+        }
+        if word.chars().all(is_op_char) {
+            pending_op.push_str(word);
+        } else {
+            tokens.push(format!("{}{}", pending_op, word));
+            pending_op.clear();
+        }
+    }
+    if !pending_op.is_empty() {
+        tokens.push(pending_op);
+    }
+
+    tokens
+}
+
+fn split_op(token: &str) -> Option<(Op, &str)> {
+    let (op, rest) = [
+        (">=", Op::GreaterEq),
+        ("<=", Op::LessEq),
+        (">", Op::Greater),
+        ("<", Op::Less),
+        ("=", Op::Exact),
+        ("^", Op::Caret),
+        ("~", Op::Tilde),
+    ]
+    .into_iter()
+    .find_map(|(prefix, op)| token.strip_prefix(prefix).map(|rest| (op, rest)))
+    .unwrap_or((Op::Exact, token));
+
+    (!rest.is_empty() && !rest.starts_with(is_op_char)).then_some((op, rest))
+}
+
+/// Whether a version is a stable release
+///
+/// Besides semver pre-releases, this rejects the development builds that
+/// `asdf latest` filters out, such as `3.13.0a1`, `3.13-dev` or `1.0.0.rc1`.
+pub fn is_stable(version: &Version) -> bool {
+    if version.is_prerelease() {
+        return false;
+    }
+
+    let lower = version.original.to_ascii_lowercase();
+    const MARKERS: [&str; 14] = [
+        "-src", "-dev", "-latest", "-stm", "rc", "-milestone", "alpha", "beta", "-pre", ".pre",
+        "-next", "snapshot", "master", "preview",
+    ];
+    if MARKERS.iter().any(|m| lower.contains(m)) {
+        return false;
+    }
+
+    // Python-style `3.13.0a1`, `3.13.0b2`, `3.13.0c1`
+    let bytes = lower.as_bytes();
+    !bytes.windows(3).any(|w| {
+        w[0].is_ascii_digit() && matches!(w[1], b'a' | b'b' | b'c') && w[2].is_ascii_digit()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn v(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    fn req(s: &str) -> VersionReq {
+        VersionReq::parse(s).unwrap()
+    }
+
+    fn list(versions: &[&str]) -> Vec<String> {
+        versions.iter().map(|s| s.to_string()).collect()
+    }
+
+    const PYTHON: [&str; 9] = [
+        "3.10.13", "3.11.7", "3.12.0", "3.12.1", "3.12.2", "3.13.0a1", "3.13.0rc1", "3.13-dev",
+        "miniconda3-4.7.12",
+    ];
+
+    #[test]
+    fn test_caret_and_tilde() {
+        assert!(req("^1.2.3").matches(&v("1.9.0")));
+        assert!(!req("^1.2.3").matches(&v("2.0.0")));
+        assert!(!req("^1.2.3").matches(&v("1.2.2")));
+        assert!(req("^0.2.3").matches(&v("0.2.9")));
+        assert!(!req("^0.2.3").matches(&v("0.3.0")));
+        assert!(!req("^0.0.3").matches(&v("0.0.4")));
+
+        assert!(req("~1.2.3").matches(&v("1.2.9")));
+        assert!(!req("~1.2.3").matches(&v("1.3.0")));
+        assert!(req("~1").matches(&v("1.9.9")));
+    }
+
+    #[test]
+    fn test_bounds_at_component_limits() {
+        assert!(req("^4294967295").matches(&v("4294967295.7.0")));
+        assert!(req("<=4294967295").matches(&v("4294967295.1.0")));
+        assert!(!req(">4294967295").matches(&v("4294967295.1.0")));
+
+        // Overflow carries into the next component
+        let tilde = req("~1.4294967295");
+        assert!(tilde.matches(&v("1.4294967295.3")));
+        assert!(!tilde.matches(&v("2.0.0")));
+        assert!(req("^0.0.4294967295").matches(&v("0.0.4294967295")));
+        assert!(!req("^0.0.4294967295").matches(&v("0.1.0")));
+    }
+
+    #[test]
+    fn test_comparators_and_ranges() {
+        let r = req(">=3.10, <3.13");
+        assert!(r.matches(&v("3.10.0")));
+        assert!(r.matches(&v("3.12.9")));
+        assert!(!r.matches(&v("3.13.0")));
+
+        assert!(req(">= 3.10 < 3.12").matches(&v("3.11.7")));
+        assert!(req(">1.2").matches(&v("1.3.0")));
+        assert!(!req(">1.2").matches(&v("1.2.5")));
+        assert!(req("<=1.2").matches(&v("1.2.5")));
+
+        let hyphen = req("3.10 - 3.12");
+        assert!(hyphen.matches(&v("3.12.2")));
+        assert!(!hyphen.matches(&v("3.13.0")));
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(req("3.12.*").matches(&v("3.12.2")));
+        assert!(!req("3.12.*").matches(&v("3.13.0")));
+        assert!(req("3.x").matches(&v("3.1.0")));
+        assert!(req("*").matches(&v("0.0.1")));
+        assert!(VersionReq::parse("3.*.1").is_err());
+    }
+
+    #[test]
+    fn test_exact() {
+        assert!(req("3.12.1").matches(&v("3.12.1")));
+        assert!(!req("3.12").matches(&v("3.12.0")));
+        assert!(req("=3.12").matches(&v("3.12.4")));
+        assert_eq!(req("lts").alias(), Some("lts"), "bare words are aliases");
+    }
+
+    #[test]
+    fn test_bare_and_equals_partial_versions() {
+        let available: Vec<String> = ["3.11.9", "3.12", "3.12.4"].iter().map(|s| s.to_string()).collect();
+
+        // The bare version is the asdf version of that name
+        assert_eq!(req("3.12").resolve(&available).as_deref(), Some("3.12"));
+        assert_eq!(req("3.13").resolve(&available), None);
+
+        // `=` fills in the missing patch, like `3.12.*`
+        assert_eq!(req("=3.12").resolve(&available).as_deref(), Some("3.12.4"));
+        assert_eq!(req("3.12.*").resolve(&available).as_deref(), Some("3.12.4"));
+    }
+
+    #[test]
+    fn test_non_version_literals() {
+        let available: Vec<String> = ["20.11.0", "system", "ref:main", "path:/opt/node"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        for literal in ["system", "ref:main", "path:/opt/node"] {
+            assert_eq!(req(literal).alias(), None);
+            assert_eq!(req(literal).resolve(&available).as_deref(), Some(literal));
+        }
+        assert_eq!(req("ref:v20.x").resolve(&available), None);
+        assert_eq!(req("latest").resolve(&available).as_deref(), Some("20.11.0"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for s in ["", ">=", "latest:", ">=1.2.3.4", "^abc", "1.2 -"] {
+            assert!(VersionReq::parse(s).is_err(), "{:?} should not parse", s);
+        }
+    }
+
+    #[test]
+    fn test_prerelease_exclusion() {
+        assert!(!req(">=3.12").matches(&v("3.13.0-rc.1")));
+        assert!(req(">=3.13.0-rc.1").matches(&v("3.13.0-rc.2")));
+        assert!(!req(">=3.13.0-rc.1").matches(&v("3.14.0-rc.1")));
+        assert!(req(">=3.13.0-rc.1").matches(&v("3.14.0")));
+    }
+
+    #[test]
+    fn test_resolve_latest() {
+        let available = list(&PYTHON);
+
+        assert_eq!(req("latest").resolve(&available).unwrap(), "3.12.2");
+        assert_eq!(req("latest:3.11").resolve(&available).unwrap(), "3.11.7");
+        assert_eq!(req("~3.12.0").resolve(&available).unwrap(), "3.12.2");
+        assert_eq!(req("<3.12").resolve(&available).unwrap(), "3.11.7");
+        assert_eq!(req("latest:4").resolve(&available), None);
+        assert_eq!(
+            req("miniconda3-4.7.12").resolve(&available).unwrap(),
+            "miniconda3-4.7.12"
+        );
+    }
+
+    #[test]
+    fn test_resolve_alias() {
+        let available = list(&["18.19.0", "20.11.0", "20.11.1", "21.6.1"]);
+        let hints = VersionHints::parse("# node release lines\nlts ^20\nlts-hydrogen ^18\nloop lts\n");
+
+        assert_eq!(
+            req("lts").resolve_with(&available, &hints).unwrap().unwrap(),
+            "20.11.1"
+        );
+        assert_eq!(
+            req("lts-hydrogen")
+                .resolve_with(&available, &hints)
+                .unwrap()
+                .unwrap(),
+            "18.19.0"
+        );
+        assert!(matches!(
+            req("lts-iron").resolve_with(&available, &hints),
+            Err(Error::UnknownVersionAlias(_))
+        ));
+        assert!(req("loop").resolve_with(&available, &hints).is_err());
+        assert_eq!(req("lts").resolve(&available), None);
+    }
+
+    #[test]
+    fn test_is_stable() {
+        for s in ["3.12.1", "20.11.0", "1.22"] {
+            assert!(is_stable(&v(s)), "{} is stable", s);
+        }
+        for s in ["3.13.0a1", "3.13.0rc1", "3.13-dev", "1.0.0-beta", "3.4.0-preview1"] {
+            assert!(!is_stable(&v(s)), "{} is not stable", s);
+        }
+    }
+
+    fn stable_version() -> impl Strategy<Value = Version> {
+        (0u32..4, 0u32..4, 0u32..4).prop_map(|(ma, mi, pa)| version(ma, mi, pa, None))
+    }
+
+    fn any_version() -> impl Strategy<Value = Version> {
+        (
+            0u32..4,
+            0u32..4,
+            0u32..4,
+            prop::option::of(prop::sample::select(vec!["alpha", "beta.1", "rc.1", "rc.2"])),
+        )
+            .prop_map(|(ma, mi, pa, pre)| version(ma, mi, pa, pre.map(String::from)))
+    }
+
+    proptest! {
+        #[test]
+        fn prop_comparators_agree_with_ord(a in stable_version(), b in stable_version()) {
+            prop_assert_eq!(req(&format!(">={}", b)).matches(&a), a >= b);
+            prop_assert_eq!(req(&format!(">{}", b)).matches(&a), a > b);
+            prop_assert_eq!(req(&format!("<{}", b)).matches(&a), a < b);
+            prop_assert_eq!(req(&format!("<={}", b)).matches(&a), a <= b);
+            prop_assert_eq!(req(&format!("={}", b)).matches(&a), a == b);
+        }
+
+        #[test]
+        fn prop_caret_and_tilde_are_bounded_below(a in stable_version(), b in stable_version()) {
+            let caret = req(&format!("^{}", b)).matches(&a);
+            let tilde = req(&format!("~{}", b)).matches(&a);
+
+            prop_assert!(!caret || a >= b);
+            prop_assert!(!tilde || a >= b);
+            prop_assert!(!tilde || caret || b.major == 0);
+            prop_assert_eq!(tilde, a >= b && (a.major, a.minor) == (b.major, b.minor));
+        }
+
+        #[test]
+        fn prop_wildcard_matches_prefix(a in stable_version(), b in stable_version()) {
+            let minor = req(&format!("{}.{}.*", b.major, b.minor)).matches(&a);
+            prop_assert_eq!(minor, (a.major, a.minor) == (b.major, b.minor));
+
+            let major = req(&format!("{}.*", b.major)).matches(&a);
+            prop_assert_eq!(major, a.major == b.major);
+        }
+
+        #[test]
+        fn prop_resolve_picks_ord_maximum(
+            available in prop::collection::vec(any_version(), 0..12),
+            floor in stable_version(),
+        ) {
+            let strings: Vec<String> = available.iter().map(|v| v.to_string()).collect();
+
+            let expected = available
+                .iter()
+                .filter(|v| !v.is_prerelease() && **v >= floor)
+                .max();
+            let resolved = req(&format!(">={}", floor)).resolve(&strings);
+            prop_assert_eq!(resolved.map(|s| v(&s)), expected.cloned());
+
+            let latest = req("latest").resolve(&strings);
+            prop_assert_eq!(
+                latest.map(|s| v(&s)),
+                available.iter().filter(|v| !v.is_prerelease()).max().cloned()
+            );
+        }
+
+        #[test]
+        fn prop_prereleases_need_explicit_opt_in(a in any_version(), b in stable_version()) {
+            prop_assume!(a.is_prerelease());
+            let at_least = req(&format!(">={}", b));
+            let caret = req(&format!("^{}", b));
+            prop_assert!(!at_least.matches(&a));
+            prop_assert!(!caret.matches(&a));
+        }
+    }
+}
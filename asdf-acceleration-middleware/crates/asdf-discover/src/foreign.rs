@@ -0,0 +1,587 @@
+//! Discovery of runtimes installed outside asdf
+//!
+//! Looks at the usual version managers (nvm, pyenv, rbenv, rustup, sdkman),
+//! Homebrew on Linux and plain system binaries.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Where a foreign runtime was found
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// Distribution package or manually installed binary
+    System,
+
+    /// Node Version Manager
+    Nvm,
+
+    /// pyenv
+    Pyenv,
+
+    /// rbenv
+    Rbenv,
+
+    /// rustup toolchains
+    Rustup,
+
+    /// SDKMAN! candidates
+    Sdkman,
+
+    /// Homebrew on Linux
+    Homebrew,
+}
+
+impl Source {
+    /// All sources, in scan order
+    pub const ALL: [Source; 7] = [
+        Source::Nvm,
+        Source::Pyenv,
+        Source::Rbenv,
+        Source::Rustup,
+        Source::Sdkman,
+        Source::Homebrew,
+        Source::System,
+    ];
+
+    /// Parse a source name as used on the command line
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|source| source.to_string() == s)
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Source::System => "system",
+            Source::Nvm => "nvm",
+            Source::Pyenv => "pyenv",
+            Source::Rbenv => "rbenv",
+            Source::Rustup => "rustup",
+            Source::Sdkman => "sdkman",
+            Source::Homebrew => "homebrew",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A runtime installed by something other than asdf
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ForeignRuntime {
+    /// Installation source
+    pub source: Source,
+
+    /// Matching asdf plugin name
+    pub plugin: String,
+
+    /// Version string as reported by the source
+    pub version: String,
+
+    /// Installation prefix (the directory containing `bin/`)
+    pub path: PathBuf,
+}
+
+impl ForeignRuntime {
+    /// The `.tool-versions` value that points asdf at this installation
+    pub fn path_version(&self) -> String {
+        format!("path:{}", self.path.display())
+    }
+}
+
+/// Locations inspected by a deep scan
+#[derive(Debug, Clone)]
+pub struct ScanRoots {
+    /// nvm root (`$NVM_DIR`)
+    pub nvm: PathBuf,
+
+    /// pyenv root (`$PYENV_ROOT`)
+    pub pyenv: PathBuf,
+
+    /// rbenv root (`$RBENV_ROOT`)
+    pub rbenv: PathBuf,
+
+    /// rustup home (`$RUSTUP_HOME`)
+    pub rustup: PathBuf,
+
+    /// sdkman root (`$SDKMAN_DIR`)
+    pub sdkman: PathBuf,
+
+    /// Homebrew prefixes to look for a `Cellar` in
+    pub homebrew: Vec<PathBuf>,
+
+    /// Directories searched for system binaries
+    pub system_bins: Vec<PathBuf>,
+}
+
+impl ScanRoots {
+    /// Default locations below a home directory, ignoring the environment
+    pub fn for_home(home: impl AsRef<Path>) -> Self {
+        let home = home.as_ref();
+        Self {
+            nvm: home.join(".nvm"),
+            pyenv: home.join(".pyenv"),
+            rbenv: home.join(".rbenv"),
+            rustup: home.join(".rustup"),
+            sdkman: home.join(".sdkman"),
+            homebrew: vec![
+                PathBuf::from("/home/linuxbrew/.linuxbrew"),
+                home.join(".linuxbrew"),
+            ],
+            system_bins: vec![PathBuf::from("/usr/local/bin"), PathBuf::from("/usr/bin")],
+        }
+    }
+
+    /// Locations for the current user, honouring the managers' own variables
+    pub fn from_env() -> Self {
+        let home = dirs_home();
+        let mut roots = Self::for_home(&home);
+
+        let var = |name: &str| env::var_os(name).map(PathBuf::from);
+        if let Some(p) = var("NVM_DIR") {
+            roots.nvm = p;
+        }
+        if let Some(p) = var("PYENV_ROOT") {
+            roots.pyenv = p;
+        }
+        if let Some(p) = var("RBENV_ROOT") {
+            roots.rbenv = p;
+        }
+        if let Some(p) = var("RUSTUP_HOME") {
+            roots.rustup = p;
+        }
+        if let Some(p) = var("SDKMAN_DIR") {
+            roots.sdkman = p;
+        }
+        if let Some(p) = var("HOMEBREW_PREFIX") {
+            roots.homebrew.insert(0, p);
+        }
+
+        roots
+    }
+}
+
+fn dirs_home() -> PathBuf {
+    env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/"))
+}
+
+/// Scan all sources
+pub fn scan(roots: &ScanRoots) -> Vec<ForeignRuntime> {
+    scan_sources(roots, &Source::ALL)
+}
+
+/// Scan only the given sources
+pub fn scan_sources(roots: &ScanRoots, sources: &[Source]) -> Vec<ForeignRuntime> {
+    let mut found = Vec::new();
+
+    for source in sources {
+        let runtimes = match source {
+            Source::Nvm => scan_nvm(&roots.nvm),
+            Source::Pyenv => scan_versions_dir(Source::Pyenv, "python", &roots.pyenv),
+            Source::Rbenv => scan_versions_dir(Source::Rbenv, "ruby", &roots.rbenv),
+            Source::Rustup => scan_rustup(&roots.rustup),
+            Source::Sdkman => scan_sdkman(&roots.sdkman),
+            Source::Homebrew => scan_homebrew(&roots.homebrew),
+            Source::System => scan_system(&roots.system_bins),
+        };
+        found.extend(runtimes);
+    }
+
+    found
+}
+
+/// List the sub-directories of `dir`, sorted by name
+fn subdirs(dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut entries: Vec<(String, PathBuf)> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            Some((name, entry.path()))
+        })
+        .collect();
+
+    entries.sort();
+    entries
+}
+
+/// `$NVM_DIR/versions/node/v20.1.0`
+fn scan_nvm(root: &Path) -> Vec<ForeignRuntime> {
+    subdirs(&root.join("versions").join("node"))
+        .into_iter()
+        .map(|(name, path)| ForeignRuntime {
+            source: Source::Nvm,
+            plugin: "nodejs".to_string(),
+            version: name.trim_start_matches('v').to_string(),
+            path,
+        })
+        .collect()
+}
+
+/// `$PYENV_ROOT/versions/3.12.1`, `$RBENV_ROOT/versions/3.2.2`
+fn scan_versions_dir(source: Source, plugin: &str, root: &Path) -> Vec<ForeignRuntime> {
+    subdirs(&root.join("versions"))
+        .into_iter()
+        // pyenv-virtualenv keeps environments under `envs`
+        .filter(|(name, _)| name != "envs")
+        .map(|(name, path)| ForeignRuntime {
+            source,
+            plugin: plugin.to_string(),
+            version: name,
+            path,
+        })
+        .collect()
+}
+
+/// Host triple architectures that terminate a rustup toolchain channel
+const RUST_ARCHES: &[&str] = &[
+    "x86_64", "aarch64", "i686", "i586", "armv7", "arm", "riscv64gc", "powerpc64le", "s390x",
+];
+
+/// Strip the host triple from a rustup toolchain name
+///
+/// `1.75.0-x86_64-unknown-linux-gnu` → `1.75.0`,
+/// `nightly-2024-01-01-aarch64-apple-darwin` → `nightly-2024-01-01`
+fn rustup_channel(toolchain: &str) -> &str {
+    RUST_ARCHES
+        .iter()
+        .filter_map(|arch| toolchain.find(&format!("-{}-", arch)))
+        .min()
+        .map(|pos| &toolchain[..pos])
+        .unwrap_or(toolchain)
+}
+
+/// `$RUSTUP_HOME/toolchains/stable-x86_64-unknown-linux-gnu`
+fn scan_rustup(root: &Path) -> Vec<ForeignRuntime> {
+    subdirs(&root.join("toolchains"))
+        .into_iter()
+        .map(|(name, path)| ForeignRuntime {
+            source: Source::Rustup,
+            plugin: "rust".to_string(),
+            version: rustup_channel(&name).to_string(),
+            path,
+        })
+        .collect()
+}
+
+/// `$SDKMAN_DIR/candidates/java/21.0.1-tem`
+fn scan_sdkman(root: &Path) -> Vec<ForeignRuntime> {
+    let mut found = Vec::new();
+
+    for (candidate, candidate_dir) in subdirs(&root.join("candidates")) {
+        for (version, path) in subdirs(&candidate_dir) {
+            // `current` is a symlink to the default version
+            if version == "current" {
+                continue;
+            }
+            found.push(ForeignRuntime {
+                source: Source::Sdkman,
+                plugin: plugin_for_tool(&candidate).to_string(),
+                version,
+                path,
+            });
+        }
+    }
+
+    found
+}
+
+/// `<prefix>/Cellar/python@3.12/3.12.1_1`
+fn scan_homebrew(prefixes: &[PathBuf]) -> Vec<ForeignRuntime> {
+    let mut found = Vec::new();
+
+    for prefix in prefixes {
+        for (formula, formula_dir) in subdirs(&prefix.join("Cellar")) {
+            let base = formula.split('@').next().unwrap_or(&formula);
+            let Some(plugin) = known_plugin(base) else {
+                continue;
+            };
+
+            for (version, path) in subdirs(&formula_dir) {
+                found.push(ForeignRuntime {
+                    source: Source::Homebrew,
+                    plugin: plugin.to_string(),
+                    version: strip_brew_revision(&version).to_string(),
+                    path,
+                });
+            }
+        }
+    }
+
+    found
+}
+
+/// Homebrew appends `_N` to versions that were rebuilt
+fn strip_brew_revision(version: &str) -> &str {
+    match version.rsplit_once('_') {
+        Some((base, rev)) if rev.chars().all(|c| c.is_ascii_digit()) => base,
+        _ => version,
+    }
+}
+
+/// System binaries worth reporting and how to ask them for their version
+const SYSTEM_TOOLS: &[(&str, &[&str])] = &[
+    ("node", &["--version"]),
+    ("python3", &["--version"]),
+    ("ruby", &["--version"]),
+    ("go", &["version"]),
+    ("java", &["-version"]),
+    ("rustc", &["--version"]),
+    ("deno", &["--version"]),
+    ("erl", &["-noshell", "-eval", "io:put_chars(erlang:system_info(otp_release)), halt()."]),
+];
+
+fn scan_system(bin_dirs: &[PathBuf]) -> Vec<ForeignRuntime> {
+    let mut found = Vec::new();
+    let mut seen = HashSet::new();
+
+    for dir in bin_dirs {
+        for (tool, args) in SYSTEM_TOOLS {
+            let binary = dir.join(tool);
+            if !binary.is_file() {
+                continue;
+            }
+
+            // /bin and /usr/bin are often the same directory
+            let canonical = fs::canonicalize(&binary).unwrap_or_else(|_| binary.clone());
+            if !seen.insert(canonical) {
+                continue;
+            }
+
+            let output = probe(&binary, args, PROBE_TIMEOUT).unwrap_or_default();
+
+            let Some(version) = extract_version(&output) else {
+                continue;
+            };
+
+            found.push(ForeignRuntime {
+                source: Source::System,
+                plugin: plugin_for_tool(tool).to_string(),
+                version,
+                path: dir.parent().unwrap_or(dir).to_path_buf(),
+            });
+        }
+    }
+
+    found
+}
+
+/// How long a system binary gets to print its version
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Run `binary` and capture its output, killing it after `timeout`
+fn probe(binary: &Path, args: &[&str], timeout: Duration) -> Option<String> {
+    let handle = duct::cmd(binary, args)
+        .stdin_null()
+        .stderr_to_stdout()
+        .stdout_capture()
+        .unchecked()
+        .start()
+        .ok()?;
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        match handle.try_wait() {
+            Ok(Some(output)) => return Some(String::from_utf8_lossy(&output.stdout).into_owned()),
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+            _ => {
+                let _ = handle.kill();
+                return None;
+            }
+        }
+    }
+}
+
+/// Pull the first dotted version number out of a `--version` banner
+pub fn extract_version(output: &str) -> Option<String> {
+    let bytes = output.as_bytes();
+    let mut start = 0;
+
+    while start < bytes.len() {
+        if bytes[start].is_ascii_digit() {
+            let end = bytes[start..]
+                .iter()
+                .position(|b| !(b.is_ascii_digit() || *b == b'.'))
+                .map(|n| start + n)
+                .unwrap_or(bytes.len());
+
+            let candidate = output[start..end].trim_end_matches('.');
+            if candidate.contains('.') {
+                return Some(candidate.to_string());
+            }
+            start = end;
+        } else {
+            start += 1;
+        }
+    }
+
+    // Single-number versions, e.g. an OTP release
+    let trimmed = output.trim();
+    if !trimmed.is_empty() && trimmed.chars().all(|c| c.is_ascii_digit()) {
+        return Some(trimmed.to_string());
+    }
+
+    None
+}
+
+/// Map a tool, formula or sdkman candidate name to a known asdf plugin
+fn known_plugin(tool: &str) -> Option<&'static str> {
+    Some(match tool {
+        "node" | "nodejs" => "nodejs",
+        "python" | "python3" => "python",
+        "ruby" => "ruby",
+        "go" | "golang" => "golang",
+        "java" | "openjdk" => "java",
+        "rust" | "rustc" => "rust",
+        "deno" => "deno",
+        "erl" | "erlang" => "erlang",
+        "elixir" => "elixir",
+        "kotlin" => "kotlin",
+        "gradle" => "gradle",
+        "maven" => "maven",
+        "scala" => "scala",
+        "sbt" => "sbt",
+        "zig" => "zig",
+        _ => return None,
+    })
+}
+
+/// Map a tool name to an asdf plugin, falling back to the name itself
+fn plugin_for_tool(tool: &str) -> &str {
+    known_plugin(tool).unwrap_or(tool)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn mkdirs(root: &Path, dirs: &[&str]) {
+        for dir in dirs {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+    }
+
+    fn roots_in(dir: &Path) -> ScanRoots {
+        let mut roots = ScanRoots::for_home(dir);
+        roots.homebrew = vec![dir.join("linuxbrew")];
+        roots.system_bins = vec![];
+        roots
+    }
+
+    #[test]
+    fn test_scan_version_managers() {
+        let dir = TempDir::new().unwrap();
+        mkdirs(
+            dir.path(),
+            &[
+                ".nvm/versions/node/v20.1.0",
+                ".pyenv/versions/3.12.1",
+                ".pyenv/versions/envs",
+                ".rbenv/versions/3.2.2",
+                ".rustup/toolchains/1.75.0-x86_64-unknown-linux-gnu",
+                ".sdkman/candidates/java/21.0.1-tem",
+                ".sdkman/candidates/java/current",
+                "linuxbrew/Cellar/python@3.11/3.11.7_1",
+                "linuxbrew/Cellar/htop/3.2.2",
+            ],
+        );
+
+        let found = scan(&roots_in(dir.path()));
+        let summary: Vec<(Source, &str, &str)> = found
+            .iter()
+            .map(|r| (r.source, r.plugin.as_str(), r.version.as_str()))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (Source::Nvm, "nodejs", "20.1.0"),
+                (Source::Pyenv, "python", "3.12.1"),
+                (Source::Rbenv, "ruby", "3.2.2"),
+                (Source::Rustup, "rust", "1.75.0"),
+                (Source::Sdkman, "java", "21.0.1-tem"),
+                (Source::Homebrew, "python", "3.11.7"),
+            ]
+        );
+        assert!(found[0].path_version().starts_with("path:"));
+    }
+
+    #[test]
+    fn test_scan_sources_filter() {
+        let dir = TempDir::new().unwrap();
+        mkdirs(dir.path(), &[".nvm/versions/node/v18.0.0", ".rbenv/versions/3.1.0"]);
+
+        let found = scan_sources(&roots_in(dir.path()), &[Source::Rbenv]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].plugin, "ruby");
+    }
+
+    #[test]
+    fn test_rustup_channel() {
+        assert_eq!(rustup_channel("stable-x86_64-unknown-linux-gnu"), "stable");
+        assert_eq!(
+            rustup_channel("nightly-2024-01-01-aarch64-apple-darwin"),
+            "nightly-2024-01-01"
+        );
+        assert_eq!(rustup_channel("my-custom"), "my-custom");
+    }
+
+    #[test]
+    fn test_extract_version() {
+        assert_eq!(extract_version("Python 3.12.1\n").as_deref(), Some("3.12.1"));
+        assert_eq!(extract_version("v20.1.0").as_deref(), Some("20.1.0"));
+        assert_eq!(
+            extract_version("go version go1.21.5 linux/amd64").as_deref(),
+            Some("1.21.5")
+        );
+        assert_eq!(
+            extract_version("openjdk version \"21.0.1\" 2023-10-17").as_deref(),
+            Some("21.0.1")
+        );
+        assert_eq!(extract_version("26").as_deref(), Some("26"));
+        assert_eq!(extract_version("command not found"), None);
+    }
+
+    #[test]
+    fn test_probe_gives_up_on_hanging_binaries() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let script = |name: &str, body: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+
+        let node = script("node", "echo v20.1.0");
+        assert_eq!(probe(&node, &["--version"], PROBE_TIMEOUT).as_deref(), Some("v20.1.0\n"));
+
+        let erl = script("erl", "exec sleep 30");
+        let started = Instant::now();
+        assert_eq!(probe(&erl, &[], Duration::from_millis(100)), None);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_source_parse() {
+        assert_eq!(Source::parse("pyenv"), Some(Source::Pyenv));
+        assert_eq!(Source::parse("conda"), None);
+    }
+
+    #[test]
+    fn test_strip_brew_revision() {
+        assert_eq!(strip_brew_revision("3.11.7_1"), "3.11.7");
+        assert_eq!(strip_brew_revision("1.0_beta"), "1.0_beta");
+    }
+}
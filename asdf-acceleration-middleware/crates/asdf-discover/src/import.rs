@@ -0,0 +1,380 @@
+//! Importing foreign runtimes and version files into `.tool-versions`
+
+use crate::foreign::ForeignRuntime;
+use anyhow::{Context, Result};
+use asdf_core::{ToolVersions, Version, VersionReq};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// A change to be made to a `.tool-versions` file
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ImportAction {
+    /// asdf plugin name
+    pub plugin: String,
+
+    /// Versions to write for the plugin
+    pub versions: Vec<String>,
+
+    /// What the entry was derived from
+    pub origin: String,
+
+    /// Whether an existing entry is replaced
+    pub replaces: bool,
+}
+
+/// Pick one installation per plugin and turn it into a `path:` entry
+///
+/// The newest version wins when several sources provide the same plugin.
+/// Plugins already pinned in `existing` are left alone unless `force` is set.
+pub fn plan_path_imports(
+    runtimes: &[ForeignRuntime],
+    existing: &ToolVersions,
+    force: bool,
+) -> Vec<ImportAction> {
+    let mut newest: BTreeMap<&str, &ForeignRuntime> = BTreeMap::new();
+
+    for runtime in runtimes {
+        newest
+            .entry(&runtime.plugin)
+            .and_modify(|current| {
+                if version_key(&runtime.version) > version_key(&current.version) {
+                    *current = runtime;
+                }
+            })
+            .or_insert(runtime);
+    }
+
+    newest
+        .into_values()
+        .filter_map(|runtime| {
+            let replaces = existing.contains(&runtime.plugin);
+            if replaces && !force {
+                return None;
+            }
+            Some(ImportAction {
+                plugin: runtime.plugin.clone(),
+                versions: vec![runtime.path_version()],
+                origin: format!("{} {}", runtime.source, runtime.version),
+                replaces,
+            })
+        })
+        .collect()
+}
+
+/// Versions that don't parse sort below everything that does
+fn version_key(version: &str) -> Option<Version> {
+    Version::parse(version).ok()
+}
+
+/// Version files understood by other managers, and the plugin they map to
+const VERSION_FILES: &[(&str, &str)] = &[
+    (".nvmrc", "nodejs"),
+    (".node-version", "nodejs"),
+    (".python-version", "python"),
+    (".ruby-version", "ruby"),
+    ("rust-toolchain.toml", "rust"),
+    ("rust-toolchain", "rust"),
+];
+
+/// Planned migrations, and the version files that could not be carried over
+#[derive(Debug, Default)]
+pub struct Migrations {
+    /// Entries to write
+    pub actions: Vec<ImportAction>,
+
+    /// Why each skipped version file was left out
+    pub skipped: Vec<String>,
+}
+
+/// Read the version files in `dir` and plan their `.tool-versions` entries
+///
+/// Partial Node.js versions such as `20` resolve to the newest matching
+/// install under `installs`; the ones that don't, and nvm aliases, are skipped.
+pub fn plan_migrations(
+    dir: &Path,
+    existing: &ToolVersions,
+    force: bool,
+    installs: &Path,
+) -> Result<Migrations> {
+    let mut migrations = Migrations::default();
+
+    for (file, plugin) in VERSION_FILES {
+        let path = dir.join(file);
+        if !path.is_file() {
+            continue;
+        }
+
+        // The first file found for a plugin wins
+        if migrations.actions.iter().any(|a| a.plugin == *plugin) {
+            continue;
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        let versions = match *file {
+            ".nvmrc" | ".node-version" => match parse_nvmrc(&content) {
+                Some(spec) => match resolve_node(&spec, &installs.join(plugin)) {
+                    Ok(version) => vec![version],
+                    Err(reason) => {
+                        migrations.skipped.push(format!("{}: {}", file, reason));
+                        continue;
+                    }
+                },
+                None => Vec::new(),
+            },
+            ".python-version" => parse_python_version(&content),
+            ".ruby-version" => parse_ruby_version(&content),
+            "rust-toolchain.toml" => parse_rust_toolchain_toml(&content)?,
+            _ => parse_rust_toolchain(&content),
+        };
+
+        if versions.is_empty() {
+            continue;
+        }
+
+        let replaces = existing.contains(plugin);
+        if replaces && !force {
+            continue;
+        }
+
+        migrations.actions.push(ImportAction {
+            plugin: plugin.to_string(),
+            versions,
+            origin: file.to_string(),
+            replaces,
+        });
+    }
+
+    Ok(migrations)
+}
+
+/// Apply planned actions to a `.tool-versions` file
+pub fn apply(tool_versions: &mut ToolVersions, actions: &[ImportAction]) {
+    for action in actions {
+        tool_versions.set(action.plugin.clone(), action.versions.clone());
+    }
+}
+
+/// Non-comment, non-empty lines
+fn meaningful_lines(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+}
+
+/// `.nvmrc`: `v20.1.0`, `20`, `lts/*`, `node`, without the `v`
+fn parse_nvmrc(content: &str) -> Option<String> {
+    meaningful_lines(content)
+        .next()
+        .map(|line| {
+            line.strip_prefix('v')
+                .filter(|v| v.starts_with(|c: char| c.is_ascii_digit()))
+                .unwrap_or(line)
+        })
+        .map(str::to_string)
+}
+
+/// Turn an `.nvmrc` version into one asdf-nodejs installs as written
+///
+/// Full versions pass through; partial ones take the newest install under
+/// `installs` they cover. nvm aliases like `lts/*` or `node` name whatever
+/// is current upstream, so they are left for the user to pin.
+fn resolve_node(spec: &str, installs: &Path) -> std::result::Result<String, String> {
+    let parts: Vec<&str> = spec.split('.').collect();
+    if !parts
+        .iter()
+        .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+    {
+        return Err(format!(
+            "nvm alias {} has no asdf equivalent, pin a version instead",
+            spec
+        ));
+    }
+    if parts.len() == 3 {
+        return Ok(spec.to_string());
+    }
+
+    let installed: Vec<String> = fs::read_dir(installs)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+
+    VersionReq::parse(&format!("{}.*", spec))
+        .ok()
+        .and_then(|req| req.resolve(&installed))
+        .ok_or_else(|| {
+            format!(
+                "no installed nodejs matches {}, install one or pin a full version",
+                spec
+            )
+        })
+}
+
+/// `.python-version`: one version per line, `system` means "not managed"
+fn parse_python_version(content: &str) -> Vec<String> {
+    meaningful_lines(content)
+        .flat_map(str::split_whitespace)
+        .filter(|v| *v != "system")
+        .map(str::to_string)
+        .collect()
+}
+
+/// `.ruby-version`: `3.2.2` or `ruby-3.2.2`
+fn parse_ruby_version(content: &str) -> Vec<String> {
+    meaningful_lines(content)
+        .next()
+        .map(|line| line.trim_start_matches("ruby-").to_string())
+        .filter(|v| v != "system")
+        .into_iter()
+        .collect()
+}
+
+/// `rust-toolchain.toml`: `[toolchain] channel = "1.75.0"`
+fn parse_rust_toolchain_toml(content: &str) -> Result<Vec<String>> {
+    let value: toml::Value = toml::from_str(content).context("Invalid rust-toolchain.toml")?;
+
+    Ok(value
+        .get("toolchain")
+        .and_then(|t| t.get("channel"))
+        .and_then(|c| c.as_str())
+        .map(|c| vec![c.to_string()])
+        .unwrap_or_default())
+}
+
+/// Legacy `rust-toolchain`: either a bare channel name or TOML
+fn parse_rust_toolchain(content: &str) -> Vec<String> {
+    if content.contains("[toolchain]") {
+        return parse_rust_toolchain_toml(content).unwrap_or_default();
+    }
+
+    meaningful_lines(content)
+        .next()
+        .map(|line| vec![line.to_string()])
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::foreign::Source;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn runtime(source: Source, plugin: &str, version: &str, path: &str) -> ForeignRuntime {
+        ForeignRuntime {
+            source,
+            plugin: plugin.to_string(),
+            version: version.to_string(),
+            path: PathBuf::from(path),
+        }
+    }
+
+    #[test]
+    fn test_plan_path_imports_picks_newest() {
+        let runtimes = vec![
+            runtime(Source::Nvm, "nodejs", "18.19.0", "/n/18"),
+            runtime(Source::Nvm, "nodejs", "20.1.0", "/n/20"),
+            runtime(Source::System, "nodejs", "12.22.9", "/usr"),
+            runtime(Source::Pyenv, "python", "3.12.1", "/p/3.12.1"),
+        ];
+
+        let existing = ToolVersions::parse("python 3.11.0\n");
+        let actions = plan_path_imports(&runtimes, &existing, false);
+
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].plugin, "nodejs");
+        assert_eq!(actions[0].versions, vec!["path:/n/20"]);
+        assert_eq!(actions[0].origin, "nvm 20.1.0");
+
+        let forced = plan_path_imports(&runtimes, &existing, true);
+        assert_eq!(forced.len(), 2);
+        assert!(forced.iter().any(|a| a.plugin == "python" && a.replaces));
+    }
+
+    #[test]
+    fn test_parse_nvmrc() {
+        assert_eq!(parse_nvmrc("v20.1.0\n").unwrap(), "20.1.0");
+        assert_eq!(parse_nvmrc("# pinned\nlts/iron\n").unwrap(), "lts/iron");
+        assert_eq!(parse_nvmrc("node").unwrap(), "node");
+        assert!(parse_nvmrc("\n").is_none());
+    }
+
+    #[test]
+    fn test_resolve_node() {
+        let installs = TempDir::new().unwrap();
+        for version in ["18.19.0", "20.1.0", "20.11.1", "21.0.0"] {
+            fs::create_dir(installs.path().join(version)).unwrap();
+        }
+
+        assert_eq!(resolve_node("20", installs.path()).unwrap(), "20.11.1");
+        assert_eq!(resolve_node("20.1", installs.path()).unwrap(), "20.1.0");
+        assert_eq!(resolve_node("22.0.1", installs.path()).unwrap(), "22.0.1");
+        assert!(resolve_node("22", installs.path()).is_err());
+        assert!(resolve_node("lts/*", installs.path()).is_err());
+        assert!(resolve_node("node", installs.path()).is_err());
+    }
+
+    #[test]
+    fn test_parse_python_and_ruby() {
+        assert_eq!(
+            parse_python_version("3.12.1\n3.11.7\nsystem\n"),
+            vec!["3.12.1", "3.11.7"]
+        );
+        assert_eq!(parse_ruby_version("ruby-3.2.2\n"), vec!["3.2.2"]);
+    }
+
+    #[test]
+    fn test_parse_rust_toolchain() {
+        let toml = "[toolchain]\nchannel = \"1.75.0\"\ncomponents = [\"clippy\"]\n";
+        assert_eq!(parse_rust_toolchain_toml(toml).unwrap(), vec!["1.75.0"]);
+        assert_eq!(parse_rust_toolchain("nightly-2024-01-01\n"), vec!["nightly-2024-01-01"]);
+        assert_eq!(parse_rust_toolchain(toml), vec!["1.75.0"]);
+    }
+
+    #[test]
+    fn test_migrate_directory() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(".nvmrc"), "v20.1.0\n").unwrap();
+        fs::write(dir.path().join(".ruby-version"), "3.2.2\n").unwrap();
+        fs::write(
+            dir.path().join("rust-toolchain.toml"),
+            "[toolchain]\nchannel = \"stable\"\n",
+        )
+        .unwrap();
+
+        let mut tv = ToolVersions::parse("ruby 3.1.0\n");
+        let migrations =
+            plan_migrations(dir.path(), &tv, false, &dir.path().join("installs")).unwrap();
+        assert!(migrations.skipped.is_empty());
+        apply(&mut tv, &migrations.actions);
+
+        assert_eq!(tv.get("nodejs").unwrap(), ["20.1.0"]);
+        assert_eq!(tv.get("ruby").unwrap(), ["3.1.0"]);
+        assert_eq!(tv.get("rust").unwrap(), ["stable"]);
+    }
+
+    #[test]
+    fn test_migrate_skips_unresolved_nvmrc() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(".nvmrc"), "lts/*\n").unwrap();
+        fs::write(dir.path().join(".node-version"), "20\n").unwrap();
+
+        let migrations = plan_migrations(
+            dir.path(),
+            &ToolVersions::default(),
+            false,
+            &dir.path().join("installs"),
+        )
+        .unwrap();
+
+        assert!(migrations.actions.is_empty());
+        assert_eq!(migrations.skipped.len(), 2);
+        assert!(migrations.skipped[0].starts_with(".nvmrc: nvm alias lts/*"));
+    }
+}
@@ -1,15 +1,29 @@
 //! asdf-discover - Auto-discovery tool for asdf runtimes
 
+mod foreign;
+mod import;
+mod manifest;
+mod nickel;
+
 use anyhow::Result;
-use asdf_core::{Plugin, Runtime};
+use asdf_config::{AcceleratorConfig, ConfigLoader};
+use asdf_core::doctor::{DoctorContext, Report, Severity};
+use asdf_core::{tool_versions, IndexSource, Plugin, Runtime, ToolVersions};
 use clap::{Parser, Subcommand};
 use colored::Colorize;
+use foreign::{ScanRoots, Source};
+use manifest::{Format, Manifest};
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(name = "asdf-discover")]
 #[command(about = "Auto-discovery tool for asdf runtimes")]
 #[command(version)]
 struct Cli {
+    /// Configuration file path
+    #[arg(short, long, global = true)]
+    config: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -36,10 +50,81 @@ enum Commands {
         /// Output file
         #[arg(short, long)]
         output: Option<String>,
+
+        /// Directories whose .tool-versions to include (defaults to home and current directory)
+        #[arg(long = "dir")]
+        dirs: Vec<PathBuf>,
+    },
+
+    /// Converge this machine to a generated configuration file
+    Apply {
+        /// Configuration file (.ncl, .json or .toml)
+        file: PathBuf,
+
+        /// Override the format inferred from the file extension
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Also remove plugins and runtimes not listed in the file
+        #[arg(long)]
+        prune: bool,
+
+        /// Show the planned changes without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Print the Nickel contract for generated configuration
+    Schema,
+
+    /// Import runtimes installed by other version managers
+    Import {
+        /// Only import from these sources (nvm, pyenv, rbenv, rustup, sdkman, homebrew, system)
+        #[arg(long = "from")]
+        sources: Vec<String>,
+
+        /// Migrate version files (.nvmrc, .python-version, ...) instead of installations
+        #[arg(long, conflicts_with = "sources")]
+        migrate: bool,
+
+        /// Directory whose .tool-versions is updated (defaults to the current directory)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+
+        /// Write to the global ~/.tool-versions
+        #[arg(long, conflicts_with = "dir")]
+        global: bool,
+
+        /// Replace plugins that already have an entry
+        #[arg(long)]
+        force: bool,
+
+        /// Show what would change without writing
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// List plugins available from the plugin index
+    Available {
+        /// Only show plugins matching this name
+        query: Option<String>,
+
+        /// Index git URL or vendored directory, highest precedence first
+        /// (defaults to the configured registry sources)
+        #[arg(long = "index")]
+        sources: Vec<String>,
+
+        /// Output format (text, json)
+        #[arg(long, default_value = "text")]
+        format: String,
     },
 
     /// Validate existing setup
-    Validate,
+    Validate {
+        /// Output format (text, json, sarif)
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
 }
 
 fn main() -> Result<()> {
@@ -47,18 +132,52 @@ fn main() -> Result<()> {
 
     match cli.command {
         Commands::Scan { deep, format } => scan(deep, &format),
-        Commands::Generate { format, output } => generate(&format, output.as_deref()),
-        Commands::Validate => validate(),
+        Commands::Generate {
+            format,
+            output,
+            dirs,
+        } => generate(&format, output.as_deref(), dirs),
+        Commands::Apply {
+            file,
+            format,
+            prune,
+            dry_run,
+        } => apply(
+            &load_config(cli.config.as_deref())?,
+            &file,
+            format.as_deref(),
+            prune,
+            dry_run,
+        ),
+        Commands::Schema => {
+            print!("{}", manifest::NICKEL_SCHEMA);
+            Ok(())
+        }
+        Commands::Import {
+            sources,
+            migrate,
+            dir,
+            global,
+            force,
+            dry_run,
+        } => import(&sources, migrate, dir, global, force, dry_run),
+        Commands::Available {
+            query,
+            sources,
+            format,
+        } => available(
+            &load_config(cli.config.as_deref())?,
+            query.as_deref(),
+            &sources,
+            &format,
+        ),
+        Commands::Validate { format } => validate(&format),
     }
 }
 
 fn scan(deep: bool, format: &str) -> Result<()> {
     println!("{} Scanning system for runtimes...", "→".cyan());
 
-    if deep {
-        println!("{} Running deep scan...", "→".cyan());
-    }
-
     let plugins = Plugin::list()?;
 
     println!("{} Found {} plugins", "✓".green(), plugins.len());
@@ -77,67 +196,340 @@ fn scan(deep: bool, format: &str) -> Result<()> {
         }
     }
 
+    let foreign = if deep {
+        println!("\n{} Running deep scan...", "→".cyan());
+        let found = foreign::scan(&ScanRoots::from_env());
+        print_foreign(&found);
+        Some(found)
+    } else {
+        None
+    };
+
     if format == "json" {
-        let json = serde_json::to_string_pretty(&plugins)?;
+        let json = match foreign {
+            Some(found) => serde_json::to_string_pretty(&serde_json::json!({
+                "plugins": plugins,
+                "foreign": found,
+            }))?,
+            None => serde_json::to_string_pretty(&plugins)?,
+        };
         println!("\n{}", json);
     }
 
     Ok(())
 }
 
-fn generate(format: &str, output: Option<&str>) -> Result<()> {
-    println!("{} Generating configuration...", "→".cyan());
+fn print_foreign(found: &[foreign::ForeignRuntime]) {
+    if found.is_empty() {
+        println!("{} No runtimes found outside asdf", "✓".green());
+        return;
+    }
 
-    let plugins = Plugin::list()?;
+    println!(
+        "{} Found {} runtimes outside asdf",
+        "!".yellow(),
+        found.len()
+    );
 
-    let config = match format {
-        "nickel" => generate_nickel_config(&plugins),
-        "json" => serde_json::to_string_pretty(&plugins)?,
-        "toml" => toml::to_string_pretty(&plugins)?,
-        _ => anyhow::bail!("Unsupported format: {}", format),
+    for source in Source::ALL {
+        let from_source: Vec<_> = found.iter().filter(|r| r.source == source).collect();
+        if from_source.is_empty() {
+            continue;
+        }
+
+        println!("\n  {}", source.to_string().bright_white());
+        for runtime in from_source {
+            println!(
+                "    {} {} {}",
+                runtime.plugin.green(),
+                runtime.version,
+                runtime.path.display().to_string().bright_black()
+            );
+        }
+    }
+
+    println!(
+        "\n{} Run `asdf-discover import` to register them with asdf",
+        "→".cyan()
+    );
+}
+
+fn import(
+    sources: &[String],
+    migrate: bool,
+    dir: Option<PathBuf>,
+    global: bool,
+    force: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let dir = if global {
+        std::env::var("HOME")
+            .map(PathBuf::from)
+            .map_err(|_| anyhow::anyhow!("HOME is not set"))?
+    } else {
+        match dir {
+            Some(d) => d,
+            None => std::env::current_dir()?,
+        }
+    };
+    let path = dir.join(tool_versions::FILE_NAME);
+    let mut tool_versions = ToolVersions::load(&path)?;
+
+    let actions = if migrate {
+        println!("{} Migrating version files in {}", "→".cyan(), dir.display());
+        let installs = asdf_core::installs_dir().unwrap_or_default();
+        let migrations = import::plan_migrations(&dir, &tool_versions, force, &installs)?;
+        for reason in &migrations.skipped {
+            println!("  {} Skipped {}", "!".yellow(), reason);
+        }
+        migrations.actions
+    } else {
+        let sources = if sources.is_empty() {
+            Source::ALL.to_vec()
+        } else {
+            sources
+                .iter()
+                .map(|s| Source::parse(s).ok_or_else(|| anyhow::anyhow!("Unknown source: {}", s)))
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        println!("{} Scanning for foreign runtimes...", "→".cyan());
+        let found = foreign::scan_sources(&ScanRoots::from_env(), &sources);
+        import::plan_path_imports(&found, &tool_versions, force)
     };
 
+    if actions.is_empty() {
+        println!("{} Nothing to import", "✓".green());
+        return Ok(());
+    }
+
+    for action in &actions {
+        let marker = if action.replaces { "~" } else { "+" };
+        println!(
+            "  {} {} {} {}",
+            marker.yellow(),
+            action.plugin.green(),
+            action.versions.join(" "),
+            format!("({})", action.origin).bright_black()
+        );
+    }
+
+    if dry_run {
+        println!("\n{} Dry run, {} not modified", "→".cyan(), path.display());
+        return Ok(());
+    }
+
+    import::apply(&mut tool_versions, &actions);
+    tool_versions.save(&path)?;
+
+    println!(
+        "\n{} Wrote {} entries to {}",
+        "✓".green(),
+        actions.len(),
+        path.display()
+    );
+
+    Ok(())
+}
+
+fn generate(format: &str, output: Option<&str>, dirs: Vec<PathBuf>) -> Result<()> {
+    let format = Format::parse(format)?;
+    let dirs = if dirs.is_empty() { default_dirs() } else { dirs };
+
+    // Keep stdout clean when the configuration itself goes there
+    if output.is_some() {
+        println!("{} Generating configuration...", "→".cyan());
+    }
+
+    let config = Manifest::capture(&dirs)?.render(format)?;
+
     if let Some(path) = output {
         std::fs::write(path, &config)?;
         println!("{} Configuration written to {}", "✓".green(), path);
     } else {
-        println!("{}", config);
+        print!("{}", config);
     }
 
     Ok(())
 }
 
-fn generate_nickel_config(plugins: &[Plugin]) -> String {
-    let mut config = String::from("{\n  asdf = {\n    plugins = [\n");
-
-    for plugin in plugins {
-        config.push_str(&format!("      \"{}\",\n", plugin.name));
+/// Home and current directory, without duplicates
+fn default_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(home) = std::env::var_os("HOME") {
+        dirs.push(PathBuf::from(home));
     }
-
-    config.push_str("    ],\n  },\n}\n");
-    config
+    if let Ok(cwd) = std::env::current_dir() {
+        if !dirs.contains(&cwd) {
+            dirs.push(cwd);
+        }
+    }
+    dirs
 }
 
-fn validate() -> Result<()> {
-    println!("{} Validating asdf setup...", "→".cyan());
+/// Load configuration from a file, or defaults and environment
+fn load_config(path: Option<&Path>) -> Result<AcceleratorConfig> {
+    let mut loader = ConfigLoader::new();
+    Ok(match path {
+        Some(path) => loader.load_file(path)?,
+        None => loader.load_with_defaults(None::<&Path>)?,
+    })
+}
 
-    if !asdf_core::is_asdf_installed() {
-        println!("{} asdf is not installed", "✗".red());
+fn apply(
+    config: &AcceleratorConfig,
+    file: &Path,
+    format: Option<&str>,
+    prune: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let format = format.map(Format::parse).transpose()?;
+    let desired = Manifest::load(file, format)?;
+
+    println!("{} Reading current setup...", "→".cyan());
+    let dirs: Vec<PathBuf> = desired.directories.iter().map(|d| d.path.clone()).collect();
+    let current = Manifest::capture(&dirs)?;
+
+    let steps = manifest::plan(&desired, &current, prune);
+    if steps.is_empty() {
+        println!("{} Already up to date", "✓".green());
         return Ok(());
     }
 
-    println!("{} asdf is installed", "✓".green());
+    println!("{} {} changes needed", "→".cyan(), steps.len());
 
-    let version = asdf_core::asdf_version()?;
-    println!("  Version: {}", version);
+    // Short names resolve through the plugin index when it can be loaded
+    let needs_index = steps
+        .iter()
+        .any(|step| matches!(step, manifest::Step::AddPlugin { url: None, .. }));
+    let index = if needs_index && !dry_run {
+        config.registry.registry()?.load().ok()
+    } else {
+        None
+    };
 
-    let dir = asdf_core::asdf_dir()?;
-    println!("  Directory: {}", dir.display());
+    let mut failures = 0;
+    for step in &steps {
+        if dry_run {
+            println!("  {} {}", "•".bright_black(), step);
+            continue;
+        }
 
-    let plugins = Plugin::list()?;
-    println!("  Plugins: {}", plugins.len());
+        match manifest::execute(step, index.as_ref()) {
+            Ok(()) => println!("  {} {}", "✓".green(), step),
+            Err(e) => {
+                failures += 1;
+                println!("  {} {}: {}", "✗".red(), step, e);
+            }
+        }
+    }
+
+    if dry_run {
+        println!("\n{} Dry run, nothing changed", "→".cyan());
+    } else if failures > 0 {
+        anyhow::bail!("{} of {} changes failed", failures, steps.len());
+    } else {
+        println!("\n{} Configuration applied", "✓".green().bold());
+    }
+
+    Ok(())
+}
+
+fn available(
+    config: &AcceleratorConfig,
+    query: Option<&str>,
+    sources: &[String],
+    format: &str,
+) -> Result<()> {
+    let mut registry = config.registry.registry()?;
+    if !sources.is_empty() {
+        registry = registry.with_sources(sources.iter().map(|s| IndexSource::parse(s)).collect());
+    }
+    let index = registry.load()?;
+
+    let installed: Vec<String> = Plugin::list()
+        .map(|plugins| plugins.into_iter().map(|p| p.name).collect())
+        .unwrap_or_default();
+    let entries: Vec<_> = match query {
+        Some(query) => index.search(query).into_iter().map(|hit| hit.entry).collect(),
+        None => index.iter().cloned().collect(),
+    };
+
+    if format == "json" {
+        let json: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "name": entry.name,
+                    "repository": entry.repository,
+                    "installed": installed.contains(&entry.name),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&json)?);
+        return Ok(());
+    }
 
-    println!("\n{} Setup is valid", "✓".green().bold());
+    println!(
+        "{} {} of {} indexed plugins",
+        "→".cyan(),
+        entries.len(),
+        index.len()
+    );
+    for entry in &entries {
+        let marker = if installed.contains(&entry.name) { "*" } else { " " };
+        println!(
+            "  {} {} {}",
+            marker.green(),
+            entry.name,
+            entry.repository.bright_black()
+        );
+    }
 
     Ok(())
 }
+
+fn validate(format: &str) -> Result<()> {
+    let ctx = DoctorContext::from_env()?;
+    let report = Report::run(&ctx);
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "sarif" => println!(
+            "{}",
+            serde_json::to_string_pretty(
+                &report.to_sarif(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+            )?
+        ),
+        "text" => {
+            println!("{} Validating asdf setup...", "→".cyan());
+            println!("  Directory: {}", ctx.data_dir.display());
+            if let Ok(version) = asdf_core::asdf_version() {
+                println!("  Version: {}", version);
+            }
+            print_report(&report);
+        }
+        _ => anyhow::bail!("Unsupported format: {}", format),
+    }
+
+    std::process::exit(report.exit_code());
+}
+
+fn print_report(report: &Report) {
+    println!();
+    print!("{}", report.render_findings());
+
+    match report.worst() {
+        None | Some(Severity::Info) => println!("{} Setup is valid", "✓".green().bold()),
+        Some(Severity::Warning) => println!(
+            "\n{} Setup works, with {} warnings",
+            "!".yellow().bold(),
+            report.count(Severity::Warning)
+        ),
+        Some(Severity::Error) => println!(
+            "\n{} Setup is broken ({} findings)",
+            "✗".red().bold(),
+            report.findings.len()
+        ),
+    }
+}
@@ -0,0 +1,585 @@
+//! Declarative description of an asdf setup
+//!
+//! `generate` captures the current machine as a [`Manifest`]; `apply` diffs a
+//! manifest against a fresh capture and converges the machine towards it.
+
+use crate::nickel;
+use anyhow::{bail, Context, Result};
+use asdf_core::{tool_versions, Index, Plugin, Runtime, ToolVersions, Version};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Version of the manifest format written by this build
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Nickel contract for manifests, published by `asdf-discover schema`
+pub const NICKEL_SCHEMA: &str = include_str!("../schema/manifest.ncl");
+
+/// Complete asdf configuration of a machine
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Manifest {
+    /// Manifest format version
+    #[serde(default = "default_version")]
+    pub version: u32,
+
+    /// Installed plugins
+    #[serde(default)]
+    pub plugins: Vec<PluginSpec>,
+
+    /// Installed runtime versions, per plugin
+    #[serde(default)]
+    pub runtimes: Vec<RuntimeSpec>,
+
+    /// `.tool-versions` files to manage
+    #[serde(default)]
+    pub directories: Vec<DirectorySpec>,
+}
+
+fn default_version() -> u32 {
+    MANIFEST_VERSION
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            plugins: Vec::new(),
+            runtimes: Vec::new(),
+            directories: Vec::new(),
+        }
+    }
+}
+
+/// A plugin and where it comes from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PluginSpec {
+    /// Plugin name
+    pub name: String,
+
+    /// Repository URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Commit, tag or branch to check out
+    #[serde(default, rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub ref_: Option<String>,
+}
+
+/// Runtime versions installed for a plugin
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RuntimeSpec {
+    /// Plugin name
+    pub plugin: String,
+
+    /// Installed versions
+    #[serde(default)]
+    pub installed: Vec<String>,
+
+    /// Version the effective `.tool-versions` selected when the manifest
+    /// was generated
+    ///
+    /// Recorded for reference; `apply` pins versions through `directories`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<String>,
+}
+
+/// Contents of a directory's `.tool-versions`
+///
+/// Only the listed plugins are managed; other entries in the file are kept.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DirectorySpec {
+    /// Directory containing the `.tool-versions` file
+    pub path: PathBuf,
+
+    /// Pinned versions per plugin
+    #[serde(default)]
+    pub tools: BTreeMap<String, Vec<String>>,
+}
+
+/// Serialization format of a manifest file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Nickel record
+    Nickel,
+
+    /// JSON document
+    Json,
+
+    /// TOML document
+    Toml,
+}
+
+impl Format {
+    /// Parse a format name
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "nickel" | "ncl" => Ok(Format::Nickel),
+            "json" => Ok(Format::Json),
+            "toml" => Ok(Format::Toml),
+            _ => bail!("Unsupported format: {}", s),
+        }
+    }
+
+    /// Guess the format from a file extension
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        Self::parse(ext).with_context(|| format!("Cannot infer format of {}", path.display()))
+    }
+}
+
+impl Manifest {
+    /// Capture the current machine state
+    ///
+    /// `directories` lists the directories whose `.tool-versions` should be
+    /// recorded; those without one are skipped. Active versions come from the
+    /// `.tool-versions` chain of the current directory.
+    pub fn capture(directories: &[PathBuf]) -> Result<Self> {
+        let plugins = Plugin::list()?;
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        let active = tool_versions::effective(&std::env::current_dir()?, home.as_deref())?;
+        let mut manifest = Manifest::default();
+
+        for plugin in &plugins {
+            manifest.plugins.push(PluginSpec {
+                name: plugin.name.clone(),
+                url: plugin.url.clone(),
+                ref_: plugin.current_ref().ok(),
+            });
+
+            let runtimes = Runtime::list_for_plugin(&plugin.name).unwrap_or_default();
+            if runtimes.is_empty() {
+                continue;
+            }
+
+            manifest.runtimes.push(RuntimeSpec {
+                plugin: plugin.name.clone(),
+                installed: runtimes.iter().map(|r| r.version.to_string()).collect(),
+                active: active
+                    .get(&plugin.name)
+                    .and_then(|versions| versions.first())
+                    .cloned(),
+            });
+        }
+
+        for dir in directories {
+            let path = dir.join(tool_versions::FILE_NAME);
+            if !path.is_file() {
+                continue;
+            }
+            let tv = ToolVersions::load(&path)?;
+            manifest.directories.push(DirectorySpec {
+                path: dir.clone(),
+                tools: tv
+                    .entries()
+                    .map(|(plugin, versions)| (plugin.to_string(), versions.to_vec()))
+                    .collect(),
+            });
+        }
+
+        Ok(manifest)
+    }
+
+    /// Serialize in the given format
+    pub fn render(&self, format: Format) -> Result<String> {
+        Ok(match format {
+            Format::Json => serde_json::to_string_pretty(self)? + "\n",
+            Format::Toml => toml::to_string_pretty(self)?,
+            Format::Nickel => {
+                let value = serde_json::to_value(self)?;
+                format!(
+                    "# asdf-discover manifest, validate with the contract from `asdf-discover schema`\n{}",
+                    nickel::render(&value)
+                )
+            }
+        })
+    }
+
+    /// Parse a manifest in the given format
+    pub fn parse(content: &str, format: Format) -> Result<Self> {
+        let manifest: Manifest = match format {
+            Format::Json => serde_json::from_str(content)?,
+            Format::Toml => toml::from_str(content)?,
+            Format::Nickel => serde_json::from_value(nickel::parse(content)?)?,
+        };
+        manifest.check_version()?;
+        Ok(manifest)
+    }
+
+    /// Load a manifest file, inferring the format from its extension
+    pub fn load(path: &Path, format: Option<Format>) -> Result<Self> {
+        let format = match format {
+            Some(f) => f,
+            None => Format::from_path(path)?,
+        };
+
+        if format == Format::Nickel {
+            let manifest: Manifest = serde_json::from_value(nickel::read_file(path)?)?;
+            manifest.check_version()?;
+            return Ok(manifest);
+        }
+
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content, format)
+    }
+
+    fn check_version(&self) -> Result<()> {
+        if self.version > MANIFEST_VERSION {
+            bail!(
+                "Manifest version {} is newer than supported version {}",
+                self.version,
+                MANIFEST_VERSION
+            );
+        }
+        Ok(())
+    }
+}
+
+/// A single change needed to converge on a manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Add a missing plugin
+    AddPlugin { name: String, url: Option<String> },
+
+    /// Check out a different plugin ref
+    CheckoutPlugin { name: String, ref_: String },
+
+    /// Install a missing runtime version
+    Install { plugin: String, version: String },
+
+    /// Write a `.tool-versions` file
+    WriteToolVersions {
+        path: PathBuf,
+        tools: BTreeMap<String, Vec<String>>,
+    },
+
+    /// Uninstall a runtime version not in the manifest
+    Uninstall { plugin: String, version: String },
+
+    /// Remove a plugin not in the manifest
+    RemovePlugin { name: String },
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::AddPlugin { name, url } => match url {
+                Some(url) => write!(f, "add plugin {} ({})", name, url),
+                None => write!(f, "add plugin {}", name),
+            },
+            Step::CheckoutPlugin { name, ref_ } => {
+                write!(f, "check out plugin {} at {}", name, ref_)
+            }
+            Step::Install { plugin, version } => write!(f, "install {}@{}", plugin, version),
+            Step::WriteToolVersions { path, tools } => {
+                write!(f, "write {} ({} tools)", path.display(), tools.len())
+            }
+            Step::Uninstall { plugin, version } => write!(f, "uninstall {}@{}", plugin, version),
+            Step::RemovePlugin { name } => write!(f, "remove plugin {}", name),
+        }
+    }
+}
+
+/// Compute the steps that turn `current` into `desired`
+///
+/// Removals are only planned when `prune` is set.
+pub fn plan(desired: &Manifest, current: &Manifest, prune: bool) -> Vec<Step> {
+    let mut steps = Vec::new();
+
+    for plugin in &desired.plugins {
+        match current.plugins.iter().find(|p| p.name == plugin.name) {
+            None => {
+                steps.push(Step::AddPlugin {
+                    name: plugin.name.clone(),
+                    url: plugin.url.clone(),
+                });
+                if let Some(ref_) = &plugin.ref_ {
+                    steps.push(Step::CheckoutPlugin {
+                        name: plugin.name.clone(),
+                        ref_: ref_.clone(),
+                    });
+                }
+            }
+            Some(existing) => {
+                if let Some(ref_) = &plugin.ref_ {
+                    if existing.ref_.as_ref() != Some(ref_) {
+                        steps.push(Step::CheckoutPlugin {
+                            name: plugin.name.clone(),
+                            ref_: ref_.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    let installed = |m: &Manifest| -> BTreeSet<(String, String)> {
+        m.runtimes
+            .iter()
+            .flat_map(|r| r.installed.iter().map(|v| (r.plugin.clone(), v.clone())))
+            .collect()
+    };
+    let have = installed(current);
+    let want = installed(desired);
+
+    for (plugin, version) in want.difference(&have) {
+        steps.push(Step::Install {
+            plugin: plugin.clone(),
+            version: version.clone(),
+        });
+    }
+
+    for dir in &desired.directories {
+        let unchanged = current.directories.iter().any(|d| {
+            d.path == dir.path
+                && dir
+                    .tools
+                    .iter()
+                    .all(|(plugin, versions)| d.tools.get(plugin) == Some(versions))
+        });
+        if !unchanged {
+            steps.push(Step::WriteToolVersions {
+                path: dir.path.join(tool_versions::FILE_NAME),
+                tools: dir.tools.clone(),
+            });
+        }
+    }
+
+    if prune {
+        for (plugin, version) in have.difference(&want) {
+            steps.push(Step::Uninstall {
+                plugin: plugin.clone(),
+                version: version.clone(),
+            });
+        }
+
+        for plugin in &current.plugins {
+            if !desired.plugins.iter().any(|p| p.name == plugin.name) {
+                steps.push(Step::RemovePlugin {
+                    name: plugin.name.clone(),
+                });
+            }
+        }
+    }
+
+    steps
+}
+
+/// Execute a single step against the real asdf installation
+///
+/// Plugins added without a URL are looked up in `index` when one is given.
+pub fn execute(step: &Step, index: Option<&Index>) -> Result<()> {
+    match step {
+        Step::AddPlugin { name, url } => {
+            let mut plugin = Plugin::new(name.as_str());
+            if let Some(url) = url {
+                plugin = plugin.with_url(url.as_str());
+            }
+            match index {
+                Some(index) => plugin.add_from(index)?,
+                None => plugin.add()?,
+            }
+        }
+        Step::CheckoutPlugin { name, ref_ } => {
+            Plugin::new(name.as_str()).with_ref(ref_.as_str()).update()?;
+        }
+        Step::Install { plugin, version } => {
+            runtime(plugin, version)?.install()?;
+        }
+        Step::WriteToolVersions { path, tools } => {
+            let mut tv = ToolVersions::load(path)?;
+            for (plugin, versions) in tools {
+                tv.set(plugin.clone(), versions.clone());
+            }
+            tv.save(path)?;
+        }
+        Step::Uninstall { plugin, version } => {
+            runtime(plugin, version)?.uninstall()?;
+        }
+        Step::RemovePlugin { name } => {
+            Plugin::new(name.as_str()).remove()?;
+        }
+    }
+    Ok(())
+}
+
+fn runtime(plugin: &str, version: &str) -> Result<Runtime> {
+    let version = Version::parse(version)
+        .with_context(|| format!("Invalid version {} for {}", version, plugin))?;
+    Ok(Runtime::new(plugin, version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Manifest {
+        Manifest {
+            version: MANIFEST_VERSION,
+            plugins: vec![
+                PluginSpec {
+                    name: "nodejs".to_string(),
+                    url: Some("https://github.com/asdf-vm/asdf-nodejs.git".to_string()),
+                    ref_: Some("0123abcd".to_string()),
+                },
+                PluginSpec {
+                    name: "golang".to_string(),
+                    url: None,
+                    ref_: None,
+                },
+            ],
+            runtimes: vec![RuntimeSpec {
+                plugin: "nodejs".to_string(),
+                installed: vec!["18.19.0".to_string(), "20.1.0".to_string()],
+                active: Some("20.1.0".to_string()),
+            }],
+            directories: vec![DirectorySpec {
+                path: PathBuf::from("/home/dev/project"),
+                tools: BTreeMap::from([
+                    ("nodejs".to_string(), vec!["20.1.0".to_string()]),
+                    ("golang".to_string(), vec!["1.21.5".to_string()]),
+                ]),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_round_trip_all_formats() {
+        let manifest = sample();
+        for format in [Format::Json, Format::Toml, Format::Nickel] {
+            let text = manifest.render(format).unwrap();
+            let parsed = Manifest::parse(&text, format).unwrap();
+            assert_eq!(parsed, manifest, "round trip failed for {:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_empty_manifest() {
+        let manifest = Manifest::default();
+        for format in [Format::Json, Format::Toml, Format::Nickel] {
+            let text = manifest.render(format).unwrap();
+            assert_eq!(Manifest::parse(&text, format).unwrap(), manifest);
+        }
+    }
+
+    #[test]
+    fn test_rejects_newer_version() {
+        let result = Manifest::parse("{\"version\": 99}", Format::Json);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(Format::from_path(Path::new("a.ncl")).unwrap(), Format::Nickel);
+        assert_eq!(Format::from_path(Path::new("a.toml")).unwrap(), Format::Toml);
+        assert!(Format::from_path(Path::new("a.yaml")).is_err());
+    }
+
+    #[test]
+    fn test_plan_converged_is_empty() {
+        let manifest = sample();
+        assert!(plan(&manifest, &manifest, true).is_empty());
+    }
+
+    #[test]
+    fn test_plan_from_scratch() {
+        let steps = plan(&sample(), &Manifest::default(), false);
+
+        assert_eq!(
+            steps[..3],
+            [
+                Step::AddPlugin {
+                    name: "nodejs".to_string(),
+                    url: Some("https://github.com/asdf-vm/asdf-nodejs.git".to_string()),
+                },
+                Step::CheckoutPlugin {
+                    name: "nodejs".to_string(),
+                    ref_: "0123abcd".to_string(),
+                },
+                Step::AddPlugin {
+                    name: "golang".to_string(),
+                    url: None,
+                },
+            ]
+        );
+        assert_eq!(
+            steps.iter().filter(|s| matches!(s, Step::Install { .. })).count(),
+            2
+        );
+        assert!(matches!(steps.last(), Some(Step::WriteToolVersions { .. })));
+    }
+
+    #[test]
+    fn test_plan_prune() {
+        let mut current = sample();
+        current.plugins.push(PluginSpec {
+            name: "ruby".to_string(),
+            url: None,
+            ref_: None,
+        });
+        current.runtimes[0].installed.push("16.0.0".to_string());
+
+        assert!(plan(&sample(), &current, false).is_empty());
+
+        let steps = plan(&sample(), &current, true);
+        assert_eq!(
+            steps,
+            vec![
+                Step::Uninstall {
+                    plugin: "nodejs".to_string(),
+                    version: "16.0.0".to_string(),
+                },
+                Step::RemovePlugin {
+                    name: "ruby".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_ignores_unmanaged_tools() {
+        let mut current = sample();
+        current.directories[0]
+            .tools
+            .insert("ruby".to_string(), vec!["3.2.2".to_string()]);
+        assert!(plan(&sample(), &current, false).is_empty());
+
+        current.directories[0]
+            .tools
+            .insert("nodejs".to_string(), vec!["18.19.0".to_string()]);
+        assert!(matches!(
+            plan(&sample(), &current, false)[..],
+            [Step::WriteToolVersions { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_write_tool_versions_step() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(tool_versions::FILE_NAME);
+        std::fs::write(&path, "# keep me\nruby 3.2.2\n").unwrap();
+
+        execute(
+            &Step::WriteToolVersions {
+                path: path.clone(),
+                tools: BTreeMap::from([("nodejs".to_string(), vec!["20.1.0".to_string()])]),
+            },
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "# keep me\nruby 3.2.2\nnodejs 20.1.0\n"
+        );
+    }
+
+    #[test]
+    fn test_schema_mentions_all_sections() {
+        for field in ["plugins", "runtimes", "directories", "Manifest"] {
+            assert!(NICKEL_SCHEMA.contains(field));
+        }
+    }
+}
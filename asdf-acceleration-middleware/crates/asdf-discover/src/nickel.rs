@@ -0,0 +1,343 @@
+//! Minimal Nickel rendering and reading
+//!
+//! Generated manifests only use the data subset of Nickel (records, arrays,
+//! strings, numbers, booleans, `null`), which we can read back without the
+//! Nickel toolchain. Anything richer is handed to `nickel export`.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Number, Value};
+use std::path::Path;
+
+/// Render a JSON value as a Nickel expression
+pub fn render(value: &Value) -> String {
+    let mut out = String::new();
+    render_value(value, 0, &mut out);
+    out.push('\n');
+    out
+}
+
+fn render_value(value: &Value, indent: usize, out: &mut String) {
+    let pad = "  ".repeat(indent + 1);
+    let close = "  ".repeat(indent);
+
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(&b.to_string()),
+        Value::Number(n) => out.push_str(&n.to_string()),
+        Value::String(s) => render_string(s, out),
+        Value::Array(items) if items.is_empty() => out.push_str("[]"),
+        Value::Array(items) => {
+            out.push_str("[\n");
+            for item in items {
+                out.push_str(&pad);
+                render_value(item, indent + 1, out);
+                out.push_str(",\n");
+            }
+            out.push_str(&close);
+            out.push(']');
+        }
+        Value::Object(fields) if fields.is_empty() => out.push_str("{}"),
+        Value::Object(fields) => {
+            out.push_str("{\n");
+            for (key, field) in fields {
+                out.push_str(&pad);
+                if is_identifier(key) {
+                    out.push_str(key);
+                } else {
+                    render_string(key, out);
+                }
+                out.push_str(" = ");
+                render_value(field, indent + 1, out);
+                out.push_str(",\n");
+            }
+            out.push_str(&close);
+            out.push('}');
+        }
+    }
+}
+
+fn render_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            // `%{` would start an interpolation
+            '%' => out.push_str("\\%"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Nickel keywords that can't be used as bare field names
+const KEYWORDS: &[&str] = &[
+    "let", "in", "fun", "if", "then", "else", "match", "import", "rec", "null", "true", "false",
+    "forall", "default", "doc", "optional", "priority", "force", "not_exported",
+];
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    let starts_ok = chars
+        .next()
+        .map(|c| c.is_ascii_alphabetic() || c == '_')
+        .unwrap_or(false);
+
+    starts_ok
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '\'')
+        && !KEYWORDS.contains(&key)
+}
+
+/// Read a Nickel file, falling back to `nickel export` for full Nickel
+pub fn read_file(path: &Path) -> Result<Value> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    match parse(&content) {
+        Ok(value) => Ok(value),
+        Err(_) => {
+            let json = duct::cmd!("nickel", "export", "--format", "json", path)
+                .read()
+                .with_context(|| {
+                    format!(
+                        "{} is not plain data and `nickel export` failed",
+                        path.display()
+                    )
+                })?;
+            Ok(serde_json::from_str(&json)?)
+        }
+    }
+}
+
+/// Parse the data subset of Nickel
+pub fn parse(input: &str) -> Result<Value> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+    };
+
+    let value = parser.value()?;
+    parser.skip_trivia();
+    if parser.pos != parser.chars.len() {
+        bail!("Unexpected trailing input at offset {}", parser.pos);
+    }
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_trivia(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.pos += 1;
+            } else if c == '#' {
+                while let Some(c) = self.peek() {
+                    self.pos += 1;
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_trivia();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            other => bail!("Expected '{}' at offset {}, found {:?}", expected, self.pos, other),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        self.skip_trivia();
+        match self.peek() {
+            Some('{') => self.record(),
+            Some('[') => self.array(),
+            Some('"') => Ok(Value::String(self.string()?)),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => match self.identifier().as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                "null" => Ok(Value::Null),
+                other => bail!("Unsupported Nickel expression '{}'", other),
+            },
+            other => bail!("Unexpected {:?} at offset {}", other, self.pos),
+        }
+    }
+
+    fn record(&mut self) -> Result<Value> {
+        self.expect('{')?;
+        let mut fields = Map::new();
+
+        loop {
+            self.skip_trivia();
+            if self.peek() == Some('}') {
+                self.pos += 1;
+                return Ok(Value::Object(fields));
+            }
+
+            let key = if self.peek() == Some('"') {
+                self.string()?
+            } else {
+                let ident = self.identifier();
+                if ident.is_empty() {
+                    bail!("Expected field name at offset {}", self.pos);
+                }
+                ident
+            };
+
+            self.expect('=')?;
+            let value = self.value()?;
+            fields.insert(key, value);
+
+            self.skip_trivia();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {}
+                other => bail!("Expected ',' or '}}' at offset {}, found {:?}", self.pos, other),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+
+        loop {
+            self.skip_trivia();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+
+            items.push(self.value()?);
+
+            self.skip_trivia();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {}
+                other => bail!("Expected ',' or ']' at offset {}, found {:?}", self.pos, other),
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> String {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '\'' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.expect('"')?;
+        let mut s = String::new();
+
+        loop {
+            match self.peek() {
+                None => bail!("Unterminated string"),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                Some('%') if self.chars.get(self.pos + 1) == Some(&'{') => {
+                    bail!("String interpolation is not plain data")
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    let escaped = match self.peek() {
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some(c @ ('"' | '\\' | '%')) => c,
+                        other => bail!("Unsupported escape {:?}", other),
+                    };
+                    s.push(escaped);
+                    self.pos += 1;
+                }
+                Some(c) => {
+                    s.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+
+        let text: String = self.chars[start..self.pos].iter().collect();
+        if let Ok(i) = text.parse::<i64>() {
+            return Ok(Value::Number(i.into()));
+        }
+        let f: f64 = text.parse().with_context(|| format!("Invalid number '{}'", text))?;
+        Number::from_f64(f)
+            .map(Value::Number)
+            .with_context(|| format!("Invalid number '{}'", text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_and_parse_round_trip() {
+        let value = json!({
+            "version": 1,
+            "plugins": [{ "name": "nodejs", "ref": "abc123" }],
+            "runtimes": [{ "plugin": "nodejs", "installed": ["20.1.0"], "active": "20.1.0" }],
+            "tools": { "golang": ["1.21.5"], "my tool": [] },
+            "note": "100% \"quoted\"\nnext",
+            "enabled": true,
+            "missing": null,
+        });
+
+        let rendered = render(&value);
+        assert!(rendered.contains("\"my tool\" = []"));
+        assert!(rendered.contains("\\%"));
+        assert_eq!(parse(&rendered).unwrap(), value);
+    }
+
+    #[test]
+    fn test_parse_comments_and_trailing_commas() {
+        let value = parse("# header\n{ a = [1, 2,], b = { c = \"d\" } } # end\n").unwrap();
+        assert_eq!(value, json!({ "a": [1, 2], "b": { "c": "d" } }));
+    }
+
+    #[test]
+    fn test_parse_rejects_expressions() {
+        assert!(parse("let x = 1 in { a = x }").is_err());
+        assert!(parse("{ a = \"%{x}\" }").is_err());
+        assert!(parse("{ a = 1 } | Contract").is_err());
+    }
+}
@@ -0,0 +1,186 @@
+//! End-to-end tests against a fake asdf backend
+
+use asdf_core::testing::{FakeAsdf, Fault};
+use assert_cmd::Command;
+use tempfile::TempDir;
+
+fn discover(fake: &FakeAsdf) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_asdf-discover"));
+    cmd.envs(fake.env()).current_dir(fake.home());
+    cmd
+}
+
+#[test]
+fn test_generate_and_apply_on_another_machine() {
+    let project = TempDir::new().unwrap();
+    std::fs::write(project.path().join(".tool-versions"), "nodejs 20.11.0\n").unwrap();
+
+    let source = FakeAsdf::new()
+        .plugin("nodejs", &["18.19.0", "20.11.0"])
+        .installed("nodejs", "20.11.0")
+        .installed("nodejs", "18.19.0")
+        .global("nodejs", "20.11.0");
+
+    let manifest = project.path().join("asdf.json");
+    discover(&source)
+        .args(["generate", "--format", "json", "--output"])
+        .arg(&manifest)
+        .arg("--dir")
+        .arg(project.path())
+        .assert()
+        .success();
+
+    let doc: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&manifest).unwrap()).unwrap();
+    assert_eq!(doc["plugins"][0]["name"], "nodejs");
+    assert_eq!(doc["runtimes"][0]["installed"].as_array().unwrap().len(), 2);
+    assert_eq!(doc["runtimes"][0]["active"], "20.11.0");
+
+    let target = FakeAsdf::new();
+    discover(&target).arg("apply").arg(&manifest).assert().success();
+
+    assert!(target.is_installed("nodejs", "18.19.0"));
+    assert!(target.is_installed("nodejs", "20.11.0"));
+    assert_eq!(target.call_count("plugin add nodejs https://github.com/asdf-vm/asdf-nodejs.git"), 1);
+
+    // Converged: a second run has nothing to do
+    discover(&target)
+        .arg("apply")
+        .arg(&manifest)
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Already up to date"));
+}
+
+#[test]
+fn test_apply_keeps_unmanaged_tools_and_converges() {
+    let project = TempDir::new().unwrap();
+    let tool_versions = project.path().join(".tool-versions");
+    std::fs::write(&tool_versions, "nodejs 20.11.0\n").unwrap();
+
+    let fake = FakeAsdf::new()
+        .plugin("nodejs", &["20.11.0"])
+        .installed("nodejs", "20.11.0");
+    let manifest = project.path().join("asdf.json");
+    discover(&fake)
+        .args(["generate", "--format", "json", "--output"])
+        .arg(&manifest)
+        .arg("--dir")
+        .arg(project.path())
+        .assert()
+        .success();
+
+    // An entry the manifest doesn't manage survives apply
+    std::fs::write(&tool_versions, "ruby 3.2.2\nnodejs 18.19.0\n").unwrap();
+    discover(&fake).arg("apply").arg(&manifest).assert().success();
+    assert_eq!(
+        std::fs::read_to_string(&tool_versions).unwrap(),
+        "ruby 3.2.2\nnodejs 20.11.0\n"
+    );
+
+    discover(&fake)
+        .args(["apply", "--dry-run"])
+        .arg(&manifest)
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Already up to date"));
+}
+
+#[test]
+fn test_apply_reports_failed_installs() {
+    let source = FakeAsdf::new()
+        .plugin("nodejs", &["20.11.0"])
+        .installed("nodejs", "20.11.0");
+    let project = TempDir::new().unwrap();
+    let manifest = project.path().join("asdf.json");
+    discover(&source)
+        .args(["generate", "--format", "json", "--output"])
+        .arg(&manifest)
+        .arg("--dir")
+        .arg(project.path())
+        .assert()
+        .success();
+
+    let target = FakeAsdf::new().fault("install-nodejs", Fault::Fail("download failed".into()));
+    discover(&target)
+        .arg("apply")
+        .arg(&manifest)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("1 of 3 changes failed"));
+
+    assert!(!target.is_installed("nodejs", "20.11.0"));
+}
+
+#[test]
+fn test_validate_exit_codes() {
+    let healthy = FakeAsdf::new()
+        .plugin("nodejs", &["20.11.0"])
+        .installed("nodejs", "20.11.0")
+        .global("nodejs", "20.11.0");
+    discover(&healthy)
+        .args(["validate", "--format", "json"])
+        .assert()
+        .code(0);
+
+    let broken = FakeAsdf::new()
+        .plugin("nodejs", &["20.11.0"])
+        .global("nodejs", "20.11.0");
+    let output = discover(&broken)
+        .args(["validate", "--format", "json"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["findings"][0]["rule"], "missing-runtime");
+}
+
+#[test]
+fn test_available_from_vendored_index() {
+    let fake = FakeAsdf::new().plugin("ruby", &[]);
+    let index = fake.root().join("asdf-plugins");
+    std::fs::create_dir_all(index.join("plugins")).unwrap();
+    for name in ["ruby", "rust", "python"] {
+        std::fs::write(
+            index.join("plugins").join(name),
+            format!("repository = https://github.com/asdf-community/asdf-{}.git\n", name),
+        )
+        .unwrap();
+    }
+
+    let output = discover(&fake)
+        .args(["available", "ru", "--format", "json", "--index"])
+        .arg(&index)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let available: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(available[0]["name"], "ruby");
+    assert_eq!(available[0]["installed"], true);
+    assert_eq!(available[1]["name"], "rust");
+    assert_eq!(available.as_array().unwrap().len(), 2);
+
+    // Sources configured for the registry apply too
+    let config = fake.root().join("config.toml");
+    std::fs::write(&config, format!("[registry]\nsources = [{:?}]\n", index)).unwrap();
+    let output = discover(&fake)
+        .args(["available", "ru", "--format", "json", "--config"])
+        .arg(&config)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let configured: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(configured, available);
+}
+
+#[test]
+fn test_import_migrate_rejects_sources() {
+    let fake = FakeAsdf::new();
+    discover(&fake)
+        .args(["import", "--migrate", "--from", "nvm"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("cannot be used with"));
+}
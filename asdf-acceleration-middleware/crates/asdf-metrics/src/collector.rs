@@ -116,10 +116,51 @@ pub fn system_info() -> SystemInfo {
             used_memory_kb: sys.used_memory(),
             total_swap_kb: sys.total_swap(),
             used_swap_kb: sys.used_swap(),
+            load_average: System::load_average().one,
         }
     }
 }
 
+/// Repeatedly samples system load
+///
+/// Cheaper than [`MetricsCollector::system_info`] for polling, since only
+/// memory is refreshed between samples.
+pub struct SystemSampler {
+    sys: System,
+    cpu_count: usize,
+}
+
+impl SystemSampler {
+    /// Create a new sampler
+    pub fn new() -> Self {
+        let mut sys = System::new();
+        sys.refresh_cpu();
+        let cpu_count = sys.cpus().len();
+
+        Self { sys, cpu_count }
+    }
+
+    /// Take a fresh sample
+    pub fn sample(&mut self) -> SystemInfo {
+        self.sys.refresh_memory();
+
+        SystemInfo {
+            cpu_count: self.cpu_count,
+            total_memory_kb: self.sys.total_memory(),
+            used_memory_kb: self.sys.used_memory(),
+            total_swap_kb: self.sys.total_swap(),
+            used_swap_kb: self.sys.used_swap(),
+            load_average: System::load_average().one,
+        }
+    }
+}
+
+impl Default for SystemSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Default for MetricsCollector {
     fn default() -> Self {
         Self::new()
@@ -143,6 +184,10 @@ pub struct SystemInfo {
 
     /// Used swap in KB
     pub used_swap_kb: u64,
+
+    /// One-minute load average (0 where unsupported)
+    #[serde(default)]
+    pub load_average: f64,
 }
 
 impl SystemInfo {
@@ -154,6 +199,24 @@ pub fn memory_usage_percent(&self) -> f64 {
             (self.used_memory_kb as f64 / self.total_memory_kb as f64) * 100.0
         }
     }
+
+    /// Get swap usage percentage
+    pub fn swap_usage_percent(&self) -> f64 {
+        if self.total_swap_kb == 0 {
+            0.0
+        } else {
+            (self.used_swap_kb as f64 / self.total_swap_kb as f64) * 100.0
+        }
+    }
+
+    /// Get the load average per CPU, where 1.0 means fully busy
+    pub fn cpu_load(&self) -> f64 {
+        if self.cpu_count == 0 {
+            0.0
+        } else {
+            self.load_average / self.cpu_count as f64
+        }
+    }
 }
 
 #[cfg(test)]
@@ -194,4 +257,28 @@ fn test_system_info() {
         let info = MetricsCollector::system_info();
         assert!(info.cpu_count > 0);
     }
+
+    #[test]
+    fn test_system_sampler() {
+        let mut sampler = SystemSampler::new();
+        let info = sampler.sample();
+        assert!(info.cpu_count > 0);
+        assert!(info.total_memory_kb > 0);
+    }
+
+    #[test]
+    fn test_load_percentages() {
+        let info = SystemInfo {
+            cpu_count: 4,
+            total_memory_kb: 8_000_000,
+            used_memory_kb: 2_000_000,
+            total_swap_kb: 0,
+            used_swap_kb: 0,
+            load_average: 6.0,
+        };
+
+        assert_eq!(info.memory_usage_percent(), 25.0);
+        assert_eq!(info.swap_usage_percent(), 0.0);
+        assert_eq!(info.cpu_load(), 1.5);
+    }
 }
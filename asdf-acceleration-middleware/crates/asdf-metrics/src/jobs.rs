@@ -0,0 +1,228 @@
+//! Log of recent operations
+//!
+//! Each batch command appends one JSON line when it finishes, so other
+//! processes (such as `asdf-monitor serve`) can report what ran without
+//! sharing memory with it. The log keeps only the most recent records.
+//!
+//! Writers hold an advisory lock on a sidecar `.lock` file while they append
+//! and trim, so concurrent commands can't lose each other's records.
+
+use crate::Result;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+/// File name of the job log in the asdf data directory
+pub const FILE_NAME: &str = "jobs.jsonl";
+
+/// Records kept when the log is trimmed
+pub const DEFAULT_CAPACITY: usize = 200;
+
+/// One finished operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobRecord {
+    /// Command that ran (`update`, `install`, ...)
+    pub command: String,
+
+    /// Start time, in seconds since the Unix epoch
+    pub started_at: u64,
+
+    /// Wall time in milliseconds
+    pub duration_ms: u64,
+
+    /// Overall status (`ok`, `partial` or `failed`)
+    pub status: String,
+
+    /// Number of tasks that succeeded
+    pub succeeded: usize,
+
+    /// Number of tasks that failed
+    pub failed: usize,
+
+    /// Names of the failed tasks
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_tasks: Vec<String>,
+}
+
+impl JobRecord {
+    /// Seconds since the Unix epoch, for [`JobRecord::started_at`]
+    pub fn timestamp(time: SystemTime) -> u64 {
+        time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+    }
+}
+
+/// Append-only log of recent jobs, one JSON record per line
+#[derive(Debug, Clone)]
+pub struct JobLog {
+    path: PathBuf,
+    capacity: usize,
+}
+
+impl JobLog {
+    /// Open the log at `path`; the file is created on first append
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+
+    /// Set how many records are kept
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Location of the log
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a record, trimming the log once it holds twice its capacity
+    pub fn append(&self, record: &JobRecord) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Released when `lock` is dropped
+        let lock = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(self.path.with_extension("jsonl.lock"))?;
+        lock.lock_exclusive()?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        drop(file);
+
+        let records = self.read()?;
+        if records.len() >= self.capacity * 2 {
+            let keep = &records[records.len() - self.capacity..];
+            let mut content = String::new();
+            for record in keep {
+                content.push_str(&serde_json::to_string(record)?);
+                content.push('\n');
+            }
+            // Rename over the log, so readers never see it half written
+            let partial = self.path.with_extension("jsonl.tmp");
+            fs::write(&partial, content)?;
+            fs::rename(&partial, &self.path)?;
+        }
+
+        drop(lock);
+        Ok(())
+    }
+
+    /// Up to `limit` records, newest first
+    pub fn recent(&self, limit: usize) -> Result<Vec<JobRecord>> {
+        let mut records = self.read()?;
+        records.reverse();
+        records.truncate(limit);
+        Ok(records)
+    }
+
+    /// Every record, oldest first; unreadable lines are skipped
+    fn read(&self) -> Result<Vec<JobRecord>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    debug!("Skipping job record: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(command: &str, started_at: u64) -> JobRecord {
+        JobRecord {
+            command: command.to_string(),
+            started_at,
+            duration_ms: 1200,
+            status: "ok".to_string(),
+            succeeded: 3,
+            failed: 0,
+            failed_tasks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_recent_newest_first() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let log = JobLog::open(dir.path().join(FILE_NAME));
+
+        assert!(log.recent(10).unwrap().is_empty());
+
+        log.append(&record("update", 1)).unwrap();
+        log.append(&record("install", 2)).unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(log.path())
+            .unwrap()
+            .write_all(b"not json\n")
+            .unwrap();
+        log.append(&record("sync", 3)).unwrap();
+
+        let recent = log.recent(2).unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].command, "sync");
+        assert_eq!(recent[1].command, "install");
+    }
+
+    #[test]
+    fn test_trimmed_to_capacity() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let log = JobLog::open(dir.path().join(FILE_NAME)).with_capacity(3);
+
+        for i in 0..6 {
+            log.append(&record("update", i)).unwrap();
+        }
+
+        let recent = log.recent(10).unwrap();
+        assert_eq!(recent.len(), 3);
+        assert_eq!(recent[0].started_at, 5);
+        assert_eq!(recent[2].started_at, 3);
+    }
+
+    #[test]
+    fn test_concurrent_appends_keep_every_record() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let log = JobLog::open(dir.path().join(FILE_NAME)).with_capacity(50);
+
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let log = &log;
+                scope.spawn(move || {
+                    for i in 0..25 {
+                        log.append(&record("update", thread * 100 + i)).unwrap();
+                    }
+                });
+            }
+        });
+
+        // 100 appends against a capacity of 50: one trim at 100 leaves 50,
+        // and no append may be lost to a racing trim
+        assert_eq!(log.recent(usize::MAX).unwrap().len(), 50);
+    }
+}
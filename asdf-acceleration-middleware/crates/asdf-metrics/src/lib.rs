@@ -2,11 +2,17 @@
 
 pub mod collector;
 pub mod error;
+pub mod jobs;
+pub mod openmetrics;
 pub mod reporter;
+pub mod trace;
 
-pub use collector::{Metrics, MetricsCollector, SystemInfo};
+pub use collector::{Metrics, MetricsCollector, SystemInfo, SystemSampler};
 pub use error::{Error, Result};
+pub use jobs::{JobLog, JobRecord};
+pub use openmetrics::{MetricType, OpenMetrics};
 pub use reporter::MetricsReporter;
+pub use trace::{TraceFormat, TraceRecorder};
 
 /// Export metrics in Prometheus format
 pub fn export_prometheus(metrics: &Metrics) -> Result<String> {
@@ -23,6 +29,7 @@ pub fn export_prometheus(metrics: &Metrics) -> Result<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
 
     #[test]
     fn test_export_prometheus() {
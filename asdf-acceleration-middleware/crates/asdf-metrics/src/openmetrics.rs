@@ -0,0 +1,135 @@
+//! OpenMetrics text exposition
+//!
+//! Writes metric families in the OpenMetrics 1.0 text format, which
+//! Prometheus also accepts. Only the gauge and counter types are needed.
+
+use std::fmt::Write;
+
+/// Content type of the exposition
+pub const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Type of a metric family
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricType {
+    /// A value that can go up and down
+    Gauge,
+
+    /// A monotonically increasing total; samples get a `_total` suffix
+    Counter,
+}
+
+/// Builder for an OpenMetrics exposition
+#[derive(Debug, Default)]
+pub struct OpenMetrics {
+    output: String,
+}
+
+impl OpenMetrics {
+    /// Start an empty exposition
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a family without labels holding a single value
+    pub fn single(&mut self, name: &str, kind: MetricType, help: &str, value: f64) -> &mut Self {
+        self.family(name, kind, help, &[(Vec::new(), value)])
+    }
+
+    /// Add a family with one sample per label set
+    pub fn family(
+        &mut self,
+        name: &str,
+        kind: MetricType,
+        help: &str,
+        samples: &[(Vec<(&str, String)>, f64)],
+    ) -> &mut Self {
+        let (type_name, suffix) = match kind {
+            MetricType::Gauge => ("gauge", ""),
+            MetricType::Counter => ("counter", "_total"),
+        };
+
+        let _ = writeln!(self.output, "# TYPE {} {}", name, type_name);
+        let _ = writeln!(self.output, "# HELP {} {}", name, escape(help));
+        if let Some(unit) = unit_of(name) {
+            let _ = writeln!(self.output, "# UNIT {} {}", name, unit);
+        }
+
+        for (labels, value) in samples {
+            let _ = write!(self.output, "{}{}", name, suffix);
+            if !labels.is_empty() {
+                let labels: Vec<String> = labels
+                    .iter()
+                    .map(|(key, value)| format!("{}=\"{}\"", key, escape(value)))
+                    .collect();
+                let _ = write!(self.output, "{{{}}}", labels.join(","));
+            }
+            let _ = writeln!(self.output, " {}", value);
+        }
+
+        self
+    }
+
+    /// Finish the exposition with the mandatory `# EOF` marker
+    pub fn finish(mut self) -> String {
+        self.output.push_str("# EOF\n");
+        self.output
+    }
+}
+
+/// Unit implied by a metric name's suffix, as OpenMetrics requires
+fn unit_of(name: &str) -> Option<&'static str> {
+    ["seconds", "bytes", "ratio"]
+        .into_iter()
+        .find(|unit| name.ends_with(&format!("_{}", unit)))
+}
+
+/// Escape a label value or help text
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exposition() {
+        let mut metrics = OpenMetrics::new();
+        metrics
+            .single("asdf_plugins", MetricType::Gauge, "Installed plugins", 3.0)
+            .single(
+                "asdf_memory_used_bytes",
+                MetricType::Gauge,
+                "Memory in use",
+                1024.0,
+            )
+            .family(
+                "asdf_tasks",
+                MetricType::Counter,
+                "Tasks run",
+                &[
+                    (vec![("result", "ok".to_string())], 5.0),
+                    (vec![("result", "say \"hi\"".to_string())], 1.5),
+                ],
+            );
+
+        assert_eq!(
+            metrics.finish(),
+            "# TYPE asdf_plugins gauge\n\
+             # HELP asdf_plugins Installed plugins\n\
+             asdf_plugins 3\n\
+             # TYPE asdf_memory_used_bytes gauge\n\
+             # HELP asdf_memory_used_bytes Memory in use\n\
+             # UNIT asdf_memory_used_bytes bytes\n\
+             asdf_memory_used_bytes 1024\n\
+             # TYPE asdf_tasks counter\n\
+             # HELP asdf_tasks Tasks run\n\
+             asdf_tasks_total{result=\"ok\"} 5\n\
+             asdf_tasks_total{result=\"say \\\"hi\\\"\"} 1.5\n\
+             # EOF\n"
+        );
+    }
+}
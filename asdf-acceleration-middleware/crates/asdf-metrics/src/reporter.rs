@@ -80,6 +80,7 @@ fn test_format_report() {
             used_memory_kb: 8_000_000,
             total_swap_kb: 4_000_000,
             used_swap_kb: 1_000_000,
+            load_average: 2.0,
         };
 
         let report = MetricsReporter::format_report(&metrics, &system_info);
@@ -96,6 +97,7 @@ fn test_to_json() {
             used_memory_kb: 4_000_000,
             total_swap_kb: 2_000_000,
             used_swap_kb: 500_000,
+            load_average: 0.5,
         };
 
         let json = MetricsReporter::to_json(&metrics, &system_info).unwrap();
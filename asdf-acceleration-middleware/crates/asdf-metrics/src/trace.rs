@@ -0,0 +1,421 @@
+//! Span traces exported to a file
+//!
+//! [`TraceRecorder`] is a `tracing` layer that keeps every closed span in
+//! memory. [`TraceRecorder::write`] saves them either as Chrome trace events,
+//! which Perfetto and `chrome://tracing` load, or as OTLP JSON for Jaeger and
+//! other OpenTelemetry tools. Nothing is sent over the network.
+
+use crate::{Error, Result};
+use serde_json::{json, Value};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Level, Subscriber};
+use tracing_subscriber::filter::{Filtered, Targets};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Target prefix of the spans recorded by [`TraceRecorder::layer`]
+const TARGET_PREFIX: &str = "asdf";
+
+/// File format written by [`TraceRecorder::write`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// Chrome trace-event JSON (Perfetto, `chrome://tracing`)
+    #[default]
+    Chrome,
+
+    /// OTLP JSON, as written by the OpenTelemetry file exporter (Jaeger)
+    Otlp,
+}
+
+impl FromStr for TraceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "chrome" => Ok(TraceFormat::Chrome),
+            "otlp" => Ok(TraceFormat::Otlp),
+            other => Err(format!(
+                "unknown trace format '{}' (expected chrome or otlp)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TraceFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceFormat::Chrome => write!(f, "chrome"),
+            TraceFormat::Otlp => write!(f, "otlp"),
+        }
+    }
+}
+
+/// A closed span
+#[derive(Debug, Clone)]
+struct SpanRecord {
+    id: u64,
+    parent: Option<u64>,
+    name: &'static str,
+    target: &'static str,
+    fields: Vec<(String, String)>,
+    thread: u64,
+    start: Duration,
+    end: Duration,
+}
+
+/// A span that is still open, kept in the registry's span extensions
+struct OpenSpan {
+    id: u64,
+    parent: Option<u64>,
+    fields: Vec<(String, String)>,
+    thread: Option<u64>,
+    start: Duration,
+}
+
+struct Shared {
+    service: String,
+    origin: Instant,
+    origin_time: SystemTime,
+    next_id: AtomicU64,
+    spans: Mutex<Vec<SpanRecord>>,
+    threads: Mutex<BTreeMap<u64, String>>,
+}
+
+/// Layer recording spans for export; clones share the same recording
+#[derive(Clone)]
+pub struct TraceRecorder {
+    shared: Arc<Shared>,
+}
+
+impl TraceRecorder {
+    /// Start recording for `service` (the process name in the trace)
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                service: service.into(),
+                origin: Instant::now(),
+                origin_time: SystemTime::now(),
+                next_id: AtomicU64::new(1),
+                spans: Mutex::new(Vec::new()),
+                threads: Mutex::new(BTreeMap::new()),
+            }),
+        }
+    }
+
+    /// This recorder as a layer that only sees `asdf*` spans, at debug and up
+    ///
+    /// The filter applies to this layer alone, so other layers keep their own.
+    pub fn layer<S>(&self) -> Filtered<TraceRecorder, Targets, S>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        self.clone()
+            .with_filter(Targets::new().with_target(TARGET_PREFIX, Level::DEBUG))
+    }
+
+    /// Number of closed spans recorded so far
+    pub fn len(&self) -> usize {
+        self.shared.spans.lock().unwrap().len()
+    }
+
+    /// Whether no span has closed yet
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Write the closed spans to `path`; spans still open are left out
+    pub fn write(&self, path: &Path, format: TraceFormat) -> Result<()> {
+        let document = match format {
+            TraceFormat::Chrome => self.chrome(),
+            TraceFormat::Otlp => self.otlp(),
+        };
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec(&document)?)
+            .map_err(|e| Error::ExportFailed(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Chrome trace events: one complete (`X`) event per span
+    fn chrome(&self) -> Value {
+        let pid = std::process::id();
+        let mut events = vec![json!({
+            "name": "process_name",
+            "ph": "M",
+            "pid": pid,
+            "args": { "name": self.shared.service },
+        })];
+
+        for (tid, name) in self.shared.threads.lock().unwrap().iter() {
+            events.push(json!({
+                "name": "thread_name",
+                "ph": "M",
+                "pid": pid,
+                "tid": tid,
+                "args": { "name": name },
+            }));
+        }
+
+        for span in self.shared.spans.lock().unwrap().iter() {
+            let args: serde_json::Map<String, Value> = span
+                .fields
+                .iter()
+                .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+                .collect();
+            events.push(json!({
+                "name": span.name,
+                "cat": span.target,
+                "ph": "X",
+                "ts": micros(span.start),
+                "dur": micros(span.end.saturating_sub(span.start)),
+                "pid": pid,
+                "tid": span.thread,
+                "args": args,
+            }));
+        }
+
+        json!({ "traceEvents": events, "displayTimeUnit": "ms" })
+    }
+
+    /// OTLP JSON: every span in one trace, under a single resource
+    fn otlp(&self) -> Value {
+        let origin = self
+            .shared
+            .origin_time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let trace_id = format!("{:032x}", origin ^ ((std::process::id() as u128) << 64));
+        let string =
+            |key: &str, value: &str| json!({ "key": key, "value": { "stringValue": value } });
+
+        let spans: Vec<Value> = self
+            .shared
+            .spans
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|span| {
+                let mut attributes = vec![
+                    string("code.namespace", span.target),
+                    json!({ "key": "thread.id", "value": { "intValue": span.thread.to_string() } }),
+                ];
+                attributes.extend(span.fields.iter().map(|(key, value)| string(key, value)));
+
+                let mut otlp = json!({
+                    "traceId": trace_id,
+                    "spanId": format!("{:016x}", span.id),
+                    "name": span.name,
+                    "kind": 1,
+                    "startTimeUnixNano": (origin + span.start.as_nanos()).to_string(),
+                    "endTimeUnixNano": (origin + span.end.as_nanos()).to_string(),
+                    "attributes": attributes,
+                });
+                if let Some(parent) = span.parent {
+                    otlp["parentSpanId"] = json!(format!("{:016x}", parent));
+                }
+                otlp
+            })
+            .collect();
+
+        json!({
+            "resourceSpans": [{
+                "resource": { "attributes": [string("service.name", &self.shared.service)] },
+                "scopeSpans": [{
+                    "scope": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                    "spans": spans,
+                }],
+            }],
+        })
+    }
+
+    /// Small per-process id for the current thread, remembering its name
+    fn thread_id(&self) -> u64 {
+        static NEXT_THREAD: AtomicU64 = AtomicU64::new(1);
+        thread_local!(static THREAD: Cell<u64> = const { Cell::new(0) });
+
+        THREAD.with(|id| {
+            if id.get() == 0 {
+                id.set(NEXT_THREAD.fetch_add(1, Ordering::Relaxed));
+            }
+            let current = std::thread::current();
+            self.shared
+                .threads
+                .lock()
+                .unwrap()
+                .entry(id.get())
+                .or_insert_with(|| current.name().unwrap_or("thread").to_string());
+            id.get()
+        })
+    }
+}
+
+impl<S> Layer<S> for TraceRecorder
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let parent = span
+            .parent()
+            .and_then(|parent| parent.extensions().get::<OpenSpan>().map(|open| open.id));
+
+        let mut fields = Vec::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+
+        span.extensions_mut().insert(OpenSpan {
+            id: self.shared.next_id.fetch_add(1, Ordering::Relaxed),
+            parent,
+            fields,
+            thread: None,
+            start: self.shared.origin.elapsed(),
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        if let Some(open) = span.extensions_mut().get_mut::<OpenSpan>() {
+            values.record(&mut FieldVisitor(&mut open.fields));
+        };
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        // A span belongs to the thread it first runs on, not where it was created
+        if let Some(open) = span.extensions_mut().get_mut::<OpenSpan>() {
+            if open.thread.is_none() {
+                open.thread = Some(self.thread_id());
+            }
+        };
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else { return };
+        let Some(open) = span.extensions_mut().remove::<OpenSpan>() else {
+            return;
+        };
+
+        let record = SpanRecord {
+            id: open.id,
+            parent: open.parent,
+            name: span.name(),
+            target: span.metadata().target(),
+            fields: open.fields,
+            thread: open.thread.unwrap_or_else(|| self.thread_id()),
+            start: open.start,
+            end: self.shared.origin.elapsed(),
+        };
+        self.shared.spans.lock().unwrap().push(record);
+    }
+}
+
+/// Collects span fields as strings
+struct FieldVisitor<'a>(&'a mut Vec<(String, String)>);
+
+impl FieldVisitor<'_> {
+    fn set(&mut self, field: &Field, value: String) {
+        match self.0.iter_mut().find(|(key, _)| key == field.name()) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((field.name().to_string(), value)),
+        }
+    }
+}
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.set(field, format!("{:?}", value));
+    }
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_nanos() as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn record(recorder: &TraceRecorder) {
+        let subscriber = tracing_subscriber::registry().with(recorder.layer());
+        tracing::subscriber::with_default(subscriber, || {
+            let batch = tracing::info_span!(target: "asdf_accelerate", "batch", total = 1);
+            let _batch = batch.enter();
+            let task = tracing::debug_span!(target: "asdf_parallel", "task", task = "nodejs");
+            task.in_scope(|| {
+                tracing::debug_span!(target: "asdf_core", "update", plugin = "nodejs")
+                    .in_scope(|| {});
+            });
+            // Other crates' spans and trace-level spans are not recorded
+            tracing::info_span!(target: "hyper", "request").in_scope(|| {});
+            tracing::trace_span!(target: "asdf_core", "noise").in_scope(|| {});
+        });
+    }
+
+    #[test]
+    fn test_chrome_events() {
+        let recorder = TraceRecorder::new("asdf-accelerate");
+        record(&recorder);
+        assert_eq!(recorder.len(), 3);
+
+        let trace = recorder.chrome();
+        let spans: Vec<&Value> = trace["traceEvents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|e| e["ph"] == "X")
+            .collect();
+        let names: Vec<&str> = spans.iter().map(|e| e["name"].as_str().unwrap()).collect();
+        // Spans close innermost first
+        assert_eq!(names, ["update", "task", "batch"]);
+        assert_eq!(spans[0]["cat"], "asdf_core");
+        assert_eq!(spans[0]["args"]["plugin"], "nodejs");
+        assert_eq!(spans[2]["args"]["total"], "1");
+        assert!(spans[2]["dur"].as_f64().unwrap() >= spans[0]["dur"].as_f64().unwrap());
+        assert_eq!(trace["traceEvents"][0]["args"]["name"], "asdf-accelerate");
+    }
+
+    #[test]
+    fn test_otlp_parents() {
+        let recorder = TraceRecorder::new("asdf-accelerate");
+        record(&recorder);
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("trace.json");
+        recorder.write(&path, TraceFormat::Otlp).unwrap();
+        let trace: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+
+        let spans = trace["resourceSpans"][0]["scopeSpans"][0]["spans"]
+            .as_array()
+            .unwrap();
+        let by_name = |name: &str| spans.iter().find(|s| s["name"] == name).unwrap();
+        let (update, task, batch) = (by_name("update"), by_name("task"), by_name("batch"));
+
+        assert_eq!(update["parentSpanId"], task["spanId"]);
+        assert_eq!(task["parentSpanId"], batch["spanId"]);
+        assert!(batch.get("parentSpanId").is_none());
+        assert_eq!(update["traceId"], batch["traceId"]);
+        assert_eq!(update["traceId"].as_str().unwrap().len(), 32);
+    }
+
+    #[test]
+    fn test_format_from_str() {
+        assert_eq!("OTLP".parse::<TraceFormat>(), Ok(TraceFormat::Otlp));
+        assert!("jaeger".parse::<TraceFormat>().is_err());
+    }
+}
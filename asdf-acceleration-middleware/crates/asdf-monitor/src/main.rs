@@ -1,6 +1,9 @@
 //! asdf-monitor - Real-time monitoring and metrics dashboard
 
+mod serve;
+
 use anyhow::Result;
+use asdf_core::doctor::{DoctorContext, Report, Severity};
 use asdf_core::Plugin;
 use asdf_metrics::{MetricsCollector, MetricsReporter};
 use clap::{Parser, Subcommand};
@@ -47,7 +50,18 @@ enum Commands {
     },
 
     /// Health check
-    Health,
+    Health {
+        /// Output format (text, json, sarif)
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+
+    /// Serve /metrics, /health and /jobs over HTTP
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:9464")]
+        listen: std::net::SocketAddr,
+    },
 }
 
 fn main() -> Result<()> {
@@ -56,7 +70,8 @@ fn main() -> Result<()> {
     match cli.command {
         Commands::Dashboard => dashboard(),
         Commands::Metrics { format, output } => metrics(&format, output.as_deref()),
-        Commands::Health => health(),
+        Commands::Health { format } => health(&format),
+        Commands::Serve { listen } => serve::run(listen),
     }
 }
 
@@ -414,21 +429,31 @@ fn export_prometheus(metrics: &asdf_metrics::Metrics) -> Result<String> {
     Ok(asdf_metrics::export_prometheus(metrics)?)
 }
 
-fn health() -> Result<()> {
-    println!("{} Running health check...", "→".cyan());
-
-    if !asdf_core::is_asdf_installed() {
-        println!("{} asdf is not installed", "✗".red());
-        return Ok(());
+fn health(format: &str) -> Result<()> {
+    let ctx = DoctorContext::from_env()?;
+    let report = Report::run(&ctx);
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "sarif" => println!(
+            "{}",
+            serde_json::to_string_pretty(
+                &report.to_sarif(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+            )?
+        ),
+        "text" => print_health(&report),
+        _ => anyhow::bail!("Unsupported format: {}", format),
     }
 
-    println!("{} asdf is installed", "✓".green());
+    std::process::exit(report.exit_code());
+}
 
-    let version = asdf_core::asdf_version()?;
-    println!("{} Version: {}", "✓".green(), version);
+fn print_health(report: &Report) {
+    println!("{} Running health check...", "→".cyan());
 
-    let plugins = Plugin::list()?;
-    println!("{} Plugins: {}", "✓".green(), plugins.len());
+    if let Ok(version) = asdf_core::asdf_version() {
+        println!("{} Version: {}", "✓".green(), version);
+    }
 
     let system_info = MetricsCollector::system_info();
     println!(
@@ -438,7 +463,11 @@ fn health() -> Result<()> {
         system_info.memory_usage_percent()
     );
 
-    println!("\n{} System is healthy", "✓".green().bold());
+    print!("{}", report.render_findings());
 
-    Ok(())
+    match report.worst() {
+        None | Some(Severity::Info) => println!("\n{} System is healthy", "✓".green().bold()),
+        Some(Severity::Warning) => println!("\n{} System is degraded", "!".yellow().bold()),
+        Some(Severity::Error) => println!("\n{} System is unhealthy", "✗".red().bold()),
+    }
 }
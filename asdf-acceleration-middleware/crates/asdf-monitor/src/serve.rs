@@ -0,0 +1,373 @@
+//! HTTP endpoint for fleet monitoring
+//!
+//! `asdf-monitor serve` exposes the same information as the `metrics` and
+//! `health` commands, plus the job log written by `asdf-accelerate`, so
+//! build hosts can be scraped and alerted on directly:
+//!
+//! - `/metrics`: OpenMetrics exposition
+//! - `/health`: doctor findings as JSON; 503 when something is broken
+//! - `/jobs?limit=N`: recent batch operations, newest first
+//!
+//! Every request samples fresh state, except that `/metrics` reuses doctor
+//! findings for [`HEALTH_TTL`] so frequent scrapes don't rerun every check.
+//! Doctor checks and `asdf` calls run on tokio's blocking pool so slow checks
+//! don't stall other scrapes.
+
+use anyhow::Result;
+use asdf_core::doctor::{DoctorContext, Report, Severity};
+use asdf_core::Plugin;
+use asdf_metrics::jobs::{self, JobLog, JobRecord};
+use asdf_metrics::openmetrics::{self, MetricType, OpenMetrics};
+use asdf_metrics::SystemSampler;
+use axum::extract::{Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use colored::Colorize;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Jobs returned by `/jobs` without a `limit`
+const DEFAULT_JOB_LIMIT: usize = 50;
+
+/// How long `/metrics` reuses a doctor report
+const HEALTH_TTL: Duration = Duration::from_secs(30);
+
+/// Shared between requests
+struct ServeState {
+    sampler: Mutex<SystemSampler>,
+    jobs: JobLog,
+    scrapes: AtomicU64,
+    health: HealthCache,
+}
+
+/// Doctor report reused across scrapes until it expires
+#[derive(Default)]
+struct HealthCache(Mutex<Option<(Instant, Option<Report>)>>);
+
+impl HealthCache {
+    /// The cached report, or a fresh one from `run` once [`HEALTH_TTL`] passed
+    fn get_or_run(&self, now: Instant, run: impl FnOnce() -> Option<Report>) -> Option<Report> {
+        let mut cached = self.0.lock().unwrap();
+        match &*cached {
+            Some((at, report)) if now.saturating_duration_since(*at) < HEALTH_TTL => report.clone(),
+            _ => {
+                let report = run();
+                *cached = Some((now, report.clone()));
+                report
+            }
+        }
+    }
+}
+
+/// Serve until interrupted
+pub fn run(listen: SocketAddr) -> Result<()> {
+    let state = Arc::new(ServeState {
+        sampler: Mutex::new(SystemSampler::new()),
+        jobs: JobLog::open(asdf_core::data_dir()?.join(jobs::FILE_NAME)),
+        scrapes: AtomicU64::new(0),
+        health: HealthCache::default(),
+    });
+
+    tokio::runtime::Runtime::new()?.block_on(async move {
+        let listener = tokio::net::TcpListener::bind(listen).await?;
+        println!(
+            "{} Listening on http://{}",
+            "✓".green(),
+            listener.local_addr()?
+        );
+
+        axum::serve(listener, router(state))
+            .with_graceful_shutdown(async {
+                let _ = tokio::signal::ctrl_c().await;
+            })
+            .await?;
+        Ok(())
+    })
+}
+
+fn router(state: Arc<ServeState>) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics))
+        .route("/health", get(health))
+        .route("/jobs", get(recent_jobs))
+        .with_state(state)
+}
+
+async fn metrics(State(state): State<Arc<ServeState>>) -> Response {
+    let scrapes = state.scrapes.fetch_add(1, Ordering::Relaxed) + 1;
+
+    match tokio::task::spawn_blocking(move || render_metrics(&state, scrapes)).await {
+        Ok(body) => ([(header::CONTENT_TYPE, openmetrics::CONTENT_TYPE)], body).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+async fn health() -> Response {
+    let report =
+        tokio::task::spawn_blocking(|| DoctorContext::from_env().map(|ctx| Report::run(&ctx)))
+            .await;
+
+    match report {
+        Ok(Ok(report)) => {
+            let (status, code) = match report.worst() {
+                None | Some(Severity::Info) => ("healthy", StatusCode::OK),
+                Some(Severity::Warning) => ("degraded", StatusCode::OK),
+                Some(Severity::Error) => ("unhealthy", StatusCode::SERVICE_UNAVAILABLE),
+            };
+            let body = serde_json::json!({
+                "status": status,
+                "findings": report.findings,
+            });
+            (code, Json(body)).into_response()
+        }
+        Ok(Err(e)) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({
+                "status": "unhealthy",
+                "error": e.to_string(),
+            })),
+        )
+            .into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(Deserialize)]
+struct JobsQuery {
+    limit: Option<usize>,
+}
+
+async fn recent_jobs(
+    State(state): State<Arc<ServeState>>,
+    Query(query): Query<JobsQuery>,
+) -> Response {
+    let limit = query.limit.unwrap_or(DEFAULT_JOB_LIMIT);
+
+    match tokio::task::spawn_blocking(move || state.jobs.recent(limit)).await {
+        Ok(Ok(jobs)) => Json(serde_json::json!({ "jobs": jobs })).into_response(),
+        Ok(Err(e)) => internal_error(e),
+        Err(e) => internal_error(e),
+    }
+}
+
+fn internal_error(error: impl std::fmt::Display) -> Response {
+    (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response()
+}
+
+/// Render the `/metrics` exposition
+fn render_metrics(state: &ServeState, scrapes: u64) -> String {
+    let system = state.sampler.lock().unwrap().sample();
+    let plugins = Plugin::list();
+    let report = state.health.get_or_run(Instant::now(), || {
+        DoctorContext::from_env().map(|ctx| Report::run(&ctx)).ok()
+    });
+    let jobs = state
+        .jobs
+        .recent(jobs::DEFAULT_CAPACITY)
+        .unwrap_or_default();
+
+    let mut out = OpenMetrics::new();
+    out.single(
+        "asdf_up",
+        MetricType::Gauge,
+        "Whether asdf answered",
+        if plugins.is_ok() { 1.0 } else { 0.0 },
+    )
+    .single(
+        "asdf_plugins",
+        MetricType::Gauge,
+        "Installed plugins",
+        plugins.map_or(0, |p| p.len()) as f64,
+    )
+    .single(
+        "asdf_system_cpus",
+        MetricType::Gauge,
+        "Logical CPUs",
+        system.cpu_count as f64,
+    )
+    .single(
+        "asdf_system_load1",
+        MetricType::Gauge,
+        "One-minute load average",
+        system.load_average,
+    )
+    .single(
+        "asdf_system_memory_used_bytes",
+        MetricType::Gauge,
+        "Memory in use",
+        (system.used_memory_kb * 1024) as f64,
+    )
+    .single(
+        "asdf_system_memory_total_bytes",
+        MetricType::Gauge,
+        "Total memory",
+        (system.total_memory_kb * 1024) as f64,
+    )
+    .single(
+        "asdf_system_swap_used_bytes",
+        MetricType::Gauge,
+        "Swap in use",
+        (system.used_swap_kb * 1024) as f64,
+    );
+
+    if let Some(report) = report {
+        let counts = report.counts();
+        let samples: Vec<_> = [Severity::Info, Severity::Warning, Severity::Error]
+            .into_iter()
+            .map(|severity| {
+                let count = counts.get(&severity).copied().unwrap_or(0);
+                (vec![("severity", severity_label(severity))], count as f64)
+            })
+            .collect();
+        out.family(
+            "asdf_health_findings",
+            MetricType::Gauge,
+            "Doctor findings by severity",
+            &samples,
+        );
+    }
+
+    render_jobs(&mut out, &jobs);
+
+    out.single(
+        "asdf_monitor_scrapes",
+        MetricType::Counter,
+        "Metrics requests served",
+        scrapes as f64,
+    );
+    out.finish()
+}
+
+/// Job log metrics, over the records the log retains
+fn render_jobs(out: &mut OpenMetrics, jobs: &[JobRecord]) {
+    let mut by_status: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    let mut tasks: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    // Newest first, so the first record per command is the last to run
+    let mut last: BTreeMap<&str, &JobRecord> = BTreeMap::new();
+
+    for job in jobs {
+        *by_status.entry((&job.command, &job.status)).or_insert(0) += 1;
+        *tasks.entry((&job.command, "succeeded")).or_insert(0) += job.succeeded;
+        *tasks.entry((&job.command, "failed")).or_insert(0) += job.failed;
+        last.entry(&job.command).or_insert(job);
+    }
+
+    let labelled = |map: &BTreeMap<(&str, &str), usize>, key: &'static str| -> Vec<_> {
+        map.iter()
+            .map(|((command, value), count)| {
+                (
+                    vec![("command", command.to_string()), (key, value.to_string())],
+                    *count as f64,
+                )
+            })
+            .collect()
+    };
+    let per_command = |value: fn(&JobRecord) -> f64| -> Vec<_> {
+        last.iter()
+            .map(|(command, job)| (vec![("command", command.to_string())], value(job)))
+            .collect()
+    };
+
+    out.family(
+        "asdf_recent_jobs",
+        MetricType::Gauge,
+        "Jobs in the job log by command and status",
+        &labelled(&by_status, "status"),
+    )
+    .family(
+        "asdf_recent_job_tasks",
+        MetricType::Gauge,
+        "Tasks of jobs in the job log by command and result",
+        &labelled(&tasks, "result"),
+    )
+    .family(
+        "asdf_last_job_timestamp_seconds",
+        MetricType::Gauge,
+        "Start time of the most recent job per command",
+        &per_command(|job| job.started_at as f64),
+    )
+    .family(
+        "asdf_last_job_duration_seconds",
+        MetricType::Gauge,
+        "Wall time of the most recent job per command",
+        &per_command(|job| job.duration_ms as f64 / 1000.0),
+    )
+    .family(
+        "asdf_last_job_failed",
+        MetricType::Gauge,
+        "Whether the most recent job per command had failures",
+        &per_command(|job| if job.failed > 0 { 1.0 } else { 0.0 }),
+    );
+}
+
+fn severity_label(severity: Severity) -> String {
+    match severity {
+        Severity::Info => "info",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(command: &str, started_at: u64, failed: usize) -> JobRecord {
+        JobRecord {
+            command: command.to_string(),
+            started_at,
+            duration_ms: 2500,
+            status: if failed > 0 { "partial" } else { "ok" }.to_string(),
+            succeeded: 2,
+            failed,
+            failed_tasks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_health_cache_expires() {
+        let cache = HealthCache::default();
+        let runs = std::cell::Cell::new(0);
+        let run = || {
+            runs.set(runs.get() + 1);
+            Some(Report::default())
+        };
+
+        let start = Instant::now();
+        assert!(cache.get_or_run(start, run).is_some());
+        assert!(cache.get_or_run(start + Duration::from_secs(1), run).is_some());
+        assert_eq!(runs.get(), 1);
+
+        assert!(cache.get_or_run(start + HEALTH_TTL, run).is_some());
+        assert_eq!(runs.get(), 2);
+    }
+
+    #[test]
+    fn test_render_jobs() {
+        let mut out = OpenMetrics::new();
+        // Newest first, as the log returns them
+        render_jobs(
+            &mut out,
+            &[
+                job("update", 300, 1),
+                job("install", 200, 0),
+                job("update", 100, 0),
+            ],
+        );
+        let text = out.finish();
+
+        assert!(text.contains("asdf_recent_jobs{command=\"update\",status=\"ok\"} 1\n"));
+        assert!(text.contains("asdf_recent_jobs{command=\"update\",status=\"partial\"} 1\n"));
+        assert!(text.contains("asdf_recent_job_tasks{command=\"update\",result=\"succeeded\"} 4\n"));
+        assert!(text.contains("asdf_last_job_timestamp_seconds{command=\"update\"} 300\n"));
+        assert!(text.contains("asdf_last_job_duration_seconds{command=\"install\"} 2.5\n"));
+        assert!(text.contains("asdf_last_job_failed{command=\"update\"} 1\n"));
+    }
+}
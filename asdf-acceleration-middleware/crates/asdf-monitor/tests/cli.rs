@@ -0,0 +1,127 @@
+//! End-to-end tests against a fake asdf backend
+
+use asdf_core::testing::FakeAsdf;
+use asdf_metrics::jobs::{self, JobLog, JobRecord};
+use assert_cmd::Command;
+
+fn monitor(fake: &FakeAsdf) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_asdf-monitor"));
+    cmd.envs(fake.env()).current_dir(fake.home());
+    cmd
+}
+
+#[test]
+fn test_health_reports_findings() {
+    let fake = FakeAsdf::new()
+        .plugin("nodejs", &["20.11.0"])
+        .installed("nodejs", "20.11.0");
+
+    monitor(&fake)
+        .args(["health"])
+        .assert()
+        .code(0)
+        .stdout(predicates::str::contains("v0.14.0-fake"));
+
+    // A local modification in the plugin checkout is a warning
+    std::fs::write(fake.data_dir().join("plugins/nodejs/bin/list-all"), "").unwrap();
+
+    let output = monitor(&fake)
+        .args(["health", "--format", "sarif"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let sarif: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(sarif["runs"][0]["results"][0]["ruleId"], "plugin-dirty");
+}
+
+/// `asdf-monitor serve` on an ephemeral port, killed on drop
+struct Server {
+    child: std::process::Child,
+    base: String,
+}
+
+impl Server {
+    fn start(fake: &FakeAsdf, env: &[(&str, std::path::PathBuf)]) -> Self {
+        use std::io::BufRead;
+
+        let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_asdf-monitor"))
+            .args(["serve", "--listen", "127.0.0.1:0"])
+            .envs(fake.env())
+            .envs(env.iter().cloned())
+            .current_dir(fake.home())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let mut line = String::new();
+        std::io::BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let base = line
+            .split_whitespace()
+            .last()
+            .expect("no listen address")
+            .to_string();
+
+        Self { child, base }
+    }
+
+    fn get(&self, path: &str) -> ureq::Response {
+        match ureq::get(&format!("{}{}", self.base, path)).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => panic!("GET {} failed: {}", path, e),
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn test_serve_metrics_health_and_jobs() {
+    let fake = FakeAsdf::new()
+        .plugin("nodejs", &["20.11.0"])
+        .installed("nodejs", "20.11.0");
+
+    let log = JobLog::open(fake.data_dir().join(jobs::FILE_NAME));
+    log.append(&JobRecord {
+        command: "update".to_string(),
+        started_at: 1_700_000_000,
+        duration_ms: 1500,
+        status: "partial".to_string(),
+        succeeded: 1,
+        failed: 1,
+        failed_tasks: vec!["ruby".to_string()],
+    })
+    .unwrap();
+
+    let server = Server::start(&fake, &[]);
+
+    let metrics = server.get("/metrics");
+    assert_eq!(metrics.status(), 200);
+    assert!(metrics.content_type().starts_with("application/openmetrics-text"));
+    let body = metrics.into_string().unwrap();
+    assert!(body.contains("asdf_up 1\n"), "{}", body);
+    assert!(body.contains("asdf_plugins 1\n"));
+    assert!(body.contains("asdf_recent_jobs{command=\"update\",status=\"partial\"} 1\n"));
+    assert!(body.ends_with("# EOF\n"));
+
+    let health = server.get("/health");
+    assert_eq!(health.status(), 200);
+    let health: serde_json::Value = serde_json::from_str(&health.into_string().unwrap()).unwrap();
+    assert_eq!(health["status"], "healthy");
+
+    let jobs = server.get("/jobs?limit=5").into_string().unwrap();
+    let jobs: serde_json::Value = serde_json::from_str(&jobs).unwrap();
+    assert_eq!(jobs["jobs"][0]["failed_tasks"][0], "ruby");
+
+    // A missing data directory is an error, which fails the health check
+    let server = Server::start(&fake, &[("ASDF_DATA_DIR", fake.home().join("missing"))]);
+    assert_eq!(server.get("/health").status(), 503);
+}
@@ -0,0 +1,44 @@
+//! Rayon vs async executor on simulated plugin syncs
+//!
+//! Each sync blocks for a fixed time, like `git fetch` waiting on the network.
+//! The rayon pool can only overlap as many as it has workers, while the async
+//! executor keeps its whole IO concurrency in flight.
+
+use asdf_parallel::{AsyncExecutor, Executor, ExecutorConfig};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::time::Duration;
+
+/// Time a simulated sync spends waiting
+const SYNC_LATENCY: Duration = Duration::from_millis(20);
+
+fn sync_plugin(_plugin: &usize) -> asdf_parallel::Result<()> {
+    std::thread::sleep(SYNC_LATENCY);
+    Ok(())
+}
+
+fn bench_plugin_sync(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("plugin_sync");
+    group.sample_size(10);
+
+    for plugins in [100, 250] {
+        group.bench_with_input(BenchmarkId::new("rayon", plugins), &plugins, |b, &n| {
+            let executor = Executor::new(ExecutorConfig::default());
+            b.iter(|| executor.execute((0..n).collect(), sync_plugin).unwrap());
+        });
+
+        group.bench_with_input(BenchmarkId::new("async", plugins), &plugins, |b, &n| {
+            let executor = AsyncExecutor::new(ExecutorConfig::default());
+            b.iter(|| {
+                runtime
+                    .block_on(executor.execute_blocking((0..n).collect(), sync_plugin))
+                    .unwrap()
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_plugin_sync);
+criterion_main!(benches);
@@ -0,0 +1,289 @@
+//! Load-driven concurrency for [`Strategy::Adaptive`]
+//!
+//! A governor thread samples CPU load, memory and swap while tasks run and
+//! moves a concurrency limit between the strategy's bounds. The limit is
+//! halved as soon as the system starts swapping, stepped down while the CPUs
+//! or memory are oversubscribed and stepped back up while there is headroom.
+//!
+//! [`Strategy::Adaptive`]: crate::Strategy::Adaptive
+
+use asdf_metrics::{SystemInfo, SystemSampler};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::debug;
+
+/// How often the governor samples system load by default
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Swap growth between samples that counts as swapping
+const SWAP_GROWTH_KB: u64 = 16 * 1024;
+
+/// Source of system load samples
+pub trait LoadProbe: Send + 'static {
+    /// Take a fresh sample
+    fn sample(&mut self) -> SystemInfo;
+}
+
+impl LoadProbe for SystemSampler {
+    fn sample(&mut self) -> SystemInfo {
+        SystemSampler::sample(self)
+    }
+}
+
+/// How loaded the system is, judged from two consecutive samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pressure {
+    /// Swapping or nearly out of memory: halve concurrency
+    Critical,
+
+    /// CPUs or memory oversubscribed: drop one task
+    High,
+
+    /// Busy but coping: hold
+    Normal,
+
+    /// Headroom available: add one task
+    Low,
+}
+
+impl Pressure {
+    /// Assess the pressure shown by `current`, relative to `previous`
+    pub fn assess(previous: &SystemInfo, current: &SystemInfo) -> Self {
+        let swapping = current.used_swap_kb > previous.used_swap_kb + SWAP_GROWTH_KB;
+        let memory = current.memory_usage_percent();
+        let cpu = current.cpu_load();
+
+        if swapping || memory >= 90.0 {
+            Pressure::Critical
+        } else if memory >= 80.0 || cpu >= 1.25 {
+            Pressure::High
+        } else if memory < 70.0 && cpu < 0.75 {
+            Pressure::Low
+        } else {
+            Pressure::Normal
+        }
+    }
+}
+
+/// A concurrency limit that can move while tasks are running
+#[derive(Debug)]
+pub struct AdaptiveLimit {
+    min: usize,
+    max: usize,
+    state: Mutex<LimitState>,
+    available: Condvar,
+}
+
+#[derive(Debug)]
+struct LimitState {
+    limit: usize,
+    active: usize,
+}
+
+impl AdaptiveLimit {
+    /// Create a limit between `min` and `max`, starting at the physical core count
+    pub fn new(min: usize, max: usize) -> Self {
+        let min = min.max(1);
+        let max = max.max(min);
+
+        Self {
+            min,
+            max,
+            state: Mutex::new(LimitState {
+                limit: crate::optimal_thread_count().clamp(min, max),
+                active: 0,
+            }),
+            available: Condvar::new(),
+        }
+    }
+
+    /// Current limit
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit
+    }
+
+    /// Move the limit in response to `pressure`, returning the new limit
+    pub fn apply(&self, pressure: Pressure) -> usize {
+        let mut state = self.state.lock().unwrap();
+
+        state.limit = match pressure {
+            Pressure::Critical => state.limit / 2,
+            Pressure::High => state.limit.saturating_sub(1),
+            Pressure::Normal => state.limit,
+            Pressure::Low => state.limit + 1,
+        }
+        .clamp(self.min, self.max);
+
+        self.available.notify_all();
+        state.limit
+    }
+
+    /// Block until a task may start
+    ///
+    /// Running tasks are never interrupted; a lowered limit takes effect as
+    /// they finish.
+    pub fn acquire(&self) -> Slot<'_> {
+        let mut state = self.state.lock().unwrap();
+        while state.active >= state.limit {
+            state = self.available.wait(state).unwrap();
+        }
+        state.active += 1;
+
+        Slot { limit: self }
+    }
+}
+
+/// Permission for one task to run, released on drop
+#[derive(Debug)]
+pub struct Slot<'a> {
+    limit: &'a AdaptiveLimit,
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.limit.state.lock().unwrap().active -= 1;
+        self.limit.available.notify_one();
+    }
+}
+
+/// Background thread that adjusts an [`AdaptiveLimit`] from load samples
+///
+/// Stops when dropped.
+pub struct Governor {
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Governor {
+    /// Start sampling `probe` every `interval`
+    pub fn start(limit: Arc<AdaptiveLimit>, mut probe: impl LoadProbe, interval: Duration) -> Self {
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let signal = Arc::clone(&stop);
+
+        let handle = std::thread::spawn(move || {
+            let (stopped, wake) = &*signal;
+            let mut previous = probe.sample();
+
+            loop {
+                let guard = stopped.lock().unwrap();
+                let (guard, _) = wake
+                    .wait_timeout_while(guard, interval, |stopped| !*stopped)
+                    .unwrap();
+                if *guard {
+                    break;
+                }
+                drop(guard);
+
+                let current = probe.sample();
+                let pressure = Pressure::assess(&previous, &current);
+                let before = limit.limit();
+                let after = limit.apply(pressure);
+                if before != after {
+                    debug!(
+                        "{:?} pressure (load {:.2}/cpu, memory {:.0}%): concurrency {} -> {}",
+                        pressure,
+                        current.cpu_load(),
+                        current.memory_usage_percent(),
+                        before,
+                        after
+                    );
+                }
+                previous = current;
+            }
+        });
+
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for Governor {
+    fn drop(&mut self) {
+        let (stopped, wake) = &*self.stop;
+        *stopped.lock().unwrap() = true;
+        wake.notify_all();
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(used_memory_kb: u64, used_swap_kb: u64, load_average: f64) -> SystemInfo {
+        SystemInfo {
+            cpu_count: 4,
+            total_memory_kb: 1_000_000,
+            used_memory_kb,
+            total_swap_kb: 1_000_000,
+            used_swap_kb,
+            load_average,
+        }
+    }
+
+    #[test]
+    fn test_pressure_assess() {
+        let idle = sample(300_000, 0, 1.0);
+
+        assert_eq!(Pressure::assess(&idle, &idle), Pressure::Low);
+        assert_eq!(
+            Pressure::assess(&idle, &sample(300_000, 0, 4.0)),
+            Pressure::Normal
+        );
+        assert_eq!(
+            Pressure::assess(&idle, &sample(300_000, 0, 6.0)),
+            Pressure::High
+        );
+        assert_eq!(
+            Pressure::assess(&idle, &sample(850_000, 0, 1.0)),
+            Pressure::High
+        );
+        assert_eq!(
+            Pressure::assess(&idle, &sample(300_000, 100_000, 1.0)),
+            Pressure::Critical
+        );
+        // Swap that is in use but not growing is not pressure
+        let swapped = sample(300_000, 100_000, 1.0);
+        assert_eq!(Pressure::assess(&swapped, &swapped), Pressure::Low);
+    }
+
+    #[test]
+    fn test_limit_stays_within_bounds() {
+        let limit = AdaptiveLimit::new(2, 6);
+
+        for _ in 0..10 {
+            limit.apply(Pressure::Low);
+        }
+        assert_eq!(limit.limit(), 6);
+
+        assert_eq!(limit.apply(Pressure::Critical), 3);
+        assert_eq!(limit.apply(Pressure::High), 2);
+        assert_eq!(limit.apply(Pressure::Critical), 2);
+        assert_eq!(limit.apply(Pressure::Normal), 2);
+    }
+
+    #[test]
+    fn test_acquire_waits_for_slot() {
+        let limit = Arc::new(AdaptiveLimit::new(1, 1));
+        let slot = limit.acquire();
+
+        let waiter = {
+            let limit = Arc::clone(&limit);
+            std::thread::spawn(move || {
+                let _slot = limit.acquire();
+            })
+        };
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(!waiter.is_finished());
+
+        drop(slot);
+        waiter.join().unwrap();
+    }
+}
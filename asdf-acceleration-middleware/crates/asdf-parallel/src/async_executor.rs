@@ -0,0 +1,436 @@
+//! Async task executor for IO-bound operations
+//!
+//! Most asdf operations spend their time waiting on subprocesses and the
+//! network. On the rayon pool each of those waits occupies a CPU-sized
+//! worker; here they run as tokio tasks bounded by a semaphore, so many more
+//! can be in flight at once. CPU-heavy work should stay on [`Executor`].
+//!
+//! [`Executor`]: crate::Executor
+
+use crate::{Error, ExecutorConfig, Result};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::{Id, JoinError, JoinSet};
+use tracing::{debug, info, info_span, warn, Instrument, Span};
+
+/// Running tasks yielding their input index, and that index by task id
+type Spawned<R> = (JoinSet<(usize, Result<R>)>, HashMap<Id, usize>);
+
+/// Async task executor with bounded concurrency
+pub struct AsyncExecutor {
+    config: Arc<ExecutorConfig>,
+    concurrency: usize,
+}
+
+impl AsyncExecutor {
+    /// Create a new executor, sized by the strategy's IO concurrency
+    pub fn new(config: ExecutorConfig) -> Self {
+        let concurrency = config.strategy.io_concurrency();
+        Self {
+            config: Arc::new(config),
+            concurrency,
+        }
+    }
+
+    /// Override the number of tasks allowed in flight at once
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Number of tasks allowed in flight at once
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Execute a collection of async tasks concurrently
+    ///
+    /// Results are returned in input order. With `fail_fast`, the first
+    /// failure aborts the tasks still running and is returned.
+    pub async fn execute<T, F, Fut, R>(&self, tasks: Vec<T>, f: F) -> Result<Vec<R>>
+    where
+        T: Clone + Debug + Send + Sync + 'static,
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R>> + Send + 'static,
+        R: Send + 'static,
+    {
+        info!(
+            "Executing {} async tasks with concurrency {}",
+            tasks.len(),
+            self.concurrency
+        );
+
+        let (mut set, _) = self.spawn(tasks, f);
+        let mut results = Vec::with_capacity(set.len());
+
+        while let Some(joined) = set.join_next().await {
+            let (index, result) = joined.map_err(join_error)?;
+            if self.config.fail_fast {
+                if let Err(e) = result {
+                    set.abort_all();
+                    return Err(e);
+                }
+            }
+            results.push((index, result));
+        }
+        results.sort_by_key(|(index, _)| *index);
+
+        // Collect successes and log failures
+        let mut successes = Vec::new();
+        let mut failures = 0;
+
+        for (_, result) in results {
+            match result {
+                Ok(r) => successes.push(r),
+                Err(e) => {
+                    warn!("Task failed: {}", e);
+                    failures += 1;
+                }
+            }
+        }
+
+        if failures > 0 {
+            warn!("{} tasks failed", failures);
+        }
+
+        if successes.is_empty() && failures > 0 {
+            Err(Error::ExecutionFailed(format!(
+                "All {} tasks failed",
+                failures
+            )))
+        } else {
+            Ok(successes)
+        }
+    }
+
+    /// Execute async tasks and return both successes and failures
+    ///
+    /// Every task runs to completion, and a task that panics counts as
+    /// failed; both lists keep input order.
+    pub async fn execute_all<T, F, Fut, R>(&self, tasks: Vec<T>, f: F) -> (Vec<R>, Vec<(T, Error)>)
+    where
+        T: Clone + Debug + Send + Sync + 'static,
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R>> + Send + 'static,
+        R: Send + 'static,
+    {
+        let mut inputs: Vec<Option<T>> = tasks.iter().cloned().map(Some).collect();
+        let (mut set, indices) = self.spawn(tasks, f);
+        let mut results = Vec::with_capacity(set.len());
+
+        while let Some(joined) = set.join_next_with_id().await {
+            match joined {
+                Ok((_, outcome)) => results.push(outcome),
+                // Tasks are never aborted here, so this is a panic
+                Err(e) => {
+                    warn!("Task panicked: {}", e);
+                    let index = indices[&e.id()];
+                    let task = inputs[index].as_ref().expect("each task finishes once");
+                    let error = Error::TaskFailed {
+                        task: format!("{:?}", task),
+                        error: e.to_string(),
+                    };
+                    results.push((index, Err(error)));
+                }
+            }
+        }
+        results.sort_by_key(|(index, _)| *index);
+
+        let mut successes = Vec::new();
+        let mut failures = Vec::new();
+
+        for (index, result) in results {
+            match result {
+                Ok(r) => successes.push(r),
+                Err(e) => failures.push((inputs[index].take().expect("each task finishes once"), e)),
+            }
+        }
+
+        (successes, failures)
+    }
+
+    /// Execute blocking tasks, such as subprocess calls, concurrently
+    ///
+    /// Each attempt runs on tokio's blocking pool while holding a permit,
+    /// inside the task's span.
+    pub async fn execute_blocking<T, F, R>(&self, tasks: Vec<T>, f: F) -> Result<Vec<R>>
+    where
+        T: Clone + Debug + Send + Sync + 'static,
+        F: Fn(&T) -> Result<R> + Send + Sync + 'static,
+        R: Send + 'static,
+    {
+        let f = Arc::new(f);
+        self.execute(tasks, move |task| {
+            let f = Arc::clone(&f);
+            async move {
+                let span = Span::current();
+                tokio::task::spawn_blocking(move || span.in_scope(|| f(&task)))
+                    .await
+                    .map_err(join_error)?
+            }
+        })
+        .await
+    }
+
+    /// Execute blocking tasks and return both successes and failures
+    pub async fn execute_blocking_all<T, F, R>(
+        &self,
+        tasks: Vec<T>,
+        f: F,
+    ) -> (Vec<R>, Vec<(T, Error)>)
+    where
+        T: Clone + Debug + Send + Sync + 'static,
+        F: Fn(&T) -> Result<R> + Send + Sync + 'static,
+        R: Send + 'static,
+    {
+        let f = Arc::new(f);
+        self.execute_all(tasks, move |task| {
+            let f = Arc::clone(&f);
+            async move {
+                let span = Span::current();
+                tokio::task::spawn_blocking(move || span.in_scope(|| f(&task)))
+                    .await
+                    .map_err(join_error)?
+            }
+        })
+        .await
+    }
+
+    /// Spawn every task behind the semaphore
+    ///
+    /// The index by task id tells which task a [`JoinError`] belongs to.
+    fn spawn<T, F, Fut, R>(&self, tasks: Vec<T>, f: F) -> Spawned<R>
+    where
+        T: Clone + Debug + Send + Sync + 'static,
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R>> + Send + 'static,
+        R: Send + 'static,
+    {
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let f = Arc::new(f);
+        let parent = Span::current();
+        let mut set = JoinSet::new();
+        let mut indices = HashMap::new();
+
+        for (index, task) in tasks.into_iter().enumerate() {
+            let semaphore = Arc::clone(&semaphore);
+            let config = Arc::clone(&self.config);
+            let f = Arc::clone(&f);
+
+            let span = info_span!(parent: &parent, "task", task = ?task);
+
+            let handle = set.spawn(
+                async move {
+                    let _permit = semaphore
+                        .acquire()
+                        .await
+                        .expect("executor semaphore is never closed");
+                    let result = execute_with_retry(&config, &task, f.as_ref()).await;
+                    (index, result)
+                }
+                .instrument(span),
+            );
+            indices.insert(handle.id(), index);
+        }
+
+        (set, indices)
+    }
+}
+
+/// Execute a single async task with retry logic
+async fn execute_with_retry<T, F, Fut, R>(config: &ExecutorConfig, task: &T, f: &F) -> Result<R>
+where
+    T: Clone + Debug,
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = Result<R>>,
+{
+    let mut attempts = 0;
+    let max_attempts = config.max_retries + 1;
+
+    loop {
+        attempts += 1;
+
+        match f(task.clone()).await {
+            Ok(result) => return Ok(result),
+            Err(e) if attempts < max_attempts => {
+                debug!(
+                    "Task {:?} failed (attempt {}/{}): {}",
+                    task, attempts, max_attempts, e
+                );
+                continue;
+            }
+            Err(e) => {
+                return Err(Error::TaskFailed {
+                    task: format!("{:?}", task),
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+}
+
+fn join_error(e: JoinError) -> Error {
+    Error::ExecutionFailed(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Strategy;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    fn executor(concurrency: usize) -> AsyncExecutor {
+        AsyncExecutor::new(ExecutorConfig::default()).with_concurrency(concurrency)
+    }
+
+    #[tokio::test]
+    async fn test_async_executor_preserves_order() {
+        let results = executor(4)
+            .execute(vec![5u64, 1, 4, 2, 3], |x| async move {
+                tokio::time::sleep(Duration::from_millis(x * 5)).await;
+                Ok(x * 2)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(results, vec![10, 2, 8, 4, 6]);
+    }
+
+    #[tokio::test]
+    async fn test_async_executor_bounds_concurrency() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let (in_flight_task, peak_task) = (Arc::clone(&in_flight), Arc::clone(&peak));
+        executor(3)
+            .execute((0..20).collect(), move |_: i32| {
+                let in_flight = Arc::clone(&in_flight_task);
+                let peak = Arc::clone(&peak_task);
+                async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_async_executor_retry() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let config = ExecutorConfig {
+            max_retries: 2,
+            ..Default::default()
+        };
+
+        let counter = Arc::clone(&calls);
+        let results = AsyncExecutor::new(config)
+            .execute(vec![1], move |x| {
+                let attempt = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                async move {
+                    if attempt < 3 {
+                        Err(Error::Other("flaky".to_string()))
+                    } else {
+                        Ok(x)
+                    }
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(results, vec![1]);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_async_executor_fail_fast() {
+        let config = ExecutorConfig {
+            strategy: Strategy::Fixed(4),
+            fail_fast: true,
+            ..Default::default()
+        };
+
+        let started = Instant::now();
+        let result = AsyncExecutor::new(config)
+            .execute(vec![0u64, 1, 2, 3], |x| async move {
+                if x == 0 {
+                    Err(Error::Other("broken".to_string()))
+                } else {
+                    tokio::time::sleep(Duration::from_secs(10)).await;
+                    Ok(x)
+                }
+            })
+            .await;
+
+        assert!(matches!(result, Err(Error::TaskFailed { .. })));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_async_executor_execute_all() {
+        let (successes, failures) = executor(2)
+            .execute_all(vec![1, 2, 3, 4], |x| async move {
+                if x % 2 == 0 {
+                    Err(Error::Other("even number".to_string()))
+                } else {
+                    Ok(x * 10)
+                }
+            })
+            .await;
+
+        assert_eq!(successes, vec![10, 30]);
+        let failed: Vec<i32> = failures.into_iter().map(|(task, _)| task).collect();
+        assert_eq!(failed, vec![2, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_async_executor_execute_all_reports_panics() {
+        let (successes, failures) = executor(2)
+            .execute_all(vec![1, 2, 3], |x| async move {
+                if x == 2 {
+                    panic!("task {} panicked", x);
+                }
+                Ok(x)
+            })
+            .await;
+
+        assert_eq!(successes, vec![1, 3]);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, 2);
+        assert!(matches!(&failures[0].1, Error::TaskFailed { task, .. } if task == "2"));
+    }
+
+    #[tokio::test]
+    async fn test_async_executor_overlaps_blocking_tasks() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let (in_flight_task, peak_task) = (Arc::clone(&in_flight), Arc::clone(&peak));
+        let results = executor(8)
+            .execute_blocking((0..8).collect(), move |x: &i32| {
+                let now = in_flight_task.fetch_add(1, Ordering::SeqCst) + 1;
+                peak_task.fetch_max(now, Ordering::SeqCst);
+
+                // Hold the permit until every task is running, or give up
+                let deadline = Instant::now() + Duration::from_secs(10);
+                while in_flight_task.load(Ordering::SeqCst) < 8 && Instant::now() < deadline {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                Ok(*x)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 8);
+        assert_eq!(peak.load(Ordering::SeqCst), 8);
+    }
+}
@@ -1,9 +1,12 @@
 //! Task executor for parallel operations
 
+use crate::adaptive::{AdaptiveLimit, Governor, LoadProbe, SAMPLE_INTERVAL};
 use crate::{Error, Result, Strategy};
+use asdf_metrics::SystemSampler;
 use rayon::prelude::*;
 use std::sync::Arc;
-use tracing::{debug, info, warn};
+use std::time::Duration;
+use tracing::{debug, info, info_span, warn, Span};
 
 /// Configuration for the executor
 #[derive(Debug, Clone)]
@@ -31,13 +34,74 @@ fn default() -> Self {
 /// Task executor
 pub struct Executor {
     config: Arc<ExecutorConfig>,
+    adaptive: Option<Adaptive>,
+}
+
+/// Load-driven state for [`Strategy::Adaptive`]
+struct Adaptive {
+    limit: Arc<AdaptiveLimit>,
+    pool: Option<rayon::ThreadPool>,
+    _governor: Governor,
 }
 
 impl Executor {
     /// Create a new executor with the given configuration
+    ///
+    /// With [`Strategy::Adaptive`], system load is sampled for as long as
+    /// the executor lives.
     pub fn new(config: ExecutorConfig) -> Self {
+        match config.strategy {
+            Strategy::Adaptive { .. } => {
+                Self::with_probe(config, SystemSampler::new(), SAMPLE_INTERVAL)
+            }
+            _ => Self {
+                config: Arc::new(config),
+                adaptive: None,
+            },
+        }
+    }
+
+    /// Create an executor whose adaptive strategy samples `probe`
+    pub fn with_probe(config: ExecutorConfig, probe: impl LoadProbe, interval: Duration) -> Self {
+        let adaptive = match config.strategy {
+            Strategy::Adaptive { min, max } => {
+                let limit = Arc::new(AdaptiveLimit::new(min, max));
+                // Size the pool for the upper bound; the limit gates the rest
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(max.max(1))
+                    .build()
+                    .map_err(|e| warn!("Using the global thread pool: {}", e))
+                    .ok();
+
+                Some(Adaptive {
+                    _governor: Governor::start(Arc::clone(&limit), probe, interval),
+                    limit,
+                    pool,
+                })
+            }
+            _ => None,
+        };
+
         Self {
             config: Arc::new(config),
+            adaptive,
+        }
+    }
+
+    /// Current concurrency limit of an adaptive executor
+    pub fn concurrency_limit(&self) -> Option<usize> {
+        self.adaptive.as_ref().map(|a| a.limit.limit())
+    }
+
+    /// Run `op` on the adaptive pool, if there is one
+    fn in_pool<OP, R>(&self, op: OP) -> R
+    where
+        OP: FnOnce() -> R + Send,
+        R: Send,
+    {
+        match self.adaptive.as_ref().and_then(|a| a.pool.as_ref()) {
+            Some(pool) => pool.install(op),
+            None => op(),
         }
     }
 
@@ -56,19 +120,22 @@ pub fn execute<T, F, R>(&self, tasks: Vec<T>, f: F) -> Result<Vec<R>>
 
         let thread_count = self.config.strategy.thread_count();
         debug!("Using {} threads", thread_count);
+        let parent = Span::current();
 
         let results: Vec<_> = if thread_count == 1 {
             // Sequential execution
             tasks
                 .iter()
-                .map(|task| self.execute_with_retry(task, &f))
+                .map(|task| self.execute_with_retry(task, &f, &parent))
                 .collect()
         } else {
             // Parallel execution
-            tasks
-                .par_iter()
-                .map(|task| self.execute_with_retry(task, &f))
-                .collect()
+            self.in_pool(|| {
+                tasks
+                    .par_iter()
+                    .map(|task| self.execute_with_retry(task, &f, &parent))
+                    .collect()
+            })
         };
 
         // Handle errors based on fail_fast setting
@@ -105,11 +172,16 @@ pub fn execute<T, F, R>(&self, tasks: Vec<T>, f: F) -> Result<Vec<R>>
     }
 
     /// Execute a single task with retry logic
-    fn execute_with_retry<T, F, R>(&self, task: &T, f: &F) -> Result<R>
+    ///
+    /// The task runs in a `task` span under `parent`, since rayon workers
+    /// don't inherit the caller's span.
+    fn execute_with_retry<T, F, R>(&self, task: &T, f: &F, parent: &Span) -> Result<R>
     where
         T: std::fmt::Debug,
         F: Fn(&T) -> Result<R>,
     {
+        let _span = info_span!(parent: parent, "task", task = ?task).entered();
+        let _slot = self.adaptive.as_ref().map(|a| a.limit.acquire());
         let mut attempts = 0;
         let max_attempts = self.config.max_retries + 1;
 
@@ -147,23 +219,26 @@ pub fn execute_all<T, F, R>(
         R: Send,
     {
         let thread_count = self.config.strategy.thread_count();
+        let parent = Span::current();
 
         let results: Vec<_> = if thread_count == 1 {
             tasks
                 .into_iter()
                 .map(|task| {
-                    let result = self.execute_with_retry(&task, &f);
+                    let result = self.execute_with_retry(&task, &f, &parent);
                     (task, result)
                 })
                 .collect()
         } else {
-            tasks
-                .into_par_iter()
-                .map(|task| {
-                    let result = self.execute_with_retry(&task, &f);
-                    (task, result)
-                })
-                .collect()
+            self.in_pool(|| {
+                tasks
+                    .into_par_iter()
+                    .map(|task| {
+                        let result = self.execute_with_retry(&task, &f, &parent);
+                        (task, result)
+                    })
+                    .collect()
+            })
         };
 
         let mut successes = Vec::new();
@@ -183,6 +258,8 @@ pub fn execute_all<T, F, R>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use asdf_metrics::SystemInfo;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn test_executor_sequential() {
@@ -255,10 +332,93 @@ fn test_executor_retry() {
         let tasks = vec![1];
 
         // This should fail even with retries
-        let result = executor.execute(tasks, |_| {
+        let result = executor.execute(tasks, |_| -> Result<()> {
             Err(Error::Other("always fails".to_string()))
         });
 
         assert!(result.is_err());
     }
+
+    /// Reports the same load on every sample
+    struct SteadyLoad(SystemInfo);
+
+    impl LoadProbe for SteadyLoad {
+        fn sample(&mut self) -> SystemInfo {
+            self.0.clone()
+        }
+    }
+
+    /// Reports swap growing on every sample
+    struct Swapping(u64);
+
+    impl LoadProbe for Swapping {
+        fn sample(&mut self) -> SystemInfo {
+            self.0 += 1_000_000;
+            SystemInfo {
+                used_swap_kb: self.0,
+                ..idle()
+            }
+        }
+    }
+
+    fn idle() -> SystemInfo {
+        SystemInfo {
+            cpu_count: 4,
+            total_memory_kb: 16_000_000,
+            used_memory_kb: 1_000_000,
+            total_swap_kb: 8_000_000,
+            used_swap_kb: 0,
+            load_average: 0.1,
+        }
+    }
+
+    fn adaptive(min: usize, max: usize, probe: impl LoadProbe) -> Executor {
+        let config = ExecutorConfig {
+            strategy: Strategy::Adaptive { min, max },
+            ..Default::default()
+        };
+        Executor::with_probe(config, probe, Duration::from_millis(5))
+    }
+
+    /// Run sleeping tasks and return the peak number running at once
+    fn peak_concurrency(executor: &Executor, tasks: usize) -> usize {
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        executor
+            .execute((0..tasks).collect(), |_| {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(10));
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(())
+            })
+            .unwrap();
+
+        peak.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn test_adaptive_grows_with_headroom() {
+        let executor = adaptive(1, 4, SteadyLoad(idle()));
+        std::thread::sleep(Duration::from_millis(100));
+
+        assert_eq!(executor.concurrency_limit(), Some(4));
+        assert!(peak_concurrency(&executor, 16) <= 4);
+    }
+
+    #[test]
+    fn test_adaptive_backs_off_when_swapping() {
+        let executor = adaptive(1, 4, Swapping(0));
+        std::thread::sleep(Duration::from_millis(100));
+
+        assert_eq!(executor.concurrency_limit(), Some(1));
+        assert_eq!(peak_concurrency(&executor, 8), 1);
+    }
+
+    #[test]
+    fn test_non_adaptive_has_no_limit() {
+        let executor = Executor::new(ExecutorConfig::default());
+        assert_eq!(executor.concurrency_limit(), None);
+    }
 }
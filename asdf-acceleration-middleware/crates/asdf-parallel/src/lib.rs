@@ -1,9 +1,16 @@
-//! Parallel execution engine for asdf operations using Rayon
+//! Parallel execution engine for asdf operations
+//!
+//! CPU-bound work runs on the Rayon pool via [`Executor`]; IO-bound work
+//! (subprocess and network waits) runs on tokio via [`AsyncExecutor`].
 
+pub mod adaptive;
+pub mod async_executor;
 pub mod error;
 pub mod executor;
 pub mod strategy;
 
+pub use adaptive::{AdaptiveLimit, Governor, LoadProbe, Pressure};
+pub use async_executor::AsyncExecutor;
 pub use error::{Error, Result};
 pub use executor::{Executor, ExecutorConfig};
 pub use strategy::Strategy;
@@ -2,10 +2,18 @@
 
 use serde::{Deserialize, Serialize};
 
+/// IO-bound tasks kept in flight per CPU by the automatic strategies
+const IO_TASKS_PER_CPU: usize = 4;
+
+/// Upper bound on IO-bound tasks in flight at once
+const MAX_IO_CONCURRENCY: usize = 256;
+
 /// Parallel execution strategy
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
 pub enum Strategy {
     /// Automatically determine optimal parallelism
+    #[default]
     Auto,
 
     /// Sequential execution (no parallelism)
@@ -16,6 +24,12 @@ pub enum Strategy {
 
     /// Parallel with maximum concurrency
     MaxParallel,
+
+    /// Between `min` and `max` jobs, adjusted to live system load
+    ///
+    /// IO-bound work barely loads the system, so the async executor always
+    /// runs it at `max`.
+    Adaptive { min: usize, max: usize },
 }
 
 impl Strategy {
@@ -26,13 +40,22 @@ pub fn thread_count(&self) -> usize {
             Strategy::Sequential => 1,
             Strategy::Fixed(n) => *n,
             Strategy::MaxParallel => num_cpus::get(),
+            Strategy::Adaptive { max, .. } => (*max).max(1),
         }
     }
-}
 
-impl Default for Strategy {
-    fn default() -> Self {
-        Strategy::Auto
+    /// Get the number of IO-bound tasks to keep in flight
+    ///
+    /// Tasks waiting on subprocesses or the network barely use a CPU, so the
+    /// automatic strategies oversubscribe the cores.
+    pub fn io_concurrency(&self) -> usize {
+        match self {
+            Strategy::Auto => (num_cpus::get() * IO_TASKS_PER_CPU).min(MAX_IO_CONCURRENCY),
+            Strategy::Sequential => 1,
+            Strategy::Fixed(n) => (*n).max(1),
+            Strategy::MaxParallel => MAX_IO_CONCURRENCY,
+            Strategy::Adaptive { max, .. } => (*max).max(1),
+        }
     }
 }
 
@@ -45,5 +68,24 @@ fn test_strategy_thread_count() {
         assert_eq!(Strategy::Sequential.thread_count(), 1);
         assert!(Strategy::Auto.thread_count() > 0);
         assert_eq!(Strategy::Fixed(4).thread_count(), 4);
+        assert_eq!(Strategy::Adaptive { min: 1, max: 6 }.thread_count(), 6);
+    }
+
+    #[test]
+    fn test_strategy_serde() {
+        let auto: Strategy = serde_json::from_str(r#""auto""#).unwrap();
+        assert_eq!(auto, Strategy::Auto);
+
+        let adaptive: Strategy =
+            serde_json::from_str(r#"{"adaptive": {"min": 2, "max": 8}}"#).unwrap();
+        assert_eq!(adaptive, Strategy::Adaptive { min: 2, max: 8 });
+    }
+
+    #[test]
+    fn test_strategy_io_concurrency() {
+        assert_eq!(Strategy::Sequential.io_concurrency(), 1);
+        assert_eq!(Strategy::Fixed(0).io_concurrency(), 1);
+        assert_eq!(Strategy::Fixed(32).io_concurrency(), 32);
+        assert!(Strategy::Auto.io_concurrency() >= Strategy::Auto.thread_count());
     }
 }
@@ -1,17 +1,27 @@
 use anyhow::{Context, Result};
-use fslint_core::{Config, PluginLoader, Scanner};
+use fslint_core::dedupe::{self, DedupeAction, DedupeReport, DuplicateGroup, Keep};
+use fslint_core::{Config, PluginLoader, ResultCache, ScannedFile, Scanner};
 use fslint_plugin_api::Plugin;
-use std::path::PathBuf;
+use fslint_plugin_host::PluginHost;
+use fslint_plugin_sdk::metadata::format_size;
+use fslint_plugin_secret_scanner::SecretScannerPlugin;
+use git2::Repository;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use crate::output::{OutputFormat, OutputFormatter};
 use crate::query::Query;
 
+/// First line after the shebang of hooks written by `fslint hook install`
+const HOOK_MARKER: &str = "# Installed by fslint hook install";
+
 pub fn scan(path: PathBuf, format: OutputFormat, query_str: Option<String>) -> Result<()> {
     // Load configuration
     let config = Config::load().context("Failed to load configuration")?;
 
     // Create plugin loader and register all plugins
-    let mut plugin_loader = create_plugin_loader();
+    let mut plugin_loader = create_plugin_loader(&config);
 
     // Set enabled plugins from config
     plugin_loader.set_enabled(config.enabled_plugins.clone());
@@ -19,8 +29,9 @@ pub fn scan(path: PathBuf, format: OutputFormat, query_str: Option<String>) -> R
     // Initialize plugins with config
     plugin_loader.initialize_all(&config.plugin_config)?;
 
-    // Create scanner
-    let mut scanner = Scanner::new(config.scanner, plugin_loader);
+    // Create scanner, reusing results from earlier runs where possible
+    let cache = open_cache_or_memory();
+    let scanner = Scanner::with_cache(config.scanner, plugin_loader, cache);
 
     // Scan directory
     let files = scanner.scan(&path)
@@ -49,8 +60,8 @@ pub fn scan(path: PathBuf, format: OutputFormat, query_str: Option<String>) -> R
 }
 
 pub fn list_plugins() -> Result<()> {
-    let plugin_loader = create_plugin_loader();
     let config = Config::load().unwrap_or_default();
+    let plugin_loader = create_plugin_loader(&config);
 
     println!("{:<25} {:<10} {:<50}", "Plugin", "Status", "Description");
     println!("{}", "-".repeat(90));
@@ -59,8 +70,10 @@ pub fn list_plugins() -> Result<()> {
         let enabled = config.is_plugin_enabled(&plugin_name);
         let status = if enabled { "enabled" } else { "disabled" };
 
-        // Get plugin description (we'll need to pass metadata)
-        let description = get_plugin_description(&plugin_name);
+        let description = plugin_loader
+            .metadata(&plugin_name)
+            .map(|m| m.description.as_str())
+            .unwrap_or_default();
 
         println!("{:<25} {:<10} {:<50}", plugin_name, status, description);
     }
@@ -100,10 +113,268 @@ pub fn show_config() -> Result<()> {
     println!("  Follow symlinks: {}", config.scanner.follow_symlinks);
     println!("  Respect .gitignore: {}", config.scanner.respect_gitignore);
 
+    println!("\nThird-party plugin directory: {:?}", config.plugin_dir_path()?);
+
+    Ok(())
+}
+
+pub fn cache_stats() -> Result<()> {
+    let path = ResultCache::default_path()?;
+    let summary = ResultCache::open(&path)?.summary()?;
+
+    println!("Cache directory: {:?}", path);
+    println!("Cached results: {}", summary.entries);
+    if let Some(bytes) = summary.disk_bytes {
+        println!("Size on disk: {:.1} MiB", bytes as f64 / (1024.0 * 1024.0));
+    }
+
+    if !summary.plugins.is_empty() {
+        println!("\nResults per plugin:");
+        for (plugin, count) in &summary.plugins {
+            println!("  {:<25} {}", plugin, count);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn cache_clear() -> Result<()> {
+    let cache = open_cache()?;
+    let entries = cache.size();
+    cache.clear()?;
+    cache.flush()?;
+    println!("Removed {} cached results", entries);
     Ok(())
 }
 
-fn create_plugin_loader() -> PluginLoader {
+pub fn cache_gc() -> Result<()> {
+    // Stamps depend on plugin configuration, so load it as scan does
+    let config = Config::load().context("Failed to load configuration")?;
+    let mut plugin_loader = create_plugin_loader(&config);
+    plugin_loader.initialize_all(&config.plugin_config)?;
+
+    let cache = open_cache()?;
+    let removed = cache.gc(plugin_loader.cache_stamps())?;
+    cache.flush()?;
+    println!("Removed {} stale results, {} left", removed, cache.size());
+    Ok(())
+}
+
+pub fn dedupe(path: PathBuf, action: Option<DedupeAction>, keep: Keep, dry_run: bool) -> Result<()> {
+    let config = Config::load().context("Failed to load configuration")?;
+    let mut plugin_loader = create_plugin_loader(&config);
+    plugin_loader.set_enabled(vec!["duplicate-finder".to_string()]);
+    plugin_loader.initialize_all(&config.plugin_config)?;
+
+    let cache = open_cache_or_memory();
+    let scanner = Scanner::with_cache(config.scanner, plugin_loader, cache);
+    let files = scanner.scan(&path)
+        .with_context(|| format!("Failed to scan directory: {:?}", path))?;
+
+    let groups = duplicate_groups(&files);
+    if groups.is_empty() {
+        println!("No duplicate files found");
+        return Ok(());
+    }
+
+    let plans = dedupe::plan(&groups, keep);
+    for plan in &plans {
+        println!("{} ({} copies of {})", &plan.hash[..16.min(plan.hash.len())],
+            plan.duplicates.len() + 1, format_size(plan.size));
+        println!("  keep   {}", display_path(&plan.keeper, &path));
+        for duplicate in &plan.duplicates {
+            println!("  {:<6} {}", action.map_or("dup", action_verb), display_path(duplicate, &path));
+        }
+    }
+    let wasted: u64 = groups.iter().map(DuplicateGroup::wasted_bytes).sum();
+    println!("\n{} groups, {} wasted", groups.len(), format_size(wasted));
+
+    let Some(action) = action else {
+        return Ok(());
+    };
+    if dry_run {
+        println!("Dry run: no files changed");
+        return Ok(());
+    }
+
+    let journal = dedupe::new_journal_path()?;
+    let report = dedupe::apply(&plans, action, &journal)?;
+    print_report(&report, action.done(), "freed");
+    if report.files > 0 {
+        println!("Undo with: fslint dedupe --undo {}", journal.display());
+    } else {
+        let _ = std::fs::remove_file(&journal);
+    }
+
+    Ok(())
+}
+
+pub fn dedupe_undo(journal: PathBuf) -> Result<()> {
+    let report = dedupe::undo(&journal)?;
+    print_report(&report, "Restored", "used again");
+    if !report.failed.is_empty() {
+        println!("Journal kept at {}", journal.display());
+    }
+    Ok(())
+}
+
+pub fn secrets_history(path: PathBuf, range: Option<String>, format: OutputFormat) -> Result<()> {
+    let (plugin, repo) = secret_scanner(&path)?;
+    let findings = plugin.scan_history(&repo, range.as_deref())?;
+    OutputFormatter::format_secrets(&findings, format);
+    if !findings.is_empty() {
+        anyhow::bail!("{} secret(s) found in git history", findings.len());
+    }
+    Ok(())
+}
+
+pub fn secrets_staged(path: PathBuf, format: OutputFormat) -> Result<()> {
+    let (plugin, repo) = secret_scanner(&path)?;
+    let findings = plugin.scan_staged(&repo)?;
+    OutputFormatter::format_secrets(&findings, format);
+    if !findings.is_empty() {
+        anyhow::bail!("{} secret(s) found in staged changes; remove them or mark them with fslint:allow",
+            findings.len());
+    }
+    Ok(())
+}
+
+/// Install a pre-commit hook that blocks commits adding secrets
+///
+/// A hook installed earlier is replaced. Any other hook is kept unless
+/// `force` is set, and then moved to `pre-commit.bak`.
+pub fn hook_install(path: PathBuf, force: bool) -> Result<()> {
+    let repo = open_repository(&path)?;
+    let hooks = hooks_dir(&repo)?;
+    let hook = hooks.join("pre-commit");
+
+    if let Ok(existing) = fs::read_to_string(&hook) {
+        if !existing.contains(HOOK_MARKER) {
+            if !force {
+                anyhow::bail!("{} already exists; use --force to replace it", hook.display());
+            }
+            // A backup from an earlier forced install may be the only copy
+            // of the user's own hook
+            let backup = hooks.join("pre-commit.bak");
+            if backup.exists() {
+                anyhow::bail!(
+                    "{} already exists; move it aside before replacing {}",
+                    backup.display(),
+                    hook.display()
+                );
+            }
+            fs::rename(&hook, &backup)
+                .with_context(|| format!("Failed to back up {}", hook.display()))?;
+            println!("Moved existing hook to {}", backup.display());
+        }
+    }
+
+    // The hook runs this binary, not whichever fslint is first on PATH
+    let exe = std::env::current_exe().context("Failed to locate the fslint binary")?;
+    let exe = exe.to_string_lossy().replace('\'', "'\\''");
+    let script = format!(
+        "#!/bin/sh\n{}\n# Blocks commits that add secrets; bypass with git commit --no-verify\nexec '{}' secrets staged --format simple\n",
+        HOOK_MARKER, exe
+    );
+    fs::create_dir_all(&hooks)
+        .with_context(|| format!("Failed to create {}", hooks.display()))?;
+    fs::write(&hook, script).with_context(|| format!("Failed to write {}", hook.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755))?;
+    }
+
+    println!("Installed pre-commit hook at {}", hook.display());
+    Ok(())
+}
+
+/// The secret scanner set up from config, and the repository at a path
+fn secret_scanner(path: &Path) -> Result<(SecretScannerPlugin, Repository)> {
+    let config = Config::load().context("Failed to load configuration")?;
+    let mut plugin = SecretScannerPlugin::new();
+    if let Some(plugin_config) = config.get_plugin_config("secret-scanner") {
+        plugin.initialize(plugin_config)?;
+    }
+    Ok((plugin, open_repository(path)?))
+}
+
+fn open_repository(path: &Path) -> Result<Repository> {
+    Repository::discover(path).with_context(|| format!("Not a git repository: {:?}", path))
+}
+
+/// Where git looks for hooks, honouring `core.hooksPath`
+fn hooks_dir(repo: &Repository) -> Result<PathBuf> {
+    let configured = repo.config()?.get_path("core.hooksPath").ok();
+    Ok(match configured {
+        Some(dir) if dir.is_relative() => repo.workdir().unwrap_or(repo.path()).join(dir),
+        Some(dir) => dir,
+        None => repo.path().join("hooks"),
+    })
+}
+
+/// Group files the duplicate finder marked, by content hash
+fn duplicate_groups(files: &[ScannedFile]) -> Vec<DuplicateGroup> {
+    let mut groups: BTreeMap<String, DuplicateGroup> = BTreeMap::new();
+    for file in files {
+        let hash = file.results.iter()
+            .filter(|r| r.plugin_name == "duplicate-finder" && r.tags.iter().any(|t| t == "duplicate"))
+            .find_map(|r| r.metadata.get("hash"));
+        if let Some(hash) = hash {
+            groups.entry(hash.clone())
+                .or_insert_with(|| DuplicateGroup {
+                    hash: hash.clone(),
+                    size: file.metadata.len(),
+                    files: Vec::new(),
+                })
+                .files.push(file.path.clone());
+        }
+    }
+
+    groups.into_values()
+        .filter(|group| group.files.len() > 1)
+        .map(|mut group| {
+            group.files.sort();
+            group
+        })
+        .collect()
+}
+
+fn action_verb(action: DedupeAction) -> &'static str {
+    match action {
+        DedupeAction::Hardlink => "link",
+        DedupeAction::Reflink => "clone",
+        DedupeAction::Delete => "delete",
+    }
+}
+
+fn display_path(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base).unwrap_or(path).display().to_string()
+}
+
+fn print_report(report: &DedupeReport, done: &str, bytes: &str) {
+    println!("{} {} files, {} {}", done, report.files, format_size(report.bytes), bytes);
+    for (path, reason) in &report.failed {
+        eprintln!("Warning: Skipped {}: {}", path.display(), reason);
+    }
+}
+
+fn open_cache() -> Result<ResultCache> {
+    ResultCache::open(ResultCache::default_path()?)
+}
+
+/// Persistent cache for a scan, or an in-memory one if it cannot be opened
+fn open_cache_or_memory() -> ResultCache {
+    open_cache().unwrap_or_else(|e| {
+        eprintln!(
+            "Warning: Result cache unavailable; this run's results are kept in memory only: {:#}",
+            e
+        );
+        ResultCache::new()
+    })
+}
+
+fn create_plugin_loader(config: &Config) -> PluginLoader {
     let mut loader = PluginLoader::new();
 
     // Register all plugins
@@ -147,19 +418,41 @@ fn create_plugin_loader() -> PluginLoader {
         fslint_plugin_secret_scanner::SecretScannerPlugin::metadata()
     );
 
+    register_wasm_plugins(&mut loader, config);
+
     loader
 }
 
-fn get_plugin_description(name: &str) -> String {
-    match name {
-        "git-status" => fslint_plugin_git_status::GitStatusPlugin::metadata().description,
-        "file-age" => fslint_plugin_file_age::FileAgePlugin::metadata().description,
-        "grouping" => fslint_plugin_grouping::GroupingPlugin::metadata().description,
-        "version-detection" => fslint_plugin_version_detection::VersionDetectionPlugin::metadata().description,
-        "ocr-status" => fslint_plugin_ocr_status::OcrStatusPlugin::metadata().description,
-        "ai-detection" => fslint_plugin_ai_detection::AiDetectionPlugin::metadata().description,
-        "duplicate-finder" => fslint_plugin_duplicate_finder::DuplicateFinderPlugin::metadata().description,
-        "secret-scanner" => fslint_plugin_secret_scanner::SecretScannerPlugin::metadata().description,
-        _ => "Unknown plugin".to_string(),
+/// Register third-party plugins found in the plugin directory
+///
+/// A plugin that fails to load is skipped with a warning, as is one whose
+/// name is already taken.
+fn register_wasm_plugins(loader: &mut PluginLoader, config: &Config) {
+    let found = config.plugin_dir_path().and_then(|dir| {
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        PluginHost::new()?.discover(&dir)
+    });
+    let found = match found {
+        Ok(found) => found,
+        Err(e) => {
+            eprintln!("Warning: Failed to load third-party plugins: {:#}", e);
+            return;
+        }
+    };
+
+    for (path, plugin) in found {
+        match plugin {
+            Ok(plugin) => {
+                let metadata = plugin.plugin_metadata();
+                if loader.metadata(&metadata.name).is_some() {
+                    eprintln!("Warning: Skipping plugin {:?}: '{}' is already registered", path, metadata.name);
+                    continue;
+                }
+                loader.register(plugin, metadata);
+            }
+            Err(e) => eprintln!("Warning: Skipping plugin {:?}: {:#}", path, e),
+        }
     }
 }
@@ -65,6 +65,102 @@ enum Commands {
         #[arg(short, long, default_value = "simple")]
         format: String,
     },
+
+    /// Find duplicate files and replace or remove the extra copies
+    Dedupe {
+        /// Path to scan
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// What to do with extra copies (hardlink, reflink, delete)
+        #[arg(short, long)]
+        action: Option<String>,
+
+        /// Which copy to keep (first, oldest, newest)
+        #[arg(short, long, default_value = "first")]
+        keep: String,
+
+        /// Show what would be done without changing anything
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+
+        /// Restore the copies recorded in an undo journal
+        #[arg(long, value_name = "JOURNAL", conflicts_with_all = ["action", "dry_run"])]
+        undo: Option<PathBuf>,
+    },
+
+    /// Manage the on-disk result cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    /// Scan git history or staged changes for secrets
+    Secrets {
+        #[command(subcommand)]
+        action: SecretsAction,
+    },
+
+    /// Manage git hooks
+    Hook {
+        #[command(subcommand)]
+        action: HookAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+    /// Show cache location, size and results per plugin
+    Stats,
+
+    /// Remove all cached results
+    Clear,
+
+    /// Remove results for deleted or changed files and outdated plugins
+    Gc,
+}
+
+#[derive(Subcommand)]
+enum SecretsAction {
+    /// Scan every commit, or a range, for secrets it added
+    History {
+        /// Path inside the repository
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Commits to scan (e.g., "main..HEAD" or "v1.0")
+        #[arg(short, long)]
+        range: Option<String>,
+
+        /// Output format (table, json, simple)
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+
+    /// Scan changes staged for the next commit
+    Staged {
+        /// Path inside the repository
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Output format (table, json, simple)
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum HookAction {
+    /// Install a pre-commit hook that blocks new secrets
+    Install {
+        /// Path inside the repository
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Replace an existing pre-commit hook, keeping it as pre-commit.bak
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 fn main() -> Result<()> {
@@ -93,6 +189,38 @@ fn main() -> Result<()> {
                 .map_err(|e| anyhow::anyhow!(e))?;
             commands::scan(path, output_format, Some(query))?;
         }
+        Commands::Dedupe { path, action, keep, dry_run, undo } => {
+            if let Some(journal) = undo {
+                commands::dedupe_undo(journal)?;
+            } else {
+                let action = action
+                    .map(|a| a.parse())
+                    .transpose()
+                    .map_err(|e: String| anyhow::anyhow!(e))?;
+                let keep = keep.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+                commands::dedupe(path, action, keep, dry_run)?;
+            }
+        }
+        Commands::Cache { action } => match action {
+            CacheAction::Stats => commands::cache_stats()?,
+            CacheAction::Clear => commands::cache_clear()?,
+            CacheAction::Gc => commands::cache_gc()?,
+        },
+        Commands::Secrets { action } => match action {
+            SecretsAction::History { path, range, format } => {
+                let output_format = OutputFormat::from_str(&format)
+                    .map_err(|e| anyhow::anyhow!(e))?;
+                commands::secrets_history(path, range, output_format)?;
+            }
+            SecretsAction::Staged { path, format } => {
+                let output_format = OutputFormat::from_str(&format)
+                    .map_err(|e| anyhow::anyhow!(e))?;
+                commands::secrets_staged(path, output_format)?;
+            }
+        },
+        Commands::Hook { action } => match action {
+            HookAction::Install { path, force } => commands::hook_install(path, force)?,
+        },
     }
 
     Ok(())
@@ -1,6 +1,7 @@
 use colored::*;
 use fslint_core::ScannedFile;
-use fslint_plugin_api::{PluginResult, PluginStatus};
+use fslint_plugin_api::{Finding, PluginResult, PluginStatus, Severity};
+use fslint_plugin_secret_scanner::HistoryFinding;
 
 pub enum OutputFormat {
     Table,
@@ -30,6 +31,51 @@ pub fn format(files: &[ScannedFile], format: OutputFormat, working_dir: &std::pa
         }
     }
 
+    /// Secrets found in git history or staged changes
+    pub fn format_secrets(findings: &[HistoryFinding], format: OutputFormat) {
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(findings).unwrap()),
+            OutputFormat::Table => {
+                let mut last_commit = None;
+                for found in findings {
+                    if last_commit != Some(&found.commit) {
+                        let commit = found.commit.as_deref().unwrap_or("staged");
+                        println!("{} {}", commit.bold(), found.author.as_deref().unwrap_or("").dimmed());
+                        last_commit = Some(&found.commit);
+                    }
+                    let finding = &found.finding;
+                    let location = finding.span.map(|s| format!(":{}", s)).unwrap_or_default();
+                    println!(
+                        "    {} {}{} {} {}",
+                        Self::colorize_severity(finding.severity),
+                        found.path.display(),
+                        location,
+                        finding.message,
+                        format!("[{}]", finding.rule).dimmed()
+                    );
+                }
+                println!("\n{} secret(s) found", findings.len());
+            }
+            OutputFormat::Simple => {
+                for found in findings {
+                    let finding = &found.finding;
+                    let location = finding.span.map(|s| format!(":{}", s)).unwrap_or_default();
+                    let author = found.author.as_deref().map(|a| format!(" ({})", a)).unwrap_or_default();
+                    println!(
+                        "{} {}{}: {}: {} [{}]{}",
+                        found.commit.as_deref().unwrap_or("staged"),
+                        found.path.display(),
+                        location,
+                        finding.severity,
+                        finding.message,
+                        finding.rule,
+                        author
+                    );
+                }
+            }
+        }
+    }
+
     fn format_table(files: &[ScannedFile], working_dir: &std::path::Path) {
         if files.is_empty() {
             println!("{}", "No files found.".dimmed());
@@ -71,6 +117,20 @@ fn format_table(files: &[ScannedFile], working_dir: &std::path::Path) {
                 group_colored,
                 other_status
             );
+
+            for finding in Self::listed_findings(&file.results) {
+                let location = finding.span.map(|s| s.to_string()).unwrap_or_default();
+                println!(
+                    "    {} {:<8} {} {}",
+                    Self::colorize_severity(finding.severity),
+                    location,
+                    finding.message,
+                    format!("[{}]", finding.rule).dimmed()
+                );
+                if let Some(fix) = &finding.fix {
+                    println!("    {:<16} {}", "", format!("fix: {}", fix).dimmed());
+                }
+            }
         }
 
         println!("\n{} files scanned", files.len());
@@ -120,6 +180,36 @@ fn format_simple(files: &[ScannedFile], working_dir: &std::path::Path) {
             }
 
             println!();
+
+            // One grep-friendly line per finding
+            for finding in Self::listed_findings(&file.results) {
+                let location = finding.span.map(|s| format!(":{}", s)).unwrap_or_default();
+                println!(
+                    "{}{}: {}: {} [{}]",
+                    relative_path, location, finding.severity, finding.message, finding.rule
+                );
+            }
+        }
+    }
+
+    /// Findings worth listing under a file
+    ///
+    /// An unlocated finding that repeats its result's message, as produced
+    /// for plugins without findings of their own, adds nothing and is left out.
+    fn listed_findings(results: &[PluginResult]) -> impl Iterator<Item = &Finding> {
+        results.iter().flat_map(|result| {
+            result.findings.iter().filter(move |finding| {
+                finding.span.is_some() || result.message.as_deref() != Some(finding.message.as_str())
+            })
+        })
+    }
+
+    fn colorize_severity(severity: Severity) -> String {
+        let text = format!("{:<7}", severity);
+        match severity {
+            Severity::Error => text.red().to_string(),
+            Severity::Warning => text.yellow().to_string(),
+            Severity::Info => text.blue().to_string(),
         }
     }
 
@@ -187,4 +277,25 @@ fn test_output_format_from_str() {
         assert!(matches!(OutputFormat::from_str("simple"), Ok(OutputFormat::Simple)));
         assert!(OutputFormat::from_str("invalid").is_err());
     }
+
+    #[test]
+    fn test_listed_findings() {
+        use fslint_plugin_api::Span;
+
+        let results = vec![
+            PluginResult::warning("legacy", "Looks odd").upgrade(),
+            PluginResult::from_findings(
+                "scanner",
+                vec![
+                    Finding::new("located", Severity::Error, "Here").with_span(Span::line(3)),
+                    Finding::new("general", Severity::Info, "Whole file"),
+                ],
+            ),
+        ];
+
+        let rules: Vec<&str> = OutputFormatter::listed_findings(&results)
+            .map(|f| f.rule.as_str())
+            .collect();
+        assert_eq!(rules, ["located", "general"]);
+    }
 }
@@ -1,4 +1,5 @@
 use fslint_core::ScannedFile;
+use fslint_plugin_api::Severity;
 
 #[derive(Debug, Clone)]
 pub struct Query {
@@ -12,6 +13,8 @@ enum Filter {
     Newest(bool),
     Plugin(String, String), // plugin_name, expected_value
     Tag(String),
+    Rule(String),
+    Severity(Severity), // at least this severe
     SizeLt(u64),
     SizeGt(u64),
 }
@@ -27,6 +30,8 @@ pub fn parse(query_str: &str) -> Result<Self, String> {
                     "ext" => Filter::Extension(value.to_string()),
                     "newest" => Filter::Newest(value == "true"),
                     "tag" => Filter::Tag(value.to_string()),
+                    "rule" => Filter::Rule(value.to_string()),
+                    "severity" => Filter::Severity(value.parse()?),
                     "size_lt" => Filter::SizeLt(value.parse().map_err(|_| "Invalid size")?),
                     "size_gt" => Filter::SizeGt(value.parse().map_err(|_| "Invalid size")?),
                     plugin_name => {
@@ -89,11 +94,30 @@ fn matches(&self, file: &ScannedFile) -> bool {
                         return false;
                     }
                 }
+                Filter::Rule(rule) => {
+                    let has_rule = file.results.iter()
+                        .flat_map(|r| &r.findings)
+                        .any(|f| &f.rule == rule);
+                    if !has_rule {
+                        return false;
+                    }
+                }
+                Filter::Severity(severity) => {
+                    let severe = file.results.iter()
+                        .flat_map(|r| &r.findings)
+                        .any(|f| f.severity >= *severity);
+                    if !severe {
+                        return false;
+                    }
+                }
                 Filter::Plugin(plugin_name, expected_value) => {
+                    // The summary message, or the rule of one of its findings
                     let matches = file.results.iter()
                         .find(|r| &r.plugin_name == plugin_name)
-                        .and_then(|r| r.message.as_ref())
-                        .map(|msg| msg.contains(expected_value))
+                        .map(|r| {
+                            r.message.as_ref().is_some_and(|msg| msg.contains(expected_value))
+                                || r.findings.iter().any(|f| &f.rule == expected_value)
+                        })
                         .unwrap_or(false);
                     if !matches {
                         return false;
@@ -142,6 +166,13 @@ fn test_query_parse_size() {
         assert_eq!(query.filters.len(), 2);
     }
 
+    #[test]
+    fn test_query_parse_findings() {
+        let query = Query::parse("rule:aws-access-key severity:warning").unwrap();
+        assert!(matches!(query.filters[1], Filter::Severity(Severity::Warning)));
+        assert!(Query::parse("severity:fatal").is_err());
+    }
+
     #[test]
     fn test_query_parse_error() {
         assert!(Query::parse("invalid_query").is_err());
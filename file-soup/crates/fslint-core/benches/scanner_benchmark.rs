@@ -1,8 +1,41 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
-use fslint_core::{Config, PluginLoader, Scanner, ScannerConfig};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId, Throughput};
+use fslint_core::{PluginLoader, Scanner, ScannerConfig};
+use fslint_plugin_api::{Plugin, PluginContext, PluginError, PluginMetadata, PluginResult};
 use std::fs;
 use tempfile::TempDir;
 
+/// CPU-bound stand-in for real plugins: reads the file and checksums it
+struct ChecksumPlugin;
+
+impl Plugin for ChecksumPlugin {
+    fn metadata() -> PluginMetadata {
+        PluginMetadata {
+            name: "checksum".into(),
+            version: "0.1.0".into(),
+            description: "Benchmark plugin".into(),
+            author: None,
+            enabled_by_default: true,
+        }
+    }
+
+    fn check(&self, context: &PluginContext) -> Result<PluginResult, PluginError> {
+        let content = fs::read(&context.path)?;
+        let mut sum = 0u64;
+        for round in 0..2000u64 {
+            for byte in &content {
+                sum = sum.rotate_left(5) ^ (*byte as u64 + round);
+            }
+        }
+        Ok(PluginResult::active("checksum", format!("{:016x}", sum)))
+    }
+}
+
+fn checksum_loader() -> PluginLoader {
+    let mut loader = PluginLoader::new();
+    loader.register(ChecksumPlugin, ChecksumPlugin::metadata());
+    loader
+}
+
 fn create_test_files(dir: &std::path::Path, count: usize) {
     for i in 0..count {
         let file_path = dir.join(format!("test_{}.txt", i));
@@ -27,11 +60,10 @@ fn bench_scanner_small(c: &mut Criterion) {
     create_test_files(temp_dir.path(), 10);
 
     let config = ScannerConfig::default();
-    let loader = PluginLoader::new();
 
     c.bench_function("scan_10_files", |b| {
         b.iter(|| {
-            let mut scanner = Scanner::new(config.clone(), loader.clone());
+            let scanner = Scanner::new(config.clone(), PluginLoader::new());
             black_box(scanner.scan(temp_dir.path()).unwrap());
         });
     });
@@ -42,11 +74,10 @@ fn bench_scanner_medium(c: &mut Criterion) {
     create_test_files(temp_dir.path(), 100);
 
     let config = ScannerConfig::default();
-    let loader = PluginLoader::new();
 
     c.bench_function("scan_100_files", |b| {
         b.iter(|| {
-            let mut scanner = Scanner::new(config.clone(), loader.clone());
+            let scanner = Scanner::new(config.clone(), PluginLoader::new());
             black_box(scanner.scan(temp_dir.path()).unwrap());
         });
     });
@@ -57,11 +88,10 @@ fn bench_scanner_large(c: &mut Criterion) {
     create_test_files(temp_dir.path(), 1000);
 
     let config = ScannerConfig::default();
-    let loader = PluginLoader::new();
 
     c.bench_function("scan_1000_files", |b| {
         b.iter(|| {
-            let mut scanner = Scanner::new(config.clone(), loader.clone());
+            let scanner = Scanner::new(config.clone(), PluginLoader::new());
             black_box(scanner.scan(temp_dir.path()).unwrap());
         });
     });
@@ -72,11 +102,10 @@ fn bench_scanner_nested(c: &mut Criterion) {
     create_nested_structure(temp_dir.path(), 3, 10);
 
     let config = ScannerConfig::default();
-    let loader = PluginLoader::new();
 
     c.bench_function("scan_nested_structure", |b| {
         b.iter(|| {
-            let mut scanner = Scanner::new(config.clone(), loader.clone());
+            let scanner = Scanner::new(config.clone(), PluginLoader::new());
             black_box(scanner.scan(temp_dir.path()).unwrap());
         });
     });
@@ -88,7 +117,7 @@ fn bench_scanner_with_cache(c: &mut Criterion) {
 
     let config = ScannerConfig::default();
     let loader = PluginLoader::new();
-    let mut scanner = Scanner::new(config, loader);
+    let scanner = Scanner::new(config, loader);
 
     // First scan to populate cache
     scanner.scan(temp_dir.path()).unwrap();
@@ -104,16 +133,47 @@ fn bench_max_depth(c: &mut Criterion) {
     let temp_dir = TempDir::new().unwrap();
     create_nested_structure(temp_dir.path(), 5, 5);
 
-    let loader = PluginLoader::new();
-
     let mut group = c.benchmark_group("max_depth");
     for depth in [1, 3, 5, 10].iter() {
-        let mut config = ScannerConfig::default();
-        config.max_depth = Some(*depth);
+        let config = ScannerConfig {
+            max_depth: Some(*depth),
+            ..Default::default()
+        };
 
         group.bench_with_input(BenchmarkId::from_parameter(depth), depth, |b, _| {
             b.iter(|| {
-                let mut scanner = Scanner::new(config.clone(), loader.clone());
+                let scanner = Scanner::new(config.clone(), PluginLoader::new());
+                black_box(scanner.scan(temp_dir.path()).unwrap());
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_thread_scaling(c: &mut Criterion) {
+    let temp_dir = TempDir::new().unwrap();
+    create_nested_structure(temp_dir.path(), 3, 25);
+    let files = 25 * (1 + 3 + 9 + 27);
+
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut thread_counts: Vec<usize> = [1, 2, 4, 8, 16]
+        .into_iter()
+        .filter(|&n| n < cores)
+        .collect();
+    thread_counts.push(cores);
+
+    let mut group = c.benchmark_group("thread_scaling");
+    group.throughput(Throughput::Elements(files as u64));
+    for threads in thread_counts {
+        let config = ScannerConfig {
+            threads: Some(threads),
+            ..ScannerConfig::default()
+        };
+
+        group.bench_with_input(BenchmarkId::from_parameter(threads), &threads, |b, _| {
+            b.iter(|| {
+                // Fresh scanner each time so every file misses the cache
+                let scanner = Scanner::new(config.clone(), checksum_loader());
                 black_box(scanner.scan(temp_dir.path()).unwrap());
             });
         });
@@ -128,6 +188,7 @@ fn bench_max_depth(c: &mut Criterion) {
     bench_scanner_large,
     bench_scanner_nested,
     bench_scanner_with_cache,
-    bench_max_depth
+    bench_max_depth,
+    bench_thread_scaling
 );
 criterion_main!(benches);
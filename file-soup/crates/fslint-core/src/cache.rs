@@ -1,71 +1,383 @@
+use anyhow::{Context, Result};
 use fslint_plugin_api::PluginResult;
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
-/// Cache key: (path, modified_time, size)
-pub type CacheKey = (PathBuf, Option<SystemTime>, u64);
+/// State of a file a cached result was computed from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// Inode number, so a file replaced by another with the same size and
+    /// mtime still misses; 0 where the platform has none
+    pub inode: u64,
+}
+
+impl FileStamp {
+    /// Stamp for a file's current metadata
+    pub fn new(metadata: &Metadata) -> Self {
+        Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            inode: inode(metadata),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn inode(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &Metadata) -> u64 {
+    0
+}
+
+/// Plugin version and configuration a cached result was computed with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginStamp {
+    pub version: String,
+    /// Hash of the plugin's `[plugin_config]` section
+    pub config_hash: String,
+}
+
+impl PluginStamp {
+    /// Stamp for a plugin version and its configuration, if any
+    pub fn new(version: impl Into<String>, config: Option<&HashMap<String, String>>) -> Self {
+        // Sorted and length-prefixed, so the hash is stable across runs and
+        // toolchains and no two configurations share an encoding
+        let mut hasher = blake3::Hasher::new();
+        for (key, value) in config
+            .map(|c| c.iter().collect::<BTreeMap<_, _>>())
+            .unwrap_or_default()
+        {
+            for part in [key, value] {
+                hasher.update(&(part.len() as u64).to_le_bytes());
+                hasher.update(part.as_bytes());
+            }
+        }
+
+        Self {
+            version: version.into(),
+            config_hash: hasher.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+/// Cached result, stored under its plugin name and path
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    path: PathBuf,
+    plugin: String,
+    file: FileStamp,
+    plugin_stamp: PluginStamp,
+    result: PluginResult,
+}
+
+/// Overview of what the cache holds
+#[derive(Debug, Clone, Default)]
+pub struct CacheSummary {
+    /// Number of cached results
+    pub entries: usize,
+    /// Cached results per plugin
+    pub plugins: BTreeMap<String, usize>,
+    /// Space taken on disk, for a persistent cache
+    pub disk_bytes: Option<u64>,
+}
+
+/// How long [`ResultCache::open`] waits for the database lock
+const LOCK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Pause between attempts to take the database lock
+const LOCK_RETRY: Duration = Duration::from_millis(10);
+
+/// Whether sled failed because another handle holds the lock on `path`
+///
+/// sled reports a held lock as `ErrorKind::Other` and drops the OS error, so
+/// try the lock on its `db` file directly
+fn is_locked(path: &Path, error: &sled::Error) -> bool {
+    let sled::Error::Io(_) = error else {
+        return false;
+    };
+    fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path.join("db"))
+        .is_ok_and(|file| matches!(file.try_lock(), Err(fs::TryLockError::WouldBlock)))
+}
+
+/// Backing store for encoded entries
+enum Store {
+    Memory(RwLock<HashMap<Vec<u8>, Vec<u8>>>),
+    Disk(sled::Db),
+}
+
+impl Store {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        match self {
+            Store::Memory(map) => Ok(map.read().unwrap_or_else(|e| e.into_inner()).get(key).cloned()),
+            Store::Disk(db) => Ok(db.get(key)?.map(|v| v.to_vec())),
+        }
+    }
+
+    fn insert(&self, key: Vec<u8>, value: Vec<u8>) -> Result<()> {
+        match self {
+            Store::Memory(map) => {
+                map.write().unwrap_or_else(|e| e.into_inner()).insert(key, value);
+            }
+            Store::Disk(db) => {
+                db.insert(key, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<()> {
+        match self {
+            Store::Memory(map) => {
+                map.write().unwrap_or_else(|e| e.into_inner()).remove(key);
+            }
+            Store::Disk(db) => {
+                db.remove(key)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        match self {
+            Store::Memory(map) => map.write().unwrap_or_else(|e| e.into_inner()).clear(),
+            Store::Disk(db) => db.clear()?,
+        }
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Store::Memory(map) => map.read().unwrap_or_else(|e| e.into_inner()).len(),
+            Store::Disk(db) => db.len(),
+        }
+    }
+
+    fn for_each(&self, mut f: impl FnMut(&[u8], &[u8])) -> Result<()> {
+        match self {
+            Store::Memory(map) => {
+                for (key, value) in map.read().unwrap_or_else(|e| e.into_inner()).iter() {
+                    f(key, value);
+                }
+            }
+            Store::Disk(db) => {
+                for item in db.iter() {
+                    let (key, value) = item?;
+                    f(&key, &value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        if let Store::Disk(db) = self {
+            db.flush()?;
+        }
+        Ok(())
+    }
+}
 
 /// Result cache for plugin execution
+///
+/// Results are kept per file and plugin, and only reused while both the
+/// [`FileStamp`] and the [`PluginStamp`] still match. [`ResultCache::new`]
+/// keeps them in memory; [`ResultCache::open`] persists them across runs.
+/// Safe to share between scanner threads.
 pub struct ResultCache {
-    cache: HashMap<CacheKey, Vec<PluginResult>>,
-    hits: usize,
-    misses: usize,
+    store: Store,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
 }
 
 impl ResultCache {
-    /// Create a new result cache
+    /// Create a new in-memory result cache
     pub fn new() -> Self {
+        Self::with_store(Store::Memory(RwLock::new(HashMap::new())))
+    }
+
+    /// Open or create a persistent result cache at `path`
+    ///
+    /// sled locks the database for as long as any handle, including its
+    /// background flusher, is alive. A cache that was just dropped is
+    /// waited for briefly; one held by another process is an error.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        fs::create_dir_all(path)
+            .with_context(|| format!("Failed to create cache directory {:?}", path))?;
+
+        let started = Instant::now();
+        let db = loop {
+            match sled::open(path) {
+                Err(e) if is_locked(path, &e) && started.elapsed() < LOCK_TIMEOUT => {
+                    thread::sleep(LOCK_RETRY);
+                }
+                Err(e) if is_locked(path, &e) => {
+                    return Err(anyhow::Error::new(e).context(format!(
+                        "Result cache at {:?} is in use by another fslint process",
+                        path
+                    )));
+                }
+                result => {
+                    break result
+                        .with_context(|| format!("Failed to open result cache at {:?}", path))?
+                }
+            }
+        };
+        Ok(Self::with_store(Store::Disk(db)))
+    }
+
+    /// Get default persistent cache path, under the user cache directory
+    pub fn default_path() -> Result<PathBuf> {
+        let dirs = directories::ProjectDirs::from("", "", "fslint")
+            .context("Failed to determine cache directory")?;
+        Ok(dirs.cache_dir().join("results"))
+    }
+
+    fn with_store(store: Store) -> Self {
         Self {
-            cache: HashMap::new(),
-            hits: 0,
-            misses: 0,
+            store,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
         }
     }
 
-    /// Get cached results
-    pub fn get(&mut self, key: &CacheKey) -> Option<Vec<PluginResult>> {
-        match self.cache.get(key) {
-            Some(results) => {
-                self.hits += 1;
-                Some(results.clone())
+    /// Get a cached result that is still valid
+    ///
+    /// Unreadable entries count as misses.
+    pub fn get(
+        &self,
+        path: &Path,
+        plugin: &str,
+        file: &FileStamp,
+        plugin_stamp: &PluginStamp,
+    ) -> Option<PluginResult> {
+        let entry = self
+            .store
+            .get(&key(plugin, path))
+            .ok()
+            .flatten()
+            .and_then(|value| serde_json::from_slice::<Entry>(&value).ok())
+            .filter(|entry| entry.file == *file && entry.plugin_stamp == *plugin_stamp);
+
+        match entry {
+            Some(entry) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(entry.result)
             }
             None => {
-                self.misses += 1;
+                self.misses.fetch_add(1, Ordering::Relaxed);
                 None
             }
         }
     }
 
-    /// Insert results into cache
-    pub fn insert(&mut self, key: CacheKey, results: Vec<PluginResult>) {
-        self.cache.insert(key, results);
+    /// Insert a result into the cache, replacing any older one
+    pub fn insert(
+        &self,
+        path: &Path,
+        plugin: &str,
+        file: &FileStamp,
+        plugin_stamp: &PluginStamp,
+        result: &PluginResult,
+    ) -> Result<()> {
+        let entry = Entry {
+            path: path.to_path_buf(),
+            plugin: plugin.to_string(),
+            file: file.clone(),
+            plugin_stamp: plugin_stamp.clone(),
+            result: result.clone(),
+        };
+        let value = serde_json::to_vec(&entry).context("Failed to serialize cache entry")?;
+        self.store.insert(key(plugin, path), value)
     }
 
     /// Clear the cache
-    pub fn clear(&mut self) {
-        self.cache.clear();
-        self.hits = 0;
-        self.misses = 0;
+    pub fn clear(&self) -> Result<()> {
+        self.store.clear()?;
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Remove results that can never hit again
+    ///
+    /// That is results of plugins missing from `plugins` or stamped
+    /// differently there, and results for files that were deleted or have
+    /// changed. Returns how many were removed.
+    pub fn gc(&self, plugins: &HashMap<String, PluginStamp>) -> Result<usize> {
+        let mut stale = Vec::new();
+        self.store.for_each(|key, value| {
+            let live = serde_json::from_slice::<Entry>(value).is_ok_and(|entry| {
+                plugins.get(&entry.plugin) == Some(&entry.plugin_stamp)
+                    && fs::metadata(&entry.path).is_ok_and(|m| FileStamp::new(&m) == entry.file)
+            });
+            if !live {
+                stale.push(key.to_vec());
+            }
+        })?;
+
+        for key in &stale {
+            self.store.remove(key)?;
+        }
+        Ok(stale.len())
+    }
+
+    /// Write pending changes to disk
+    pub fn flush(&self) -> Result<()> {
+        self.store.flush().context("Failed to flush result cache")
+    }
+
+    /// Summarize cache contents
+    pub fn summary(&self) -> Result<CacheSummary> {
+        let mut summary = CacheSummary::default();
+        self.store.for_each(|_, value| {
+            summary.entries += 1;
+            if let Ok(entry) = serde_json::from_slice::<Entry>(value) {
+                *summary.plugins.entry(entry.plugin).or_default() += 1;
+            }
+        })?;
+        if let Store::Disk(db) = &self.store {
+            summary.disk_bytes = Some(db.size_on_disk()?);
+        }
+        Ok(summary)
     }
 
     /// Get cache statistics (hits, misses)
     pub fn stats(&self) -> (usize, usize) {
-        (self.hits, self.misses)
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
     }
 
     /// Get cache size
     pub fn size(&self) -> usize {
-        self.cache.len()
+        self.store.len()
     }
 
     /// Get hit rate
     pub fn hit_rate(&self) -> f64 {
-        let total = self.hits + self.misses;
+        let (hits, misses) = self.stats();
+        let total = hits + misses;
         if total == 0 {
             0.0
         } else {
-            self.hits as f64 / total as f64
+            hits as f64 / total as f64
         }
     }
 }
@@ -76,28 +388,37 @@ fn default() -> Self {
     }
 }
 
+/// Store key: plugin name, then path, so one plugin's entries sit together
+fn key(plugin: &str, path: &Path) -> Vec<u8> {
+    let mut key = Vec::with_capacity(plugin.len() + 1 + path.as_os_str().len());
+    key.extend_from_slice(plugin.as_bytes());
+    key.push(0);
+    key.extend_from_slice(path.as_os_str().as_encoded_bytes());
+    key
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use fslint_plugin_api::{PluginResult, PluginStatus};
+    use tempfile::TempDir;
+
+    fn stamps(dir: &TempDir) -> (PathBuf, FileStamp, PluginStamp) {
+        let path = dir.path().join("test.txt");
+        fs::write(&path, "content").unwrap();
+        let file = FileStamp::new(&fs::metadata(&path).unwrap());
+        (path, file, PluginStamp::new("0.1.0", None))
+    }
 
     #[test]
     fn test_cache_basic() {
-        let mut cache = ResultCache::new();
-        let key = (PathBuf::from("/test"), None, 0);
-
-        let result = PluginResult {
-            plugin_name: "test".into(),
-            status: PluginStatus::Active,
-            message: None,
-            color: None,
-            tags: vec![],
-            metadata: HashMap::new(),
-        };
+        let dir = TempDir::new().unwrap();
+        let (path, file, plugin) = stamps(&dir);
+        let cache = ResultCache::new();
+        let result = PluginResult::active("test", "message");
 
-        assert!(cache.get(&key).is_none());
-        cache.insert(key.clone(), vec![result.clone()]);
-        assert!(cache.get(&key).is_some());
+        assert!(cache.get(&path, "test", &file, &plugin).is_none());
+        cache.insert(&path, "test", &file, &plugin, &result).unwrap();
+        assert!(cache.get(&path, "test", &file, &plugin).is_some());
 
         let (hits, misses) = cache.stats();
         assert_eq!(hits, 1);
@@ -106,23 +427,95 @@ fn test_cache_basic() {
 
     #[test]
     fn test_cache_hit_rate() {
-        let mut cache = ResultCache::new();
-        let key = (PathBuf::from("/test"), None, 0);
-
-        let result = PluginResult {
-            plugin_name: "test".into(),
-            status: PluginStatus::Active,
-            message: None,
-            color: None,
-            tags: vec![],
-            metadata: HashMap::new(),
-        };
+        let dir = TempDir::new().unwrap();
+        let (path, file, plugin) = stamps(&dir);
+        let cache = ResultCache::new();
 
-        cache.insert(key.clone(), vec![result]);
-        cache.get(&key); // hit
-        cache.get(&key); // hit
-        cache.get(&(PathBuf::from("/other"), None, 0)); // miss
+        cache.insert(&path, "test", &file, &plugin, &PluginResult::inactive("test")).unwrap();
+        cache.get(&path, "test", &file, &plugin); // hit
+        cache.get(&path, "test", &file, &plugin); // hit
+        cache.get(Path::new("/other"), "test", &file, &plugin); // miss
 
         assert_eq!(cache.hit_rate(), 2.0 / 3.0);
     }
+
+    #[test]
+    fn test_cache_invalidation() {
+        let dir = TempDir::new().unwrap();
+        let (path, file, plugin) = stamps(&dir);
+        let cache = ResultCache::new();
+        cache.insert(&path, "test", &file, &plugin, &PluginResult::inactive("test")).unwrap();
+
+        let grown = FileStamp { size: file.size + 1, ..file.clone() };
+        assert!(cache.get(&path, "test", &grown, &plugin).is_none());
+
+        let upgraded = PluginStamp::new("0.2.0", None);
+        assert!(cache.get(&path, "test", &file, &upgraded).is_none());
+
+        let config = HashMap::from([("threshold".to_string(), "7".to_string())]);
+        let configured = PluginStamp::new("0.1.0", Some(&config));
+        assert!(cache.get(&path, "test", &file, &configured).is_none());
+
+        assert!(cache.get(&path, "test", &file, &plugin).is_some());
+    }
+
+    #[test]
+    fn test_plugin_stamp_is_stable() {
+        // Stamps are persisted, so the hash must not change between builds
+        let config = HashMap::from([
+            ("threshold".to_string(), "7".to_string()),
+            ("mode".to_string(), "strict".to_string()),
+        ]);
+        let stamp = PluginStamp::new("0.1.0", Some(&config));
+        assert_eq!(stamp.config_hash, "814deaee73263c7c43822a4cbaa23e42b0bb121a1fc0c7fc485cd6a0ad064ebb");
+
+        let split = HashMap::from([("ab".to_string(), "c".to_string())]);
+        let moved = HashMap::from([("a".to_string(), "bc".to_string())]);
+        assert_ne!(
+            PluginStamp::new("0.1.0", Some(&split)),
+            PluginStamp::new("0.1.0", Some(&moved))
+        );
+    }
+
+    #[test]
+    fn test_cache_persistent_and_gc() {
+        let dir = TempDir::new().unwrap();
+        let (path, file, plugin) = stamps(&dir);
+        let db = dir.path().join("cache");
+        let plugins = HashMap::from([("test".to_string(), plugin.clone())]);
+
+        {
+            let cache = ResultCache::open(&db).unwrap();
+            cache.insert(&path, "test", &file, &plugin, &PluginResult::inactive("test")).unwrap();
+            cache.insert(&path, "gone", &file, &plugin, &PluginResult::inactive("gone")).unwrap();
+            cache.flush().unwrap();
+        }
+
+        let cache = ResultCache::open(&db).unwrap();
+        assert!(cache.get(&path, "test", &file, &plugin).is_some());
+        assert_eq!(cache.summary().unwrap().entries, 2);
+
+        // Plugin no longer registered
+        assert_eq!(cache.gc(&plugins).unwrap(), 1);
+        // File deleted
+        fs::remove_file(&path).unwrap();
+        assert_eq!(cache.gc(&plugins).unwrap(), 1);
+        assert_eq!(cache.size(), 0);
+    }
+
+    #[test]
+    fn test_cache_open_waits_for_lock() {
+        let dir = TempDir::new().unwrap();
+        let db = dir.path().join("cache");
+
+        // Reopening right after a drop races sled's flusher for the lock
+        for _ in 0..20 {
+            drop(ResultCache::open(&db).unwrap());
+        }
+
+        let held = ResultCache::open(&db).unwrap();
+        let err = ResultCache::open(&db).err().unwrap();
+        assert!(format!("{:#}", err).contains("in use by another fslint process"), "{:#}", err);
+        drop(held);
+    }
 }
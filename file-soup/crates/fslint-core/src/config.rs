@@ -13,6 +13,9 @@ pub struct Config {
     pub plugin_config: HashMap<String, HashMap<String, String>>,
     /// Scanner configuration
     pub scanner: ScannerConfig,
+    /// Directory with third-party WebAssembly plugins
+    #[serde(default)]
+    pub plugin_dir: Option<PathBuf>,
 }
 
 /// Scanner configuration
@@ -28,6 +31,9 @@ pub struct ScannerConfig {
     pub respect_gitignore: bool,
     /// Maximum number of files to scan
     pub max_files: Option<usize>,
+    /// Worker threads for walking and running plugins (default: one per core)
+    #[serde(default)]
+    pub threads: Option<usize>,
 }
 
 impl Default for Config {
@@ -40,6 +46,7 @@ fn default() -> Self {
             ],
             plugin_config: HashMap::new(),
             scanner: ScannerConfig::default(),
+            plugin_dir: None,
         }
     }
 }
@@ -52,6 +59,7 @@ fn default() -> Self {
             follow_symlinks: false,
             respect_gitignore: true,
             max_files: None,
+            threads: None,
         }
     }
 }
@@ -64,6 +72,17 @@ pub fn config_path() -> Result<PathBuf> {
         Ok(dirs.config_dir().join("config.toml"))
     }
 
+    /// Get third-party plugin directory, `plugins` in the user data directory
+    /// unless configured
+    pub fn plugin_dir_path(&self) -> Result<PathBuf> {
+        if let Some(dir) = &self.plugin_dir {
+            return Ok(dir.clone());
+        }
+        let dirs = directories::ProjectDirs::from("", "", "fslint")
+            .context("Failed to determine data directory")?;
+        Ok(dirs.data_dir().join("plugins"))
+    }
+
     /// Load configuration from file
     pub fn load() -> Result<Self> {
         let path = Self::config_path()?;
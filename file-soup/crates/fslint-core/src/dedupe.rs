@@ -0,0 +1,448 @@
+use anyhow::{bail, Context, Result};
+use filetime::FileTime;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+/// Files with identical content
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    /// BLAKE3 hash of the content
+    pub hash: String,
+    pub size: u64,
+    /// Copies, sorted by path
+    pub files: Vec<PathBuf>,
+}
+
+impl DuplicateGroup {
+    /// Bytes taken up by all but one copy
+    pub fn wasted_bytes(&self) -> u64 {
+        self.size * self.files.len().saturating_sub(1) as u64
+    }
+}
+
+/// Which copy of a group survives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keep {
+    /// First by path
+    First,
+    /// Least recently modified
+    Oldest,
+    /// Most recently modified
+    Newest,
+}
+
+impl FromStr for Keep {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "first" => Ok(Self::First),
+            "oldest" => Ok(Self::Oldest),
+            "newest" => Ok(Self::Newest),
+            _ => Err(format!("Unknown keeper: {} (expected first, oldest or newest)", s)),
+        }
+    }
+}
+
+/// What happens to the other copies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DedupeAction {
+    /// Replace with a hard link to the keeper
+    Hardlink,
+    /// Replace with a copy-on-write clone of the keeper
+    Reflink,
+    /// Delete
+    Delete,
+}
+
+impl DedupeAction {
+    /// Past tense, for reports
+    pub fn done(&self) -> &'static str {
+        match self {
+            Self::Hardlink => "Hardlinked",
+            Self::Reflink => "Reflinked",
+            Self::Delete => "Deleted",
+        }
+    }
+}
+
+impl FromStr for DedupeAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hardlink" => Ok(Self::Hardlink),
+            "reflink" => Ok(Self::Reflink),
+            "delete" => Ok(Self::Delete),
+            _ => Err(format!("Unknown dedupe action: {} (expected hardlink, reflink or delete)", s)),
+        }
+    }
+}
+
+/// The keeper chosen for a group and the copies to act on
+#[derive(Debug, Clone)]
+pub struct DedupePlan {
+    pub hash: String,
+    pub size: u64,
+    pub keeper: PathBuf,
+    pub duplicates: Vec<PathBuf>,
+}
+
+/// One replaced or deleted copy, a line of the undo journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub action: DedupeAction,
+    pub keeper: PathBuf,
+    pub duplicate: PathBuf,
+    /// Content hash, checked against the keeper before undoing
+    pub hash: String,
+    /// Modification time and permissions of the copy, restored on undo
+    pub modified: Option<SystemTime>,
+    pub readonly: bool,
+}
+
+/// Outcome of [`apply`] or [`undo`]
+#[derive(Debug, Default)]
+pub struct DedupeReport {
+    /// Copies acted on
+    pub files: usize,
+    /// Bytes freed, or taken up again by an undo
+    pub bytes: u64,
+    /// Copies left alone, with the reason
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Choose a keeper for each group; paths already hard linked to the
+/// keeper are not duplicates
+pub fn plan(groups: &[DuplicateGroup], keep: Keep) -> Vec<DedupePlan> {
+    groups
+        .iter()
+        .filter(|group| group.files.len() > 1)
+        .map(|group| {
+            let modified = |path: &PathBuf| fs::metadata(path).and_then(|m| m.modified()).ok();
+            let keeper = match keep {
+                Keep::First => group.files.iter().min(),
+                // Unreadable times sort first, so they are never kept as newest
+                Keep::Oldest => group.files.iter().min_by_key(|p| (modified(p).is_none(), modified(p))),
+                Keep::Newest => group.files.iter().max_by_key(|p| modified(p)),
+            }
+            .cloned()
+            .unwrap_or_default();
+            let keeper_id = file_id(&keeper);
+
+            DedupePlan {
+                hash: group.hash.clone(),
+                size: group.size,
+                duplicates: group
+                    .files
+                    .iter()
+                    .filter(|p| **p != keeper && (keeper_id.is_none() || file_id(p) != keeper_id))
+                    .cloned()
+                    .collect(),
+                keeper,
+            }
+        })
+        .collect()
+}
+
+/// Device and inode of a file, where the platform has them
+#[cfg(unix)]
+fn file_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|m| (m.dev(), m.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+/// Directory holding undo journals
+pub fn journal_dir() -> Result<PathBuf> {
+    let dirs = directories::ProjectDirs::from("", "", "fslint")
+        .context("Failed to determine data directory")?;
+    Ok(dirs.data_dir().join("dedupe"))
+}
+
+/// Path for a new undo journal, named after the current time
+pub fn new_journal_path() -> Result<PathBuf> {
+    let name = chrono::Local::now().format("%Y%m%d-%H%M%S%.3f");
+    Ok(journal_dir()?.join(format!("{}.jsonl", name)))
+}
+
+/// Carry out `plans`, recording every copy in the journal at `journal`
+///
+/// Every file is hashed again first and copies that no longer match their
+/// keeper are left alone. Journal entries are written before the change
+/// they describe, so an interrupted run can still be undone.
+pub fn apply(plans: &[DedupePlan], action: DedupeAction, journal: &Path) -> Result<DedupeReport> {
+    if let Some(dir) = journal.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    }
+    let mut journal_file = OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .open(journal)
+        .with_context(|| format!("Failed to create undo journal {:?}", journal))?;
+
+    let mut report = DedupeReport::default();
+    for plan in plans {
+        match hash_file(&plan.keeper) {
+            Ok(hash) if hash == plan.hash => {}
+            Ok(_) => {
+                report.failed.push((plan.keeper.clone(), "Changed since the scan".to_string()));
+                continue;
+            }
+            Err(e) => {
+                report.failed.push((plan.keeper.clone(), e.to_string()));
+                continue;
+            }
+        }
+
+        for duplicate in &plan.duplicates {
+            let entry = match journal_entry(action, plan, duplicate) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    report.failed.push((duplicate.clone(), e));
+                    continue;
+                }
+            };
+
+            serde_json::to_writer(&mut journal_file, &entry)?;
+            writeln!(journal_file)?;
+            journal_file.sync_data()?;
+
+            match replace(action, &plan.keeper, duplicate) {
+                Ok(()) => {
+                    report.files += 1;
+                    report.bytes += plan.size;
+                }
+                Err(e) => report.failed.push((duplicate.clone(), e.to_string())),
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Restore every copy recorded in `journal` from its keeper
+///
+/// Copies come back as separate files with their old modification time
+/// and permissions. The journal is removed once everything is restored.
+pub fn undo(journal: &Path) -> Result<DedupeReport> {
+    let file = File::open(journal)
+        .with_context(|| format!("Failed to open undo journal {:?}", journal))?;
+    let mut entries = Vec::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: JournalEntry = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid undo journal {:?}, line {}", journal, number + 1))?;
+        entries.push(entry);
+    }
+
+    let mut report = DedupeReport::default();
+    for entry in entries.iter().rev() {
+        match restore(entry) {
+            Ok(size) => {
+                report.files += 1;
+                report.bytes += size;
+            }
+            Err(e) => report.failed.push((entry.duplicate.clone(), e.to_string())),
+        }
+    }
+
+    if report.failed.is_empty() {
+        fs::remove_file(journal)
+            .with_context(|| format!("Failed to remove undo journal {:?}", journal))?;
+    }
+    Ok(report)
+}
+
+/// Check that `duplicate` still matches its keeper and record how it looks
+fn journal_entry(action: DedupeAction, plan: &DedupePlan, duplicate: &Path) -> Result<JournalEntry, String> {
+    let hash = hash_file(duplicate).map_err(|e| e.to_string())?;
+    if hash != plan.hash {
+        return Err("Changed since the scan".to_string());
+    }
+    let metadata = fs::metadata(duplicate).map_err(|e| e.to_string())?;
+
+    Ok(JournalEntry {
+        action,
+        keeper: plan.keeper.clone(),
+        duplicate: duplicate.to_path_buf(),
+        hash,
+        modified: metadata.modified().ok(),
+        readonly: metadata.permissions().readonly(),
+    })
+}
+
+/// Replace `duplicate` according to `action`, atomically where possible
+fn replace(action: DedupeAction, keeper: &Path, duplicate: &Path) -> io::Result<()> {
+    let temp = temp_path(duplicate);
+    match action {
+        DedupeAction::Hardlink => fs::hard_link(keeper, &temp)?,
+        DedupeAction::Reflink => reflink_copy::reflink(keeper, &temp)?,
+        DedupeAction::Delete => return fs::remove_file(duplicate),
+    }
+
+    fs::rename(&temp, duplicate).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+/// Recreate the copy in `entry` from its keeper; returns its size
+fn restore(entry: &JournalEntry) -> Result<u64> {
+    if hash_file(&entry.keeper)? != entry.hash {
+        bail!("Keeper {:?} has changed", entry.keeper);
+    }
+
+    // Copying breaks any link to the keeper
+    let temp = temp_path(&entry.duplicate);
+    let result = (|| {
+        let size = fs::copy(&entry.keeper, &temp)?;
+        if let Some(modified) = entry.modified {
+            filetime::set_file_mtime(&temp, FileTime::from_system_time(modified))?;
+        }
+        let mut permissions = fs::metadata(&temp)?.permissions();
+        permissions.set_readonly(entry.readonly);
+        fs::set_permissions(&temp, permissions)?;
+        fs::rename(&temp, &entry.duplicate)?;
+        Ok::<_, io::Error>(size)
+    })();
+
+    result.map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        e.into()
+    })
+}
+
+/// Hidden file next to `path`, to be renamed over it
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!(".{}.fslint-dedupe", name))
+}
+
+/// BLAKE3 of a whole file, as the duplicate finder computes it
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn group(dir: &Path, names: &[&str], content: &str) -> DuplicateGroup {
+        let files: Vec<PathBuf> = names.iter().map(|name| dir.join(name)).collect();
+        for file in &files {
+            fs::write(file, content).unwrap();
+        }
+        DuplicateGroup {
+            hash: blake3::hash(content.as_bytes()).to_hex().to_string(),
+            size: content.len() as u64,
+            files,
+        }
+    }
+
+    #[test]
+    fn test_plan_keeper() {
+        let dir = TempDir::new().unwrap();
+        let group = group(dir.path(), &["a", "b", "c"], "same");
+        let old = SystemTime::now() - Duration::from_secs(3600);
+        filetime::set_file_mtime(&group.files[1], FileTime::from_system_time(old)).unwrap();
+        assert_eq!(group.wasted_bytes(), 8);
+
+        let plans = plan(std::slice::from_ref(&group), Keep::First);
+        assert_eq!(plans[0].keeper, group.files[0]);
+        assert_eq!(plans[0].duplicates, [group.files[1].clone(), group.files[2].clone()]);
+
+        assert_eq!(plan(std::slice::from_ref(&group), Keep::Oldest)[0].keeper, group.files[1]);
+        assert_ne!(plan(std::slice::from_ref(&group), Keep::Newest)[0].keeper, group.files[1]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hardlink_and_undo() {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = TempDir::new().unwrap();
+        let group = group(dir.path(), &["a", "b"], "same");
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        filetime::set_file_mtime(&group.files[1], FileTime::from_system_time(old)).unwrap();
+        let journal = dir.path().join("journal/run.jsonl");
+
+        let plans = plan(std::slice::from_ref(&group), Keep::First);
+        let report = apply(&plans, DedupeAction::Hardlink, &journal).unwrap();
+        assert_eq!((report.files, report.bytes), (1, 4));
+        let inode = |path: &Path| fs::metadata(path).unwrap().ino();
+        assert_eq!(inode(&group.files[0]), inode(&group.files[1]));
+
+        let report = undo(&journal).unwrap();
+        assert_eq!(report.files, 1);
+        assert!(report.failed.is_empty());
+        assert_ne!(inode(&group.files[0]), inode(&group.files[1]));
+        assert_eq!(fs::metadata(&group.files[1]).unwrap().modified().unwrap(), old);
+        assert!(!journal.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plan_skips_existing_links() {
+        let dir = TempDir::new().unwrap();
+        let mut group = group(dir.path(), &["a", "c"], "same");
+        let link = dir.path().join("b");
+        fs::hard_link(&group.files[0], &link).unwrap();
+        group.files.insert(1, link);
+
+        let plans = plan(std::slice::from_ref(&group), Keep::First);
+        assert_eq!(plans[0].keeper, group.files[0]);
+        assert_eq!(plans[0].duplicates, [group.files[2].clone()]);
+    }
+
+    #[test]
+    fn test_delete_and_undo() {
+        let dir = TempDir::new().unwrap();
+        let group = group(dir.path(), &["a", "b", "c"], "same");
+        let journal = dir.path().join("run.jsonl");
+
+        let plans = plan(std::slice::from_ref(&group), Keep::First);
+        let report = apply(&plans, DedupeAction::Delete, &journal).unwrap();
+        assert_eq!(report.files, 2);
+        assert!(!group.files[1].exists());
+        assert!(!group.files[2].exists());
+
+        undo(&journal).unwrap();
+        for file in &group.files {
+            assert_eq!(fs::read_to_string(file).unwrap(), "same");
+        }
+    }
+
+    #[test]
+    fn test_changed_copy_left_alone() {
+        let dir = TempDir::new().unwrap();
+        let group = group(dir.path(), &["a", "b", "c"], "same");
+        fs::write(&group.files[2], "edited").unwrap();
+        let journal = dir.path().join("run.jsonl");
+
+        let plans = plan(std::slice::from_ref(&group), Keep::First);
+        let report = apply(&plans, DedupeAction::Delete, &journal).unwrap();
+        assert_eq!(report.files, 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, group.files[2]);
+        assert_eq!(fs::read_to_string(&group.files[2]).unwrap(), "edited");
+
+        // Journals are never overwritten
+        assert!(apply(&plans, DedupeAction::Delete, &journal).is_err());
+    }
+}
@@ -1,11 +1,13 @@
 pub mod cache;
 pub mod config;
+pub mod dedupe;
 pub mod plugin_loader;
 pub mod scanner;
 pub mod safety;
 
-pub use cache::ResultCache;
+pub use cache::{CacheSummary, FileStamp, PluginStamp, ResultCache};
 pub use config::{Config, ScannerConfig};
+pub use dedupe::{DedupeAction, DedupePlan, DedupeReport, DuplicateGroup, Keep};
 pub use plugin_loader::PluginLoader;
 pub use scanner::{Scanner, ScannedFile};
 pub use safety::SafetyChecker;
@@ -1,10 +1,17 @@
 use anyhow::Result;
-use fslint_plugin_api::{Plugin, PluginContext, PluginResult};
+use fslint_plugin_api::{Plugin, PluginContext, PluginMetadata, PluginResult, ScanState};
+use rayon::prelude::*;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::cache::PluginStamp;
 
 /// Plugin loader that manages all plugins
 pub struct PluginLoader {
     plugins: HashMap<String, Box<dyn Plugin>>,
+    metadata: HashMap<String, PluginMetadata>,
+    /// Stamps of cacheable plugins, for the result cache
+    stamps: HashMap<String, PluginStamp>,
     enabled_plugins: Vec<String>,
 }
 
@@ -13,19 +20,29 @@ impl PluginLoader {
     pub fn new() -> Self {
         Self {
             plugins: HashMap::new(),
+            metadata: HashMap::new(),
+            stamps: HashMap::new(),
             enabled_plugins: Vec::new(),
         }
     }
 
     /// Register a plugin
-    pub fn register<P: Plugin + 'static>(&mut self, plugin: P, metadata: fslint_plugin_api::PluginMetadata) {
+    pub fn register<P: Plugin + 'static>(&mut self, plugin: P, metadata: PluginMetadata) {
         let name = metadata.name.clone();
+        let enabled = metadata.enabled_by_default;
         self.plugins.insert(name.clone(), Box::new(plugin));
-        if metadata.enabled_by_default {
+        self.metadata.insert(name.clone(), metadata);
+        self.restamp(&name, None);
+        if enabled {
             self.enabled_plugins.push(name);
         }
     }
 
+    /// Get metadata of a registered plugin
+    pub fn metadata(&self, name: &str) -> Option<&PluginMetadata> {
+        self.metadata.get(name)
+    }
+
     /// Enable a plugin
     pub fn enable(&mut self, name: impl Into<String>) {
         let name = name.into();
@@ -55,22 +72,95 @@ pub fn list_enabled(&self) -> Vec<String> {
     }
 
     /// Run all enabled plugins on a context
+    ///
+    /// Each plugin is its own rayon task, so a slow plugin on one file does
+    /// not hold up the others. Results keep the enabled-plugin order.
     pub fn run_plugins(&self, context: &PluginContext) -> Result<Vec<PluginResult>> {
-        let mut results = Vec::new();
+        let results = self
+            .enabled_plugins
+            .par_iter()
+            .filter_map(|plugin_name| self.run_plugin(plugin_name, context))
+            .collect();
+
+        Ok(results)
+    }
 
-        for plugin_name in &self.enabled_plugins {
-            if let Some(plugin) = self.plugins.get(plugin_name) {
-                match plugin.check(context) {
-                    Ok(result) => results.push(result),
+    /// Run one plugin on a context
+    ///
+    /// Returns `None` if the plugin is not registered or fails; failures are
+    /// logged but don't fail the whole scan. Results of plugins that report
+    /// no findings are upgraded with [`PluginResult::upgrade`].
+    pub fn run_plugin(&self, name: &str, context: &PluginContext) -> Option<PluginResult> {
+        let plugin = self.plugins.get(name)?;
+        match plugin.check(context) {
+            Ok(result) => Some(result.upgrade()),
+            Err(e) => {
+                eprintln!("Warning: Plugin '{}' error: {}", name, e);
+                None
+            }
+        }
+    }
+
+    /// Start a scan of `root` for all enabled plugins
+    ///
+    /// Returns the state of each plugin that keeps any. A plugin that fails
+    /// to start is logged and checks files without state.
+    pub fn begin_scan(&self, root: &Path) -> HashMap<String, ScanState> {
+        self.enabled_plugins
+            .iter()
+            .filter_map(|name| {
+                let plugin = self.plugins.get(name)?;
+                match plugin.begin_scan(root) {
+                    Ok(state) => state.map(|state| (name.clone(), state)),
                     Err(e) => {
-                        // Log error but don't fail the whole scan
-                        eprintln!("Warning: Plugin '{}' error: {}", plugin_name, e);
+                        eprintln!("Warning: Plugin '{}' error: {}", name, e);
+                        None
                     }
                 }
+            })
+            .collect()
+    }
+
+    /// Finish a scan for one plugin
+    ///
+    /// Failures are logged and leave the results of `check` in place.
+    pub fn finish_scan(&self, name: &str, state: &ScanState) -> HashMap<PathBuf, PluginResult> {
+        let Some(plugin) = self.plugins.get(name) else {
+            return HashMap::new();
+        };
+        match plugin.finish_scan(state) {
+            Ok(results) => results
+                .into_iter()
+                .map(|(path, result)| (path, result.upgrade()))
+                .collect(),
+            Err(e) => {
+                eprintln!("Warning: Plugin '{}' error: {}", name, e);
+                HashMap::new()
             }
         }
+    }
 
-        Ok(results)
+    /// Get the cache stamp of a plugin, if its results may be cached
+    pub fn cache_stamp(&self, name: &str) -> Option<&PluginStamp> {
+        self.stamps.get(name)
+    }
+
+    /// Get cache stamps of all cacheable plugins
+    pub fn cache_stamps(&self) -> &HashMap<String, PluginStamp> {
+        &self.stamps
+    }
+
+    fn restamp(&mut self, name: &str, config: Option<&HashMap<String, String>>) {
+        let cacheable = self.plugins.get(name).is_some_and(|p| p.cacheable());
+        match self.metadata.get(name) {
+            Some(metadata) if cacheable => {
+                let stamp = PluginStamp::new(metadata.version.clone(), config);
+                self.stamps.insert(name.to_string(), stamp);
+            }
+            _ => {
+                self.stamps.remove(name);
+            }
+        }
     }
 
     /// Initialize all plugins with configuration
@@ -80,6 +170,12 @@ pub fn initialize_all(&mut self, configs: &HashMap<String, HashMap<String, Strin
                 plugin.initialize(config)?;
             }
         }
+
+        // Cached results are only valid for the configuration they ran with
+        let names: Vec<String> = self.plugins.keys().cloned().collect();
+        for name in names {
+            self.restamp(&name, configs.get(&name));
+        }
         Ok(())
     }
 
@@ -141,4 +237,18 @@ fn test_plugin_enable_disable() {
         loader.disable("test");
         assert!(!loader.is_enabled("test"));
     }
+
+    #[test]
+    fn test_cache_stamp_follows_config() {
+        let mut loader = PluginLoader::new();
+        loader.register(TestPlugin, TestPlugin::metadata());
+        let before = loader.cache_stamp("test").cloned().unwrap();
+
+        let config = HashMap::from([("key".to_string(), "value".to_string())]);
+        loader.initialize_all(&HashMap::from([("test".to_string(), config)])).unwrap();
+        let after = loader.cache_stamp("test").unwrap();
+
+        assert_eq!(after.version, "0.1.0");
+        assert_ne!(after.config_hash, before.config_hash);
+    }
 }
@@ -1,15 +1,19 @@
 use anyhow::{Context, Result};
-use fslint_plugin_api::{PluginContext, PluginResult};
+use fslint_plugin_api::{PluginContext, PluginResult, ScanState};
+use ignore::{WalkBuilder, WalkState};
+use rayon::prelude::*;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
-use ignore::WalkBuilder;
+use std::sync::mpsc;
 
 use crate::config::ScannerConfig;
 use crate::plugin_loader::PluginLoader;
-use crate::cache::ResultCache;
+use crate::cache::{FileStamp, ResultCache};
 
 /// File scanner that walks directories and applies plugins
+///
+/// Directories are walked with `ignore`'s parallel walker and files are
+/// checked on a rayon pool, both sized by `ScannerConfig::threads`.
 pub struct Scanner {
     config: ScannerConfig,
     plugin_loader: PluginLoader,
@@ -31,142 +35,191 @@ pub struct ScannedFile {
 }
 
 impl Scanner {
-    /// Create a new scanner
+    /// Create a new scanner with an in-memory cache
     pub fn new(config: ScannerConfig, plugin_loader: PluginLoader) -> Self {
+        Self::with_cache(config, plugin_loader, ResultCache::new())
+    }
+
+    /// Create a new scanner using `cache`, such as a persistent one
+    pub fn with_cache(config: ScannerConfig, plugin_loader: PluginLoader, cache: ResultCache) -> Self {
         Self {
             config,
             plugin_loader,
-            cache: ResultCache::new(),
+            cache,
         }
     }
 
     /// Scan a directory
-    pub fn scan<P: AsRef<Path>>(&mut self, path: P) -> Result<Vec<ScannedFile>> {
+    ///
+    /// Files come back sorted by path, however the work was scheduled.
+    /// Plugins get `begin_scan` before the first file and `finish_scan`
+    /// after the last, with state that lives only as long as this scan.
+    pub fn scan<P: AsRef<Path>>(&self, path: P) -> Result<Vec<ScannedFile>> {
         let path = path.as_ref();
         let working_dir = path.canonicalize()
             .with_context(|| format!("Failed to canonicalize path: {:?}", path))?;
 
-        let mut scanned_files = Vec::new();
-        let entries = self.collect_entries(&working_dir)?;
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.config.threads.unwrap_or(0))
+            .build()
+            .context("Failed to start scanner thread pool")?;
 
-        for entry in entries {
-            match self.scan_entry(&entry, &working_dir) {
-                Ok(scanned) => scanned_files.push(scanned),
-                Err(e) => {
-                    eprintln!("Warning: Failed to scan {:?}: {}", entry.path, e);
-                }
-            }
+        let entries = self.collect_entries(&working_dir);
+        let states = self.plugin_loader.begin_scan(&working_dir);
 
-            // Check max_files limit
-            if let Some(max) = self.config.max_files {
-                if scanned_files.len() >= max {
-                    eprintln!("Warning: Reached max files limit ({})", max);
-                    break;
-                }
-            }
-        }
+        let mut scanned_files: Vec<ScannedFile> = pool.install(|| {
+            entries
+                .par_iter()
+                .filter_map(|entry| match self.scan_entry(entry, &working_dir, &states) {
+                    Ok(scanned) => Some(scanned),
+                    Err(e) => {
+                        eprintln!("Warning: Failed to scan {:?}: {}", entry.path, e);
+                        None
+                    }
+                })
+                .collect()
+        });
+        // On the same pool, for plugins that finish in parallel
+        pool.install(|| self.finish_scan(&mut scanned_files, &states));
+        self.cache.flush()?;
 
         Ok(scanned_files)
     }
 
-    /// Collect directory entries based on configuration
-    fn collect_entries(&self, path: &Path) -> Result<Vec<FileEntry>> {
-        let mut entries = Vec::new();
+    /// Merge what plugins report once the whole tree has been checked
+    ///
+    /// Final results of cacheable plugins with scan state are cached here,
+    /// to be offered back as `PluginContext::previous` on the next scan.
+    fn finish_scan(&self, files: &mut [ScannedFile], states: &HashMap<String, ScanState>) {
+        // Enabled order, so results stay in the same order on every run
+        for name in self.plugin_loader.list_enabled() {
+            let Some(state) = states.get(&name) else {
+                continue;
+            };
 
-        if self.config.respect_gitignore {
-            // Use ignore crate for .gitignore support
-            let mut builder = WalkBuilder::new(path);
-            builder
-                .hidden(!self.config.include_hidden)
-                .follow_links(self.config.follow_symlinks);
+            let mut late = self.plugin_loader.finish_scan(&name, state);
+            let stamp = self.plugin_loader.cache_stamp(&name);
 
-            if let Some(depth) = self.config.max_depth {
-                builder.max_depth(Some(depth));
-            }
+            for file in files.iter_mut() {
+                if let Some(result) = late.remove(&file.path) {
+                    match file.results.iter_mut().find(|r| r.plugin_name == result.plugin_name) {
+                        Some(existing) => *existing = result,
+                        None => file.results.push(result),
+                    }
+                }
 
-            for result in builder.build() {
-                match result {
-                    Ok(entry) => {
-                        if entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
-                            if let Ok(metadata) = entry.metadata() {
-                                entries.push(FileEntry {
-                                    path: entry.path().to_path_buf(),
-                                    metadata,
-                                });
-                            }
-                        }
+                let Some(stamp) = stamp else {
+                    continue;
+                };
+                if let Some(result) = file.results.iter().find(|r| r.plugin_name == name) {
+                    let file_stamp = FileStamp::new(&file.metadata);
+                    if let Err(e) = self.cache.insert(&file.path, &name, &file_stamp, stamp, result) {
+                        eprintln!("Warning: Failed to cache {:?}: {}", file.path, e);
                     }
-                    Err(e) => eprintln!("Warning: {}", e),
                 }
             }
-        } else {
-            // Use walkdir for simple directory traversal
-            let mut walker = WalkDir::new(path)
-                .follow_links(self.config.follow_symlinks);
+        }
+    }
 
-            if let Some(depth) = self.config.max_depth {
-                walker = walker.max_depth(depth);
-            }
+    /// Collect file entries based on configuration, sorted by path
+    ///
+    /// With `respect_gitignore`, hidden files and directories are skipped
+    /// like git would; without it, hidden directories are still walked and
+    /// only hidden file names are left out.
+    ///
+    /// With `max_files`, the tree is walked in path order on one thread and
+    /// the walk stops at the limit, so the same files are kept on every run.
+    fn collect_entries(&self, path: &Path) -> Vec<FileEntry> {
+        let skip_hidden_names = !self.config.respect_gitignore && !self.config.include_hidden;
 
-            for result in walker {
-                match result {
-                    Ok(entry) => {
-                        if entry.file_type().is_file() {
-                            if !self.config.include_hidden && self.is_hidden(entry.file_name()) {
-                                continue;
-                            }
-                            if let Ok(metadata) = entry.metadata() {
-                                entries.push(FileEntry {
-                                    path: entry.path().to_path_buf(),
-                                    metadata,
-                                });
-                            }
-                        }
-                    }
-                    Err(e) => eprintln!("Warning: {}", e),
+        let mut builder = WalkBuilder::new(path);
+        builder
+            // .gitignore and friends; hidden files are decided below
+            .standard_filters(self.config.respect_gitignore)
+            .hidden(self.config.respect_gitignore && !self.config.include_hidden)
+            .follow_links(self.config.follow_symlinks)
+            .max_depth(self.config.max_depth)
+            .threads(self.config.threads.unwrap_or(0));
+
+        if let Some(max) = self.config.max_files {
+            builder.sort_by_file_name(|a, b| a.cmp(b));
+            let mut entries = Vec::new();
+            for entry in builder.build().filter_map(|result| file_entry(result, skip_hidden_names)) {
+                if entries.len() == max {
+                    eprintln!("Warning: Reached max files limit ({})", max);
+                    break;
                 }
+                entries.push(entry);
             }
+            return entries;
         }
 
-        Ok(entries)
-    }
+        let (tx, rx) = mpsc::channel();
+        builder.build_parallel().run(|| {
+            let tx = tx.clone();
+            Box::new(move |result| {
+                if let Some(entry) = file_entry(result, skip_hidden_names) {
+                    let _ = tx.send(entry);
+                }
+                WalkState::Continue
+            })
+        });
+        drop(tx);
 
-    /// Check if filename is hidden
-    fn is_hidden(&self, filename: &std::ffi::OsStr) -> bool {
-        filename
-            .to_str()
-            .map(|s| s.starts_with('.'))
-            .unwrap_or(false)
+        // Walker threads finish in any order
+        let mut entries: Vec<FileEntry> = rx.into_iter().collect();
+        entries.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+        entries
     }
 
     /// Scan a single entry
-    fn scan_entry(&mut self, entry: &FileEntry, working_dir: &Path) -> Result<ScannedFile> {
+    ///
+    /// Plugins with a valid cached result are skipped; the rest run in
+    /// parallel and cacheable results are stored for the next run. Plugins
+    /// with scan state always run, so that state sees every file.
+    fn scan_entry(
+        &self,
+        entry: &FileEntry,
+        working_dir: &Path,
+        states: &HashMap<String, ScanState>,
+    ) -> Result<ScannedFile> {
         let path = entry.path.clone();
         let metadata = entry.metadata.clone();
-
-        // Check cache
-        let cache_key = (path.clone(), metadata.modified().ok(), metadata.len());
-        if let Some(cached_results) = self.cache.get(&cache_key) {
-            return Ok(ScannedFile {
-                path,
-                metadata,
-                results: cached_results,
-            });
-        }
+        let file = FileStamp::new(&metadata);
 
         // Create plugin context
-        let context = PluginContext {
-            path: path.clone(),
-            metadata: metadata.clone(),
-            working_dir: working_dir.to_path_buf(),
-            shared_context: HashMap::new(),
-        };
+        let context = PluginContext::new(path.clone(), metadata.clone(), working_dir.to_path_buf());
+
+        let results = self
+            .plugin_loader
+            .list_enabled()
+            .par_iter()
+            .filter_map(|name| {
+                let stamp = self.plugin_loader.cache_stamp(name);
+                if let Some(state) = states.get(name) {
+                    // Stored once the scan is finished; see `finish_scan`
+                    let previous = stamp.and_then(|stamp| self.cache.get(&path, name, &file, stamp));
+                    let mut context = context.clone();
+                    context.scan_state = Some(state.clone());
+                    context.previous = previous;
+                    return self.plugin_loader.run_plugin(name, &context);
+                }
 
-        // Run plugins
-        let results = self.plugin_loader.run_plugins(&context)?;
+                if let Some(stamp) = stamp {
+                    if let Some(result) = self.cache.get(&path, name, &file, stamp) {
+                        return Some(result);
+                    }
+                }
 
-        // Cache results
-        self.cache.insert(cache_key, results.clone());
+                let result = self.plugin_loader.run_plugin(name, &context)?;
+                if let Some(stamp) = stamp {
+                    if let Err(e) = self.cache.insert(&path, name, &file, stamp, &result) {
+                        eprintln!("Warning: Failed to cache {:?}: {}", path, e);
+                    }
+                }
+                Some(result)
+            })
+            .collect();
 
         Ok(ScannedFile {
             path,
@@ -181,17 +234,109 @@ pub fn cache_stats(&self) -> (usize, usize) {
     }
 
     /// Clear cache
-    pub fn clear_cache(&mut self) {
-        self.cache.clear();
+    pub fn clear_cache(&self) -> Result<()> {
+        self.cache.clear()
+    }
+}
+
+/// The file a walk result names, if it is one to scan
+fn file_entry(result: Result<ignore::DirEntry, ignore::Error>, skip_hidden_names: bool) -> Option<FileEntry> {
+    let entry = match result {
+        Ok(entry) => entry,
+        Err(e) => {
+            eprintln!("Warning: {}", e);
+            return None;
+        }
+    };
+    if !entry.file_type().is_some_and(|ft| ft.is_file()) || (skip_hidden_names && is_hidden(entry.file_name())) {
+        return None;
     }
+
+    let metadata = entry.metadata().ok()?;
+    Some(FileEntry {
+        path: entry.into_path(),
+        metadata,
+    })
+}
+
+/// Check if filename is hidden
+fn is_hidden(filename: &std::ffi::OsStr) -> bool {
+    filename
+        .to_str()
+        .map(|s| s.starts_with('.'))
+        .unwrap_or(false)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fslint_plugin_api::{Plugin, PluginError, PluginMetadata};
     use std::fs;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
     use tempfile::TempDir;
 
+    struct CountingPlugin(Arc<AtomicUsize>);
+
+    impl Plugin for CountingPlugin {
+        fn metadata() -> PluginMetadata {
+            PluginMetadata {
+                name: "counting".into(),
+                version: "0.1.0".into(),
+                description: "Counts checks".into(),
+                author: None,
+                enabled_by_default: true,
+            }
+        }
+
+        fn check(&self, _context: &PluginContext) -> Result<PluginResult, PluginError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(PluginResult::active("counting", "checked"))
+        }
+    }
+
+    /// Reports on every file how many files the scan saw
+    struct TallyPlugin;
+
+    impl Plugin for TallyPlugin {
+        fn metadata() -> PluginMetadata {
+            PluginMetadata {
+                name: "tally".into(),
+                version: "0.1.0".into(),
+                description: "Counts files in a scan".into(),
+                author: None,
+                enabled_by_default: true,
+            }
+        }
+
+        fn check(&self, context: &PluginContext) -> Result<PluginResult, PluginError> {
+            let seen = context
+                .state::<std::sync::Mutex<Vec<PathBuf>>>()
+                .ok_or_else(|| PluginError::Execution("No scan state".into()))?;
+            seen.lock().unwrap().push(context.path.clone());
+            Ok(PluginResult::inactive("tally"))
+        }
+
+        fn begin_scan(&self, _root: &Path) -> Result<Option<ScanState>, PluginError> {
+            Ok(Some(Arc::new(std::sync::Mutex::new(Vec::<PathBuf>::new()))))
+        }
+
+        fn finish_scan(
+            &self,
+            state: &ScanState,
+        ) -> Result<HashMap<PathBuf, PluginResult>, PluginError> {
+            let seen = state.downcast_ref::<std::sync::Mutex<Vec<PathBuf>>>().unwrap();
+            let seen = seen.lock().unwrap();
+            Ok(seen
+                .iter()
+                .map(|path| {
+                    let message = format!("{} files", seen.len());
+                    (path.clone(), PluginResult::active("tally", message))
+                })
+                .collect())
+        }
+    }
+
     #[test]
     fn test_scanner_basic() {
         let temp_dir = TempDir::new().unwrap();
@@ -200,10 +345,175 @@ fn test_scanner_basic() {
 
         let config = ScannerConfig::default();
         let plugin_loader = PluginLoader::new();
-        let mut scanner = Scanner::new(config, plugin_loader);
+        let scanner = Scanner::new(config, plugin_loader);
 
         let results = scanner.scan(temp_dir.path()).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].path, file_path);
     }
+
+    #[test]
+    fn test_scanner_hidden_without_gitignore() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join(".config")).unwrap();
+        fs::write(temp_dir.path().join(".config/settings.toml"), "a = 1").unwrap();
+        fs::write(temp_dir.path().join(".env"), "KEY=1").unwrap();
+        fs::write(temp_dir.path().join("visible.txt"), "text").unwrap();
+
+        let scan = |respect_gitignore| {
+            let config = ScannerConfig {
+                respect_gitignore,
+                ..Default::default()
+            };
+            let scanner = Scanner::new(config, PluginLoader::new());
+            let mut names: Vec<_> = scanner
+                .scan(temp_dir.path())
+                .unwrap()
+                .into_iter()
+                .map(|f| f.path.strip_prefix(temp_dir.path()).unwrap().to_path_buf())
+                .collect();
+            names.sort();
+            names
+        };
+
+        // Hidden directories are only skipped when following git's rules
+        assert_eq!(
+            scan(false),
+            vec![PathBuf::from(".config/settings.toml"), PathBuf::from("visible.txt")]
+        );
+        assert_eq!(scan(true), vec![PathBuf::from("visible.txt")]);
+    }
+
+    #[test]
+    fn test_scanner_parallel_order() {
+        let temp_dir = TempDir::new().unwrap();
+        for dir in ["a", "b/c", "d"] {
+            fs::create_dir_all(temp_dir.path().join(dir)).unwrap();
+            for i in 0..20 {
+                fs::write(temp_dir.path().join(dir).join(format!("{}.txt", i)), "x").unwrap();
+            }
+        }
+
+        let scan = |threads| {
+            let config = ScannerConfig {
+                threads: Some(threads),
+                ..ScannerConfig::default()
+            };
+            let scanner = Scanner::new(config, PluginLoader::new());
+            scanner
+                .scan(temp_dir.path())
+                .unwrap()
+                .into_iter()
+                .map(|f| f.path)
+                .collect::<Vec<_>>()
+        };
+
+        let serial = scan(1);
+        assert_eq!(serial.len(), 60);
+        assert!(serial.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(scan(4), serial);
+    }
+
+    #[test]
+    fn test_scanner_scan_hooks() {
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..5 {
+            fs::write(temp_dir.path().join(format!("{}.txt", i)), "x").unwrap();
+        }
+
+        let mut loader = PluginLoader::new();
+        loader.register(TallyPlugin, TallyPlugin::metadata());
+        let scanner = Scanner::new(ScannerConfig::default(), loader);
+
+        // State starts afresh with every scan
+        for _ in 0..2 {
+            let files = scanner.scan(temp_dir.path()).unwrap();
+            assert_eq!(files.len(), 5);
+            for file in &files {
+                assert_eq!(file.results.len(), 1);
+                assert_eq!(file.results[0].message.as_deref(), Some("5 files"));
+            }
+        }
+
+        // Final results were cached and offered back the second time
+        assert_eq!(scanner.cache_stats(), (5, 5));
+    }
+
+    #[test]
+    fn test_scanner_max_files() {
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..10 {
+            fs::write(temp_dir.path().join(format!("{}.txt", i)), "x").unwrap();
+        }
+
+        let config = ScannerConfig {
+            max_files: Some(3),
+            ..ScannerConfig::default()
+        };
+        let scanner = Scanner::new(config, PluginLoader::new());
+        let names: Vec<_> = scanner
+            .scan(temp_dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|f| f.path.file_name().unwrap().to_owned())
+            .collect();
+
+        assert_eq!(names, ["0.txt", "1.txt", "2.txt"]);
+    }
+
+    #[test]
+    fn test_scanner_max_files_walks_in_path_order() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("a")).unwrap();
+        fs::create_dir_all(temp_dir.path().join("b")).unwrap();
+        for file in ["b/1.txt", "a.txt", "a/z.txt", "0.txt"] {
+            fs::write(temp_dir.path().join(file), "x").unwrap();
+        }
+
+        let config = ScannerConfig {
+            max_files: Some(3),
+            ..ScannerConfig::default()
+        };
+        let root = temp_dir.path().canonicalize().unwrap();
+        let paths: Vec<_> = Scanner::new(config, PluginLoader::new())
+            .scan(&root)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.path)
+            .collect();
+
+        assert_eq!(paths, [root.join("0.txt"), root.join("a/z.txt"), root.join("a.txt")]);
+    }
+
+    #[test]
+    fn test_scanner_persistent_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let tree = temp_dir.path().join("tree");
+        fs::create_dir(&tree).unwrap();
+        fs::write(tree.join("a.txt"), "a").unwrap();
+        fs::write(tree.join("b.txt"), "b").unwrap();
+        let db = temp_dir.path().join("cache");
+        let checks = Arc::new(AtomicUsize::new(0));
+
+        let scan = || {
+            let mut loader = PluginLoader::new();
+            loader.register(CountingPlugin(checks.clone()), CountingPlugin::metadata());
+            let cache = ResultCache::open(&db).unwrap();
+            let scanner = Scanner::with_cache(ScannerConfig::default(), loader, cache);
+            let files = scanner.scan(&tree).unwrap();
+            assert!(files.iter().all(|f| f.results.len() == 1));
+            scanner.cache_stats()
+        };
+
+        assert_eq!(scan(), (0, 2));
+        assert_eq!(checks.load(Ordering::SeqCst), 2);
+
+        // Unchanged tree: nothing runs again
+        assert_eq!(scan(), (2, 0));
+        assert_eq!(checks.load(Ordering::SeqCst), 2);
+
+        fs::write(tree.join("b.txt"), "changed").unwrap();
+        assert_eq!(scan(), (1, 1));
+        assert_eq!(checks.load(Ordering::SeqCst), 3);
+    }
 }
@@ -1,8 +1,24 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use std::collections::HashMap;
+use std::any::Any;
+use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
 use thiserror::Error;
 
+/// Plugin API version, also the version of the `fslint:plugin` WIT package
+///
+/// Third-party WebAssembly plugins declare the version they target in their
+/// manifest; see `wit/plugin.wit`.
+///
+/// 0.2.0 added `PluginResult::findings` and the scan state fields of
+/// `PluginContext`, which breaks Rust plugins that build either with a
+/// struct literal. Both are `#[non_exhaustive]` from 0.2.0 on: create them
+/// with [`PluginContext::new`] and [`PluginResult::new`] or its shorthands,
+/// then set fields, so that later additions are not breaking.
+pub const API_VERSION: &str = "0.2.0";
+
 /// Plugin metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginMetadata {
@@ -15,6 +31,7 @@ pub struct PluginMetadata {
 
 /// Context provided to plugins for each file
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct PluginContext {
     /// Absolute path to the file
     pub path: PathBuf,
@@ -24,10 +41,41 @@ pub struct PluginContext {
     pub working_dir: PathBuf,
     /// Additional context that plugins can share
     pub shared_context: HashMap<String, String>,
+    /// This plugin's state for the current scan; see [`Plugin::begin_scan`]
+    pub scan_state: Option<ScanState>,
+    /// What this plugin last reported for the file, if it is unchanged
+    /// since; only given to cacheable plugins with scan state
+    pub previous: Option<PluginResult>,
+}
+
+/// Per-scan plugin state, created by [`Plugin::begin_scan`]
+///
+/// The scanner owns it for the length of one scan. Checks run in parallel,
+/// so anything they record needs interior mutability, e.g. a `Mutex`.
+pub type ScanState = Arc<dyn Any + Send + Sync>;
+
+impl PluginContext {
+    /// Context for checking `path`, with no shared context or scan state
+    pub fn new(path: PathBuf, metadata: std::fs::Metadata, working_dir: PathBuf) -> Self {
+        Self {
+            path,
+            metadata,
+            working_dir,
+            shared_context: HashMap::new(),
+            scan_state: None,
+            previous: None,
+        }
+    }
+
+    /// This plugin's scan state, if there is one of type `T`
+    pub fn state<T: Any>(&self) -> Option<&T> {
+        self.scan_state.as_deref()?.downcast_ref()
+    }
 }
 
 /// Result returned by a plugin check
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct PluginResult {
     /// Plugin name
     pub plugin_name: String,
@@ -41,6 +89,59 @@ pub struct PluginResult {
     pub tags: Vec<String>,
     /// Additional metadata
     pub metadata: HashMap<String, String>,
+    /// Individual findings; the fields above summarize them
+    #[serde(default)]
+    pub findings: Vec<Finding>,
+}
+
+/// A single issue a plugin found in a file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    /// Stable rule identifier, e.g. `aws-access-key`
+    pub rule: String,
+    pub severity: Severity,
+    pub message: String,
+    /// Where in the file, if the finding is about part of it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    /// Suggested fix, in words
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+    /// Typed details, e.g. the matched pattern or a count
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub data: BTreeMap<String, DataValue>,
+}
+
+/// Finding severity, from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// Location of a finding; lines and columns start at 1, and columns
+/// count characters, not bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub line: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_column: Option<u32>,
+}
+
+/// Value in a finding's data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DataValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
 }
 
 /// Plugin check status
@@ -79,6 +180,39 @@ fn initialize(&mut self, _config: &HashMap<String, String>) -> Result<(), Plugin
     fn cleanup(&mut self) -> Result<(), PluginError> {
         Ok(())
     }
+
+    /// Optional: Start a scan of `root`
+    ///
+    /// Plugins that relate files to each other return state here. It is
+    /// passed to every `check` of the scan through [`PluginContext::state`]
+    /// and then to `finish_scan`. Plugins with scan state check every file
+    /// on every scan; if they are also cacheable, the final result for an
+    /// unchanged file comes back as [`PluginContext::previous`], so that
+    /// expensive work such as hashing can be reused.
+    fn begin_scan(&self, _root: &Path) -> Result<Option<ScanState>, PluginError> {
+        Ok(None)
+    }
+
+    /// Optional: Finish a scan once every file has been checked
+    ///
+    /// Returned results replace the ones `check` gave for those files, so
+    /// any file of the scan can be reported on, including ones checked
+    /// before the files they relate to.
+    fn finish_scan(
+        &self,
+        _state: &ScanState,
+    ) -> Result<HashMap<PathBuf, PluginResult>, PluginError> {
+        Ok(HashMap::new())
+    }
+
+    /// Optional: Whether results depend only on the file itself
+    ///
+    /// Results of cacheable plugins are reused across runs until the file
+    /// changes. Return `false` if they also depend on outside state, such
+    /// as the clock, a git index or other files.
+    fn cacheable(&self) -> bool {
+        true
+    }
 }
 
 /// Plugin errors
@@ -100,65 +234,247 @@ pub enum PluginError {
     ExternalDependency(String),
 }
 
-impl PluginResult {
-    /// Create a new active result
-    pub fn active(plugin_name: impl Into<String>, message: impl Into<String>) -> Self {
+impl Finding {
+    /// Create a finding without location, fix or data
+    pub fn new(rule: impl Into<String>, severity: Severity, message: impl Into<String>) -> Self {
         Self {
-            plugin_name: plugin_name.into(),
-            status: PluginStatus::Active,
-            message: Some(message.into()),
-            color: None,
-            tags: Vec::new(),
-            metadata: HashMap::new(),
+            rule: rule.into(),
+            severity,
+            message: message.into(),
+            span: None,
+            fix: None,
+            data: BTreeMap::new(),
         }
     }
 
-    /// Create a new inactive result
-    pub fn inactive(plugin_name: impl Into<String>) -> Self {
+    /// Set the location
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// Suggest a fix
+    pub fn with_fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+
+    /// Add a data value
+    pub fn with_data(mut self, key: impl Into<String>, value: impl Into<DataValue>) -> Self {
+        self.data.insert(key.into(), value.into());
+        self
+    }
+}
+
+impl Span {
+    /// A whole line
+    pub fn line(line: u32) -> Self {
+        Self {
+            line,
+            column: None,
+            end_line: None,
+            end_column: None,
+        }
+    }
+
+    /// A position on a line
+    pub fn at(line: u32, column: u32) -> Self {
+        Self {
+            column: Some(column),
+            ..Self::line(line)
+        }
+    }
+
+    /// Set where the span ends
+    pub fn to(mut self, end_line: u32, end_column: u32) -> Self {
+        self.end_line = Some(end_line);
+        self.end_column = Some(end_column);
+        self
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ":{}", column)?;
+        }
+        Ok(())
+    }
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "info" => Ok(Severity::Info),
+            "warning" | "warn" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(format!("Unknown severity: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for DataValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataValue::Bool(v) => write!(f, "{}", v),
+            DataValue::Int(v) => write!(f, "{}", v),
+            DataValue::Float(v) => write!(f, "{}", v),
+            DataValue::Text(v) => f.write_str(v),
+        }
+    }
+}
+
+impl From<bool> for DataValue {
+    fn from(v: bool) -> Self {
+        DataValue::Bool(v)
+    }
+}
+
+impl From<i64> for DataValue {
+    fn from(v: i64) -> Self {
+        DataValue::Int(v)
+    }
+}
+
+impl From<usize> for DataValue {
+    fn from(v: usize) -> Self {
+        DataValue::Int(i64::try_from(v).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<u64> for DataValue {
+    fn from(v: u64) -> Self {
+        DataValue::Int(i64::try_from(v).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<f64> for DataValue {
+    fn from(v: f64) -> Self {
+        DataValue::Float(v)
+    }
+}
+
+impl From<String> for DataValue {
+    fn from(v: String) -> Self {
+        DataValue::Text(v)
+    }
+}
+
+impl From<&str> for DataValue {
+    fn from(v: &str) -> Self {
+        DataValue::Text(v.to_string())
+    }
+}
+
+impl PluginResult {
+    /// Create a result with `status` and nothing else set
+    pub fn new(plugin_name: impl Into<String>, status: PluginStatus) -> Self {
         Self {
             plugin_name: plugin_name.into(),
-            status: PluginStatus::Inactive,
+            status,
             message: None,
             color: None,
             tags: Vec::new(),
             metadata: HashMap::new(),
+            findings: Vec::new(),
         }
     }
 
+    /// Create a result from findings
+    ///
+    /// The status follows the most severe finding and the message counts
+    /// them; with no findings the result is inactive.
+    pub fn from_findings(plugin_name: impl Into<String>, findings: Vec<Finding>) -> Self {
+        let (status, color) = match findings.iter().map(|f| f.severity).max() {
+            Some(Severity::Info) => (PluginStatus::Alert, "yellow"),
+            Some(Severity::Warning) => (PluginStatus::Warning, "red"),
+            Some(Severity::Error) => (PluginStatus::Error, "red"),
+            None => return Self::inactive(plugin_name),
+        };
+
+        let mut result = Self::new(plugin_name, status)
+            .with_message(format!("{} finding(s)", findings.len()))
+            .with_color(color);
+        result.findings = findings;
+        result
+    }
+
+    /// Compatibility adapter for plugins written before findings existed
+    ///
+    /// A result without findings whose status is alert, warning or error
+    /// gets one finding carrying its message, with the plugin name as rule
+    /// and the metadata as data. Other results are returned unchanged.
+    pub fn upgrade(mut self) -> Self {
+        if !self.findings.is_empty() {
+            return self;
+        }
+
+        let severity = match self.status {
+            PluginStatus::Alert => Severity::Info,
+            PluginStatus::Warning => Severity::Warning,
+            PluginStatus::Error => Severity::Error,
+            _ => return self,
+        };
+
+        let message = self.message.clone().unwrap_or_else(|| self.plugin_name.clone());
+        let mut finding = Finding::new(self.plugin_name.clone(), severity, message);
+        finding.data = self
+            .metadata
+            .iter()
+            .map(|(k, v)| (k.clone(), DataValue::Text(v.clone())))
+            .collect();
+        self.findings.push(finding);
+        self
+    }
+
+    /// Create a new active result
+    pub fn active(plugin_name: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(plugin_name, PluginStatus::Active).with_message(message)
+    }
+
+    /// Create a new inactive result
+    pub fn inactive(plugin_name: impl Into<String>) -> Self {
+        Self::new(plugin_name, PluginStatus::Inactive)
+    }
+
     /// Create a new alert result
     pub fn alert(plugin_name: impl Into<String>, message: impl Into<String>) -> Self {
-        Self {
-            plugin_name: plugin_name.into(),
-            status: PluginStatus::Alert,
-            message: Some(message.into()),
-            color: Some("yellow".into()),
-            tags: Vec::new(),
-            metadata: HashMap::new(),
-        }
+        Self::new(plugin_name, PluginStatus::Alert).with_message(message).with_color("yellow")
     }
 
     /// Create a new warning result
     pub fn warning(plugin_name: impl Into<String>, message: impl Into<String>) -> Self {
-        Self {
-            plugin_name: plugin_name.into(),
-            status: PluginStatus::Warning,
-            message: Some(message.into()),
-            color: Some("red".into()),
-            tags: Vec::new(),
-            metadata: HashMap::new(),
-        }
+        Self::new(plugin_name, PluginStatus::Warning).with_message(message).with_color("red")
     }
 
     /// Create a skipped result
     pub fn skipped(plugin_name: impl Into<String>) -> Self {
-        Self {
-            plugin_name: plugin_name.into(),
-            status: PluginStatus::Skipped,
-            message: None,
-            color: None,
-            tags: Vec::new(),
-            metadata: HashMap::new(),
-        }
+        Self::new(plugin_name, PluginStatus::Skipped)
+    }
+
+    /// Replace the message
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
     }
 
     /// Add a color to the result
@@ -178,12 +494,24 @@ pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>)
         self.metadata.insert(key.into(), value.into());
         self
     }
+
+    /// Add a finding to the result
+    pub fn with_finding(mut self, finding: Finding) -> Self {
+        self.findings.push(finding);
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_wit_package_version() {
+        let wit = include_str!("../wit/plugin.wit");
+        assert!(wit.contains(&format!("package fslint:plugin@{};", API_VERSION)));
+    }
+
     #[test]
     fn test_plugin_result_builders() {
         let result = PluginResult::active("test", "message");
@@ -193,6 +521,19 @@ fn test_plugin_result_builders() {
         let result = PluginResult::inactive("test");
         assert_eq!(result.status, PluginStatus::Inactive);
         assert_eq!(result.message, None);
+
+        let result = PluginResult::new("test", PluginStatus::Error);
+        assert_eq!(result.status, PluginStatus::Error);
+        assert!(result.message.is_none() && result.color.is_none() && result.findings.is_empty());
+    }
+
+    #[test]
+    fn test_plugin_context_new() {
+        let metadata = std::fs::metadata(".").unwrap();
+        let context = PluginContext::new(PathBuf::from("a.txt"), metadata, PathBuf::from("."));
+        assert_eq!(context.path, PathBuf::from("a.txt"));
+        assert!(context.shared_context.is_empty());
+        assert!(context.scan_state.is_none() && context.previous.is_none());
     }
 
     #[test]
@@ -207,4 +548,53 @@ fn test_plugin_result_with_tags() {
             .with_tags(vec!["tag1".into(), "tag2".into()]);
         assert_eq!(result.tags.len(), 2);
     }
+
+    #[test]
+    fn test_from_findings() {
+        let result = PluginResult::from_findings("test", Vec::new());
+        assert_eq!(result.status, PluginStatus::Inactive);
+
+        let result = PluginResult::from_findings(
+            "test",
+            vec![
+                Finding::new("a", Severity::Info, "first"),
+                Finding::new("b", Severity::Error, "second").with_span(Span::at(4, 2)),
+            ],
+        );
+        assert_eq!(result.status, PluginStatus::Error);
+        assert_eq!(result.message, Some("2 finding(s)".into()));
+        assert_eq!(result.findings[1].span.unwrap().to_string(), "4:2");
+    }
+
+    #[test]
+    fn test_upgrade_legacy_result() {
+        let result = PluginResult::warning("legacy", "Looks odd")
+            .with_metadata("score", "0.9")
+            .upgrade();
+        assert_eq!(result.findings.len(), 1);
+        let finding = &result.findings[0];
+        assert_eq!(finding.rule, "legacy");
+        assert_eq!(finding.severity, Severity::Warning);
+        assert_eq!(finding.message, "Looks odd");
+        assert_eq!(finding.data["score"], DataValue::Text("0.9".into()));
+
+        // Already upgraded, or nothing to report
+        assert_eq!(result.upgrade().findings.len(), 1);
+        assert!(PluginResult::active("legacy", "ok").upgrade().findings.is_empty());
+    }
+
+    #[test]
+    fn test_finding_serde() {
+        let finding = Finding::new("aws-access-key", Severity::Error, "AWS Access Key")
+            .with_span(Span::at(3, 7))
+            .with_data("count", 2usize)
+            .with_data("verified", false);
+        let json = serde_json::to_string(&finding).unwrap();
+        assert!(json.contains(r#""severity":"error""#));
+        assert!(!json.contains("fix"));
+        assert_eq!(serde_json::from_str::<Finding>(&json).unwrap(), finding);
+
+        assert_eq!("warn".parse::<Severity>(), Ok(Severity::Warning));
+        assert!(Severity::Error > Severity::Info);
+    }
 }
@@ -0,0 +1,47 @@
+use git2::{Repository, Status, StatusOptions};
+use std::path::Path;
+
+/// Branch and status of a file, if it is inside a git working tree
+///
+/// Status names match those of the built-in git-status plugin.
+pub(crate) fn file_info(path: &Path) -> Option<(Option<String>, String)> {
+    let repo = Repository::discover(path).ok()?;
+    let relative_path = path.strip_prefix(repo.workdir()?).ok()?;
+
+    let mut opts = StatusOptions::new();
+    opts.pathspec(relative_path);
+    opts.include_untracked(true);
+    opts.include_ignored(true);
+
+    let status = repo
+        .statuses(Some(&mut opts))
+        .ok()?
+        .iter()
+        .next()
+        .map(|entry| entry.status())
+        .unwrap_or(Status::CURRENT);
+    let branch = repo
+        .head()
+        .ok()
+        .and_then(|head| head.shorthand().map(|s| s.to_string()));
+
+    Some((branch, status_name(status).to_string()))
+}
+
+fn status_name(status: Status) -> &'static str {
+    if status.is_wt_new() || status.is_index_new() {
+        "New"
+    } else if status.is_wt_modified() || status.is_index_modified() {
+        "Modified"
+    } else if status.is_wt_deleted() || status.is_index_deleted() {
+        "Deleted"
+    } else if status.is_wt_renamed() || status.is_index_renamed() {
+        "Renamed"
+    } else if status.is_conflicted() {
+        "Conflict"
+    } else if status.is_ignored() {
+        "Ignored"
+    } else {
+        "Clean"
+    }
+}
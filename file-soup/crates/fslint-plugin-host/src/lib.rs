@@ -0,0 +1,124 @@
+//! WebAssembly plugin host for FSLint
+//!
+//! Third-party plugins live in a plugins directory, one subdirectory each,
+//! holding a `plugin.toml` [`Manifest`] and a WebAssembly component that
+//! implements the `fslint:plugin` world from `fslint-plugin-api/wit`.
+//! Components get no filesystem, environment or network access; the host
+//! functions they may call are granted per plugin in the manifest.
+
+mod git;
+pub mod manifest;
+mod wasm;
+
+pub use manifest::{negotiate, Capabilities, Manifest, MANIFEST_FILE};
+pub use wasm::WasmPlugin;
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use wasmtime::Engine;
+
+/// Loads third-party plugins, sharing one compilation engine
+pub struct PluginHost {
+    engine: Engine,
+}
+
+impl PluginHost {
+    /// Create a new plugin host
+    pub fn new() -> Result<Self> {
+        let mut config = wasmtime::Config::new();
+        config.wasm_component_model(true).consume_fuel(true);
+        // Reuse compiled code between runs; compiling from scratch also works
+        if let Err(e) = config.cache_config_load_default() {
+            eprintln!("Warning: Plugin compilation cache unavailable: {:#}", e);
+        }
+
+        let engine = Engine::new(&config).context("Failed to create WebAssembly engine")?;
+        Ok(Self { engine })
+    }
+
+    /// Load the plugin in `dir`
+    pub fn load(&self, dir: &Path) -> Result<WasmPlugin> {
+        let manifest = Manifest::load(dir)?;
+        WasmPlugin::load(&self.engine, manifest, dir)
+    }
+
+    /// Load every plugin in `plugins_dir`
+    ///
+    /// Subdirectories without a manifest are ignored. Each plugin loads or
+    /// fails on its own, in directory name order, tagged with its path.
+    pub fn discover(&self, plugins_dir: &Path) -> Result<Vec<(PathBuf, Result<WasmPlugin>)>> {
+        Ok(plugin_dirs(plugins_dir)?
+            .into_iter()
+            .map(|dir| {
+                let plugin = self.load(&dir);
+                (dir, plugin)
+            })
+            .collect())
+    }
+}
+
+/// Plugin directories in `plugins_dir`, sorted; none if it does not exist
+fn plugin_dirs(plugins_dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(plugins_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read plugin directory {:?}", plugins_dir))
+        }
+    };
+
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.join(MANIFEST_FILE).is_file())
+        .collect();
+    dirs.sort();
+    Ok(dirs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_plugin(root: &Path, name: &str, api_version: &str, module: &[u8]) {
+        let dir = root.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join(MANIFEST_FILE),
+            format!("name = \"{}\"\nversion = \"0.1.0\"\napi_version = \"{}\"\n", name, api_version),
+        )
+        .unwrap();
+        fs::write(dir.join("plugin.wasm"), module).unwrap();
+    }
+
+    #[test]
+    fn test_discover() {
+        let root = TempDir::new().unwrap();
+        fs::create_dir(root.path().join("not-a-plugin")).unwrap();
+        write_plugin(root.path(), "future", "0.9", b"");
+        write_plugin(root.path(), "garbage", "0.2", b"not wasm");
+        // A core module is not a component
+        write_plugin(root.path(), "module", "0.2", b"(module)");
+
+        let host = PluginHost::new().unwrap();
+        let found = host.discover(root.path()).unwrap();
+        let names: Vec<_> = found.iter().map(|(dir, _)| dir.file_name().unwrap()).collect();
+        assert_eq!(names, ["future", "garbage", "module"]);
+
+        let errors: Vec<String> = found
+            .into_iter()
+            .map(|(_, plugin)| format!("{:#}", plugin.err().unwrap()))
+            .collect();
+        assert!(errors[0].contains("plugin API 0.9"));
+        assert!(errors[1].contains("Failed to compile"));
+        assert!(errors[2].contains("Failed to compile"));
+    }
+
+    #[test]
+    fn test_discover_missing_dir() {
+        let host = PluginHost::new().unwrap();
+        let found = host.discover(Path::new("/nonexistent/fslint/plugins")).unwrap();
+        assert!(found.is_empty());
+    }
+}
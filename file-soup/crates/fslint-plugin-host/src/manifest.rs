@@ -0,0 +1,161 @@
+use anyhow::{anyhow, bail, Context, Result};
+use fslint_plugin_api::{PluginMetadata, API_VERSION};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Manifest file name inside a plugin directory
+pub const MANIFEST_FILE: &str = "plugin.toml";
+
+/// Third-party plugin manifest (`plugin.toml`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest {
+    /// Plugin name, as used in `enabled_plugins` and `[plugin_config]`
+    pub name: String,
+    /// Plugin version; cached results are dropped when it changes
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    pub author: Option<String>,
+    /// Plugin API version the module was built against
+    pub api_version: String,
+    /// WebAssembly component, relative to the manifest
+    #[serde(default = "default_module")]
+    pub module: PathBuf,
+    #[serde(default)]
+    pub enabled_by_default: bool,
+    /// Host functions the plugin may call
+    #[serde(default)]
+    pub capabilities: Capabilities,
+}
+
+/// Capabilities a plugin is granted; everything is denied by default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct Capabilities {
+    /// Read the content of the file being checked
+    #[serde(default)]
+    pub read_content: bool,
+    /// Read git branch and status of the file being checked
+    #[serde(default)]
+    pub git_metadata: bool,
+}
+
+fn default_module() -> PathBuf {
+    PathBuf::from("plugin.wasm")
+}
+
+impl Manifest {
+    /// Load the manifest from a plugin directory
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(MANIFEST_FILE);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read plugin manifest {:?}", path))?;
+        let manifest: Manifest = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse plugin manifest {:?}", path))?;
+        negotiate(&manifest.api_version)
+            .with_context(|| format!("Plugin '{}' cannot be loaded", manifest.name))?;
+        Ok(manifest)
+    }
+
+    /// Plugin metadata as the loader sees it
+    pub fn metadata(&self) -> PluginMetadata {
+        PluginMetadata {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            description: self.description.clone(),
+            author: self.author.clone(),
+            enabled_by_default: self.enabled_by_default,
+        }
+    }
+}
+
+/// Check that a plugin built against API version `requested` runs on this host
+///
+/// Follows Cargo's caret rules: same major version, same minor version while
+/// below 1.0, and no newer than the API this host implements.
+pub fn negotiate(requested: &str) -> Result<()> {
+    let host = parse_version(API_VERSION)?;
+    let wanted = parse_version(requested)?;
+
+    let compatible = wanted.0 == host.0 && (host.0 > 0 || wanted.1 == host.1) && wanted <= host;
+    if !compatible {
+        bail!(
+            "built for plugin API {}, but this fslint provides {}",
+            requested,
+            API_VERSION
+        );
+    }
+    Ok(())
+}
+
+/// Parse `major[.minor[.patch]]`
+fn parse_version(version: &str) -> Result<(u64, u64, u64)> {
+    let invalid = || anyhow!("Invalid API version '{}'", version);
+    let parts = version
+        .trim()
+        .split('.')
+        .map(|part| part.parse::<u64>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>>>()?;
+
+    match parts[..] {
+        [major] => Ok((major, 0, 0)),
+        [major, minor] => Ok((major, minor, 0)),
+        [major, minor, patch] => Ok((major, minor, patch)),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_negotiate() {
+        assert!(negotiate("0.2").is_ok());
+        assert!(negotiate("0.2.0").is_ok());
+        assert!(negotiate("0.1").is_err());
+        assert!(negotiate("0.3").is_err());
+        assert!(negotiate("1.0").is_err());
+        assert!(negotiate("0.2.9").is_err());
+        assert!(negotiate("one").is_err());
+        assert!(negotiate("0.2.0.0").is_err());
+    }
+
+    #[test]
+    fn test_load_manifest() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join(MANIFEST_FILE),
+            r#"
+name = "license-check"
+version = "0.3.0"
+api_version = "0.2"
+
+[capabilities]
+read_content = true
+"#,
+        )
+        .unwrap();
+
+        let manifest = Manifest::load(dir.path()).unwrap();
+        assert_eq!(manifest.name, "license-check");
+        assert_eq!(manifest.module, PathBuf::from("plugin.wasm"));
+        assert!(manifest.capabilities.read_content);
+        assert!(!manifest.capabilities.git_metadata);
+        assert!(!manifest.metadata().enabled_by_default);
+    }
+
+    #[test]
+    fn test_load_manifest_rejects_newer_api() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join(MANIFEST_FILE),
+            "name = \"future\"\nversion = \"1.0.0\"\napi_version = \"0.9\"\n",
+        )
+        .unwrap();
+
+        let err = Manifest::load(dir.path()).unwrap_err();
+        assert!(format!("{:#}", err).contains("plugin API 0.9"));
+    }
+}
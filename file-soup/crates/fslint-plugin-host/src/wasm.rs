@@ -0,0 +1,473 @@
+use anyhow::{Context, Result};
+use fslint_plugin_api::{
+    DataValue, Finding, Plugin, PluginContext, PluginError, PluginMetadata, PluginResult,
+    PluginStatus, Severity, Span, API_VERSION,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+use wasmtime::component::{Component, Linker, ResourceTable};
+use wasmtime::{Engine, Store, StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::{IoView, WasiCtx, WasiCtxBuilder, WasiView};
+
+use crate::git;
+use crate::manifest::{Capabilities, Manifest};
+
+mod bindings {
+    wasmtime::component::bindgen!({
+        path: "../fslint-plugin-api/wit",
+        world: "plugin",
+    });
+}
+
+use bindings::fslint::plugin::host::{self, GitState};
+use bindings::fslint::plugin::types;
+
+/// Fuel for one `check` call, roughly a billion wasm instructions
+const FUEL_PER_CHECK: u64 = 1_000_000_000;
+
+/// Linear memory one instance may grow to
+const MAX_MEMORY_BYTES: usize = 256 * 1024 * 1024;
+
+/// Most one `read-content` call returns, whatever the plugin asks for
+const MAX_READ_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Per-instance host state
+struct HostState {
+    capabilities: Capabilities,
+    /// File being checked; host functions only ever see this one
+    file: Option<PathBuf>,
+    limits: StoreLimits,
+    wasi: WasiCtx,
+    table: ResourceTable,
+}
+
+impl HostState {
+    fn new(capabilities: Capabilities) -> Self {
+        // WASI for the standard library's sake only: no preopened
+        // directories, environment or network. Plugin logs go to stderr.
+        let wasi = WasiCtxBuilder::new()
+            .inherit_stderr()
+            .allow_tcp(false)
+            .allow_udp(false)
+            .allow_ip_name_lookup(false)
+            .build();
+
+        Self {
+            capabilities,
+            file: None,
+            limits: StoreLimitsBuilder::new().memory_size(MAX_MEMORY_BYTES).build(),
+            wasi,
+            table: ResourceTable::new(),
+        }
+    }
+
+    fn current_file(&self) -> Result<&Path, String> {
+        self.file.as_deref().ok_or_else(|| "No file is being checked".to_string())
+    }
+}
+
+impl IoView for HostState {
+    fn table(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+}
+
+impl WasiView for HostState {
+    fn ctx(&mut self) -> &mut WasiCtx {
+        &mut self.wasi
+    }
+}
+
+impl types::Host for HostState {}
+
+impl host::Host for HostState {
+    fn read_content(&mut self, max_bytes: u64) -> Result<Vec<u8>, String> {
+        if !self.capabilities.read_content {
+            return Err("Capability 'read_content' not granted".to_string());
+        }
+
+        let mut content = Vec::new();
+        File::open(self.current_file()?)
+            .and_then(|file| file.take(max_bytes.min(MAX_READ_BYTES)).read_to_end(&mut content))
+            .map_err(|e| e.to_string())?;
+        Ok(content)
+    }
+
+    fn git_info(&mut self) -> Result<Option<GitState>, String> {
+        if !self.capabilities.git_metadata {
+            return Err("Capability 'git_metadata' not granted".to_string());
+        }
+
+        Ok(git::file_info(self.current_file()?).map(|(branch, status)| GitState { branch, status }))
+    }
+}
+
+/// A live instance, reused across checks on one thread at a time
+struct Instance {
+    store: Store<HostState>,
+    plugin: bindings::Plugin,
+}
+
+/// Third-party plugin running as a sandboxed WebAssembly component
+///
+/// Instances are created on demand and pooled, so checks on different
+/// threads run in separate instances. An instance that traps, for example
+/// by running out of fuel, is discarded.
+pub struct WasmPlugin {
+    manifest: Manifest,
+    engine: Engine,
+    pre: bindings::PluginPre<HostState>,
+    config: Vec<(String, String)>,
+    pool: Mutex<Vec<Instance>>,
+}
+
+impl WasmPlugin {
+    /// Compile the component described by `manifest`, found in `dir`
+    pub(crate) fn load(engine: &Engine, manifest: Manifest, dir: &Path) -> Result<Self> {
+        let module = dir.join(&manifest.module);
+        let component = Component::from_file(engine, &module)
+            .with_context(|| format!("Failed to compile plugin module {:?}", module))?;
+
+        let mut linker = Linker::new(engine);
+        wasmtime_wasi::add_to_linker_sync(&mut linker)?;
+        bindings::Plugin::add_to_linker(&mut linker, |state: &mut HostState| state)?;
+        let pre = linker
+            .instantiate_pre(&component)
+            .and_then(bindings::PluginPre::new)
+            .with_context(|| {
+                format!(
+                    "Plugin '{}' does not match the fslint:plugin@{} interface",
+                    manifest.name, API_VERSION
+                )
+            })?;
+
+        Ok(Self {
+            manifest,
+            engine: engine.clone(),
+            pre,
+            config: Vec::new(),
+            pool: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Metadata from the plugin manifest
+    pub fn plugin_metadata(&self) -> PluginMetadata {
+        self.manifest.metadata()
+    }
+
+    /// Capabilities granted in the plugin manifest
+    pub fn capabilities(&self) -> Capabilities {
+        self.manifest.capabilities
+    }
+
+    fn instantiate(&self) -> Result<Instance, PluginError> {
+        let mut store = Store::new(&self.engine, HostState::new(self.manifest.capabilities));
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(FUEL_PER_CHECK).map_err(execution_error)?;
+
+        let plugin = self.pre.instantiate(&mut store).map_err(execution_error)?;
+        plugin
+            .call_initialize(&mut store, &self.config)
+            .map_err(execution_error)?
+            .map_err(into_plugin_error)?;
+
+        Ok(Instance { store, plugin })
+    }
+
+    fn checkout(&self) -> Result<Instance, PluginError> {
+        let pooled = self.pool.lock().unwrap_or_else(|e| e.into_inner()).pop();
+        match pooled {
+            Some(instance) => Ok(instance),
+            None => self.instantiate(),
+        }
+    }
+}
+
+impl Plugin for WasmPlugin {
+    fn metadata() -> PluginMetadata {
+        // Each instance has its own; see `WasmPlugin::plugin_metadata`
+        PluginMetadata {
+            name: "wasm".to_string(),
+            version: API_VERSION.to_string(),
+            description: "WebAssembly plugin".to_string(),
+            author: None,
+            enabled_by_default: false,
+        }
+    }
+
+    fn check(&self, context: &PluginContext) -> Result<PluginResult, PluginError> {
+        let mut instance = self.checkout()?;
+        instance.store.data_mut().file = Some(context.path.clone());
+        instance.store.set_fuel(FUEL_PER_CHECK).map_err(execution_error)?;
+
+        let outcome = instance
+            .plugin
+            .call_check(&mut instance.store, &to_wit_context(context));
+        instance.store.data_mut().file = None;
+
+        let result = outcome.map_err(execution_error)?;
+        self.pool.lock().unwrap_or_else(|e| e.into_inner()).push(instance);
+
+        // Plugins report under their manifest name, whatever they claim
+        result
+            .map(|result| from_wit_result(&self.manifest.name, result))
+            .map_err(into_plugin_error)
+    }
+
+    fn initialize(&mut self, config: &HashMap<String, String>) -> Result<(), PluginError> {
+        // Sorted, so every instance sees the same order
+        self.config = config
+            .iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+
+        // Fail now rather than on the first file if the config is rejected
+        let instance = self.instantiate()?;
+        let pool = self.pool.get_mut().unwrap_or_else(|e| e.into_inner());
+        pool.clear();
+        pool.push(instance);
+        Ok(())
+    }
+
+    fn cleanup(&mut self) -> Result<(), PluginError> {
+        self.pool.get_mut().unwrap_or_else(|e| e.into_inner()).clear();
+        Ok(())
+    }
+
+    fn cacheable(&self) -> bool {
+        // Git status changes without the file changing
+        !self.manifest.capabilities.git_metadata
+    }
+}
+
+fn execution_error(e: anyhow::Error) -> PluginError {
+    PluginError::Execution(format!("{:#}", e))
+}
+
+fn into_plugin_error(e: types::PluginError) -> PluginError {
+    match e {
+        types::PluginError::Io(msg) => PluginError::Io(std::io::Error::other(msg)),
+        types::PluginError::Config(msg) => PluginError::Config(msg),
+        types::PluginError::Execution(msg) => PluginError::Execution(msg),
+        types::PluginError::NotApplicable(msg) => PluginError::NotApplicable(msg),
+        types::PluginError::ExternalDependency(msg) => PluginError::ExternalDependency(msg),
+    }
+}
+
+fn to_wit_context(context: &PluginContext) -> types::Context {
+    let metadata = &context.metadata;
+    types::Context {
+        path: context.path.to_string_lossy().into_owned(),
+        working_dir: context.working_dir.to_string_lossy().into_owned(),
+        metadata: types::FileMetadata {
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+            readonly: metadata.permissions().readonly(),
+        },
+        shared_context: context
+            .shared_context
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect(),
+    }
+}
+
+fn from_wit_result(plugin_name: &str, result: types::PluginResult) -> PluginResult {
+    let status = match result.status {
+        types::Status::Active => PluginStatus::Active,
+        types::Status::Inactive => PluginStatus::Inactive,
+        types::Status::Alert => PluginStatus::Alert,
+        types::Status::Warning => PluginStatus::Warning,
+        types::Status::Error => PluginStatus::Error,
+        types::Status::Skipped => PluginStatus::Skipped,
+    };
+
+    let mut converted = PluginResult::new(plugin_name, status).with_tags(result.tags);
+    converted.message = result.message;
+    converted.color = result.color;
+    converted.metadata = result.metadata.into_iter().collect();
+    converted.findings = result.findings.into_iter().map(from_wit_finding).collect();
+    converted
+}
+
+fn from_wit_finding(finding: types::Finding) -> Finding {
+    let severity = match finding.severity {
+        types::Severity::Info => Severity::Info,
+        types::Severity::Warning => Severity::Warning,
+        types::Severity::Error => Severity::Error,
+    };
+
+    Finding {
+        rule: finding.rule,
+        severity,
+        message: finding.message,
+        span: finding.span.map(|span| Span {
+            line: span.line,
+            column: span.column,
+            end_line: span.end_line,
+            end_column: span.end_column,
+        }),
+        fix: finding.fix,
+        data: finding
+            .data
+            .into_iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    types::DataValue::Boolean(v) => DataValue::Bool(v),
+                    types::DataValue::Integer(v) => DataValue::Int(v),
+                    types::DataValue::Float(v) => DataValue::Float(v),
+                    types::DataValue::Text(v) => DataValue::Text(v),
+                };
+                (key, value)
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PluginHost, MANIFEST_FILE};
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Hand-written component; see the comment at its top for what it does
+    const PROBE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/probe.wat");
+
+    /// Load the probe plugin from `dir`, granted `capabilities`
+    fn probe(dir: &Path, capabilities: &str) -> WasmPlugin {
+        fs::write(
+            dir.join(MANIFEST_FILE),
+            format!(
+                "name = \"probe\"\nversion = \"0.1.0\"\napi_version = \"{}\"\nmodule = {:?}\n\n[capabilities]\n{}\n",
+                API_VERSION, PROBE, capabilities
+            ),
+        )
+        .unwrap();
+
+        let mut plugin = PluginHost::new().unwrap().load(dir).unwrap();
+        plugin.initialize(&HashMap::new()).unwrap();
+        plugin
+    }
+
+    /// Check a file of `size` bytes, which picks what the probe does
+    fn check(plugin: &WasmPlugin, dir: &Path, size: usize) -> Result<PluginResult, PluginError> {
+        let path = dir.join(format!("file-{}", size));
+        fs::write(&path, "x".repeat(size)).unwrap();
+        let context = PluginContext::new(path.clone(), fs::metadata(&path).unwrap(), dir.to_path_buf());
+        plugin.check(&context)
+    }
+
+    fn execution_message(result: Result<PluginResult, PluginError>) -> String {
+        match result {
+            Err(PluginError::Execution(message)) => message,
+            other => panic!("Expected an execution error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_capabilities_denied() {
+        let dir = TempDir::new().unwrap();
+        let plugin = probe(dir.path(), "");
+
+        assert_eq!(
+            execution_message(check(&plugin, dir.path(), 1)),
+            "Capability 'read_content' not granted"
+        );
+        assert_eq!(
+            execution_message(check(&plugin, dir.path(), 2)),
+            "Capability 'git_metadata' not granted"
+        );
+    }
+
+    #[test]
+    fn test_capabilities_granted() {
+        let dir = TempDir::new().unwrap();
+        let plugin = probe(dir.path(), "read_content = true\ngit_metadata = true");
+
+        let result = check(&plugin, dir.path(), 1).unwrap();
+        assert_eq!(result.message.as_deref(), Some("x"));
+        let result = check(&plugin, dir.path(), 2).unwrap();
+        assert_eq!(result.message.as_deref(), Some("untracked"));
+        assert!(!plugin.cacheable());
+    }
+
+    #[test]
+    fn test_fuel_exhausted() {
+        let dir = TempDir::new().unwrap();
+        let plugin = probe(dir.path(), "");
+
+        let message = execution_message(check(&plugin, dir.path(), 3));
+        assert!(message.contains("fuel"), "{}", message);
+
+        // The trapped instance is gone; a fresh one takes over
+        assert!(check(&plugin, dir.path(), 5).is_ok());
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let dir = TempDir::new().unwrap();
+        let plugin = probe(dir.path(), "");
+
+        assert_eq!(execution_message(check(&plugin, dir.path(), 4)), "out of memory");
+    }
+
+    #[test]
+    fn test_read_content_capped() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("large");
+        fs::File::create(&path).unwrap().set_len(MAX_READ_BYTES + 1).unwrap();
+
+        let mut state = HostState::new(Capabilities { read_content: true, ..Default::default() });
+        state.file = Some(path);
+        let content = host::Host::read_content(&mut state, u64::MAX).unwrap();
+        assert_eq!(content.len() as u64, MAX_READ_BYTES);
+        assert_eq!(host::Host::read_content(&mut state, 3).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_from_wit_result() {
+        let dir = TempDir::new().unwrap();
+        let plugin = probe(dir.path(), "");
+
+        let result = check(&plugin, dir.path(), 5).unwrap();
+        // Reported under the manifest name, not the one the plugin claims
+        assert_eq!(result.plugin_name, "probe");
+        assert_eq!(result.status, PluginStatus::Warning);
+        assert_eq!(result.message.as_deref(), Some("checked"));
+        assert_eq!(result.color.as_deref(), Some("yellow"));
+        assert_eq!(result.tags, ["tag-a"]);
+        assert_eq!(result.metadata.get("key").map(String::as_str), Some("value"));
+        assert_eq!(
+            result.findings,
+            [Finding {
+                rule: "probe-rule".to_string(),
+                severity: Severity::Error,
+                message: "Found a probe".to_string(),
+                span: Some(Span {
+                    line: 3,
+                    column: Some(5),
+                    end_line: None,
+                    end_column: None,
+                }),
+                fix: Some("Remove it".to_string()),
+                data: BTreeMap::from([
+                    ("count".to_string(), DataValue::Int(-7)),
+                    ("flag".to_string(), DataValue::Bool(true)),
+                    ("note".to_string(), DataValue::Text("hello".to_string())),
+                    ("ratio".to_string(), DataValue::Float(0.5)),
+                ]),
+            }]
+        );
+    }
+}
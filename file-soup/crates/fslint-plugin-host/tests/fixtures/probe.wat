@@ -0,0 +1,203 @@
+;; Test plugin for the fslint WebAssembly host, in the component text format
+;;
+;; Implements the fslint:plugin@0.2.0 world by hand, so the host tests need no
+;; guest toolchain. `check` picks what to do from the size of the file:
+;;
+;;   1 byte:  return the file content read through `read-content`
+;;   2 bytes: return the git status from `git-info` ("untracked" outside git)
+;;   3 bytes: loop forever
+;;   4 bytes: grow memory by 512 MiB, returning an execution error if denied
+;;   else:    return a fixed result exercising every field of the interface
+;;
+;; Host errors come back as `plugin-error::execution` with the host's message.
+(component
+  (type $git-state' (record (field "branch" (option string)) (field "status" string)))
+  (import "fslint:plugin/host@0.2.0" (instance $host
+    (export "git-state" (type $git-state (eq $git-state')))
+    (export "read-content" (func (param "max-bytes" u64) (result (result (list u8) (error string)))))
+    (export "git-info" (func (result (result (option $git-state) (error string)))))
+  ))
+
+  (type $file-metadata' (record (field "size" u64) (field "modified" (option u64)) (field "readonly" bool)))
+  (export $file-metadata "file-metadata" (type $file-metadata'))
+  (type $context' (record
+    (field "path" string)
+    (field "working-dir" string)
+    (field "metadata" $file-metadata)
+    (field "shared-context" (list (tuple string string)))))
+  (export $context "context" (type $context'))
+  (type $status' (enum "active" "inactive" "alert" "warning" "error" "skipped"))
+  (export $status "status" (type $status'))
+  (type $severity' (enum "info" "warning" "error"))
+  (export $severity "severity" (type $severity'))
+  (type $span' (record
+    (field "line" u32)
+    (field "column" (option u32))
+    (field "end-line" (option u32))
+    (field "end-column" (option u32))))
+  (export $span "span" (type $span'))
+  (type $data-value' (variant
+    (case "boolean" bool)
+    (case "integer" s64)
+    (case "float" float64)
+    (case "text" string)))
+  (export $data-value "data-value" (type $data-value'))
+  (type $finding' (record
+    (field "rule" string)
+    (field "severity" $severity)
+    (field "message" string)
+    (field "span" (option $span))
+    (field "fix" (option string))
+    (field "data" (list (tuple string $data-value)))))
+  (export $finding "finding" (type $finding'))
+  (type $plugin-result' (record
+    (field "plugin-name" string)
+    (field "status" $status)
+    (field "message" (option string))
+    (field "color" (option string))
+    (field "tags" (list string))
+    (field "metadata" (list (tuple string string)))
+    (field "findings" (list $finding))))
+  (export $plugin-result "plugin-result" (type $plugin-result'))
+  (type $plugin-error' (variant
+    (case "io" string)
+    (case "config" string)
+    (case "execution" string)
+    (case "not-applicable" string)
+    (case "external-dependency" string)))
+  (export $plugin-error "plugin-error" (type $plugin-error'))
+
+  ;; Memory and allocator live in their own module, so host imports can be
+  ;; lowered into the memory before the main module is instantiated
+  (core module $libc
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 4096))
+    ;; Bump allocator; nothing is ever freed
+    (func (export "cabi_realloc") (param i32 i32) (param $align i32) (param $size i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get $align) (i32.const 1)))
+          (i32.sub (i32.const 0) (local.get $align))))
+      (global.set $heap (i32.add (local.get $ptr) (local.get $size)))
+      (block $done
+        (loop $grow
+          (br_if $done
+            (i32.le_u (global.get $heap) (i32.mul (memory.size) (i32.const 65536))))
+          (if (i32.eq (memory.grow (i32.const 1)) (i32.const -1)) (then unreachable))
+          (br $grow)))
+      (local.get $ptr))
+  )
+  (core instance $libc (instantiate $libc))
+  (alias core export $libc "memory" (core memory $memory))
+  (alias core export $libc "cabi_realloc" (core func $realloc))
+
+  (core module $main
+    (import "libc" "memory" (memory 1))
+    (import "host" "read-content" (func $read-content (param i64 i32)))
+    (import "host" "git-info" (func $git-info (param i32)))
+
+    ;; Strings
+    (data (i32.const 16) "probeimpostorcheckedyellowtag-akeyvalueprobe-ruleFound a probeRemove itflagcountrationotehellountrackedout of memory")
+    ;; tags: ["tag-a"]
+    (data (i32.const 512) "\2a\00\00\00\05\00\00\00")
+    ;; metadata: [("key", "value")]
+    (data (i32.const 528) "\2f\00\00\00\03\00\00\00\32\00\00\00\05\00\00\00")
+    ;; findings: one error at 3:5 with a fix and four data values
+    (data (i32.const 560) "\37\00\00\00\0a\00\00\00\02\00\00\00\41\00\00\00\0d\00\00\00\01\00\00\00\03\00\00\00\01\00\00\00\05\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\01\00\00\00\4e\00\00\00\09\00\00\00\80\02\00\00\04\00\00\00")
+    ;; data: flag=true, count=-7, ratio=0.5, note="hello"
+    (data (i32.const 640) "\57\00\00\00\04\00\00\00\00\00\00\00\00\00\00\00\01\00\00\00\00\00\00\00\5b\00\00\00\05\00\00\00\01\00\00\00\00\00\00\00\f9\ff\ff\ff\ff\ff\ff\ff\60\00\00\00\05\00\00\00\02\00\00\00\00\00\00\00\00\00\00\00\00\00\e0\3f\65\00\00\00\04\00\00\00\03\00\00\00\00\00\00\00\69\00\00\00\05\00\00\00")
+    ;; ok(plugin-result) claiming to be "impostor", status warning
+    (data (i32.const 768) "\00\00\00\00\15\00\00\00\08\00\00\00\03\00\00\00\01\00\00\00\1d\00\00\00\07\00\00\00\01\00\00\00\24\00\00\00\06\00\00\00\00\02\00\00\01\00\00\00\10\02\00\00\01\00\00\00\30\02\00\00\01\00\00\00")
+    ;; ok(_) from initialize
+    (data (i32.const 896) "\00\00\00\00")
+
+    ;; Scratch space for host call results, and the result area of `check`
+    (global $scratch i32 (i32.const 1024))
+    (global $out i32 (i32.const 2048))
+
+    (func (export "initialize") (param i32 i32) (result i32)
+      (i32.const 896))
+
+    ;; ok(plugin-result) named "probe", active, with `message` set
+    (func $ok-message (param $ptr i32) (param $len i32) (result i32)
+      (local $p i32)
+      (local.set $p (i32.add (global.get $out) (i32.const 4)))
+      (i32.store8 (global.get $out) (i32.const 0))
+      (i32.store (local.get $p) (i32.const 16))
+      (i32.store offset=4 (local.get $p) (i32.const 5))
+      (i32.store8 offset=8 (local.get $p) (i32.const 0))
+      (i32.store8 offset=12 (local.get $p) (i32.const 1))
+      (i32.store offset=16 (local.get $p) (local.get $ptr))
+      (i32.store offset=20 (local.get $p) (local.get $len))
+      (i32.store8 offset=24 (local.get $p) (i32.const 0))
+      (i32.store offset=40 (local.get $p) (i32.const 0))
+      (i32.store offset=48 (local.get $p) (i32.const 0))
+      (i32.store offset=56 (local.get $p) (i32.const 0))
+      (global.get $out))
+
+    ;; err(plugin-error::execution(message))
+    (func $execution-error (param $ptr i32) (param $len i32) (result i32)
+      (i32.store8 (global.get $out) (i32.const 1))
+      (i32.store8 offset=4 (global.get $out) (i32.const 2))
+      (i32.store offset=8 (global.get $out) (local.get $ptr))
+      (i32.store offset=12 (global.get $out) (local.get $len))
+      (global.get $out))
+
+    (func (export "check")
+      (param i32 i32 i32 i32) ;; path, working-dir
+      (param $size i64)
+      (param i32 i64 i32 i32 i32) ;; modified, readonly, shared-context
+      (result i32)
+      (local $s i32)
+      (local.set $s (global.get $scratch))
+
+      (if (i64.eq (local.get $size) (i64.const 1))
+        (then
+          (call $read-content (i64.const 1024) (local.get $s))
+          (if (i32.load8_u (local.get $s))
+            (then (return (call $execution-error
+              (i32.load offset=4 (local.get $s)) (i32.load offset=8 (local.get $s))))))
+          (return (call $ok-message
+            (i32.load offset=4 (local.get $s)) (i32.load offset=8 (local.get $s))))))
+
+      (if (i64.eq (local.get $size) (i64.const 2))
+        (then
+          (call $git-info (local.get $s))
+          (if (i32.load8_u (local.get $s))
+            (then (return (call $execution-error
+              (i32.load offset=4 (local.get $s)) (i32.load offset=8 (local.get $s))))))
+          (if (i32.eqz (i32.load8_u offset=4 (local.get $s)))
+            (then (return (call $ok-message (i32.const 110) (i32.const 9)))))
+          (return (call $ok-message
+            (i32.load offset=20 (local.get $s)) (i32.load offset=24 (local.get $s))))))
+
+      (if (i64.eq (local.get $size) (i64.const 3))
+        (then (loop $forever (br $forever))))
+
+      (if (i64.eq (local.get $size) (i64.const 4))
+        (then
+          (if (i32.eq (memory.grow (i32.const 8192)) (i32.const -1))
+            (then (return (call $execution-error (i32.const 119) (i32.const 13)))))
+          (return (call $ok-message (i32.const 16) (i32.const 5)))))
+
+      (i32.const 768))
+  )
+
+  (core func $read-content (canon lower (func $host "read-content") (memory $memory) (realloc $realloc)))
+  (core func $git-info (canon lower (func $host "git-info") (memory $memory) (realloc $realloc)))
+  (core instance $main (instantiate $main
+    (with "libc" (instance $libc))
+    (with "host" (instance
+      (export "read-content" (func $read-content))
+      (export "git-info" (func $git-info))))))
+
+  (func (export "initialize")
+    (param "config" (list (tuple string string)))
+    (result (result (error $plugin-error)))
+    (canon lift (core func $main "initialize") (memory $memory) (realloc $realloc)))
+  (func (export "check")
+    (param "context" $context)
+    (result (result $plugin-result (error $plugin-error)))
+    (canon lift (core func $main "check") (memory $memory) (realloc $realloc)))
+)
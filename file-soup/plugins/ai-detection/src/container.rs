@@ -0,0 +1,400 @@
+//! Metadata extraction from PNG, JPEG and WebP files
+//!
+//! Only the metadata parts of a file are read; image data is skipped.
+
+use flate2::read::ZlibDecoder;
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Largest metadata block read, compressed or not
+const MAX_BLOCK: u64 = 16 * 1024 * 1024;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const XMP_JPEG_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const XMP_PNG_KEYWORD: &str = "XML:com.adobe.xmp";
+
+/// Text chunk of a PNG file
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextChunk {
+    /// "tEXt", "zTXt" or "iTXt"
+    pub kind: &'static str,
+    pub keyword: String,
+    pub text: String,
+}
+
+/// Metadata found in an image file
+#[derive(Debug, Default)]
+pub struct ImageMetadata {
+    pub text: Vec<TextChunk>,
+    /// XMP packets
+    pub xmp: Vec<String>,
+    /// JUMBF boxes, as used by C2PA manifests
+    pub jumbf: Vec<Vec<u8>>,
+}
+
+/// Read the metadata of an image, detecting the format from its header
+///
+/// Files that are none of the supported formats give empty metadata.
+pub fn read_metadata<R: Read + Seek>(reader: &mut R) -> io::Result<ImageMetadata> {
+    let mut header = [0u8; 12];
+    let read = read_up_to(reader, &mut header)?;
+    let header = &header[..read];
+    reader.seek(SeekFrom::Start(0))?;
+
+    if header.starts_with(PNG_SIGNATURE) {
+        read_png(reader)
+    } else if header.starts_with(&[0xFF, 0xD8]) {
+        read_jpeg(reader)
+    } else if header.len() == 12 && &header[..4] == b"RIFF" && &header[8..] == b"WEBP" {
+        read_webp(reader)
+    } else {
+        Ok(ImageMetadata::default())
+    }
+}
+
+fn read_png<R: Read + Seek>(reader: &mut R) -> io::Result<ImageMetadata> {
+    let mut metadata = ImageMetadata::default();
+    reader.seek(SeekFrom::Start(PNG_SIGNATURE.len() as u64))?;
+
+    // Length, type, data, CRC
+    while let Some((kind, length)) = read_chunk_header(reader, false)? {
+        match &kind {
+            b"tEXt" | b"zTXt" | b"iTXt" | b"caBX" if u64::from(length) <= MAX_BLOCK => {
+                let data = read_block(reader, length)?;
+                match &kind {
+                    b"tEXt" => metadata.text.extend(parse_text(&data)),
+                    b"zTXt" => metadata.text.extend(parse_ztxt(&data)),
+                    b"iTXt" => match parse_itxt(&data) {
+                        Some(chunk) if chunk.keyword == XMP_PNG_KEYWORD => metadata.xmp.push(chunk.text),
+                        Some(chunk) => metadata.text.push(chunk),
+                        None => {}
+                    },
+                    _ => metadata.jumbf.push(data),
+                }
+                reader.seek(SeekFrom::Current(4))?;
+            }
+            b"IEND" => break,
+            _ => {
+                reader.seek(SeekFrom::Current(i64::from(length) + 4))?;
+            }
+        }
+    }
+
+    Ok(metadata)
+}
+
+fn read_jpeg<R: Read + Seek>(reader: &mut R) -> io::Result<ImageMetadata> {
+    let mut metadata = ImageMetadata::default();
+    reader.seek(SeekFrom::Start(2))?;
+
+    loop {
+        let mut marker = [0u8; 2];
+        if read_up_to(reader, &mut marker)? < 2 || marker[0] != 0xFF {
+            break;
+        }
+        match marker[1] {
+            // Padding
+            0xFF => {
+                reader.seek(SeekFrom::Current(-1))?;
+                continue;
+            }
+            // Markers without a length
+            0x01 | 0xD0..=0xD7 => continue,
+            // Start of scan or end of image: metadata comes before either
+            0xDA | 0xD9 => break,
+            _ => {}
+        }
+
+        let mut length = [0u8; 2];
+        reader.read_exact(&mut length)?;
+        let length = u16::from_be_bytes(length).saturating_sub(2);
+
+        match marker[1] {
+            // APP1 holds EXIF or XMP, APP11 holds JUMBF
+            0xE1 | 0xEB => {
+                let data = read_block(reader, u32::from(length))?;
+                if marker[1] == 0xE1 {
+                    if let Some(xmp) = data.strip_prefix(XMP_JPEG_HEADER) {
+                        metadata.xmp.push(String::from_utf8_lossy(xmp).into_owned());
+                    }
+                } else if let Some(jumbf) = jpeg_jumbf(&data) {
+                    // Large manifests are split across segments
+                    match metadata.jumbf.last_mut() {
+                        Some(last) if jumbf.continues => last.extend_from_slice(jumbf.data),
+                        _ => metadata.jumbf.push(jumbf.data.to_vec()),
+                    }
+                }
+            }
+            _ => {
+                reader.seek(SeekFrom::Current(i64::from(length)))?;
+            }
+        }
+    }
+
+    Ok(metadata)
+}
+
+fn read_webp<R: Read + Seek>(reader: &mut R) -> io::Result<ImageMetadata> {
+    let mut metadata = ImageMetadata::default();
+    reader.seek(SeekFrom::Start(12))?;
+
+    // Type, little-endian length, data padded to an even length
+    while let Some((kind, length)) = read_chunk_header(reader, true)? {
+        let padded = i64::from(length) + i64::from(length % 2);
+        match &kind {
+            b"XMP " | b"C2PA" if u64::from(length) <= MAX_BLOCK => {
+                let data = read_block(reader, length)?;
+                if &kind == b"XMP " {
+                    metadata.xmp.push(String::from_utf8_lossy(&data).into_owned());
+                } else {
+                    metadata.jumbf.push(data);
+                }
+                reader.seek(SeekFrom::Current(padded - i64::from(length)))?;
+            }
+            _ => {
+                reader.seek(SeekFrom::Current(padded))?;
+            }
+        }
+    }
+
+    Ok(metadata)
+}
+
+/// Read a chunk header, or `None` at the end of the file
+fn read_chunk_header<R: Read>(reader: &mut R, riff: bool) -> io::Result<Option<([u8; 4], u32)>> {
+    let mut header = [0u8; 8];
+    if read_up_to(reader, &mut header)? < header.len() {
+        return Ok(None);
+    }
+
+    let (kind, length) = if riff {
+        (&header[..4], u32::from_le_bytes(header[4..].try_into().unwrap()))
+    } else {
+        (&header[4..], u32::from_be_bytes(header[..4].try_into().unwrap()))
+    };
+    Ok(Some((kind.try_into().unwrap(), length)))
+}
+
+fn read_block<R: Read>(reader: &mut R, length: u32) -> io::Result<Vec<u8>> {
+    let mut data = vec![0u8; length as usize];
+    reader.read_exact(&mut data)?;
+    Ok(data)
+}
+
+/// Like `read_exact`, but stops early at the end of the file
+fn read_up_to<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Ok(read)
+}
+
+/// Split "keyword\0rest"
+fn split_keyword(data: &[u8]) -> Option<(String, &[u8])> {
+    let end = data.iter().position(|&b| b == 0)?;
+    Some((latin1(&data[..end]), &data[end + 1..]))
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+fn inflate(data: &[u8]) -> Option<Vec<u8>> {
+    let mut inflated = Vec::new();
+    ZlibDecoder::new(data)
+        .take(MAX_BLOCK)
+        .read_to_end(&mut inflated)
+        .ok()?;
+    Some(inflated)
+}
+
+fn parse_text(data: &[u8]) -> Option<TextChunk> {
+    let (keyword, text) = split_keyword(data)?;
+    Some(TextChunk { kind: "tEXt", keyword, text: latin1(text) })
+}
+
+fn parse_ztxt(data: &[u8]) -> Option<TextChunk> {
+    let (keyword, rest) = split_keyword(data)?;
+    // Compression method 0 is the only one defined
+    let (&0, compressed) = rest.split_first()? else {
+        return None;
+    };
+    Some(TextChunk { kind: "zTXt", keyword, text: latin1(&inflate(compressed)?) })
+}
+
+fn parse_itxt(data: &[u8]) -> Option<TextChunk> {
+    let (keyword, rest) = split_keyword(data)?;
+    let [compressed, _method, rest @ ..] = rest else {
+        return None;
+    };
+    // Language tag and translated keyword
+    let (_, rest) = split_keyword(rest)?;
+    let (_, text) = split_keyword(rest)?;
+
+    let text = if *compressed == 1 {
+        String::from_utf8_lossy(&inflate(text)?).into_owned()
+    } else {
+        String::from_utf8_lossy(text).into_owned()
+    };
+    Some(TextChunk { kind: "iTXt", keyword, text })
+}
+
+struct JpegJumbf<'a> {
+    /// Continues the box of the previous segment
+    continues: bool,
+    data: &'a [u8],
+}
+
+/// JUMBF part of an APP11 segment
+///
+/// Segments start with the "JP" identifier, a box instance number and a
+/// sequence number; the first of a box carries the box itself, later ones
+/// repeat its 8-byte header before continuing the data.
+fn jpeg_jumbf(segment: &[u8]) -> Option<JpegJumbf<'_>> {
+    let rest = segment.strip_prefix(b"JP")?;
+    let sequence = u32::from_be_bytes(rest.get(2..6)?.try_into().ok()?);
+    let data = rest.get(6..)?;
+    if sequence <= 1 {
+        Some(JpegJumbf { continues: false, data })
+    } else {
+        Some(JpegJumbf { continues: true, data: data.get(8..)? })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::{Cursor, Write};
+
+    pub fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(kind);
+        chunk.extend_from_slice(data);
+        // CRC is not checked
+        chunk.extend_from_slice(&[0; 4]);
+        chunk
+    }
+
+    /// PNG with the given chunks around a fake image data chunk
+    pub fn png(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend(png_chunk(b"IHDR", &[0; 13]));
+        png.extend(png_chunk(b"IDAT", &[0; 64]));
+        for chunk in chunks {
+            png.extend_from_slice(chunk);
+        }
+        png.extend(png_chunk(b"IEND", &[]));
+        png
+    }
+
+    pub fn text_chunk(keyword: &str, text: &str) -> Vec<u8> {
+        png_chunk(b"tEXt", format!("{}\0{}", keyword, text).as_bytes())
+    }
+
+    pub fn jpeg_segment(marker: u8, data: &[u8]) -> Vec<u8> {
+        let mut segment = vec![0xFF, marker];
+        segment.extend_from_slice(&(data.len() as u16 + 2).to_be_bytes());
+        segment.extend_from_slice(data);
+        segment
+    }
+
+    /// JPEG with the given segments before a fake scan
+    pub fn jpeg(segments: &[Vec<u8>]) -> Vec<u8> {
+        let mut jpeg = vec![0xFF, 0xD8];
+        for segment in segments {
+            jpeg.extend_from_slice(segment);
+        }
+        jpeg.extend(jpeg_segment(0xDA, &[0; 8]));
+        jpeg.extend_from_slice(&[0x12, 0x34, 0xFF, 0xD9]);
+        jpeg
+    }
+
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_png_text_chunks() {
+        let mut ztxt = b"prompt\0\0".to_vec();
+        ztxt.extend(zlib(b"{\"3\": {}}"));
+        let mut itxt = b"workflow\0\x01\0en\0\0".to_vec();
+        itxt.extend(zlib("{\"nodes\": [\"\u{e9}\"]}".as_bytes()));
+        let xmp = format!("{}\0\0\0\0\0<x:xmpmeta/>", XMP_PNG_KEYWORD);
+
+        let file = png(&[
+            text_chunk("parameters", "a cat\nSteps: 20"),
+            png_chunk(b"zTXt", &ztxt),
+            png_chunk(b"iTXt", &itxt),
+            png_chunk(b"iTXt", xmp.as_bytes()),
+            png_chunk(b"caBX", b"jumb"),
+        ]);
+        let metadata = read_metadata(&mut Cursor::new(file)).unwrap();
+
+        assert_eq!(metadata.text.len(), 3);
+        assert_eq!(metadata.text[0], TextChunk {
+            kind: "tEXt",
+            keyword: "parameters".into(),
+            text: "a cat\nSteps: 20".into(),
+        });
+        assert_eq!(metadata.text[1].text, "{\"3\": {}}");
+        assert_eq!(metadata.text[2].text, "{\"nodes\": [\"\u{e9}\"]}");
+        assert_eq!(metadata.xmp, ["<x:xmpmeta/>"]);
+        assert_eq!(metadata.jumbf, [b"jumb".to_vec()]);
+    }
+
+    #[test]
+    fn test_jpeg_xmp_and_jumbf() {
+        let mut xmp = XMP_JPEG_HEADER.to_vec();
+        xmp.extend_from_slice(b"<x:xmpmeta/>");
+        let first = [b"JP\x00\x01\x00\x00\x00\x01".as_slice(), b"\0\0\0\x20jumbfirst"].concat();
+        let second = [b"JP\x00\x01\x00\x00\x00\x02".as_slice(), b"\0\0\0\x20jumb", b"second"].concat();
+
+        let file = jpeg(&[
+            jpeg_segment(0xE0, b"JFIF\0"),
+            jpeg_segment(0xE1, &xmp),
+            jpeg_segment(0xEB, &first),
+            jpeg_segment(0xEB, &second),
+        ]);
+        let metadata = read_metadata(&mut Cursor::new(file)).unwrap();
+
+        assert_eq!(metadata.xmp, ["<x:xmpmeta/>"]);
+        assert_eq!(metadata.jumbf, [b"\0\0\0\x20jumbfirstsecond".to_vec()]);
+    }
+
+    #[test]
+    fn test_webp_chunks() {
+        let mut body = b"WEBP".to_vec();
+        for (kind, data) in [(b"VP8 ", &b"abc"[..]), (b"XMP ", b"<x:xmpmeta/>"), (b"C2PA", b"jumb")] {
+            body.extend_from_slice(kind);
+            body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            body.extend_from_slice(data);
+            if data.len() % 2 == 1 {
+                body.push(0);
+            }
+        }
+        let mut file = b"RIFF".to_vec();
+        file.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        file.extend(body);
+
+        let metadata = read_metadata(&mut Cursor::new(file)).unwrap();
+        assert_eq!(metadata.xmp, ["<x:xmpmeta/>"]);
+        assert_eq!(metadata.jumbf, [b"jumb".to_vec()]);
+    }
+
+    #[test]
+    fn test_truncated_and_unknown_files() {
+        let mut file = png(&[text_chunk("parameters", "Steps: 20")]);
+        file.truncate(file.len() - 20);
+        assert!(read_metadata(&mut Cursor::new(file)).is_err());
+
+        let metadata = read_metadata(&mut Cursor::new(b"GIF89a".to_vec())).unwrap();
+        assert!(metadata.text.is_empty() && metadata.xmp.is_empty());
+    }
+}
@@ -1,8 +1,138 @@
-use fslint_plugin_api::{Plugin, PluginContext, PluginError, PluginMetadata, PluginResult, PluginStatus};
+mod container;
+
+use fslint_plugin_api::{Finding, Plugin, PluginContext, PluginError, PluginMetadata, PluginResult, PluginStatus, Severity};
 use fslint_plugin_sdk::path;
-use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufReader;
+use std::path::Path;
+
+use crate::container::{ImageMetadata, TextChunk};
+
+/// Generator names as they appear in metadata, lowercase, and how to report them
+const GENERATORS: &[(&str, &str)] = &[
+    ("stable diffusion", "Stable Diffusion"),
+    ("stablediffusion", "Stable Diffusion"),
+    ("automatic1111", "Stable Diffusion"),
+    ("comfyui", "ComfyUI"),
+    ("invokeai", "InvokeAI"),
+    ("fooocus", "Fooocus"),
+    ("novelai", "NovelAI"),
+    ("midjourney", "Midjourney"),
+    ("dall-e", "DALL-E"),
+    ("dall·e", "DALL-E"),
+    ("dalle", "DALL-E"),
+    ("chatgpt", "ChatGPT"),
+    ("openai", "OpenAI"),
+    ("firefly", "Adobe Firefly"),
+    ("ideogram", "Ideogram"),
+    ("leonardo.ai", "Leonardo.Ai"),
+    ("bing image creator", "Bing Image Creator"),
+];
+
+/// Phrases that suggest AI without naming a generator
+const GENERIC_MARKERS: &[&str] = &["ai generated", "ai-generated", "artificial intelligence", "generative"];
+
+/// EXIF tags that name the software or describe the image
+const EXIF_TAGS: &[&str] = &[
+    "Software",
+    "ProcessingSoftware",
+    "Artist",
+    "Make",
+    "Model",
+    "ImageDescription",
+    "UserComment",
+];
+
+/// PNG text keywords written by a single generator
+const GENERATOR_KEYWORDS: &[(&str, &str)] = &[
+    ("invokeai_metadata", "InvokeAI"),
+    ("invokeai_graph", "InvokeAI"),
+    ("sd-metadata", "InvokeAI"),
+    ("Dream", "InvokeAI"),
+    ("fooocus_scheme", "Fooocus"),
+];
+
+/// IPTC digital source types; see https://cv.iptc.org/newscodes/digitalsourcetype/
+const SOURCE_TYPE_PREFIX: &str = "digitalsourcetype/";
+const TRAINED_ALGORITHMIC_MEDIA: &str = "trainedAlgorithmicMedia";
+const COMPOSITE_WITH_TRAINED_ALGORITHMIC_MEDIA: &str = "compositeWithTrainedAlgorithmicMedia";
+
+/// How sure a piece of evidence is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    /// Wording that hints at AI
+    Low,
+    /// A generator named where software is recorded
+    Medium,
+    /// Data only a generator writes, or a declared AI source
+    High,
+}
+
+impl Confidence {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+/// One sign that an image was generated
+#[derive(Debug, Clone)]
+pub struct Evidence {
+    /// Kind of metadata: "exif", "png_text", "xmp" or "c2pa"
+    pub source: &'static str,
+    pub generator: Option<&'static str>,
+    pub confidence: Confidence,
+    pub detail: String,
+}
+
+impl Evidence {
+    fn new(source: &'static str, generator: Option<&'static str>, confidence: Confidence, detail: String) -> Self {
+        Self { source, generator, confidence, detail }
+    }
+
+    /// Evidence from free text naming a generator or hinting at AI
+    fn from_text(source: &'static str, field: &str, value: &str) -> Option<Self> {
+        if let Some(generator) = generator_in(value) {
+            let detail = format!("{} is \"{}\"", field, excerpt(value));
+            return Some(Self::new(source, Some(generator), Confidence::Medium, detail));
+        }
+
+        let lower = value.to_lowercase();
+        GENERIC_MARKERS.iter().find(|marker| lower.contains(*marker)).map(|marker| {
+            let detail = format!("{} mentions \"{}\"", field, marker);
+            Self::new(source, None, Confidence::Low, detail)
+        })
+    }
+}
+
+/// What the metadata of an image says about where it came from
+#[derive(Debug, Default)]
+pub struct Provenance {
+    pub evidence: Vec<Evidence>,
+    /// Claim generator of a C2PA manifest, if there is one
+    pub c2pa: Option<String>,
+}
+
+impl Provenance {
+    /// Highest confidence of any evidence
+    pub fn confidence(&self) -> Option<Confidence> {
+        self.evidence.iter().map(|e| e.confidence).max()
+    }
+
+    /// Generator named by the most confident evidence that names one
+    pub fn generator(&self) -> Option<&'static str> {
+        // `max_by_key` keeps the last maximum, so reverse to prefer the first
+        self.evidence
+            .iter()
+            .filter(|e| e.generator.is_some())
+            .rev()
+            .max_by_key(|e| e.confidence)
+            .and_then(|e| e.generator)
+    }
+}
 
 pub struct AiDetectionPlugin;
 
@@ -11,47 +141,109 @@ pub fn new() -> Self {
         Self
     }
 
-    fn check_exif_for_ai(&self, path: &std::path::Path) -> Result<Option<String>, String> {
-        let file = File::open(path).map_err(|e| e.to_string())?;
-        let mut reader = BufReader::new(file);
-
-        let exif_reader = exif::Reader::new();
-        let exif = exif_reader.read_from_container(&mut reader)
-            .map_err(|e| e.to_string())?;
-
-        // Check for common AI generation markers
-        let ai_markers = vec![
-            "Software",
-            "ProcessingSoftware",
-            "Creator",
-            "Model",
-        ];
-
-        for tag_name in ai_markers {
-            if let Some(field) = exif.fields().find(|f| format!("{}", f.tag) == tag_name) {
-                let value = format!("{}", field.display_value());
-                let value_lower = value.to_lowercase();
-
-                // Check for common AI tool markers
-                if value_lower.contains("stable diffusion") ||
-                   value_lower.contains("midjourney") ||
-                   value_lower.contains("dall-e") ||
-                   value_lower.contains("dalle") ||
-                   value_lower.contains("ai generated") ||
-                   value_lower.contains("artificial intelligence") ||
-                   value_lower.contains("generative") {
-                    return Ok(Some(value));
+    /// Collect evidence from EXIF, PNG text chunks, XMP and C2PA manifests
+    pub fn inspect(&self, path: &Path) -> Result<Provenance, String> {
+        let mut provenance = Provenance::default();
+        provenance.evidence.extend(self.check_exif_for_ai(path));
+
+        let mut reader = BufReader::new(File::open(path).map_err(|e| e.to_string())?);
+        // Damaged metadata is no reason to give up on what EXIF showed
+        let metadata = container::read_metadata(&mut reader).unwrap_or_default();
+        self.check_metadata(&metadata, &mut provenance);
+
+        Ok(provenance)
+    }
+
+    fn check_exif_for_ai(&self, path: &Path) -> Vec<Evidence> {
+        let Ok(file) = File::open(path) else {
+            return Vec::new();
+        };
+        let Ok(exif) = exif::Reader::new().read_from_container(&mut BufReader::new(file)) else {
+            return Vec::new();
+        };
+
+        exif.fields()
+            .filter_map(|field| {
+                let tag = field.tag.to_string();
+                if !EXIF_TAGS.contains(&tag.as_str()) {
+                    return None;
+                }
+                let value = field.display_value().to_string();
+                let value = value.trim_matches('"');
+
+                // AUTOMATIC1111 stores its settings here in JPEG and WebP
+                if tag == "UserComment" && is_sd_parameters(value) {
+                    let detail = "UserComment holds Stable Diffusion generation settings".to_string();
+                    return Some(Evidence::new("exif", Some("Stable Diffusion"), Confidence::High, detail));
+                }
+                Evidence::from_text("exif", &tag, value)
+            })
+            .collect()
+    }
+
+    fn check_metadata(&self, metadata: &ImageMetadata, provenance: &mut Provenance) {
+        provenance.evidence.extend(metadata.text.iter().filter_map(check_png_text_chunk));
+
+        for xmp in &metadata.xmp {
+            if let Some(evidence) = source_type_evidence("xmp", xmp, "XMP DigitalSourceType") {
+                provenance.evidence.push(evidence);
+            }
+            for property in ["CreatorTool", "Software", "Agent"] {
+                if let Some(value) = xmp_property(xmp, property) {
+                    provenance.evidence.extend(Evidence::from_text("xmp", &format!("XMP {}", property), &value));
                 }
             }
         }
 
-        Ok(None)
+        for jumbf in metadata.jumbf.iter().filter(|data| is_c2pa(data)) {
+            let generator = c2pa_claim_generator(jumbf);
+            let text = String::from_utf8_lossy(jumbf);
+            if let Some(evidence) = source_type_evidence("c2pa", &text, "C2PA action digitalSourceType") {
+                provenance.evidence.push(evidence);
+            }
+            if let Some(generator) = &generator {
+                provenance.evidence.extend(Evidence::from_text("c2pa", "C2PA claim generator", generator));
+            }
+            provenance.c2pa = Some(generator.unwrap_or_else(|| "unknown generator".to_string()));
+        }
     }
 
-    fn check_png_text_chunks(&self, _path: &std::path::Path) -> Result<Option<String>, String> {
-        // Placeholder for PNG tEXt chunk checking
-        // Would check for Stable Diffusion parameters, etc.
-        Ok(None)
+    fn detection_result(provenance: &Provenance) -> PluginResult {
+        let mut tags = vec!["ai".to_string(), "generated".to_string()];
+        if provenance.c2pa.is_some() {
+            tags.push("c2pa".to_string());
+        }
+
+        let generator = provenance.generator().unwrap_or("unknown generator");
+        let confidence = provenance.confidence().unwrap_or(Confidence::Low);
+        let mut methods: Vec<&str> = provenance.evidence.iter().map(|e| e.source).collect();
+        methods.dedup();
+
+        let mut result = PluginResult::new("ai-detection", PluginStatus::Alert)
+            .with_message(format!("AI-generated ({}, {} confidence)", generator, confidence.as_str()))
+            .with_color("magenta")
+            .with_tags(tags)
+            .with_metadata("ai_tool", generator)
+            .with_metadata("confidence", confidence.as_str())
+            .with_metadata("detection_method", methods.join(","))
+            .with_metadata(
+                "evidence",
+                provenance.evidence.iter().map(|e| e.detail.as_str()).collect::<Vec<_>>().join("; "),
+            );
+        if let Some(c2pa) = &provenance.c2pa {
+            result = result.with_metadata("c2pa_generator", c2pa.as_str());
+        }
+
+        for evidence in &provenance.evidence {
+            let mut finding = Finding::new("ai-generated", Severity::Info, evidence.detail.as_str())
+                .with_data("source", evidence.source)
+                .with_data("confidence", evidence.confidence.as_str());
+            if let Some(generator) = evidence.generator {
+                finding = finding.with_data("generator", generator);
+            }
+            result = result.with_finding(finding);
+        }
+        result
     }
 }
 
@@ -65,8 +257,8 @@ impl Plugin for AiDetectionPlugin {
     fn metadata() -> PluginMetadata {
         PluginMetadata {
             name: "ai-detection".to_string(),
-            version: "0.1.0".to_string(),
-            description: "Detects AI-generated images via EXIF tags and PNG metadata".to_string(),
+            version: "0.2.0".to_string(),
+            description: "Detects AI-generated images via EXIF, PNG text chunks, XMP and C2PA".to_string(),
             author: Some("FSLint Contributors".to_string()),
             enabled_by_default: false,
         }
@@ -83,49 +275,199 @@ fn check(&self, context: &PluginContext) -> Result<PluginResult, PluginError> {
             return Ok(PluginResult::skipped("ai-detection"));
         }
 
-        // Check EXIF data
-        if let Ok(Some(ai_tool)) = self.check_exif_for_ai(&context.path) {
-            let mut result = PluginResult {
-                plugin_name: "ai-detection".to_string(),
-                status: PluginStatus::Alert,
-                message: Some(format!("AI-generated ({})", ai_tool)),
-                color: Some("magenta".to_string()),
-                tags: vec!["ai".to_string(), "generated".to_string()],
-                metadata: HashMap::new(),
-            };
-
-            result.metadata.insert("ai_tool".to_string(), ai_tool);
-            result.metadata.insert("detection_method".to_string(), "exif".to_string());
-
-            return Ok(result);
+        let provenance = self.inspect(&context.path).map_err(PluginError::Execution)?;
+        if !provenance.evidence.is_empty() {
+            return Ok(Self::detection_result(&provenance));
         }
 
-        // Check PNG text chunks
-        if ext.as_deref() == Some("png") {
-            if let Ok(Some(ai_tool)) = self.check_png_text_chunks(&context.path) {
-                let mut result = PluginResult {
-                    plugin_name: "ai-detection".to_string(),
-                    status: PluginStatus::Alert,
-                    message: Some(format!("AI-generated ({})", ai_tool)),
-                    color: Some("magenta".to_string()),
-                    tags: vec!["ai".to_string(), "generated".to_string()],
-                    metadata: HashMap::new(),
-                };
-
-                result.metadata.insert("ai_tool".to_string(), ai_tool);
-                result.metadata.insert("detection_method".to_string(), "png_text".to_string());
-
-                return Ok(result);
-            }
+        // Signed provenance without any sign of AI, e.g. from a camera
+        if let Some(c2pa) = provenance.c2pa {
+            return Ok(PluginResult::active("ai-detection", format!("Content Credentials ({})", c2pa))
+                .with_color("cyan")
+                .with_tags(vec!["c2pa".to_string()])
+                .with_metadata("c2pa_generator", c2pa));
         }
 
         Ok(PluginResult::inactive("ai-detection"))
     }
 }
 
+fn check_png_text_chunk(chunk: &TextChunk) -> Option<Evidence> {
+    let keyword = chunk.keyword.as_str();
+    let text = chunk.text.trim();
+    let looks_like_json = text.starts_with('{');
+
+    match keyword {
+        "parameters" if is_sd_parameters(text) => Some(Evidence::new(
+            "png_text",
+            Some("Stable Diffusion"),
+            Confidence::High,
+            format!("{} '{}' holds Stable Diffusion generation settings", chunk.kind, keyword),
+        )),
+        "parameters" => Some(Evidence::new(
+            "png_text",
+            Some("Stable Diffusion"),
+            Confidence::Medium,
+            format!("{} '{}' holds a prompt", chunk.kind, keyword),
+        )),
+        // The API prompt is keyed by node id with a class_type each; the
+        // editor workflow lists nodes
+        "prompt" | "workflow" if looks_like_json => {
+            let graph = text.contains("\"class_type\"") || text.contains("\"nodes\"");
+            let confidence = if graph { Confidence::High } else { Confidence::Medium };
+            Some(Evidence::new(
+                "png_text",
+                Some("ComfyUI"),
+                confidence,
+                format!("{} '{}' holds a ComfyUI {}", chunk.kind, keyword, if graph { "node graph" } else { "JSON document" }),
+            ))
+        }
+        _ => match GENERATOR_KEYWORDS.iter().find(|(k, _)| *k == keyword) {
+            Some((_, generator)) => Some(Evidence::new(
+                "png_text",
+                Some(generator),
+                Confidence::High,
+                format!("{} '{}' is written by {}", chunk.kind, keyword, generator),
+            )),
+            None => Evidence::from_text("png_text", &format!("{} '{}'", chunk.kind, keyword), text),
+        },
+    }
+}
+
+/// AUTOMATIC1111-style settings: the prompt, then "Steps: 20, Sampler: ..."
+fn is_sd_parameters(text: &str) -> bool {
+    text.contains("Steps: ") && (text.contains("Sampler: ") || text.contains("Seed: "))
+}
+
+fn generator_in(text: &str) -> Option<&'static str> {
+    let lower = text.to_lowercase();
+    GENERATORS
+        .iter()
+        .find(|(needle, _)| lower.contains(needle))
+        .map(|(_, generator)| *generator)
+}
+
+fn excerpt(value: &str) -> String {
+    const MAX_CHARS: usize = 60;
+    let value = value.trim();
+    if value.chars().count() <= MAX_CHARS {
+        value.to_string()
+    } else {
+        format!("{}…", value.chars().take(MAX_CHARS).collect::<String>())
+    }
+}
+
+/// Evidence from an IPTC digital source type anywhere in `text`
+fn source_type_evidence(source: &'static str, text: &str, field: &str) -> Option<Evidence> {
+    let source_types: Vec<&str> = text
+        .match_indices(SOURCE_TYPE_PREFIX)
+        .map(|(i, prefix)| {
+            let rest = &text[i + prefix.len()..];
+            let end = rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(rest.len());
+            &rest[..end]
+        })
+        .collect();
+
+    let (source_type, confidence) = if source_types.contains(&TRAINED_ALGORITHMIC_MEDIA) {
+        (TRAINED_ALGORITHMIC_MEDIA, Confidence::High)
+    } else if source_types.contains(&COMPOSITE_WITH_TRAINED_ALGORITHMIC_MEDIA) {
+        (COMPOSITE_WITH_TRAINED_ALGORITHMIC_MEDIA, Confidence::Medium)
+    } else {
+        return None;
+    };
+    Some(Evidence::new(source, None, confidence, format!("{} is {}", field, source_type)))
+}
+
+/// Value of an XMP property, written as an attribute or an element
+fn xmp_property(xmp: &str, name: &str) -> Option<String> {
+    let attribute = format!(":{}=", name);
+    if let Some(i) = xmp.find(&attribute) {
+        let rest = &xmp[i + attribute.len()..];
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &rest[1..];
+        return value.find(quote).map(|end| value[..end].to_string());
+    }
+
+    let open = format!(":{}>", name);
+    let i = xmp.find(&open)?;
+    let rest = &xmp[i + open.len()..];
+    let value = &rest[..rest.find('<')?];
+    Some(value.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// A JUMBF box labelled as a C2PA manifest store
+fn is_c2pa(jumbf: &[u8]) -> bool {
+    find(jumbf, b"jumd").is_some() && find(jumbf, b"c2pa").is_some()
+}
+
+/// Claim generator of a C2PA manifest, from its CBOR-encoded claim
+///
+/// Version 1 claims have a `claim_generator` string, version 2 a
+/// `claim_generator_info` map with a `name`.
+fn c2pa_claim_generator(jumbf: &[u8]) -> Option<String> {
+    if let Some(generator) = cbor_text_after(jumbf, "claim_generator") {
+        return Some(generator);
+    }
+    let info = find(jumbf, &cbor_text("claim_generator_info"))?;
+    cbor_text_after(&jumbf[info..], "name")
+}
+
+/// The CBOR text string following the CBOR text string `key`
+fn cbor_text_after(data: &[u8], key: &str) -> Option<String> {
+    let key = cbor_text(key);
+    let start = find(data, &key)? + key.len();
+    let (&header, rest) = data[start..].split_first()?;
+
+    let (length, rest) = match header {
+        0x60..=0x77 => (usize::from(header - 0x60), rest),
+        0x78 => (usize::from(*rest.first()?), &rest[1..]),
+        0x79 => (usize::from(u16::from_be_bytes(rest.get(..2)?.try_into().ok()?)), &rest[2..]),
+        _ => return None,
+    };
+    std::str::from_utf8(rest.get(..length)?).ok().map(str::to_string)
+}
+
+fn cbor_text(text: &str) -> Vec<u8> {
+    let mut encoded = match text.len() {
+        len @ 0..=23 => vec![0x60 + len as u8],
+        len => vec![0x78, len as u8],
+    };
+    encoded.extend_from_slice(text.as_bytes());
+    encoded
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::container::tests::{jpeg, jpeg_segment, png, png_chunk, text_chunk};
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    const TRAINED: &str = "http://cv.iptc.org/newscodes/digitalsourcetype/trainedAlgorithmicMedia";
+
+    fn check(dir: &TempDir, name: &str, content: &[u8]) -> PluginResult {
+        let path = dir.path().join(name);
+        fs::write(&path, content).unwrap();
+        let context = PluginContext::new(path.clone(), fs::metadata(&path).unwrap(), PathBuf::from("."));
+        AiDetectionPlugin::new().check(&context).unwrap()
+    }
+
+    /// A minimal C2PA manifest store with a claim and an actions assertion
+    fn c2pa_manifest(claim_generator: &str, source_type: Option<&str>) -> Vec<u8> {
+        let mut manifest = b"\0\0\x01\0jumb\0\0\0\x1ajumdc2pa\0\x11\0\x10\0\xaa\0\x38\x9b\x71\x3dc2pa\0".to_vec();
+        manifest.extend(cbor_text("claim_generator"));
+        manifest.extend(cbor_text(claim_generator));
+        if let Some(source_type) = source_type {
+            manifest.extend(cbor_text("digitalSourceType"));
+            manifest.extend(cbor_text(source_type));
+        }
+        manifest
+    }
 
     #[test]
     fn test_plugin_metadata() {
@@ -133,4 +475,92 @@ fn test_plugin_metadata() {
         assert_eq!(metadata.name, "ai-detection");
         assert!(!metadata.enabled_by_default);
     }
+
+    #[test]
+    fn test_stable_diffusion_parameters() {
+        let dir = TempDir::new().unwrap();
+        let parameters = "a lighthouse at dusk\nSteps: 30, Sampler: DPM++ 2M Karras, CFG scale: 7, Seed: 42";
+        let result = check(&dir, "sd.png", &png(&[text_chunk("parameters", parameters)]));
+
+        assert_eq!(result.status, PluginStatus::Alert);
+        assert_eq!(result.message.as_deref(), Some("AI-generated (Stable Diffusion, high confidence)"));
+        assert_eq!(result.metadata["detection_method"], "png_text");
+        assert_eq!(result.findings.len(), 1);
+        assert_eq!(result.findings[0].rule, "ai-generated");
+    }
+
+    #[test]
+    fn test_comfyui_chunks() {
+        let dir = TempDir::new().unwrap();
+        let file = png(&[
+            text_chunk("prompt", r#"{"3": {"class_type": "KSampler", "inputs": {}}}"#),
+            text_chunk("workflow", r#"{"last_node_id": 9, "nodes": []}"#),
+        ]);
+        let result = check(&dir, "comfy.png", &file);
+
+        assert_eq!(result.metadata["ai_tool"], "ComfyUI");
+        assert_eq!(result.metadata["confidence"], "high");
+        assert_eq!(result.findings.len(), 2);
+    }
+
+    #[test]
+    fn test_xmp_digital_source_type() {
+        let dir = TempDir::new().unwrap();
+        let xmp = format!(
+            "http://ns.adobe.com/xap/1.0/\0<x:xmpmeta><rdf:Description xmp:CreatorTool=\"Adobe Firefly\" \
+             Iptc4xmpExt:DigitalSourceType=\"{}\"/></x:xmpmeta>",
+            TRAINED
+        );
+        let result = check(&dir, "firefly.jpg", &jpeg(&[jpeg_segment(0xE1, xmp.as_bytes())]));
+
+        assert_eq!(result.message.as_deref(), Some("AI-generated (Adobe Firefly, high confidence)"));
+        assert_eq!(result.metadata["detection_method"], "xmp");
+        assert!(result.metadata["evidence"].contains("XMP DigitalSourceType is trainedAlgorithmicMedia"));
+    }
+
+    #[test]
+    fn test_c2pa_manifest() {
+        let dir = TempDir::new().unwrap();
+        let manifest = c2pa_manifest("ChatGPT", Some(TRAINED));
+        let result = check(&dir, "openai.png", &png(&[png_chunk(b"caBX", &manifest)]));
+
+        assert_eq!(result.message.as_deref(), Some("AI-generated (ChatGPT, high confidence)"));
+        assert_eq!(result.metadata["c2pa_generator"], "ChatGPT");
+        assert!(result.tags.contains(&"c2pa".to_string()));
+
+        // Content Credentials alone are no sign of AI
+        let manifest = c2pa_manifest("Adobe Photoshop 25.0", None);
+        let result = check(&dir, "edited.png", &png(&[png_chunk(b"caBX", &manifest)]));
+        assert_eq!(result.status, PluginStatus::Active);
+        assert_eq!(result.message.as_deref(), Some("Content Credentials (Adobe Photoshop 25.0)"));
+    }
+
+    #[test]
+    fn test_exif_software() {
+        // Big-endian TIFF with a single Software entry
+        let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01\x01\x31\0\x02\0\0\0\x0b\0\0\0\x1a\0\0\0\0".to_vec();
+        exif.extend_from_slice(b"Midjourney\0");
+
+        let dir = TempDir::new().unwrap();
+        let result = check(&dir, "mj.jpg", &jpeg(&[jpeg_segment(0xE1, &exif)]));
+        assert_eq!(result.message.as_deref(), Some("AI-generated (Midjourney, medium confidence)"));
+        assert_eq!(result.metadata["detection_method"], "exif");
+    }
+
+    #[test]
+    fn test_plain_image() {
+        let dir = TempDir::new().unwrap();
+        let result = check(&dir, "photo.png", &png(&[text_chunk("Software", "GIMP 2.10")]));
+        assert_eq!(result.status, PluginStatus::Inactive);
+    }
+
+    #[test]
+    fn test_xmp_property() {
+        assert_eq!(xmp_property("<a xmp:CreatorTool='Tool'/>", "CreatorTool").as_deref(), Some("Tool"));
+        assert_eq!(
+            xmp_property("<xmp:CreatorTool> Tool </xmp:CreatorTool>", "CreatorTool").as_deref(),
+            Some("Tool")
+        );
+        assert_eq!(xmp_property("<a/>", "CreatorTool"), None);
+    }
 }
@@ -1,12 +1,68 @@
-use fslint_plugin_api::{Plugin, PluginContext, PluginError, PluginMetadata, PluginResult, PluginStatus};
-use sha2::{Sha256, Digest};
+use fslint_plugin_api::{
+    Finding, Plugin, PluginContext, PluginError, PluginMetadata, PluginResult, ScanState, Severity,
+};
+use fslint_plugin_sdk::metadata::format_size;
+use rayon::prelude::*;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufReader, Read};
-use std::sync::Mutex;
+use std::hash::Hash;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
-lazy_static::lazy_static! {
-    static ref FILE_HASHES: Mutex<HashMap<String, Vec<String>>> = Mutex::new(HashMap::new());
+/// Bytes hashed at each end of a file by the partial-hash stage
+const PARTIAL_BYTES: u64 = 64 * 1024;
+
+/// A file that may have duplicates
+struct Candidate {
+    path: PathBuf,
+    size: u64,
+    /// BLAKE3 of the first and last `PARTIAL_BYTES`
+    partial_hash: Option<String>,
+    /// BLAKE3 of the whole file
+    hash: Option<String>,
+    /// Device and inode, where the platform has them
+    file_id: Option<(u64, u64)>,
+    /// Other paths hard linked to this one, which are not extra copies
+    links: Vec<PathBuf>,
+}
+
+impl Candidate {
+    fn ensure_partial_hash(&mut self) -> io::Result<()> {
+        if self.partial_hash.is_none() {
+            self.partial_hash = Some(hash_ends(&self.path, self.size)?);
+        }
+        Ok(())
+    }
+
+    fn ensure_hash(&mut self) -> io::Result<()> {
+        if self.hash.is_none() {
+            // Small files were read whole by the partial stage
+            self.hash = match &self.partial_hash {
+                Some(partial) if self.size <= 2 * PARTIAL_BYTES => Some(partial.clone()),
+                _ => Some(hash_file(&self.path)?),
+            };
+        }
+        Ok(())
+    }
+
+    /// Inactive result carrying the hashes known so far, for the cache
+    fn result(&self) -> PluginResult {
+        let mut result = PluginResult::inactive("duplicate-finder");
+        if let Some(partial) = &self.partial_hash {
+            result = result.with_metadata("partial_hash", partial.as_str());
+        }
+        if let Some(hash) = &self.hash {
+            result = result.with_metadata("hash", hash.as_str());
+        }
+        result
+    }
+}
+
+/// Files seen in one scan
+#[derive(Default)]
+struct DuplicateScan {
+    candidates: Mutex<Vec<Candidate>>,
 }
 
 pub struct DuplicateFinderPlugin {
@@ -24,35 +80,46 @@ pub fn with_min_size(min_size: u64) -> Self {
         Self { min_size }
     }
 
-    fn calculate_hash(&self, path: &std::path::Path) -> Result<String, String> {
-        let file = File::open(path).map_err(|e| e.to_string())?;
-        let mut reader = BufReader::new(file);
-        let mut hasher = Sha256::new();
-        let mut buffer = [0; 8192];
+    /// Result for one member of a group of identical files; only the
+    /// first member carries the group's wasted bytes, so they add up once
+    fn duplicate_result(group: &[Candidate], member: &Candidate) -> PluginResult {
+        let hash = member.hash.clone().unwrap_or_default();
+        let first = group.first().is_some_and(|c| c.path == member.path);
+        let wasted = member.size * (group.len() as u64 - 1);
+        let others: Vec<String> = group
+            .iter()
+            .filter(|c| c.path != member.path)
+            .map(|c| c.path.to_string_lossy().to_string())
+            .collect();
 
-        loop {
-            let count = reader.read(&mut buffer).map_err(|e| e.to_string())?;
-            if count == 0 {
-                break;
-            }
-            hasher.update(&buffer[..count]);
-        }
-
-        let result = hasher.finalize();
-        Ok(hex::encode(result))
-    }
-
-    fn check_duplicate(&self, path: &std::path::Path, hash: &str) -> Option<Vec<String>> {
-        let mut hashes = FILE_HASHES.lock().unwrap();
-
-        if let Some(paths) = hashes.get(hash) {
-            // Found duplicate
-            Some(paths.clone())
+        let mut finding = Finding::new(
+            "duplicate-file",
+            Severity::Warning,
+            format!("Same content as {}", others.join(", ")),
+        )
+        .with_fix("Keep one copy and remove or link the others, e.g. with `fslint dedupe`")
+        .with_data("copies", group.len())
+        .with_data("hash", hash.as_str());
+        let message = if first {
+            finding = finding.with_data("wasted_bytes", wasted);
+            format!("Duplicate ({} copies, {} wasted)", group.len(), format_size(wasted))
         } else {
-            // First occurrence
-            hashes.insert(hash.to_string(), vec![path.to_string_lossy().to_string()]);
-            None
+            format!("Duplicate ({} copies)", group.len())
+        };
+
+        let mut result = PluginResult::from_findings("duplicate-finder", vec![finding])
+            .with_message(message)
+            .with_tags(vec!["duplicate".to_string()])
+            .with_metadata("hash", hash)
+            .with_metadata("duplicate_count", group.len().to_string())
+            .with_metadata("duplicates", others.join(";"));
+        if first {
+            result = result.with_metadata("wasted_bytes", wasted.to_string());
         }
+        if let Some(partial) = &member.partial_hash {
+            result = result.with_metadata("partial_hash", partial.as_str());
+        }
+        result
     }
 }
 
@@ -66,8 +133,8 @@ impl Plugin for DuplicateFinderPlugin {
     fn metadata() -> PluginMetadata {
         PluginMetadata {
             name: "duplicate-finder".to_string(),
-            version: "0.1.0".to_string(),
-            description: "Finds duplicate files using SHA-256 hash comparison".to_string(),
+            version: "0.3.0".to_string(),
+            description: "Finds duplicate files by size, then partial and full BLAKE3 hashes".to_string(),
             author: Some("FSLint Contributors".to_string()),
             enabled_by_default: false,
         }
@@ -81,35 +148,29 @@ fn check(&self, context: &PluginContext) -> Result<PluginResult, PluginError> {
             return Ok(PluginResult::skipped("duplicate-finder"));
         }
 
-        // Calculate hash
-        let hash = self.calculate_hash(&context.path)
-            .map_err(|e| PluginError::Execution(format!("Failed to hash file: {}", e)))?;
-
-        // Check for duplicates
-        match self.check_duplicate(&context.path, &hash) {
-            Some(duplicate_paths) => {
-                let count = duplicate_paths.len();
-                let mut result = PluginResult {
-                    plugin_name: "duplicate-finder".to_string(),
-                    status: PluginStatus::Warning,
-                    message: Some(format!("Duplicate ({} copies)", count + 1)),
-                    color: Some("red".to_string()),
-                    tags: vec!["duplicate".to_string()],
-                    metadata: HashMap::new(),
-                };
-
-                result.metadata.insert("hash".to_string(), hash);
-                result.metadata.insert("duplicate_count".to_string(), (count + 1).to_string());
-                result.metadata.insert("duplicates".to_string(), duplicate_paths.join(";"));
-
-                Ok(result)
-            }
-            None => {
-                let mut result = PluginResult::inactive("duplicate-finder");
-                result.metadata.insert("hash".to_string(), hash);
-                Ok(result)
-            }
+        // Hashing waits for `finish_scan`, and only happens for files whose
+        // size is shared; hashes from an earlier scan are reused
+        let known = |key: &str| {
+            context.previous.as_ref().and_then(|r| r.metadata.get(key)).cloned()
+        };
+        let candidate = Candidate {
+            path: context.path.clone(),
+            size: file_size,
+            partial_hash: known("partial_hash"),
+            hash: known("hash"),
+            file_id: file_id(&context.metadata),
+            links: Vec::new(),
+        };
+        let result = candidate.result();
+
+        if let Some(scan) = context.state::<DuplicateScan>() {
+            scan.candidates
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(candidate);
         }
+
+        Ok(result)
     }
 
     fn initialize(&mut self, config: &HashMap<String, String>) -> Result<(), PluginError> {
@@ -120,16 +181,162 @@ fn initialize(&mut self, config: &HashMap<String, String>) -> Result<(), PluginE
         Ok(())
     }
 
-    fn cleanup(&mut self) -> Result<(), PluginError> {
-        // Clear the hash cache
-        FILE_HASHES.lock().unwrap().clear();
-        Ok(())
+    fn begin_scan(&self, _root: &Path) -> Result<Option<ScanState>, PluginError> {
+        Ok(Some(Arc::new(DuplicateScan::default())))
+    }
+
+    fn finish_scan(&self, state: &ScanState) -> Result<HashMap<PathBuf, PluginResult>, PluginError> {
+        let scan = state
+            .downcast_ref::<DuplicateScan>()
+            .ok_or_else(|| PluginError::Execution("Unexpected scan state".to_string()))?;
+        let candidates = std::mem::take(&mut *scan.candidates.lock().unwrap_or_else(|e| e.into_inner()));
+
+        // Hard links to one file are a single copy: keep the first path and
+        // hash only that
+        let candidates = collapse_links(candidates);
+
+        // Each stage only looks at files still sharing a key with another
+        let (pending, _) = partition_shared(candidates, |c| c.size);
+        let pending = hashed(pending, Candidate::ensure_partial_hash);
+        let (pending, mut unique) = partition_shared(pending, |c| (c.size, c.partial_hash.clone()));
+        let pending = hashed(pending, Candidate::ensure_hash);
+        let (duplicates, rest) = partition_shared(pending, |c| (c.size, c.hash.clone()));
+        unique.extend(rest);
+
+        // Hard links share the hashes of the path they were folded into
+        let mut results: HashMap<PathBuf, PluginResult> = unique
+            .iter()
+            .flat_map(|c| c.links.iter().chain([&c.path]).map(|path| (path.clone(), c.result())))
+            .collect();
+
+        let mut groups: HashMap<Option<String>, Vec<Candidate>> = HashMap::new();
+        for candidate in duplicates {
+            groups.entry(candidate.hash.clone()).or_default().push(candidate);
+        }
+        for mut group in groups.into_values() {
+            // Checks ran in parallel; list copies in a stable order
+            group.sort_by(|a, b| a.path.cmp(&b.path));
+            for member in &group {
+                results.insert(member.path.clone(), Self::duplicate_result(&group, member));
+                for link in &member.links {
+                    results.insert(link.clone(), member.result());
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    fn cacheable(&self) -> bool {
+        // Only hashes are reused, through `PluginContext::previous`;
+        // duplicates are worked out afresh on every scan
+        true
     }
 }
 
+/// Fold candidates that are hard links to one file into the first by path
+fn collapse_links(mut candidates: Vec<Candidate>) -> Vec<Candidate> {
+    candidates.sort_by(|a, b| a.path.cmp(&b.path));
+    let mut seen: HashMap<(u64, u64), usize> = HashMap::new();
+    let mut kept: Vec<Candidate> = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        match candidate.file_id.and_then(|id| seen.get(&id)) {
+            Some(&index) => kept[index].links.push(candidate.path),
+            None => {
+                if let Some(id) = candidate.file_id {
+                    seen.insert(id, kept.len());
+                }
+                kept.push(candidate);
+            }
+        }
+    }
+    kept
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Split candidates into those sharing `key` with another and the rest
+fn partition_shared<K: Eq + Hash>(
+    candidates: Vec<Candidate>,
+    key: impl Fn(&Candidate) -> K,
+) -> (Vec<Candidate>, Vec<Candidate>) {
+    let mut counts: HashMap<K, usize> = HashMap::new();
+    for candidate in &candidates {
+        *counts.entry(key(candidate)).or_default() += 1;
+    }
+    candidates
+        .into_iter()
+        .partition(|candidate| counts[&key(candidate)] > 1)
+}
+
+/// Run a hashing stage in parallel, dropping files that cannot be read
+fn hashed(candidates: Vec<Candidate>, stage: fn(&mut Candidate) -> io::Result<()>) -> Vec<Candidate> {
+    candidates
+        .into_par_iter()
+        .filter_map(|mut candidate| match stage(&mut candidate) {
+            Ok(()) => Some(candidate),
+            Err(e) => {
+                eprintln!("Warning: Failed to hash {:?}: {}", candidate.path, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// BLAKE3 of the first and last `PARTIAL_BYTES` of a file; the whole
+/// file if it is no larger than both together
+fn hash_ends(path: &Path, size: u64) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut buffer = Vec::with_capacity(2 * PARTIAL_BYTES as usize);
+    (&mut file).take(PARTIAL_BYTES).read_to_end(&mut buffer)?;
+    if size > PARTIAL_BYTES {
+        file.seek(SeekFrom::Start(size.saturating_sub(PARTIAL_BYTES).max(PARTIAL_BYTES)))?;
+        file.take(PARTIAL_BYTES).read_to_end(&mut buffer)?;
+    }
+    Ok(blake3::hash(&buffer).to_hex().to_string())
+}
+
+/// BLAKE3 of a whole file
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fslint_plugin_api::PluginStatus;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Run one scan over `names` in `dir`, offering `previous` results
+    fn scan(
+        plugin: &DuplicateFinderPlugin,
+        dir: &Path,
+        names: &[&str],
+        previous: &HashMap<PathBuf, PluginResult>,
+    ) -> HashMap<PathBuf, PluginResult> {
+        let state = plugin.begin_scan(dir).unwrap().unwrap();
+        for name in names {
+            let path = dir.join(name);
+            let mut context =
+                PluginContext::new(path.clone(), fs::metadata(&path).unwrap(), dir.to_path_buf());
+            context.scan_state = Some(state.clone());
+            context.previous = previous.get(&path).cloned();
+            assert_eq!(plugin.check(&context).unwrap().status, PluginStatus::Inactive);
+        }
+        plugin.finish_scan(&state).unwrap()
+    }
 
     #[test]
     fn test_plugin_metadata() {
@@ -147,4 +354,105 @@ fn test_min_size_config() {
         plugin.initialize(&config).unwrap();
         assert_eq!(plugin.min_size, 2048);
     }
+
+    #[test]
+    fn test_every_copy_reported() {
+        let temp_dir = TempDir::new().unwrap();
+        for (name, content) in [("a.txt", "same"), ("b.txt", "same"), ("c.txt", "same"), ("d.txt", "diff"), ("e.txt", "unique size")] {
+            fs::write(temp_dir.path().join(name), content).unwrap();
+        }
+        let names = ["a.txt", "b.txt", "c.txt", "d.txt", "e.txt"];
+
+        let plugin = DuplicateFinderPlugin::with_min_size(0);
+        let results = scan(&plugin, temp_dir.path(), &names, &HashMap::new());
+
+        let first = &results[&temp_dir.path().join("a.txt")];
+        assert_eq!(first.status, PluginStatus::Warning);
+        assert_eq!(first.message.as_deref(), Some("Duplicate (3 copies, 8 B wasted)"));
+        assert_eq!(first.metadata["wasted_bytes"], "8");
+        assert_eq!(first.findings[0].rule, "duplicate-file");
+
+        // Wasted bytes are reported once per group
+        let second = &results[&temp_dir.path().join("b.txt")];
+        assert_eq!(second.status, PluginStatus::Warning);
+        assert_eq!(second.message.as_deref(), Some("Duplicate (3 copies)"));
+        assert!(!second.metadata.contains_key("wasted_bytes"));
+        assert!(!second.findings[0].data.contains_key("wasted_bytes"));
+
+        // Same size, so hashed, but not a duplicate
+        let other = &results[&temp_dir.path().join("d.txt")];
+        assert_eq!(other.status, PluginStatus::Inactive);
+        assert!(other.metadata.contains_key("partial_hash"));
+
+        // A unique size is never read
+        assert!(!results.contains_key(&temp_dir.path().join("e.txt")));
+
+        // Nothing carries over into the next scan
+        assert_eq!(scan(&plugin, temp_dir.path(), &names, &HashMap::new()).len(), 4);
+    }
+
+    #[test]
+    fn test_full_hash_after_partial_match() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut content = vec![0u8; 4 * PARTIAL_BYTES as usize];
+        fs::write(temp_dir.path().join("a.bin"), &content).unwrap();
+        fs::write(temp_dir.path().join("b.bin"), &content).unwrap();
+        // Same ends, different middle
+        content[2 * PARTIAL_BYTES as usize] = 1;
+        fs::write(temp_dir.path().join("c.bin"), &content).unwrap();
+
+        let plugin = DuplicateFinderPlugin::new();
+        let results = scan(&plugin, temp_dir.path(), &["a.bin", "b.bin", "c.bin"], &HashMap::new());
+
+        let partials: Vec<&String> = results.values().map(|r| &r.metadata["partial_hash"]).collect();
+        assert!(partials.iter().all(|p| *p == partials[0]));
+        assert_eq!(results[&temp_dir.path().join("a.bin")].status, PluginStatus::Warning);
+        assert_eq!(results[&temp_dir.path().join("b.bin")].status, PluginStatus::Warning);
+        assert_eq!(results[&temp_dir.path().join("c.bin")].status, PluginStatus::Inactive);
+    }
+
+    #[test]
+    fn test_previous_hashes_reused() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "aaaa").unwrap();
+        fs::write(temp_dir.path().join("b.txt"), "bbbb").unwrap();
+
+        // Hashes from an earlier scan are trusted, so these two now match
+        let previous: HashMap<PathBuf, PluginResult> = ["a.txt", "b.txt"]
+            .iter()
+            .map(|name| {
+                let result = PluginResult::inactive("duplicate-finder")
+                    .with_metadata("partial_hash", "cached")
+                    .with_metadata("hash", "cached");
+                (temp_dir.path().join(name), result)
+            })
+            .collect();
+
+        let plugin = DuplicateFinderPlugin::with_min_size(0);
+        let results = scan(&plugin, temp_dir.path(), &["a.txt", "b.txt"], &previous);
+        assert_eq!(results[&temp_dir.path().join("a.txt")].metadata["hash"], "cached");
+        assert_eq!(results[&temp_dir.path().join("a.txt")].status, PluginStatus::Warning);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hard_links_are_one_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "same").unwrap();
+        fs::hard_link(temp_dir.path().join("a.txt"), temp_dir.path().join("b.txt")).unwrap();
+
+        let plugin = DuplicateFinderPlugin::with_min_size(0);
+        let results = scan(&plugin, temp_dir.path(), &["a.txt", "b.txt"], &HashMap::new());
+        assert!(results.is_empty());
+
+        // A real copy makes two copies, not three
+        fs::write(temp_dir.path().join("c.txt"), "same").unwrap();
+        let results = scan(&plugin, temp_dir.path(), &["a.txt", "b.txt", "c.txt"], &HashMap::new());
+        let first = &results[&temp_dir.path().join("a.txt")];
+        assert_eq!(first.message.as_deref(), Some("Duplicate (2 copies, 4 B wasted)"));
+        assert_eq!(results[&temp_dir.path().join("c.txt")].status, PluginStatus::Warning);
+        let link = &results[&temp_dir.path().join("b.txt")];
+        assert_eq!(link.status, PluginStatus::Inactive);
+        assert_eq!(link.metadata["hash"], first.metadata["hash"]);
+    }
 }
@@ -70,14 +70,10 @@ fn check(&self, context: &PluginContext) -> Result<PluginResult, PluginError> {
             format!("Modified {} days ago", age_days)
         };
 
-        let mut result = PluginResult {
-            plugin_name: "file-age".to_string(),
-            status,
-            message: Some(message),
-            color: Some(color.to_string()),
-            tags: vec!["age".to_string()],
-            metadata: HashMap::new(),
-        };
+        let mut result = PluginResult::new("file-age", status)
+            .with_message(message)
+            .with_color(color)
+            .with_tags(vec!["age".to_string()]);
 
         result.metadata.insert("age_days".to_string(), age_days.to_string());
         result.metadata.insert("category".to_string(), category.to_string());
@@ -92,6 +88,11 @@ fn initialize(&mut self, config: &HashMap<String, String>) -> Result<(), PluginE
         }
         Ok(())
     }
+
+    fn cacheable(&self) -> bool {
+        // Age grows every day the file stays the same
+        false
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,5 @@
 use fslint_plugin_api::{Plugin, PluginContext, PluginError, PluginMetadata, PluginResult, PluginStatus};
 use git2::{Repository, Status, StatusOptions};
-use std::collections::HashMap;
 use std::path::Path;
 
 pub struct GitStatusPlugin;
@@ -102,20 +101,21 @@ fn check(&self, context: &PluginContext) -> Result<PluginResult, PluginError> {
 
         let (message, color, plugin_status) = Self::status_to_message(status);
 
-        let mut result = PluginResult {
-            plugin_name: "git-status".to_string(),
-            status: plugin_status,
-            message: Some(message.clone()),
-            color: Some(color),
-            tags: vec!["git".to_string()],
-            metadata: HashMap::new(),
-        };
+        let mut result = PluginResult::new("git-status", plugin_status)
+            .with_message(message.clone())
+            .with_color(color)
+            .with_tags(vec!["git".to_string()]);
 
         result.metadata.insert("branch".to_string(), branch);
         result.metadata.insert("status".to_string(), message);
 
         Ok(result)
     }
+
+    fn cacheable(&self) -> bool {
+        // Status changes with the index, not the file
+        false
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,5 @@
 use fslint_plugin_api::{Plugin, PluginContext, PluginError, PluginMetadata, PluginResult, PluginStatus};
 use fslint_plugin_sdk::{path, patterns};
-use std::collections::HashMap;
 
 pub struct GroupingPlugin;
 
@@ -126,14 +125,10 @@ fn metadata() -> PluginMetadata {
     fn check(&self, context: &PluginContext) -> Result<PluginResult, PluginError> {
         match self.detect_group(context) {
             Some((group_name, color, tag)) => {
-                let mut result = PluginResult {
-                    plugin_name: "grouping".to_string(),
-                    status: PluginStatus::Active,
-                    message: Some(group_name.clone()),
-                    color: Some(color),
-                    tags: vec!["group".to_string(), tag.clone()],
-                    metadata: HashMap::new(),
-                };
+                let mut result = PluginResult::new("grouping", PluginStatus::Active)
+                    .with_message(group_name.clone())
+                    .with_color(color)
+                    .with_tags(vec!["group".to_string(), tag.clone()]);
 
                 result.metadata.insert("group".to_string(), group_name);
                 result.metadata.insert("group_tag".to_string(), tag);
@@ -164,12 +159,7 @@ fn test_detect_node_modules() {
         let path = PathBuf::from("/project/node_modules/package/index.js");
         let metadata = std::fs::metadata(".").unwrap(); // Dummy metadata
 
-        let context = PluginContext {
-            path,
-            metadata,
-            working_dir: PathBuf::from("/project"),
-            shared_context: HashMap::new(),
-        };
+        let context = PluginContext::new(path, metadata, PathBuf::from("/project"));
 
         let result = plugin.check(&context).unwrap();
         assert_eq!(result.status, PluginStatus::Active);
@@ -1,6 +1,67 @@
-use fslint_plugin_api::{Plugin, PluginContext, PluginError, PluginMetadata, PluginResult, PluginStatus};
+use fslint_plugin_api::{
+    Finding, Plugin, PluginContext, PluginError, PluginMetadata, PluginResult, PluginStatus, Severity,
+};
 use fslint_plugin_sdk::path;
-use std::collections::HashMap;
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Share of the page an image must cover to count as a scanned page
+const FULL_PAGE_COVERAGE: f32 = 0.8;
+
+/// What a page of a PDF holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PageKind {
+    /// Shows extractable text
+    Text,
+    /// No text, but an image covering (nearly) the whole page
+    Scanned,
+    /// No text, only smaller images
+    Image,
+    /// Neither text nor images
+    Blank,
+}
+
+/// Text layer of a whole document
+#[derive(Debug, Default)]
+struct TextLayer {
+    pages: u32,
+    text_pages: u32,
+    scanned_pages: u32,
+    /// Pages with images but no text, numbered from 1
+    needs_ocr: Vec<u32>,
+}
+
+impl TextLayer {
+    fn add(&mut self, number: u32, kind: PageKind) {
+        self.pages += 1;
+        match kind {
+            PageKind::Text => self.text_pages += 1,
+            PageKind::Scanned => {
+                self.scanned_pages += 1;
+                self.needs_ocr.push(number);
+            }
+            PageKind::Image => self.needs_ocr.push(number),
+            PageKind::Blank => {}
+        }
+    }
+
+    /// "none", "partial" or "full"; blank pages never need a text layer,
+    /// so a document of only blank pages is "blank"
+    fn status(&self) -> &'static str {
+        if self.text_pages == 0 && self.needs_ocr.is_empty() {
+            "blank"
+        } else if self.text_pages == 0 {
+            "none"
+        } else if self.needs_ocr.is_empty() {
+            "full"
+        } else {
+            "partial"
+        }
+    }
+}
 
 pub struct OcrStatusPlugin;
 
@@ -9,15 +70,68 @@ pub fn new() -> Self {
         Self
     }
 
-    fn check_pdf_ocr(&self, _path: &std::path::Path) -> Result<bool, String> {
-        // Placeholder implementation
-        // In a real implementation, we would:
-        // 1. Open the PDF
-        // 2. Check if it contains extractable text
-        // 3. Compare text content vs image content ratio
+    fn check_pdf_ocr(&self, path: &Path) -> Result<TextLayer, String> {
+        let mut doc = Document::load(path).map_err(|e| e.to_string())?;
+        if doc.is_encrypted() {
+            // Many PDFs are encrypted with an empty user password
+            doc.decrypt("").map_err(|_| "Encrypted PDF".to_string())?;
+        }
 
-        // For now, we'll return a placeholder
-        Err("PDF OCR detection not yet implemented".to_string())
+        let mut layer = TextLayer::default();
+        for (number, page_id) in doc.get_pages() {
+            let kind = classify_page(&doc, number, page_id)
+                .map_err(|e| format!("Page {}: {}", number, e))?;
+            layer.add(number, kind);
+        }
+        Ok(layer)
+    }
+
+    fn layer_result(layer: &TextLayer) -> PluginResult {
+        // Nothing to search or to OCR
+        if layer.status() == "blank" {
+            return PluginResult::inactive("ocr-status")
+                .with_message("No text or images")
+                .with_tags(vec!["pdf".to_string()])
+                .with_metadata("ocr_status", "blank")
+                .with_metadata("pages", layer.pages.to_string());
+        }
+
+        let (message, color, status) = match layer.status() {
+            "full" => ("Fully searchable".to_string(), "green", PluginStatus::Active),
+            "partial" => (
+                format!("Partial text layer ({} of {} pages)", layer.text_pages, layer.pages),
+                "yellow",
+                PluginStatus::Alert,
+            ),
+            _ => ("No text layer (needs OCR)".to_string(), "yellow", PluginStatus::Alert),
+        };
+
+        let mut result = PluginResult::new("ocr-status", status)
+            .with_message(message)
+            .with_color(color)
+            .with_tags(vec!["pdf".to_string(), "ocr".to_string()])
+            .with_metadata("ocr_status", layer.status())
+            .with_metadata("has_ocr", (layer.status() == "full").to_string())
+            .with_metadata("pages", layer.pages.to_string())
+            .with_metadata("text_pages", layer.text_pages.to_string())
+            .with_metadata("scanned_pages", layer.scanned_pages.to_string());
+
+        if !layer.needs_ocr.is_empty() {
+            let pages = page_ranges(&layer.needs_ocr);
+            result = result
+                .with_metadata("pages_without_text", pages.as_str())
+                .with_finding(
+                    Finding::new(
+                        "missing-text-layer",
+                        Severity::Warning,
+                        format!("No text on page {}", pages),
+                    )
+                    .with_fix("Run OCR on the document, e.g. `ocrmypdf --skip-text in.pdf out.pdf`")
+                    .with_data("pages", pages)
+                    .with_data("count", layer.needs_ocr.len()),
+                );
+        }
+        result
     }
 }
 
@@ -31,7 +145,7 @@ impl Plugin for OcrStatusPlugin {
     fn metadata() -> PluginMetadata {
         PluginMetadata {
             name: "ocr-status".to_string(),
-            version: "0.1.0".to_string(),
+            version: "0.2.0".to_string(),
             description: "Detects OCR status in PDFs (text layer present/absent)".to_string(),
             author: Some("FSLint Contributors".to_string()),
             enabled_by_default: false,
@@ -46,37 +160,470 @@ fn check(&self, context: &PluginContext) -> Result<PluginResult, PluginError> {
         }
 
         match self.check_pdf_ocr(&context.path) {
-            Ok(has_ocr) => {
-                let (message, color, status) = if has_ocr {
-                    ("PDF with text layer", "green", PluginStatus::Active)
+            Ok(layer) => Ok(Self::layer_result(&layer)),
+            Err(e) => Ok(PluginResult::warning("ocr-status", format!("Unreadable PDF: {}", e))
+                .with_tags(vec!["pdf".to_string()])),
+        }
+    }
+}
+
+/// What a page's content draws, including the forms it invokes
+#[derive(Debug, Default)]
+struct Drawn {
+    /// Glyphs shown by the page's own content stream
+    page_text: bool,
+    /// Glyphs shown inside form XObjects
+    form_text: bool,
+    /// Area of the largest image, in default user space
+    largest_image: f32,
+}
+
+impl Drawn {
+    /// Images fill the unit square, scaled by the CTM
+    fn add_image(&mut self, ctm: Matrix) {
+        self.largest_image = self.largest_image.max((ctm[0] * ctm[3] - ctm[1] * ctm[2]).abs());
+    }
+}
+
+/// Work out what one page holds from its content stream
+fn classify_page(doc: &Document, number: u32, page_id: ObjectId) -> lopdf::Result<PageKind> {
+    let (own, inherited) = doc.get_page_resources(page_id).unwrap_or((None, Vec::new()));
+    let resources: Vec<&Dictionary> = own
+        .into_iter()
+        .chain(inherited.into_iter().filter_map(|id| doc.get_dictionary(id).ok()))
+        .collect();
+
+    let mut drawn = Drawn::default();
+    let content = doc.get_page_content(page_id)?;
+    walk_content(doc, &content, &resources, IDENTITY, &mut HashSet::new(), &mut drawn)?;
+
+    // Glyphs that extract to nothing, like fonts without a Unicode mapping,
+    // are not searchable. Extraction does not look inside forms, and if it
+    // fails, trust the operators.
+    let has_text = drawn.form_text
+        || drawn.page_text
+            && doc
+                .extract_text(&[number])
+                .map(|text| text.chars().any(|c| !c.is_whitespace()))
+                .unwrap_or(true);
+
+    let largest_image = drawn.largest_image;
+    Ok(if has_text {
+        PageKind::Text
+    } else if largest_image > 0.0 && largest_image >= FULL_PAGE_COVERAGE * page_area(doc, page_id) {
+        PageKind::Scanned
+    } else if largest_image > 0.0 {
+        PageKind::Image
+    } else {
+        PageKind::Blank
+    })
+}
+
+/// Follow one content stream drawn at `base`, descending into the forms it
+/// invokes. `forms` holds the forms being drawn, so one that invokes itself,
+/// directly or not, is not followed again.
+fn walk_content(
+    doc: &Document,
+    content: &[u8],
+    resources: &[&Dictionary],
+    base: Matrix,
+    forms: &mut HashSet<ObjectId>,
+    drawn: &mut Drawn,
+) -> lopdf::Result<()> {
+    let content = Content::decode(&strip_inline_images(content))?;
+    let in_form = !forms.is_empty();
+
+    let mut ctm = base;
+    let mut saved = Vec::new();
+    for operation in &content.operations {
+        match operation.operator.as_str() {
+            "q" => saved.push(ctm),
+            "Q" => ctm = saved.pop().unwrap_or(base),
+            "cm" => ctm = multiply(matrix(&operation.operands).unwrap_or(IDENTITY), ctm),
+            "Tj" | "TJ" | "'" | "\"" if operation.operands.iter().any(has_glyphs) => {
+                if in_form {
+                    drawn.form_text = true;
                 } else {
-                    ("PDF without text (needs OCR)", "yellow", PluginStatus::Alert)
+                    drawn.page_text = true;
+                }
+            }
+            // Left in place of each inline image by `strip_inline_images`
+            "BI" => drawn.add_image(ctm),
+            "Do" => {
+                let Some((id, stream)) = operation
+                    .operands
+                    .first()
+                    .and_then(|o| o.as_name().ok())
+                    .and_then(|name| xobject(doc, resources, name))
+                else {
+                    continue;
                 };
+                match stream.dict.get(b"Subtype").and_then(Object::as_name) {
+                    Ok(b"Image") => drawn.add_image(ctm),
+                    Ok(b"Form") => {
+                        let Some(id) = id.filter(|id| forms.insert(*id)) else {
+                            continue;
+                        };
+                        let form_matrix = deref_array(doc, stream.dict.get(b"Matrix").ok())
+                            .and_then(|values| matrix(values))
+                            .unwrap_or(IDENTITY);
+                        // Forms without their own resources use the page's
+                        let form_resources = match deref_dict(doc, stream.dict.get(b"Resources").ok()) {
+                            Some(own) => vec![own],
+                            None => resources.to_vec(),
+                        };
+                        let form_content = stream
+                            .decompressed_content()
+                            .unwrap_or_else(|_| stream.content.clone());
 
-                let mut result = PluginResult {
-                    plugin_name: "ocr-status".to_string(),
-                    status,
-                    message: Some(message.to_string()),
-                    color: Some(color.to_string()),
-                    tags: vec!["pdf".to_string(), "ocr".to_string()],
-                    metadata: HashMap::new(),
-                };
+                        let walked = walk_content(
+                            doc,
+                            &form_content,
+                            &form_resources,
+                            multiply(form_matrix, ctm),
+                            forms,
+                            drawn,
+                        );
+                        forms.remove(&id);
+                        walked?;
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
 
-                result.metadata.insert("has_ocr".to_string(), has_ocr.to_string());
+/// Replace each inline image, `BI` … `ID` data `EI`, with a bare `BI`
+/// operator, since the content parser cannot read the image data
+fn strip_inline_images(content: &[u8]) -> Cow<'_, [u8]> {
+    let is_space = |c: u8| b" \t\r\n\x0c\0".contains(&c);
+    let is_delimiter = |c: u8| is_space(c) || b"()<>[]{}/%".contains(&c);
+    // Whether `token` stands on its own at `at`
+    let token_at = |at: usize, token: &[u8]| {
+        content[at..].starts_with(token)
+            && (at == 0 || is_delimiter(content[at - 1]))
+            && content.get(at + token.len()).is_none_or(|&c| is_delimiter(c))
+    };
 
-                Ok(result)
+    let mut stripped = Vec::new();
+    let mut copied = 0;
+    let mut i = 0;
+    while i < content.len() {
+        match content[i] {
+            b'(' => i = string_end(content, i),
+            b'%' => {
+                while i < content.len() && !b"\r\n".contains(&content[i]) {
+                    i += 1;
+                }
             }
-            Err(_e) => {
-                // OCR detection not available or failed
-                Ok(PluginResult::skipped("ocr-status"))
+            _ if token_at(i, b"BI") => {
+                // One white-space byte separates `ID` from the data, and the
+                // data ends at the first `EI` standing on its own
+                let end = (i + 2..content.len())
+                    .find(|&at| token_at(at, b"ID"))
+                    .and_then(|id| (id + 3..content.len()).find(|&at| is_space(content[at - 1]) && token_at(at, b"EI")));
+                let Some(end) = end else {
+                    break;
+                };
+                stripped.extend_from_slice(&content[copied..i]);
+                stripped.extend_from_slice(b"BI");
+                copied = end + 2;
+                i = copied;
             }
+            _ => i += 1,
+        }
+    }
+
+    if copied == 0 {
+        Cow::Borrowed(content)
+    } else {
+        stripped.extend_from_slice(&content[copied..]);
+        Cow::Owned(stripped)
+    }
+}
+
+/// Index just past the literal string opening at `start`
+fn string_end(content: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i < content.len() {
+        match content[i] {
+            b'\\' => i += 1,
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    content.len()
+}
+
+/// XObject `name` and its object id, from the first resources that have it
+fn xobject<'a>(
+    doc: &'a Document,
+    resources: &[&'a Dictionary],
+    name: &[u8],
+) -> Option<(Option<ObjectId>, &'a lopdf::Stream)> {
+    resources.iter().find_map(|resources| {
+        let object = deref_dict(doc, resources.get(b"XObject").ok())?.get(name).ok()?;
+        let (id, object) = doc.dereference(object).ok()?;
+        Some((id, object.as_stream().ok()?))
+    })
+}
+
+type Matrix = [f32; 6];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// `m` applied before `n`
+fn multiply(m: Matrix, n: Matrix) -> Matrix {
+    [
+        m[0] * n[0] + m[1] * n[2],
+        m[0] * n[1] + m[1] * n[3],
+        m[2] * n[0] + m[3] * n[2],
+        m[2] * n[1] + m[3] * n[3],
+        m[4] * n[0] + m[5] * n[2] + n[4],
+        m[4] * n[1] + m[5] * n[3] + n[5],
+    ]
+}
+
+/// Six numbers as a matrix, as in `cm` operands or a form's `/Matrix`
+fn matrix(values: &[Object]) -> Option<Matrix> {
+    let m: Vec<f32> = values.iter().filter_map(|o| o.as_float().ok()).collect();
+    match m[..] {
+        [a, b, c, d, e, f] => Some([a, b, c, d, e, f]),
+        _ => None,
+    }
+}
+
+fn has_glyphs(operand: &Object) -> bool {
+    match operand {
+        Object::String(bytes, _) => !bytes.is_empty(),
+        Object::Array(items) => items.iter().any(has_glyphs),
+        _ => false,
+    }
+}
+
+/// Area of the page's media box, which may be inherited from the page tree
+fn page_area(doc: &Document, page_id: ObjectId) -> f32 {
+    let mut node = doc.get_dictionary(page_id).ok();
+    let mut seen = HashSet::new();
+    while let Some(dict) = node {
+        let corners: Option<Vec<f32>> = deref_array(doc, dict.get(b"MediaBox").ok())
+            .map(|values| values.iter().filter_map(|v| v.as_float().ok()).collect());
+        if let Some([x0, y0, x1, y1]) = corners.as_deref() {
+            return ((x1 - x0) * (y1 - y0)).abs();
         }
+
+        node = dict
+            .get(b"Parent")
+            .and_then(Object::as_reference)
+            .ok()
+            .filter(|id| seen.insert(*id))
+            .and_then(|id| doc.get_dictionary(id).ok());
     }
+
+    // US Letter, the PDF default
+    612.0 * 792.0
+}
+
+fn deref_dict<'a>(doc: &'a Document, object: Option<&'a Object>) -> Option<&'a Dictionary> {
+    doc.dereference(object?).ok()?.1.as_dict().ok()
+}
+
+fn deref_array<'a>(doc: &'a Document, object: Option<&'a Object>) -> Option<&'a Vec<Object>> {
+    doc.dereference(object?).ok()?.1.as_array().ok()
+}
+
+/// Page numbers as ranges, e.g. "1-3, 7"
+fn page_ranges(pages: &[u32]) -> String {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for &page in pages {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == page => *end = page,
+            _ => ranges.push((page, page)),
+        }
+    }
+
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use lopdf::content::Operation;
+    use lopdf::{dictionary, Stream};
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    /// Page content for generated fixtures
+    enum Fixture {
+        Text(&'static str),
+        /// Image scaled to this share of the page's width and height
+        Image(f32),
+        /// Inline image, scaled the same way
+        InlineImage(f32),
+        /// Full-page image inside a form whose `/Matrix` scales it by this
+        Form(f32),
+        /// Text inside a form
+        FormText(&'static str),
+        /// Full-page image inside a form that also invokes itself
+        FormCycle,
+        Blank,
+    }
+
+    /// Draw the image XObject `Im1` over this share of the page
+    fn draw_image(scale: f32) -> Vec<Operation> {
+        vec![
+            Operation::new("q", vec![]),
+            Operation::new(
+                "cm",
+                vec![(612.0 * scale).into(), 0.into(), 0.into(), (792.0 * scale).into(), 0.into(), 0.into()],
+            ),
+            Operation::new("Do", vec!["Im1".into()]),
+            Operation::new("Q", vec![]),
+        ]
+    }
+
+    fn show_text(text: &str) -> Vec<Operation> {
+        vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 12.into()]),
+            Operation::new("Td", vec![72.into(), 720.into()]),
+            Operation::new("Tj", vec![Object::string_literal(text)]),
+            Operation::new("ET", vec![]),
+        ]
+    }
+
+    /// Write a Letter-sized PDF with one page per fixture
+    fn write_pdf(path: &Path, pages: &[Fixture]) {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        // 2x2 grey pixels standing in for a scan
+        let image_id = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 2,
+                "Height" => 2,
+                "ColorSpace" => "DeviceGray",
+                "BitsPerComponent" => 8,
+            },
+            vec![0x80; 4],
+        ));
+
+        let mut kids = Vec::new();
+        for page in pages {
+            let form_id = doc.new_object_id();
+            let (form_operations, form_scale) = match page {
+                Fixture::Form(scale) => (Some(draw_image(1.0)), *scale),
+                Fixture::FormText(text) => (Some(show_text(text)), 1.0),
+                Fixture::FormCycle => {
+                    let mut operations = vec![Operation::new("Do", vec!["Fm1".into()])];
+                    operations.extend(draw_image(1.0));
+                    (Some(operations), 1.0)
+                }
+                _ => (None, 1.0),
+            };
+            if let Some(operations) = form_operations {
+                let form = Stream::new(
+                    dictionary! {
+                        "Type" => "XObject",
+                        "Subtype" => "Form",
+                        "BBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                        "Matrix" => vec![form_scale.into(), 0.into(), 0.into(), form_scale.into(), 0.into(), 0.into()],
+                        "Resources" => dictionary! {
+                            "Font" => dictionary! { "F1" => font_id },
+                            "XObject" => dictionary! { "Im1" => image_id, "Fm1" => form_id },
+                        },
+                    },
+                    Content { operations }.encode().unwrap(),
+                );
+                doc.objects.insert(form_id, Object::Stream(form));
+            }
+
+            let content = match page {
+                Fixture::Text(text) => Content { operations: show_text(text) }.encode().unwrap(),
+                Fixture::Image(scale) => Content { operations: draw_image(*scale) }.encode().unwrap(),
+                // 2x2 grey pixels, with bytes the content parser would choke on
+                Fixture::InlineImage(scale) => {
+                    let mut content = format!(
+                        "q {} 0 0 {} 0 0 cm BI /W 2 /H 2 /CS /G /BPC 8 ID ",
+                        612.0 * scale,
+                        792.0 * scale
+                    )
+                    .into_bytes();
+                    content.extend_from_slice(b"\xff)(\x80 EI Q");
+                    content
+                }
+                Fixture::Form(_) | Fixture::FormText(_) | Fixture::FormCycle => b"/Fm1 Do".to_vec(),
+                Fixture::Blank => Vec::new(),
+            };
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+            let mut xobjects = dictionary! { "Im1" => image_id };
+            if doc.objects.contains_key(&form_id) {
+                xobjects.set("Fm1", form_id);
+            }
+            kids.push(Object::Reference(doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+                "Resources" => dictionary! {
+                    "Font" => dictionary! { "F1" => font_id },
+                    "XObject" => xobjects,
+                },
+            })));
+        }
+
+        // Media box inherited from the page tree
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Count" => kids.len() as i64,
+                "Kids" => kids,
+                "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.save(path).unwrap();
+    }
+
+    fn check(dir: &TempDir, name: &str, pages: &[Fixture]) -> PluginResult {
+        let path = dir.path().join(name);
+        write_pdf(&path, pages);
+        let context = PluginContext::new(path.clone(), fs::metadata(&path).unwrap(), PathBuf::from("."));
+        OcrStatusPlugin::new().check(&context).unwrap()
+    }
 
     #[test]
     fn test_plugin_metadata() {
@@ -84,4 +631,110 @@ fn test_plugin_metadata() {
         assert_eq!(metadata.name, "ocr-status");
         assert!(!metadata.enabled_by_default);
     }
+
+    #[test]
+    fn test_fully_searchable() {
+        let dir = TempDir::new().unwrap();
+        let result = check(&dir, "text.pdf", &[Fixture::Text("Invoice"), Fixture::Text("Page two"), Fixture::Blank]);
+
+        assert_eq!(result.status, PluginStatus::Active);
+        assert_eq!(result.message.as_deref(), Some("Fully searchable"));
+        assert_eq!(result.metadata["pages"], "3");
+        assert_eq!(result.metadata["text_pages"], "2");
+        assert_eq!(result.metadata["has_ocr"], "true");
+        assert!(result.findings.is_empty());
+    }
+
+    #[test]
+    fn test_scanned_without_text() {
+        let dir = TempDir::new().unwrap();
+        let result = check(&dir, "scan.pdf", &[Fixture::Image(1.0), Fixture::Image(0.95)]);
+
+        assert_eq!(result.status, PluginStatus::Alert);
+        assert_eq!(result.message.as_deref(), Some("No text layer (needs OCR)"));
+        assert_eq!(result.metadata["ocr_status"], "none");
+        assert_eq!(result.metadata["scanned_pages"], "2");
+        assert_eq!(result.metadata["pages_without_text"], "1-2");
+        assert_eq!(result.findings[0].rule, "missing-text-layer");
+    }
+
+    #[test]
+    fn test_partial_text_layer() {
+        let dir = TempDir::new().unwrap();
+        let result = check(
+            &dir,
+            "mixed.pdf",
+            &[Fixture::Text("Cover letter"), Fixture::Image(1.0), Fixture::Text("Appendix"), Fixture::Image(0.3)],
+        );
+
+        assert_eq!(result.message.as_deref(), Some("Partial text layer (2 of 4 pages)"));
+        assert_eq!(result.metadata["ocr_status"], "partial");
+        // The small image is not a scan, but still has no text
+        assert_eq!(result.metadata["scanned_pages"], "1");
+        assert_eq!(result.metadata["pages_without_text"], "2, 4");
+    }
+
+    #[test]
+    fn test_images_inside_forms() {
+        let dir = TempDir::new().unwrap();
+        let result = check(&dir, "forms.pdf", &[Fixture::Form(1.0), Fixture::Form(0.5), Fixture::FormCycle]);
+
+        assert_eq!(result.metadata["ocr_status"], "none");
+        // The form's matrix shrinks the second page's image to a quarter
+        assert_eq!(result.metadata["scanned_pages"], "2");
+        assert_eq!(result.metadata["pages_without_text"], "1-3");
+    }
+
+    #[test]
+    fn test_text_inside_form() {
+        let dir = TempDir::new().unwrap();
+        let result = check(&dir, "form-text.pdf", &[Fixture::FormText("Letterhead")]);
+        assert_eq!(result.metadata["ocr_status"], "full");
+    }
+
+    #[test]
+    fn test_inline_images() {
+        let dir = TempDir::new().unwrap();
+        let result = check(&dir, "inline.pdf", &[Fixture::InlineImage(1.0), Fixture::InlineImage(0.3)]);
+
+        assert_eq!(result.status, PluginStatus::Alert);
+        assert_eq!(result.metadata["scanned_pages"], "1");
+        assert_eq!(result.metadata["pages_without_text"], "1-2");
+    }
+
+    #[test]
+    fn test_blank_document() {
+        let dir = TempDir::new().unwrap();
+        let result = check(&dir, "blank.pdf", &[Fixture::Blank, Fixture::Blank]);
+
+        assert_eq!(result.status, PluginStatus::Inactive);
+        assert_eq!(result.message.as_deref(), Some("No text or images"));
+        assert_eq!(result.metadata["ocr_status"], "blank");
+        assert!(result.findings.is_empty());
+    }
+
+    #[test]
+    fn test_strip_inline_images() {
+        let content = b"(BI ID EI) Tj BI /W 1 ID \x01EI EI Q";
+        assert_eq!(&strip_inline_images(content)[..], b"(BI ID EI) Tj BI Q");
+        assert!(matches!(strip_inline_images(b"q Q"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_unreadable_pdf() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("broken.pdf");
+        fs::write(&path, "not a pdf").unwrap();
+        let context = PluginContext::new(path.clone(), fs::metadata(&path).unwrap(), PathBuf::from("."));
+
+        let result = OcrStatusPlugin::new().check(&context).unwrap();
+        assert_eq!(result.status, PluginStatus::Warning);
+        assert!(result.message.unwrap().starts_with("Unreadable PDF"));
+    }
+
+    #[test]
+    fn test_page_ranges() {
+        assert_eq!(page_ranges(&[1, 2, 3, 7, 9, 10]), "1-3, 7, 9-10");
+        assert_eq!(page_ranges(&[4]), "4");
+    }
 }
@@ -0,0 +1,373 @@
+//! Detection rules in gitleaks' TOML format
+//!
+//! Supported are `[[rules]]` with `id`, `description`, `regex`, `secretGroup`,
+//! `entropy`, `keywords` and `path`, rule and global allowlists (both the
+//! `[allowlist]` and `[[allowlists]]` forms) and `[extend] useDefault`.
+//! Other keys, like `tags`, are accepted and ignored. Rules may also set a
+//! `severity`, which gitleaks does not know.
+
+use fslint_plugin_api::Severity;
+use regex::Regex;
+use serde::Deserialize;
+use std::path::Path;
+
+const DEFAULT_RULES: &str = include_str!("../rules/default.toml");
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RulesFile {
+    #[serde(default)]
+    extend: Extend,
+    #[serde(default)]
+    rules: Vec<RuleConfig>,
+    allowlist: Option<AllowlistConfig>,
+    #[serde(default)]
+    allowlists: Vec<AllowlistConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Extend {
+    #[serde(default)]
+    use_default: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RuleConfig {
+    id: String,
+    description: Option<String>,
+    regex: Option<String>,
+    secret_group: Option<usize>,
+    entropy: Option<f64>,
+    #[serde(default)]
+    keywords: Vec<String>,
+    path: Option<String>,
+    severity: Option<Severity>,
+    allowlist: Option<AllowlistConfig>,
+    #[serde(default)]
+    allowlists: Vec<AllowlistConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AllowlistConfig {
+    #[serde(default)]
+    condition: Condition,
+    #[serde(default)]
+    regexes: Vec<String>,
+    #[serde(default)]
+    regex_target: RegexTarget,
+    #[serde(default)]
+    paths: Vec<String>,
+    #[serde(default)]
+    stopwords: Vec<String>,
+    /// Only for global allowlists: the rules it applies to, all if empty
+    #[serde(default)]
+    target_rules: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+enum Condition {
+    /// Any check allows a finding
+    #[default]
+    Or,
+    /// All configured checks must
+    And,
+}
+
+/// What allowlist regexes are matched against
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegexTarget {
+    #[default]
+    Secret,
+    Match,
+    Line,
+}
+
+/// A candidate secret, as allowlists see it
+pub struct Candidate<'a> {
+    pub rule: &'a str,
+    pub path: &'a Path,
+    pub secret: &'a str,
+    /// Whole regex match
+    pub matched: &'a str,
+    pub line: &'a str,
+}
+
+#[derive(Debug)]
+pub struct Allowlist {
+    condition: Condition,
+    regexes: Vec<Regex>,
+    regex_target: RegexTarget,
+    paths: Vec<Regex>,
+    stopwords: Vec<String>,
+    target_rules: Vec<String>,
+}
+
+impl Allowlist {
+    fn compile(config: AllowlistConfig) -> Result<Self, String> {
+        Ok(Self {
+            condition: config.condition,
+            regexes: compile_all(&config.regexes)?,
+            regex_target: config.regex_target,
+            paths: compile_all(&config.paths)?,
+            stopwords: config.stopwords.iter().map(|s| s.to_lowercase()).collect(),
+            target_rules: config.target_rules,
+        })
+    }
+
+    /// Whether the allowlist lets the candidate through
+    pub fn allows(&self, candidate: &Candidate) -> bool {
+        if !self.target_rules.is_empty() && !self.target_rules.iter().any(|r| r == candidate.rule) {
+            return false;
+        }
+
+        let target = match self.regex_target {
+            RegexTarget::Secret => candidate.secret,
+            RegexTarget::Match => candidate.matched,
+            RegexTarget::Line => candidate.line,
+        };
+        let path = candidate.path.to_string_lossy();
+        let secret = candidate.secret.to_lowercase();
+
+        // Unconfigured checks neither allow nor block
+        let checks = [
+            (!self.regexes.is_empty()).then(|| self.regexes.iter().any(|r| r.is_match(target))),
+            (!self.paths.is_empty()).then(|| self.paths.iter().any(|r| r.is_match(&path))),
+            (!self.stopwords.is_empty()).then(|| self.stopwords.iter().any(|w| secret.contains(w.as_str()))),
+        ];
+        let mut configured = checks.into_iter().flatten().peekable();
+        if configured.peek().is_none() {
+            return false;
+        }
+        match self.condition {
+            Condition::Or => configured.any(|allowed| allowed),
+            Condition::And => configured.all(|allowed| allowed),
+        }
+    }
+
+    /// Whether the allowlist skips the whole file, whatever is in it
+    fn allows_path(&self, path: &Path) -> bool {
+        let only_paths = self.regexes.is_empty() && self.stopwords.is_empty() && self.target_rules.is_empty();
+        let path = path.to_string_lossy();
+        only_paths && self.paths.iter().any(|r| r.is_match(&path))
+    }
+}
+
+#[derive(Debug)]
+pub struct Rule {
+    pub id: String,
+    pub description: String,
+    pub regex: Option<Regex>,
+    secret_group: Option<usize>,
+    /// Least Shannon entropy of the secret
+    pub entropy: Option<f64>,
+    /// Lowercase; a line must contain one of them, if any are given
+    keywords: Vec<String>,
+    path: Option<Regex>,
+    pub severity: Severity,
+    allowlists: Vec<Allowlist>,
+}
+
+impl Rule {
+    fn compile(config: RuleConfig) -> Result<Self, String> {
+        let context = |e: String| format!("Rule '{}': {}", config.id, e);
+        if config.regex.is_none() && config.path.is_none() {
+            return Err(context("needs a regex or a path".to_string()));
+        }
+
+        let allowlists = config
+            .allowlist
+            .into_iter()
+            .chain(config.allowlists)
+            .map(Allowlist::compile)
+            .collect::<Result<_, _>>()
+            .map_err(context)?;
+
+        Ok(Self {
+            description: config.description.unwrap_or_else(|| config.id.clone()),
+            regex: config.regex.as_deref().map(compile).transpose().map_err(context)?,
+            secret_group: config.secret_group,
+            entropy: config.entropy,
+            keywords: config.keywords.iter().map(|k| k.to_lowercase()).collect(),
+            path: config.path.as_deref().map(compile).transpose().map_err(context)?,
+            severity: config.severity.unwrap_or(Severity::Error),
+            allowlists,
+            id: config.id,
+        })
+    }
+
+    /// Whether the rule looks at files at this path at all
+    pub fn applies_to(&self, path: &Path) -> bool {
+        self.path.as_ref().is_none_or(|r| r.is_match(&path.to_string_lossy()))
+    }
+
+    /// Whether the line may hold a match, going by the rule's keywords
+    pub fn wants_line(&self, lowercase_line: &str) -> bool {
+        self.keywords.is_empty() || self.keywords.iter().any(|k| lowercase_line.contains(k.as_str()))
+    }
+
+    /// The secret within a match: `secretGroup`, else the first group that
+    /// matched, else the whole match
+    pub fn secret<'a>(&self, captures: &regex::Captures<'a>) -> Option<regex::Match<'a>> {
+        match self.secret_group {
+            Some(group) => captures.get(group),
+            None => captures.iter().skip(1).flatten().next().or_else(|| captures.get(0)),
+        }
+    }
+
+    pub fn allows(&self, candidate: &Candidate) -> bool {
+        self.allowlists.iter().any(|a| a.allows(candidate))
+    }
+}
+
+/// Rules and global allowlists
+#[derive(Debug)]
+pub struct RuleSet {
+    pub rules: Vec<Rule>,
+    allowlists: Vec<Allowlist>,
+}
+
+impl RuleSet {
+    /// The built-in rules
+    pub fn builtin() -> Self {
+        Self::parse(DEFAULT_RULES).expect("built-in secret rules are valid")
+    }
+
+    /// Load rules from a gitleaks-style TOML file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read rules {:?}: {}", path, e))?;
+        Self::parse(&content).map_err(|e| format!("Invalid rules {:?}: {}", path, e))
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let file: RulesFile = toml::from_str(content).map_err(|e| e.to_string())?;
+        let use_default = file.extend.use_default;
+
+        let mut set = Self {
+            rules: file.rules.into_iter().map(Rule::compile).collect::<Result<_, _>>()?,
+            allowlists: file
+                .allowlist
+                .into_iter()
+                .chain(file.allowlists)
+                .map(Allowlist::compile)
+                .collect::<Result<_, _>>()?,
+        };
+
+        if use_default {
+            // Rules of the same id override the built-in ones
+            let builtin = Self::builtin();
+            let own: Vec<String> = set.rules.iter().map(|r| r.id.clone()).collect();
+            set.rules.extend(builtin.rules.into_iter().filter(|r| !own.contains(&r.id)));
+            set.allowlists.extend(builtin.allowlists);
+        }
+        Ok(set)
+    }
+
+    /// Whether a global allowlist lets the candidate through
+    pub fn allows(&self, candidate: &Candidate) -> bool {
+        self.allowlists.iter().any(|a| a.allows(candidate))
+    }
+
+    /// Whether a global allowlist skips the whole file
+    pub fn skips_path(&self, path: &Path) -> bool {
+        self.allowlists.iter().any(|a| a.allows_path(path))
+    }
+}
+
+fn compile(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| e.to_string())
+}
+
+fn compile_all(patterns: &[String]) -> Result<Vec<Regex>, String> {
+    patterns.iter().map(|p| compile(p)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate<'a>(rule: &'a str, path: &'a str, secret: &'a str) -> Candidate<'a> {
+        Candidate { rule, path: Path::new(path), secret, matched: secret, line: secret }
+    }
+
+    #[test]
+    fn test_builtin_rules() {
+        let set = RuleSet::builtin();
+        assert_eq!(set.rules.len(), 10);
+        assert!(set.skips_path(Path::new("/repo/web/package-lock.json")));
+        assert!(!set.skips_path(Path::new("/repo/web/package.json")));
+    }
+
+    #[test]
+    fn test_gitleaks_rules_file() {
+        let set = RuleSet::parse(
+            r#"
+            [extend]
+            useDefault = true
+
+            [[rules]]
+            id = "internal-token"
+            description = "Internal Token"
+            regex = '''itk_([a-z0-9]{24})'''
+            keywords = ["itk_"]
+            tags = ["internal"]
+            severity = "warning"
+
+            [rules.allowlist]
+            stopwords = ["test"]
+
+            [[allowlists]]
+            targetRules = ["internal-token"]
+            paths = ['''fixtures/''']
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(set.rules.len(), 11);
+        let rule = &set.rules[0];
+        assert_eq!(rule.severity, Severity::Warning);
+        assert!(rule.wants_line("token = itk_..."));
+        assert!(!rule.wants_line("nothing here"));
+
+        let captures = rule.regex.as_ref().unwrap().captures("itk_abcdefghijklmnopqrstuvwx").unwrap();
+        assert_eq!(rule.secret(&captures).unwrap().as_str(), "abcdefghijklmnopqrstuvwx");
+
+        assert!(rule.allows(&candidate("internal-token", "src/a.rs", "test0000")));
+        assert!(!rule.allows(&candidate("internal-token", "src/a.rs", "live0000")));
+        assert!(set.allows(&candidate("internal-token", "tests/fixtures/a.rs", "live0000")));
+        assert!(!set.allows(&candidate("aws-access-key", "tests/fixtures/a.rs", "live0000")));
+    }
+
+    #[test]
+    fn test_allowlist_and_condition() {
+        let set = RuleSet::parse(
+            r#"
+            [[rules]]
+            id = "token"
+            regex = '''tok_\w+'''
+
+            [[allowlists]]
+            condition = "AND"
+            regexTarget = "line"
+            regexes = ['''mock''']
+            paths = ['''tests/''']
+            "#,
+        )
+        .unwrap();
+
+        let line = |line| Candidate { rule: "token", path: Path::new("tests/a.rs"), secret: "tok_1", matched: "tok_1", line };
+        assert!(set.allows(&line("let mock = tok_1;")));
+        assert!(!set.allows(&line("let live = tok_1;")));
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(RuleSet::parse("[[rules]]\nid = \"empty\"\n").unwrap_err().contains("needs a regex"));
+        assert!(RuleSet::parse("[[rules]]\nid = \"bad\"\nregex = '(('\n").is_err());
+    }
+}
@@ -1,8 +1,14 @@
-use fslint_plugin_api::{Plugin, PluginContext, PluginError, PluginMetadata, PluginResult, PluginStatus};
+use fslint_plugin_api::{
+    Finding, Plugin, PluginContext, PluginError, PluginMetadata, PluginResult, PluginStatus,
+    ScanState, Severity,
+};
 use fslint_plugin_sdk::path;
 use regex::Regex;
 use lazy_static::lazy_static;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
 lazy_static! {
     static ref VERSION_PATTERNS: Vec<Regex> = vec![
@@ -14,6 +20,20 @@
     ];
 }
 
+/// A file as one member of a version family
+struct Member {
+    path: PathBuf,
+    version: Option<String>,
+    priority: i32,
+    modified: Option<SystemTime>,
+}
+
+/// Files seen in one scan, grouped by directory and unversioned name
+#[derive(Default)]
+struct VersionScan {
+    families: Mutex<HashMap<(PathBuf, String), Vec<Member>>>,
+}
+
 pub struct VersionDetectionPlugin;
 
 impl VersionDetectionPlugin {
@@ -40,6 +60,68 @@ fn detect_version(&self, filename: &str) -> Option<(String, i32)> {
         None
     }
 
+    /// File name without its version marker, e.g. `report.txt` for
+    /// `report_v2.txt` or `report (1).txt`
+    fn base_name(&self, filename: &str) -> String {
+        for pattern in VERSION_PATTERNS.iter() {
+            if let Some(caps) = pattern.captures(filename) {
+                let start = caps.get(0).map(|m| m.start()).unwrap_or(0);
+                let ext = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+                return format!("{}{}", filename[..start].trim_end(), ext);
+            }
+        }
+
+        filename.to_string()
+    }
+
+    /// Results for every member of a family with more than one file
+    ///
+    /// The latest member has the highest priority, then the newest
+    /// modification time; an unversioned file counts as version 0.
+    fn family_results(&self, mut family: Vec<Member>) -> Vec<(PathBuf, PluginResult)> {
+        family.sort_by(|a, b| {
+            (b.priority, b.modified)
+                .cmp(&(a.priority, a.modified))
+                .then(a.path.cmp(&b.path))
+        });
+        let size = family.len();
+        let latest = family[0].path.to_string_lossy().to_string();
+        let version_of = |member: &Member| {
+            member.version.clone().unwrap_or_else(|| "unversioned".to_string())
+        };
+
+        family
+            .iter()
+            .enumerate()
+            .map(|(rank, member)| {
+                let result = if rank == 0 {
+                    let message = format!("Latest of {} versions ({})", size, version_of(member));
+                    PluginResult::active("version-detection", message).with_color("green")
+                } else {
+                    let finding = Finding::new(
+                        "superseded-version",
+                        Severity::Info,
+                        format!("Older version of {}", latest),
+                    )
+                    .with_fix(format!("Archive or remove it in favor of {}", latest))
+                    .with_data("family_size", size)
+                    .with_data("latest", latest.as_str());
+
+                    let message = format!("Superseded ({}, {} of {})", version_of(member), rank + 1, size);
+                    PluginResult::from_findings("version-detection", vec![finding]).with_message(message)
+                };
+
+                let result = result
+                    .with_tags(vec!["version".to_string()])
+                    .with_metadata("version", version_of(member))
+                    .with_metadata("priority", member.priority.to_string())
+                    .with_metadata("family_size", size.to_string())
+                    .with_metadata("latest", latest.clone());
+                (member.path.clone(), result)
+            })
+            .collect()
+    }
+
     fn categorize_version(&self, priority: i32) -> (&str, &str, PluginStatus) {
         if priority >= 1000 {
             ("Latest Version", "green", PluginStatus::Active)
@@ -63,7 +145,7 @@ impl Plugin for VersionDetectionPlugin {
     fn metadata() -> PluginMetadata {
         PluginMetadata {
             name: "version-detection".to_string(),
-            version: "0.1.0".to_string(),
+            version: "0.2.0".to_string(),
             description: "Detects versioned files (file_v1, file_v2, file_final)".to_string(),
             author: Some("FSLint Contributors".to_string()),
             enabled_by_default: false,
@@ -73,19 +155,33 @@ fn metadata() -> PluginMetadata {
     fn check(&self, context: &PluginContext) -> Result<PluginResult, PluginError> {
         let filename = path::filename(&context.path)
             .ok_or_else(|| PluginError::NotApplicable("No filename".to_string()))?;
+        let detected = self.detect_version(&filename);
+
+        // Families are only known once the scan has seen every file
+        if let Some(scan) = context.state::<VersionScan>() {
+            let dir = context.path.parent().map(Path::to_path_buf).unwrap_or_default();
+            let member = Member {
+                path: context.path.clone(),
+                version: detected.as_ref().map(|(version, _)| version.clone()),
+                priority: detected.as_ref().map(|(_, priority)| *priority).unwrap_or(0),
+                modified: context.metadata.modified().ok(),
+            };
+            scan.families
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .entry((dir, self.base_name(&filename)))
+                .or_default()
+                .push(member);
+        }
 
-        match self.detect_version(&filename) {
+        match detected {
             Some((version, priority)) => {
                 let (category, color, status) = self.categorize_version(priority);
 
-                let mut result = PluginResult {
-                    plugin_name: "version-detection".to_string(),
-                    status,
-                    message: Some(format!("{} ({})", category, version)),
-                    color: Some(color.to_string()),
-                    tags: vec!["version".to_string()],
-                    metadata: HashMap::new(),
-                };
+                let mut result = PluginResult::new("version-detection", status)
+                    .with_message(format!("{} ({})", category, version))
+                    .with_color(color)
+                    .with_tags(vec!["version".to_string()]);
 
                 result.metadata.insert("version".to_string(), version);
                 result.metadata.insert("priority".to_string(), priority.to_string());
@@ -96,6 +192,28 @@ fn check(&self, context: &PluginContext) -> Result<PluginResult, PluginError> {
             None => Ok(PluginResult::inactive("version-detection")),
         }
     }
+
+    fn begin_scan(&self, _root: &Path) -> Result<Option<ScanState>, PluginError> {
+        Ok(Some(Arc::new(VersionScan::default())))
+    }
+
+    fn finish_scan(&self, state: &ScanState) -> Result<HashMap<PathBuf, PluginResult>, PluginError> {
+        let scan = state
+            .downcast_ref::<VersionScan>()
+            .ok_or_else(|| PluginError::Execution("Unexpected scan state".to_string()))?;
+        let families = std::mem::take(&mut *scan.families.lock().unwrap_or_else(|e| e.into_inner()));
+
+        Ok(families
+            .into_values()
+            .filter(|family| family.len() > 1 && family.iter().any(|m| m.version.is_some()))
+            .flat_map(|family| self.family_results(family))
+            .collect())
+    }
+
+    fn cacheable(&self) -> bool {
+        // Family membership depends on the other files in the directory
+        false
+    }
 }
 
 #[cfg(test)]
@@ -121,6 +239,45 @@ fn test_detect_version() {
         assert!(plugin.detect_version("regular.txt").is_none());
     }
 
+    #[test]
+    fn test_base_name() {
+        let plugin = VersionDetectionPlugin::new();
+
+        assert_eq!(plugin.base_name("report_v2.txt"), "report.txt");
+        assert_eq!(plugin.base_name("report_final.txt"), "report.txt");
+        assert_eq!(plugin.base_name("report (1).txt"), "report.txt");
+        assert_eq!(plugin.base_name("report.txt"), "report.txt");
+    }
+
+    #[test]
+    fn test_every_family_member_reported() {
+        let plugin = VersionDetectionPlugin::new();
+        let state = plugin.begin_scan(Path::new("/docs")).unwrap().unwrap();
+        let metadata = std::fs::metadata(".").unwrap(); // Dummy metadata
+
+        for name in ["report.txt", "report_v1.txt", "report_v2.txt", "notes.txt", "other_v3.txt"] {
+            let mut context = PluginContext::new(
+                PathBuf::from("/docs").join(name),
+                metadata.clone(),
+                PathBuf::from("/docs"),
+            );
+            context.scan_state = Some(state.clone());
+            plugin.check(&context).unwrap();
+        }
+
+        let results = plugin.finish_scan(&state).unwrap();
+        assert_eq!(results.len(), 3);
+
+        let latest = &results[&PathBuf::from("/docs/report_v2.txt")];
+        assert_eq!(latest.status, PluginStatus::Active);
+        assert_eq!(latest.message.as_deref(), Some("Latest of 3 versions (2)"));
+
+        let original = &results[&PathBuf::from("/docs/report.txt")];
+        assert_eq!(original.status, PluginStatus::Alert);
+        assert_eq!(original.findings[0].rule, "superseded-version");
+        assert_eq!(original.metadata["latest"], "/docs/report_v2.txt");
+    }
+
     #[test]
     fn test_version_priority() {
         let plugin = VersionDetectionPlugin::new();